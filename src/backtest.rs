@@ -0,0 +1,695 @@
+//! Offline backtesting of strategies over historical candles
+//!
+//! The `Backtester` replays candles through a `Strategy` the same way a market
+//! would: price updates, ticks, and fills when the price path touches an
+//! order's limit. Fills are booked with `PaperPosition` accounting, so results
+//! match what the paper trading market would report.
+//!
+//! History from other venues (e.g. Kraken or Binance exports) is loaded with
+//! `PriceSeries::from_csv`, from either trades or OHLC rows.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let candles = Candle::load_csv("hype_1h.csv")?;
+//! let report = Backtester::new(strategy, "HYPE/USDC")
+//!     .with_fee_rate(0.00035)
+//!     .run(&candles);
+//! println!("PnL {:.2}, max drawdown {:.2}", report.total_pnl, report.max_drawdown);
+//! ```
+
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::market::{FeeRates, OrderFill, OrderRequest, PaperPosition};
+use crate::strategy::Strategy;
+use crate::{CandlesSnapshotResponse, Error, InfoClient};
+
+/// Fill passes per price segment before the backtester assumes a runaway strategy
+const MAX_FILLS_PER_SEGMENT: usize = 10_000;
+
+/// One OHLC candle
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Candle {
+    /// Open time in milliseconds
+    pub time: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+impl Candle {
+    /// Convert a candle from `InfoClient::candles_snapshot`
+    pub fn from_snapshot(candle: &CandlesSnapshotResponse) -> Result<Self, Error> {
+        let parse = |value: &str| value.parse::<f64>().map_err(|_| Error::FloatStringParse);
+        Ok(Self {
+            time: candle.time_open,
+            open: parse(&candle.open)?,
+            high: parse(&candle.high)?,
+            low: parse(&candle.low)?,
+            close: parse(&candle.close)?,
+        })
+    }
+
+    /// Fetch candles for `coin` between two millisecond timestamps
+    pub async fn fetch(
+        info_client: &InfoClient,
+        coin: &str,
+        interval: &str,
+        start_time: u64,
+        end_time: u64,
+    ) -> Result<Vec<Self>, Error> {
+        info_client
+            .candles_snapshot(coin.to_string(), interval.to_string(), start_time, end_time)
+            .await?
+            .iter()
+            .map(Self::from_snapshot)
+            .collect()
+    }
+
+    /// Read candles from a CSV file with `time,open,high,low,close` columns
+    pub fn load_csv(path: impl AsRef<Path>) -> Result<Vec<Self>, Error> {
+        let file = std::fs::File::open(path.as_ref())
+            .map_err(|e| Error::GenericReader(format!("{}: {e}", path.as_ref().display())))?;
+        Self::read_csv(std::io::BufReader::new(file))
+    }
+
+    /// Parse CSV candles; columns are found by header name and extra columns are ignored
+    pub fn read_csv(reader: impl BufRead) -> Result<Vec<Self>, Error> {
+        let mut lines = reader.lines();
+        let header = lines
+            .next()
+            .ok_or_else(|| Error::GenericParse("empty candle CSV".to_string()))?
+            .map_err(|e| Error::GenericReader(e.to_string()))?;
+        let columns: HashMap<String, usize> = header
+            .split(',')
+            .enumerate()
+            .map(|(i, name)| (name.trim().to_lowercase(), i))
+            .collect();
+        let column = |name: &str| {
+            columns
+                .get(name)
+                .copied()
+                .ok_or_else(|| Error::GenericParse(format!("candle CSV has no '{name}' column")))
+        };
+        let (time, open, high, low, close) = (
+            column("time")?,
+            column("open")?,
+            column("high")?,
+            column("low")?,
+            column("close")?,
+        );
+
+        let mut candles = Vec::new();
+        for (line_no, line) in lines.enumerate() {
+            let line = line.map_err(|e| Error::GenericReader(e.to_string()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let field = |index: usize| {
+                fields.get(index).copied().ok_or_else(|| {
+                    Error::GenericParse(format!("candle CSV line {} is short", line_no + 2))
+                })
+            };
+            let number = |index: usize| {
+                field(index)?.parse::<f64>().map_err(|_| {
+                    Error::GenericParse(format!("bad number on candle CSV line {}", line_no + 2))
+                })
+            };
+            candles.push(Candle {
+                time: field(time)?.parse().map_err(|_| {
+                    Error::GenericParse(format!("bad time on candle CSV line {}", line_no + 2))
+                })?,
+                open: number(open)?,
+                high: number(high)?,
+                low: number(low)?,
+                close: number(close)?,
+            });
+        }
+        Ok(candles)
+    }
+
+    /// Prices visited within the candle: open, the nearer extreme, the other extreme, close
+    fn path(&self) -> [f64; 4] {
+        if self.close >= self.open {
+            [self.open, self.low, self.high, self.close]
+        } else {
+            [self.open, self.high, self.low, self.close]
+        }
+    }
+}
+
+/// Price history from a CSV file, replayed as candles
+///
+/// Files with `open`, `high` and `low` columns next to the price column are
+/// read as OHLC candles, with the price column as the close. Any other file
+/// is read as ticks, each becoming a candle that opens and closes at its
+/// price. Rows are sorted by time.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PriceSeries {
+    candles: Vec<Candle>,
+}
+
+impl PriceSeries {
+    /// Load prices from `path`, finding `time_col` and `price_col` by header name
+    ///
+    /// Timestamps may be Unix seconds (with fractions), milliseconds,
+    /// microseconds, RFC 3339 or `YYYY-MM-DD HH:MM:SS` in UTC.
+    pub fn from_csv(
+        path: impl AsRef<Path>,
+        time_col: &str,
+        price_col: &str,
+    ) -> Result<Self, Error> {
+        let file = std::fs::File::open(path.as_ref())
+            .map_err(|e| Error::GenericReader(format!("{}: {e}", path.as_ref().display())))?;
+        Self::read_csv(std::io::BufReader::new(file), time_col, price_col)
+    }
+
+    /// Parse CSV prices; see `from_csv`
+    pub fn read_csv(reader: impl BufRead, time_col: &str, price_col: &str) -> Result<Self, Error> {
+        let mut lines = reader.lines();
+        let header = lines
+            .next()
+            .ok_or_else(|| Error::GenericParse("empty price CSV".to_string()))?
+            .map_err(|e| Error::GenericReader(e.to_string()))?;
+        let columns: HashMap<String, usize> = header
+            .split(',')
+            .enumerate()
+            .map(|(i, name)| (name.trim().trim_matches('"').to_lowercase(), i))
+            .collect();
+        let column = |name: &str| {
+            columns
+                .get(&name.to_lowercase())
+                .copied()
+                .ok_or_else(|| Error::GenericParse(format!("price CSV has no '{name}' column")))
+        };
+        let (time, price) = (column(time_col)?, column(price_col)?);
+        let ohlc = match (column("open"), column("high"), column("low")) {
+            (Ok(open), Ok(high), Ok(low)) => Some((open, high, low)),
+            _ => None,
+        };
+
+        let mut candles = Vec::new();
+        for (line_no, line) in lines.enumerate() {
+            let line = line.map_err(|e| Error::GenericReader(e.to_string()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line
+                .split(',')
+                .map(|field| field.trim().trim_matches('"'))
+                .collect();
+            let field = |index: usize| {
+                fields.get(index).copied().ok_or_else(|| {
+                    Error::GenericParse(format!("price CSV line {} is short", line_no + 2))
+                })
+            };
+            let number = |index: usize| {
+                field(index)?.parse::<f64>().map_err(|_| {
+                    Error::GenericParse(format!("bad number on price CSV line {}", line_no + 2))
+                })
+            };
+            let time = parse_timestamp(field(time)?).ok_or_else(|| {
+                Error::GenericParse(format!("bad time on price CSV line {}", line_no + 2))
+            })?;
+            let close = number(price)?;
+            candles.push(match ohlc {
+                Some((open, high, low)) => Candle {
+                    time,
+                    open: number(open)?,
+                    high: number(high)?,
+                    low: number(low)?,
+                    close,
+                },
+                None => Candle {
+                    time,
+                    open: close,
+                    high: close,
+                    low: close,
+                    close,
+                },
+            });
+        }
+        candles.sort_by_key(|candle| candle.time);
+        Ok(Self { candles })
+    }
+
+    /// Candles to pass to `Backtester::run`
+    pub fn candles(&self) -> &[Candle] {
+        &self.candles
+    }
+
+    pub fn len(&self) -> usize {
+        self.candles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.candles.is_empty()
+    }
+}
+
+/// Milliseconds since the epoch from a CSV timestamp
+///
+/// Plain numbers are told apart by size: below 1e11 they are seconds (up to
+/// the year 5138), below 1e14 milliseconds, and microseconds above that.
+fn parse_timestamp(value: &str) -> Option<u64> {
+    if let Ok(number) = value.parse::<f64>() {
+        if !number.is_finite() || number < 0.0 {
+            return None;
+        }
+        let ms = if number < 1e11 {
+            number * 1000.0
+        } else if number < 1e14 {
+            number
+        } else {
+            number / 1000.0
+        };
+        return Some(ms.round() as u64);
+    }
+    let ms = match chrono::DateTime::parse_from_rfc3339(value) {
+        Ok(time) => time.timestamp_millis(),
+        Err(_) => chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+            .ok()?
+            .and_utc()
+            .timestamp_millis(),
+    };
+    u64::try_from(ms).ok()
+}
+
+/// Summary of a backtest run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BacktestReport {
+    /// Realized + unrealized PnL minus fees
+    pub total_pnl: f64,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub total_fees: f64,
+    /// Largest drop in equity from a previous peak
+    pub max_drawdown: f64,
+    /// Fills that reduced the position, i.e. closed a trade
+    pub roundtrips: u32,
+    pub fills: u32,
+    /// Mean over standard deviation of per-candle equity changes (not annualized)
+    pub sharpe: f64,
+    pub final_position: f64,
+    /// Equity (total PnL) at each candle close
+    pub equity_curve: Vec<f64>,
+}
+
+/// Replays candles through a strategy with simulated fills
+pub struct Backtester<S: Strategy> {
+    strategy: S,
+    asset: String,
+    fee_rate: f64,
+    orders: Vec<OrderRequest>,
+    position: PaperPosition,
+    total_fees: f64,
+    fills: u32,
+    roundtrips: u32,
+    /// Open time of the candle being replayed, stamped on its fills
+    time_ms: u64,
+}
+
+impl<S: Strategy> Backtester<S> {
+    pub fn new(strategy: S, asset: impl Into<String>) -> Self {
+        Self {
+            strategy,
+            asset: asset.into(),
+            fee_rate: 0.0,
+            orders: Vec::new(),
+            position: PaperPosition::default(),
+            total_fees: 0.0,
+            fills: 0,
+            roundtrips: 0,
+            time_ms: 0,
+        }
+    }
+
+    /// Charge `fee_rate` of the notional on every fill (0.00035 = 3.5 bps)
+    pub fn with_fee_rate(mut self, fee_rate: f64) -> Self {
+        self.fee_rate = fee_rate;
+        self
+    }
+
+    /// Charge fills at an account's maker rate, e.g. from `FeeRates::fetch_or_default`
+    ///
+    /// Backtest fills are resting limit orders, so the taker rate is unused.
+    pub fn with_fee_rates(self, rates: FeeRates) -> Self {
+        self.with_fee_rate(rates.maker)
+    }
+
+    /// Start from an existing position, e.g. the inventory a grid expects to hold
+    pub fn with_initial_position(mut self, size: f64, entry_price: f64) -> Self {
+        self.position = PaperPosition {
+            size,
+            entry_price,
+            realized_pnl: 0.0,
+        };
+        self
+    }
+
+    pub fn strategy(&self) -> &S {
+        &self.strategy
+    }
+
+    /// Orders still resting when the run ended
+    pub fn open_orders(&self) -> &[OrderRequest] {
+        &self.orders
+    }
+
+    /// Run the strategy over `candles` in order and summarize the result
+    pub fn run(&mut self, candles: &[Candle]) -> BacktestReport {
+        let orders = self.strategy.on_start();
+        self.add_orders(orders);
+
+        let mut equity_curve = Vec::with_capacity(candles.len());
+        let mut peak = 0.0_f64;
+        let mut max_drawdown = 0.0_f64;
+        let mut last_price = None;
+
+        for candle in candles {
+            self.time_ms = candle.time;
+            let orders = self.strategy.on_tick(candle.time / 1000);
+            self.add_orders(orders);
+
+            for price in candle.path() {
+                let from = last_price.unwrap_or(price);
+                self.match_segment(from, price);
+                let orders = self.strategy.on_price_update(&self.asset, price);
+                self.add_orders(orders);
+                // Orders placed at this price may already be marketable
+                self.match_segment(price, price);
+                last_price = Some(price);
+            }
+
+            let equity = self.equity(candle.close);
+            peak = peak.max(equity);
+            max_drawdown = max_drawdown.max(peak - equity);
+            equity_curve.push(equity);
+        }
+
+        let close = last_price.unwrap_or(0.0);
+        let unrealized_pnl = self.position.unrealized_pnl(close);
+        BacktestReport {
+            total_pnl: self.equity(close),
+            realized_pnl: self.position.realized_pnl,
+            unrealized_pnl,
+            total_fees: self.total_fees,
+            max_drawdown,
+            roundtrips: self.roundtrips,
+            fills: self.fills,
+            sharpe: sharpe(&equity_curve),
+            final_position: self.position.size,
+            equity_curve,
+        }
+    }
+
+    fn equity(&self, price: f64) -> f64 {
+        self.position.realized_pnl + self.position.unrealized_pnl(price) - self.total_fees
+    }
+
+    /// Fill orders the price touches while moving from `from` to `to`
+    ///
+    /// Orders fill at their limit price in the order the path reaches them.
+    /// Orders returned by the strategy are matched against the rest of the
+    /// segment.
+    fn match_segment(&mut self, from: f64, to: f64) {
+        let mut current = from;
+        for _ in 0..MAX_FILLS_PER_SEGMENT {
+            // Distance along the path until each order fills
+            let next = self
+                .orders
+                .iter()
+                .enumerate()
+                .filter_map(|(index, order)| {
+                    let limit = order.limit_price;
+                    let marketable = if order.is_buy() {
+                        current <= limit
+                    } else {
+                        current >= limit
+                    };
+                    if marketable {
+                        return Some((index, 0.0));
+                    }
+                    let reached = if order.is_buy() {
+                        to <= limit
+                    } else {
+                        to >= limit
+                    };
+                    reached.then(|| (index, (limit - current).abs()))
+                })
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+
+            let Some((index, distance)) = next else {
+                return;
+            };
+            let order = self.orders.remove(index);
+            if distance > 0.0 {
+                current = order.limit_price;
+            }
+            self.fill(order);
+        }
+        log::warn!("Backtest: fill limit reached in one price segment, stopping matching");
+    }
+
+    fn fill(&mut self, order: OrderRequest) {
+        let size_before = self.position.size.abs();
+        self.position
+            .apply_fill(order.qty, order.limit_price, order.is_buy());
+        if self.position.size.abs() < size_before {
+            self.roundtrips += 1;
+        }
+        self.total_fees += order.qty * order.limit_price * self.fee_rate;
+        self.fills += 1;
+
+        let fill = OrderFill::new(order.order_id, &order.asset, order.qty, order.limit_price)
+            .with_tag(order.tag)
+            .with_time(self.time_ms);
+        let orders = self.strategy.on_order_filled(&fill);
+        self.add_orders(orders);
+    }
+
+    /// Rest `orders`, dropping the ones they replace and the ones the
+    /// strategy cancelled
+    fn add_orders(&mut self, orders: Vec<OrderRequest>) {
+        let mut removed = self.strategy.drain_cancels();
+        removed.extend(orders.iter().filter_map(|order| order.replaces));
+        if !removed.is_empty() {
+            self.orders
+                .retain(|order| !removed.contains(&order.order_id));
+        }
+        self.orders.extend(orders);
+    }
+}
+
+/// Per-period Sharpe ratio of equity changes, 0 when undefined
+fn sharpe(equity_curve: &[f64]) -> f64 {
+    let returns: Vec<f64> = equity_curve.windows(2).map(|w| w[1] - w[0]).collect();
+    if returns.len() < 2 {
+        return 0.0;
+    }
+    let n = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / n;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    let std_dev = variance.sqrt();
+    if std_dev > 0.0 {
+        mean / std_dev
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::AssetPrecision;
+    use crate::strategy::spot_grid::{GridMode, SpotGridStrategy};
+
+    /// Candles oscillating between 95 and 125 around a 100-120 grid
+    fn oscillating_candles(count: usize) -> Vec<Candle> {
+        let price = |i: usize| 110.0 + 15.0 * (i as f64 * 0.5).sin();
+        (0..count)
+            .map(|i| {
+                let (open, close) = (price(i), price(i + 1));
+                Candle {
+                    time: i as u64 * 3_600_000,
+                    open,
+                    high: open.max(close) + 0.5,
+                    low: open.min(close) - 0.5,
+                    close,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_grid_backtest_over_oscillating_prices() {
+        let grid = SpotGridStrategy::new(
+            "SOL".to_string(),
+            100.0,
+            120.0,
+            5,
+            GridMode::Arithmetic,
+            Some(1.0),
+            None,
+            AssetPrecision {
+                sz_decimals: 2,
+                price_decimals: 2,
+                max_decimals: 6,
+            },
+            110.0,
+        );
+        // The grid starts out holding inventory for the two zones above 110
+        let mut backtester = Backtester::new(grid, "SOL").with_initial_position(2.0, 110.0);
+
+        let report = backtester.run(&oscillating_candles(200));
+
+        assert!(report.roundtrips > 10, "{report:?}");
+        assert!(report.fills > report.roundtrips);
+        assert!(report.realized_pnl > 0.0);
+        assert!(report.max_drawdown >= 0.0);
+        assert_eq!(report.equity_curve.len(), 200);
+        assert!(report.sharpe.is_finite());
+        // Every zone always has exactly one order working
+        assert_eq!(backtester.open_orders().len(), 4);
+        // Backtest accounting agrees with the strategy's own books
+        let status = backtester.strategy().status();
+        assert!((status.position - report.final_position).abs() < 1e-9);
+        assert!((status.realized_pnl - report.realized_pnl).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_fees_and_drawdown() {
+        #[derive(Default)]
+        struct BuyOnce {
+            bought: bool,
+        }
+        impl Strategy for BuyOnce {
+            fn on_price_update(&mut self, asset: &str, price: f64) -> Vec<OrderRequest> {
+                if self.bought {
+                    return vec![];
+                }
+                self.bought = true;
+                vec![OrderRequest::buy(1, asset, 1.0, price)]
+            }
+            fn on_order_filled(&mut self, _fill: &OrderFill) -> Vec<OrderRequest> {
+                vec![]
+            }
+        }
+        let flat = |time, price| Candle {
+            time,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+        };
+
+        let mut backtester = Backtester::new(BuyOnce::default(), "BTC").with_fee_rate(0.01);
+        let report = backtester.run(&[flat(0, 100.0), flat(1, 110.0), flat(2, 90.0)]);
+
+        assert_eq!(report.fills, 1);
+        assert_eq!(report.roundtrips, 0);
+        assert!((report.total_fees - 1.0).abs() < 1e-9);
+        assert!((report.total_pnl - (-11.0)).abs() < 1e-9);
+        // Peak 9 (at 110) to -11 (at 90)
+        assert!((report.max_drawdown - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_read_csv() {
+        let csv = "time,open,high,low,close,volume\n\
+                   0,100,105,95,102,10\n\
+                   \n\
+                   3600000,102,103,99,100,12\n";
+        let candles = Candle::read_csv(csv.as_bytes()).unwrap();
+
+        assert_eq!(candles.len(), 2);
+        assert_eq!(
+            candles[1],
+            Candle {
+                time: 3_600_000,
+                open: 102.0,
+                high: 103.0,
+                low: 99.0,
+                close: 100.0,
+            }
+        );
+        // Falling candle visits the high first
+        assert_eq!(candles[1].path(), [102.0, 103.0, 99.0, 100.0]);
+
+        assert!(Candle::read_csv("time,open,high,low\n".as_bytes()).is_err());
+        assert!(Candle::read_csv("time,open,high,low,close\n0,1,2,x,1\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_price_series_from_csv() {
+        // Kraken-style trades: fractional seconds, out of order, extra columns
+        let trades = "price,volume,time,side\n\
+                      101.5,0.2,1616663619.5,b\n\
+                      100.0,1.0,1616663618.25,s\n\
+                      \n\
+                      99.0,0.5,1616663620,s\n";
+        let series = PriceSeries::read_csv(trades.as_bytes(), "time", "price").unwrap();
+        assert_eq!(series.len(), 3);
+        assert_eq!(series.candles()[0].time, 1_616_663_618_250);
+        assert_eq!(series.candles()[1].low, 101.5);
+        assert_eq!(series.candles()[2].close, 99.0);
+
+        // Binance-style klines in milliseconds, RFC 3339 and plain dates
+        let klines = "Open Time,Open,High,Low,Close\n\
+                      1616662800000,100,105,95,102\n\
+                      2021-03-25T10:00:00Z,102,103,99,100\n\
+                      2021-03-25 11:00:00,100,101,98,99\n";
+        let series = PriceSeries::read_csv(klines.as_bytes(), "open time", "close").unwrap();
+        let times: Vec<u64> = series.candles().iter().map(|c| c.time).collect();
+        assert_eq!(
+            times,
+            [1_616_662_800_000, 1_616_666_400_000, 1_616_670_000_000]
+        );
+        assert_eq!(
+            series.candles()[0],
+            Candle {
+                time: 1_616_662_800_000,
+                open: 100.0,
+                high: 105.0,
+                low: 95.0,
+                close: 102.0,
+            }
+        );
+
+        assert!(PriceSeries::read_csv("time,price\n".as_bytes(), "ts", "price").is_err());
+        assert!(
+            PriceSeries::read_csv("time,price\nyesterday,1\n".as_bytes(), "time", "price").is_err()
+        );
+    }
+
+    #[test]
+    fn test_replay_csv_through_noop_strategy() {
+        let path = std::env::temp_dir().join(format!("prices_{}.csv", std::process::id()));
+        std::fs::write(&path, "timestamp,price\n1700000000,50\n1700000001,51\n").unwrap();
+        let series = PriceSeries::from_csv(&path, "timestamp", "price").unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let report = Backtester::new(crate::strategy::NoOpStrategy, "BTC").run(series.candles());
+        assert_eq!(report.fills, 0);
+        assert_eq!(report.equity_curve, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_from_snapshot() {
+        let snapshot: CandlesSnapshotResponse = serde_json::from_value(serde_json::json!({
+            "t": 1000, "T": 2000, "s": "BTC", "i": "1h",
+            "o": "100.5", "c": "101", "h": "102", "l": "99.5", "v": "12", "n": 3
+        }))
+        .unwrap();
+        let candle = Candle::from_snapshot(&snapshot).unwrap();
+        assert_eq!(candle.time, 1000);
+        assert_eq!(candle.open, 100.5);
+        assert_eq!(candle.low, 99.5);
+    }
+}
@@ -1,6 +1,7 @@
 use hyperliquid_rust_sdk::{
     bot::BotRunner,
     strategy::{
+        composite::CompositeStrategyFactory, dca::DcaStrategyFactory,
         spot_grid::SpotGridStrategyFactory, NoOpStrategy, Strategy, StrategyFactory,
         StrategyRegistry,
     },
@@ -30,11 +31,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // In a real app, you'd register all your strategies here
     registry.register("noop", NoOpStrategyFactory);
     registry.register("spot_grid", SpotGridStrategyFactory);
+    registry.register("dca", DcaStrategyFactory);
 
-    // 3. Create Runner
-    let args: Vec<String> = std::env::args().collect();
+    // Strategies that can run side by side inside a "composite"
+    let mut sub_registry = StrategyRegistry::new();
+    sub_registry.register("spot_grid", SpotGridStrategyFactory);
+    sub_registry.register("dca", DcaStrategyFactory);
+    registry.register("composite", CompositeStrategyFactory::new(sub_registry));
+
+    // 3. Create Runner; `--preview` prints the starting levels and exits
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let preview = args.iter().any(|arg| arg == "--preview");
     let default_config = "config.toml".to_string();
-    let config_path = args.get(1).unwrap_or(&default_config);
+    let config_path = args
+        .iter()
+        .find(|arg| !arg.starts_with("--"))
+        .unwrap_or(&default_config);
     if !std::path::Path::new(config_path).exists() {
         eprintln!(
             "Config file '{}' not found. Please create one.",
@@ -46,6 +58,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let runner = BotRunner::new(config_path, registry)?;
 
     // 4. Run
+    if preview {
+        if let Err(e) = runner.preview().await {
+            eprintln!("Preview error: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
     if let Err(e) = runner.run().await {
         eprintln!("Bot execution error: {}", e);
         std::process::exit(1);
@@ -4,7 +4,7 @@ This is an example of a basic market making strategy.
 We subscribe to the current mid price and build a market around this price. Whenever our market becomes outdated, we place and cancel orders to renew it.
 */
 use alloy::signers::local::PrivateKeySigner;
-use hyperliquid_rust_sdk::{MarketMaker, MarketMakerInput};
+use hyperliquid_rust_sdk::{MarketMaker, MarketMakerInput, MarketMakerLevel};
 
 #[tokio::main]
 async fn main() {
@@ -21,6 +21,19 @@ async fn main() {
         half_spread: 1,
         max_absolute_position_size: 0.5,
         decimals: 1,
+        target_inventory: 0.0,
+        max_inventory: 0.5,
+        skew_per_unit: 2.0,
+        levels: vec![
+            MarketMakerLevel {
+                offset_bps: 0.0,
+                size: 0.1,
+            },
+            MarketMakerLevel {
+                offset_bps: 5.0,
+                size: 0.15,
+            },
+        ],
         wallet,
     };
     MarketMaker::new(market_maker_input).await.start().await
@@ -1,9 +1,41 @@
 //! Bot - MarketListener that wraps a Strategy
 
-use log::{debug, info};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-use crate::market::{MarketListener, OrderFill, OrderRequest};
-use crate::strategy::{Strategy, StrategyStatus};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use super::metrics::BotMetrics;
+use super::notifier::{Notification, NotificationEvent, Notifier};
+use super::trade_log::TradeLog;
+use crate::market::{
+    AssetPrecision, FeedBacklog, MarketBook, MarketCommand, MarketError, MarketListener, OrderFill,
+    OrderRequest, OrderSide,
+};
+use crate::strategy::{RangeDirection, Strategy, StrategyContext, StrategyEvent, StrategyStatus};
+
+/// Operator-controlled run state of a bot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BotStatus {
+    /// Strategy orders are passed to the market
+    #[default]
+    Running,
+    /// Running, but price has left the strategy's range and every level waits
+    /// on one side; back to running once it returns
+    #[serde(rename = "range_exited")]
+    RangeExited { direction: RangeDirection },
+    /// Fills are still tracked, but new strategy orders are held back until resumed
+    Paused,
+    /// Orders were cancelled and the position closed; strategy orders are dropped
+    Flattened,
+    /// The market shut down and cancelled its orders; strategy orders are dropped
+    Stopped,
+}
 
 /// Bot wraps a Strategy and implements MarketListener
 ///
@@ -28,12 +60,185 @@ use crate::strategy::{Strategy, StrategyStatus};
 pub struct Bot<S: Strategy> {
     /// The trading strategy
     strategy: S,
+    /// Status JSON pushed to live subscribers after every market event
+    status_tx: broadcast::Sender<String>,
+    /// Strategy events pushed to live subscribers as they happen
+    event_tx: broadcast::Sender<StrategyEvent>,
+    /// Latest venue order book, if the market streams one
+    market_book: Option<MarketBook>,
+    /// CSV log every fill is appended to, if enabled
+    trade_log: Option<TradeLog>,
+    /// Side and unfilled quantity of orders returned by the strategy, by order id
+    open_orders: HashMap<u64, (OrderSide, f64)>,
+    /// Fills processed since the bot was created
+    fills_total: u64,
+    /// WebSocket reconnects reported by the market
+    reconnects_total: u64,
+    /// When the bot was created, for uptime
+    started_at: Instant,
+    /// The same moment as Unix seconds, for display
+    start_time: u64,
+    /// Run state set through the control endpoints
+    bot_status: BotStatus,
+    /// Status a paused bot goes back to on resume, kept current by range events
+    resume_status: BotStatus,
+    /// The strategy's `on_stop` has run
+    strategy_stopped: bool,
+    /// The market reported no prices for a while; cleared by the next one
+    feed_stalled: bool,
+    /// How far the market lags its WebSocket feed, as of the last tick
+    feed_backlog: FeedBacklog,
+    /// Precision rejections since asset metadata was last re-fetched
+    precision_rejects: u32,
+    /// Strategy orders held back while paused
+    held_orders: Vec<OrderRequest>,
+    /// Commands waiting to be picked up by the market
+    commands: Vec<MarketCommand>,
+    /// Flatten and stop as soon as price leaves the strategy's range
+    flatten_on_range_exit: bool,
+    /// JSON file the strategy's lifetime totals are saved to
+    checkpoint: Option<PathBuf>,
+    /// Highest total PnL seen, once the first event has been processed
+    peak_pnl: Option<f64>,
+    /// Fall in total PnL from `peak_pnl`
+    current_drawdown: f64,
+    /// Largest `current_drawdown` seen
+    max_drawdown: f64,
+    /// Drawdown percentage that raises `DrawdownExceeded`
+    drawdown_alert_pct: Option<f64>,
+    /// The alert fired and drawdown hasn't dropped back below the threshold
+    drawdown_alerted: bool,
+    /// Told about fills, stops, range exits, drawdown alerts and errors
+    notifiers: Vec<Arc<dyn Notifier>>,
 }
 
 impl<S: Strategy> Bot<S> {
+    /// Capacity of the live status channel; slow subscribers skip stale updates
+    const STATUS_CHANNEL_CAPACITY: usize = 16;
+
+    /// Capacity of the strategy event channel
+    const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+    /// Precision rejections after which the market is asked to re-fetch
+    /// asset metadata, in case the cached decimals are stale
+    const PRECISION_REJECTS_BEFORE_REFRESH: u32 = 3;
+
     /// Create a new bot wrapping the given strategy
     pub fn new(strategy: S) -> Self {
-        Self { strategy }
+        let (status_tx, _) = broadcast::channel(Self::STATUS_CHANNEL_CAPACITY);
+        let (event_tx, _) = broadcast::channel(Self::EVENT_CHANNEL_CAPACITY);
+        Self {
+            strategy,
+            status_tx,
+            event_tx,
+            market_book: None,
+            trade_log: None,
+            open_orders: HashMap::new(),
+            fills_total: 0,
+            reconnects_total: 0,
+            started_at: Instant::now(),
+            start_time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            bot_status: BotStatus::Running,
+            resume_status: BotStatus::Running,
+            strategy_stopped: false,
+            feed_stalled: false,
+            feed_backlog: FeedBacklog::default(),
+            precision_rejects: 0,
+            held_orders: Vec::new(),
+            commands: Vec::new(),
+            flatten_on_range_exit: false,
+            checkpoint: None,
+            peak_pnl: None,
+            current_drawdown: 0.0,
+            max_drawdown: 0.0,
+            drawdown_alert_pct: None,
+            drawdown_alerted: false,
+            notifiers: Vec::new(),
+        }
+    }
+
+    /// Append every fill to the given trade log (builder pattern)
+    pub fn with_trade_log(mut self, trade_log: TradeLog) -> Self {
+        self.trade_log = Some(trade_log);
+        self
+    }
+
+    /// Keep the strategy's lifetime totals in a JSON file (builder pattern)
+    ///
+    /// Totals saved there by an earlier run are restored right away. The
+    /// file is rewritten after every fill and on shutdown.
+    pub fn with_checkpoint(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => match serde_json::from_str(&contents) {
+                Ok(checkpoint) => {
+                    info!(
+                        "Bot[{}]: restoring totals from {}",
+                        self.strategy.name(),
+                        path.display()
+                    );
+                    self.strategy.restore(checkpoint);
+                }
+                Err(e) => warn!(
+                    "Bot[{}]: ignoring unreadable checkpoint {}: {}",
+                    self.strategy.name(),
+                    path.display(),
+                    e
+                ),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!(
+                "Bot[{}]: failed to read checkpoint {}: {}",
+                self.strategy.name(),
+                path.display(),
+                e
+            ),
+        }
+        self.checkpoint = Some(path);
+        self
+    }
+
+    /// Close out and stop once price leaves the strategy's range (builder pattern)
+    ///
+    /// Instead of holding the inventory through a breakout, the bot flattens
+    /// (cancels every order and closes the position with the market's
+    /// slippage-limited IOC) on the first `RangeExited` event and then stays
+    /// stopped.
+    pub fn with_flatten_on_range_exit(mut self, enabled: bool) -> Self {
+        self.flatten_on_range_exit = enabled;
+        self
+    }
+
+    /// Raise `StrategyEvent::DrawdownExceeded` when the drawdown reaches
+    /// `pct` percent of peak equity (builder pattern)
+    ///
+    /// The event goes to `subscribe_events` subscribers and is logged. It
+    /// fires again only after the drawdown has dropped back below `pct`.
+    /// Strategies that report no investment have no drawdown percentage, so
+    /// the alert never fires for them.
+    pub fn with_drawdown_alert(mut self, pct: f64) -> Self {
+        self.drawdown_alert_pct = Some(pct);
+        self
+    }
+
+    /// Tell `notifier` about fills, stops, range exits, drawdown alerts and
+    /// errors (builder pattern)
+    pub fn with_notifier(mut self, notifier: impl Notifier + 'static) -> Self {
+        self.notifiers.push(Arc::new(notifier));
+        self
+    }
+
+    /// Give the strategy read-only exchange access (builder pattern)
+    pub fn with_context(mut self, context: StrategyContext) -> Self {
+        self.strategy.set_context(context);
+        self
+    }
+
+    /// The trade log fills are appended to, if enabled
+    pub fn trade_log(&self) -> Option<&TradeLog> {
+        self.trade_log.as_ref()
     }
 
     /// Get a reference to the underlying strategy
@@ -48,32 +253,448 @@ impl<S: Strategy> Bot<S> {
 
     /// Call strategy's on_start and return initial orders
     pub fn start(&mut self) -> Vec<OrderRequest> {
-        self.strategy.on_start()
+        let orders = self.strategy.on_start();
+        self.release_orders(orders)
     }
 
     /// Call strategy's on_stop and return final orders
+    ///
+    /// The strategy is only stopped once; later calls return no orders.
     pub fn stop(&mut self) -> Vec<OrderRequest> {
-        self.strategy.on_stop()
+        if std::mem::replace(&mut self.strategy_stopped, true) {
+            return vec![];
+        }
+        let orders = self.strategy.on_stop();
+        self.release_orders(orders)
+    }
+
+    /// Stop the bot if its market hasn't, and flush the trade log and
+    /// checkpoint to disk
+    ///
+    /// Waits for the log's writes to reach the disk, so fills logged just
+    /// before a stop survive it. The runner calls this on every way a market
+    /// can end; the strategy's `on_stop` runs only the first time, whatever
+    /// the bot's status, and later calls only flush.
+    pub fn shutdown(&mut self) {
+        MarketListener::on_stop(self);
+    }
+
+    fn persist(&mut self) {
+        self.flush_trade_log();
+        self.save_checkpoint();
+    }
+
+    /// Write the strategy's checkpoint next to its file and swap it in, so a
+    /// crash mid-write leaves the previous one intact
+    fn save_checkpoint(&self) {
+        let (Some(path), Some(checkpoint)) = (&self.checkpoint, self.strategy.checkpoint()) else {
+            return;
+        };
+        let tmp = path.with_extension("tmp");
+        let written = serde_json::to_vec(&checkpoint)
+            .map_err(std::io::Error::from)
+            .and_then(|json| std::fs::write(&tmp, json))
+            .and_then(|()| std::fs::rename(&tmp, path));
+        if let Err(e) = written {
+            warn!(
+                "Bot[{}]: failed to save checkpoint {}: {}",
+                self.strategy.name(),
+                path.display(),
+                e
+            );
+        }
+    }
+
+    fn flush_trade_log(&mut self) {
+        let Some(trade_log) = self.trade_log.as_mut() else {
+            return;
+        };
+        if let Err(e) = trade_log.flush() {
+            warn!(
+                "Bot[{}]: failed to flush trade log {}: {}",
+                self.strategy.name(),
+                trade_log.path().display(),
+                e
+            );
+        }
+    }
+
+    /// Current run state
+    pub fn bot_status(&self) -> BotStatus {
+        self.bot_status
+    }
+
+    /// Whether strategy orders go to the market, in range or not
+    fn is_running(&self) -> bool {
+        matches!(
+            self.bot_status,
+            BotStatus::Running | BotStatus::RangeExited { .. }
+        )
+    }
+
+    /// Whether the price feed has gone quiet for the traded asset
+    pub fn feed_stalled(&self) -> bool {
+        self.feed_stalled
+    }
+
+    /// Stop passing new strategy orders to the market
+    ///
+    /// The strategy keeps receiving prices and fills; the orders it returns are
+    /// held until `resume`. Returns false unless the bot was running.
+    pub fn pause(&mut self) -> bool {
+        if !self.is_running() {
+            return false;
+        }
+        info!("Bot[{}]: paused", self.strategy.name());
+        self.resume_status = self.bot_status;
+        self.bot_status = BotStatus::Paused;
+        true
+    }
+
+    /// Resume a paused bot
+    ///
+    /// The bot goes back to the status it was paused in, or to where range
+    /// events moved it since. Orders held while paused are handed to the
+    /// market with the next event. Returns false unless the bot was paused.
+    pub fn resume(&mut self) -> bool {
+        if self.bot_status != BotStatus::Paused {
+            return false;
+        }
+        info!(
+            "Bot[{}]: resumed with {} held order(s)",
+            self.strategy.name(),
+            self.held_orders.len()
+        );
+        self.bot_status = self.resume_status;
+        true
+    }
+
+    /// Ask the market to cancel all orders and close the position
+    ///
+    /// This is terminal: later strategy orders are dropped, since the strategy's
+    /// view of its position no longer matches the account. Returns false if the
+    /// bot was already flattened or has stopped.
+    pub fn flatten(&mut self) -> bool {
+        if matches!(self.bot_status, BotStatus::Flattened | BotStatus::Stopped) {
+            return false;
+        }
+        warn!("Bot[{}]: flattening", self.strategy.name());
+        self.wind_down(BotStatus::Flattened, &[MarketCommand::Flatten]);
+        true
+    }
+
+    /// Stop trading in `status`, dropping held and tracked orders, and queue
+    /// `commands` for the market
+    fn wind_down(&mut self, status: BotStatus, commands: &[MarketCommand]) {
+        self.bot_status = status;
+        self.held_orders.clear();
+        self.open_orders.clear();
+        self.commands.extend_from_slice(commands);
+        self.notify(NotificationEvent::Stopped { bot_status: status });
+        self.publish_status();
     }
 
     /// Get the strategy's current status
     ///
     /// Returns a `StrategyStatus` containing PnL, position, and other metrics.
-    /// Useful for monitoring dashboards and APIs.
+    /// Useful for monitoring dashboards and APIs. The run state is added as
+    /// `custom.bot_status`, a stalled price feed as `custom.feed_stalled`, the
+    /// feed backlog as `custom.feed_backlog`, the bot's start (Unix seconds)
+    /// and uptime as `custom.start_time` and `custom.uptime_secs` and, when
+    /// the market streams the order book, the latest snapshot as
+    /// `custom.market_book`. Drawdowns are the bot's.
     pub fn status(&self) -> StrategyStatus {
-        self.strategy.status()
+        let mut status = self.with_drawdown(self.strategy.status());
+        if status.custom.is_null() {
+            status.custom = serde_json::Value::Object(Default::default());
+        }
+        if let Some(custom) = status.custom.as_object_mut() {
+            custom.insert("bot_status".to_string(), serde_json::json!(self.bot_status));
+            custom.insert(
+                "feed_stalled".to_string(),
+                serde_json::json!(self.feed_stalled),
+            );
+            custom.insert(
+                "feed_backlog".to_string(),
+                serde_json::json!(self.feed_backlog),
+            );
+            custom.insert("start_time".to_string(), serde_json::json!(self.start_time));
+            custom.insert(
+                "uptime_secs".to_string(),
+                serde_json::json!(self.started_at.elapsed().as_secs_f64()),
+            );
+            if let Some(Ok(book)) = self.market_book.as_ref().map(serde_json::to_value) {
+                custom.insert("market_book".to_string(), book);
+            }
+        }
+        status
+    }
+
+    /// The strategy's status as one compact line of JSON, for log-based monitoring
+    ///
+    /// The level book and strategy-specific detail are left out to keep the
+    /// line short; `custom` only carries `bot_status`. The line parses back
+    /// into a `StrategyStatus`.
+    pub fn status_line(&self) -> String {
+        let mut status = self.with_drawdown(self.strategy.status());
+        status.book = None;
+        status.custom = serde_json::json!({ "bot_status": self.bot_status });
+        serde_json::to_string(&status).unwrap_or_default()
     }
 
     /// Get the strategy's status as JSON
     ///
     /// Convenience method for HTTP APIs.
     pub fn status_json(&self) -> serde_json::Value {
-        serde_json::to_value(self.strategy.status()).unwrap_or_default()
+        serde_json::to_value(self.status()).unwrap_or_default()
     }
 
     pub fn render_dashboard(&self) -> String {
         // Use generic dashboard for all strategies
-        crate::bot::dashboard::render_dashboard(&self.strategy.status())
+        crate::bot::dashboard::render_dashboard(&self.status())
+    }
+
+    /// Current metrics snapshot for monitoring (`/metrics`)
+    ///
+    /// Active buys/sells count the strategy's orders that haven't fully filled yet.
+    pub fn metrics(&self) -> BotMetrics {
+        let active_buys = self
+            .open_orders
+            .values()
+            .filter(|(side, _)| side.is_buy())
+            .count();
+        BotMetrics {
+            active_buys,
+            active_sells: self.open_orders.len() - active_buys,
+            fills_total: self.fills_total,
+            reconnects_total: self.reconnects_total,
+            feed_queue_depth: self.feed_backlog.depth,
+            feed_dropped_total: self.feed_backlog.dropped,
+            uptime_seconds: self.started_at.elapsed().as_secs_f64(),
+            ..BotMetrics::from_status(&self.strategy.status())
+        }
+    }
+
+    /// Subscribe to live status updates
+    ///
+    /// The receiver gets the status JSON (as sent by `status_json`) after
+    /// every price update and fill the bot processes.
+    pub fn subscribe_status(&self) -> broadcast::Receiver<String> {
+        self.status_tx.subscribe()
+    }
+
+    /// Subscribe to strategy events, such as completed roundtrips
+    ///
+    /// Events are also logged, whether or not anyone subscribes.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<StrategyEvent> {
+        self.event_tx.subscribe()
+    }
+
+    /// Log the events the strategy queued, follow range exits in the run
+    /// state and pass the events to subscribers
+    fn forward_events(&mut self) {
+        for event in self.strategy.drain_events() {
+            match &event {
+                StrategyEvent::RoundtripClosed(rt) => info!(
+                    "Bot[{}]: roundtrip closed: {} {} -> {}, pnl {:.4}",
+                    self.strategy.name(),
+                    rt.size,
+                    rt.entry_price,
+                    rt.exit_price,
+                    rt.pnl
+                ),
+                StrategyEvent::RangeExited { direction } => {
+                    warn!(
+                        "Bot[{}]: price left the range ({:?})",
+                        self.strategy.name(),
+                        direction
+                    );
+                    self.notify(NotificationEvent::RangeExited {
+                        direction: *direction,
+                    });
+                    if self.flatten_on_range_exit && self.is_running() {
+                        warn!("Bot[{}]: flattening and stopping", self.strategy.name());
+                        let commands = [MarketCommand::Flatten, MarketCommand::Stop];
+                        self.wind_down(BotStatus::Stopped, &commands);
+                    } else if self.bot_status == BotStatus::Running {
+                        self.bot_status = BotStatus::RangeExited {
+                            direction: *direction,
+                        };
+                    } else if self.bot_status == BotStatus::Paused
+                        && self.resume_status == BotStatus::Running
+                    {
+                        self.resume_status = BotStatus::RangeExited {
+                            direction: *direction,
+                        };
+                    }
+                }
+                StrategyEvent::RangeReentered => {
+                    info!("Bot[{}]: price back in range", self.strategy.name());
+                    if let BotStatus::RangeExited { .. } = self.bot_status {
+                        self.bot_status = BotStatus::Running;
+                    }
+                    if let BotStatus::RangeExited { .. } = self.resume_status {
+                        self.resume_status = BotStatus::Running;
+                    }
+                }
+                StrategyEvent::DrawdownExceeded { .. } => {}
+            }
+            let _ = self.event_tx.send(event);
+        }
+    }
+
+    /// Report an error outside the bot's callbacks, such as its market failing
+    pub fn notify_error(&self, message: impl Into<String>) {
+        self.notify(NotificationEvent::Error {
+            message: message.into(),
+        });
+    }
+
+    /// Hand `event` to every notifier
+    fn notify(&self, event: NotificationEvent) {
+        if self.notifiers.is_empty() {
+            return;
+        }
+        let asset = self.strategy.status().asset;
+        let notification = Notification::new(self.strategy.name(), asset, event);
+        for notifier in &self.notifiers {
+            notifier.notify(&notification);
+        }
+    }
+
+    fn with_drawdown(&self, mut status: StrategyStatus) -> StrategyStatus {
+        status.current_drawdown = self.current_drawdown;
+        status.max_drawdown = self.max_drawdown;
+        status
+    }
+
+    /// Follow peak total PnL and the drawdown from it, and raise the alert
+    /// when the drawdown crosses its threshold
+    fn track_drawdown(&mut self) {
+        let status = self.strategy.status();
+        let pnl = status.total_pnl();
+        let peak = self.peak_pnl.map_or(pnl, |peak| peak.max(pnl));
+        self.peak_pnl = Some(peak);
+        self.current_drawdown = peak - pnl;
+        self.max_drawdown = self.max_drawdown.max(self.current_drawdown);
+
+        let Some(threshold) = self.drawdown_alert_pct else {
+            return;
+        };
+        let drawdown_pct = self.with_drawdown(status).drawdown_pct();
+        match drawdown_pct {
+            Some(drawdown_pct) if drawdown_pct >= threshold => {
+                if self.drawdown_alerted {
+                    return;
+                }
+                self.drawdown_alerted = true;
+                warn!(
+                    "Bot[{}]: drawdown {:.4} ({:.2}%) reached the {:.2}% alert threshold",
+                    self.strategy.name(),
+                    self.current_drawdown,
+                    drawdown_pct,
+                    threshold
+                );
+                let _ = self.event_tx.send(StrategyEvent::DrawdownExceeded {
+                    drawdown: self.current_drawdown,
+                    drawdown_pct,
+                });
+                self.notify(NotificationEvent::DrawdownExceeded {
+                    drawdown: self.current_drawdown,
+                    drawdown_pct,
+                });
+            }
+            _ => self.drawdown_alerted = false,
+        }
+    }
+
+    /// Pass strategy orders to the market according to the run state
+    fn release_orders(&mut self, orders: Vec<OrderRequest>) -> Vec<OrderRequest> {
+        let orders = match self.bot_status {
+            BotStatus::Running | BotStatus::RangeExited { .. } if self.held_orders.is_empty() => {
+                orders
+            }
+            BotStatus::Running | BotStatus::RangeExited { .. } => {
+                let mut released = std::mem::take(&mut self.held_orders);
+                released.extend(orders);
+                released
+            }
+            BotStatus::Paused => {
+                for order in orders {
+                    self.hold_order(order);
+                }
+                return vec![];
+            }
+            BotStatus::Flattened | BotStatus::Stopped => return vec![],
+        };
+        self.track_orders(&orders);
+        orders
+    }
+
+    /// Hold `order` back while paused
+    ///
+    /// The market never saw held orders, so one replacing a held order takes
+    /// its place, replacing in turn whatever the held one was to replace.
+    fn hold_order(&mut self, mut order: OrderRequest) {
+        if let Some(replaced) = order.replaces {
+            if let Some(i) = self.held_orders.iter().position(|o| o.order_id == replaced) {
+                order.replaces = self.held_orders.remove(i).replaces;
+            }
+        }
+        self.held_orders.push(order);
+    }
+
+    /// Remember the side of orders handed to the market until they fully fill
+    fn track_orders(&mut self, orders: &[OrderRequest]) {
+        for order in orders {
+            if let Some(replaced) = order.replaces {
+                self.open_orders.remove(&replaced);
+            }
+            self.open_orders
+                .insert(order.order_id, (order.side, order.qty));
+        }
+    }
+
+    /// Account for a fill and append it to the trade log, if enabled
+    fn record_fill(&mut self, fill: &OrderFill) {
+        self.fills_total += 1;
+        let side = match self.open_orders.get_mut(&fill.order_id) {
+            Some((side, remaining)) => {
+                let side = *side;
+                *remaining -= fill.qty;
+                if *remaining <= f64::EPSILON {
+                    self.open_orders.remove(&fill.order_id);
+                }
+                Some(side)
+            }
+            None => None,
+        };
+        self.notify(NotificationEvent::Fill {
+            order_id: fill.order_id,
+            side,
+            qty: fill.qty,
+            price: fill.price,
+            fee: fill.fee,
+        });
+
+        let Some(trade_log) = self.trade_log.as_mut() else {
+            return;
+        };
+        if let Err(e) = trade_log.append(fill, side, fill.fee) {
+            warn!(
+                "Bot[{}]: failed to write trade log {}: {}",
+                self.strategy.name(),
+                trade_log.path().display(),
+                e
+            );
+        }
+    }
+
+    /// Push the current status to live subscribers, if there are any
+    fn publish_status(&self) {
+        if self.status_tx.receiver_count() > 0 {
+            let _ = self.status_tx.send(self.status_json().to_string());
+        }
     }
 }
 
@@ -85,6 +706,14 @@ impl<S: Strategy> MarketListener for Bot<S> {
             asset,
             price
         );
+        if self.feed_stalled {
+            info!(
+                "Bot[{}]: prices for {} are back",
+                self.strategy.name(),
+                asset
+            );
+            self.feed_stalled = false;
+        }
         let orders = self.strategy.on_price_update(asset, price);
         if !orders.is_empty() {
             info!(
@@ -93,19 +722,174 @@ impl<S: Strategy> MarketListener for Bot<S> {
                 orders.len()
             );
         }
+        self.forward_events();
+        self.track_drawdown();
+        let orders = self.release_orders(orders);
+        self.publish_status();
         orders
     }
 
     fn on_order_filled(&mut self, fill: OrderFill) -> Vec<OrderRequest> {
-        self.strategy.on_order_filled(&fill)
+        self.record_fill(&fill);
+        let orders = self.strategy.on_order_filled(&fill);
+        self.save_checkpoint();
+        self.forward_events();
+        self.track_drawdown();
+        let orders = self.release_orders(orders);
+        self.publish_status();
+        orders
+    }
+
+    fn on_order_rejected(&mut self, order_id: u64, reason: String) -> Vec<OrderRequest> {
+        warn!(
+            "Bot[{}]: order {} rejected: {}",
+            self.strategy.name(),
+            order_id,
+            reason
+        );
+        self.open_orders.remove(&order_id);
+        self.notify(NotificationEvent::Error {
+            message: format!("order {} rejected: {}", order_id, reason),
+        });
+        if let MarketError::PrecisionViolation(_) = MarketError::from_exchange(&reason) {
+            self.precision_rejects += 1;
+            if self.precision_rejects >= Self::PRECISION_REJECTS_BEFORE_REFRESH {
+                info!(
+                    "Bot[{}]: {} precision rejections, re-fetching asset metadata",
+                    self.strategy.name(),
+                    self.precision_rejects
+                );
+                self.precision_rejects = 0;
+                self.commands.push(MarketCommand::RefreshPrecision);
+            }
+        }
+        let orders = self.strategy.on_order_rejected(order_id, &reason);
+        self.forward_events();
+        let orders = self.release_orders(orders);
+        self.publish_status();
+        orders
+    }
+
+    fn on_tick(&mut self, now: u64) -> Vec<OrderRequest> {
+        let orders = self.strategy.on_tick(now);
+        if !orders.is_empty() {
+            info!(
+                "Bot[{}]: strategy returned {} order(s) on tick",
+                self.strategy.name(),
+                orders.len()
+            );
+        }
+        // Also flushes orders held while paused
+        self.forward_events();
+        let orders = self.release_orders(orders);
+        if !orders.is_empty() {
+            self.publish_status();
+        }
+        orders
+    }
+
+    fn on_open_orders(&mut self, open: &[u64]) -> Vec<OrderRequest> {
+        let orders = self.strategy.on_open_orders(open);
+        self.forward_events();
+        let orders = self.release_orders(orders);
+        if !orders.is_empty() {
+            self.publish_status();
+        }
+        orders
+    }
+
+    fn on_book_update(&mut self, _asset: &str, book: &MarketBook) {
+        self.market_book = Some(book.clone());
+    }
+
+    fn on_feed_stalled(&mut self, asset: &str, silent_secs: u64) {
+        warn!(
+            "Bot[{}]: no price for {} in {}s, strategy is idle",
+            self.strategy.name(),
+            asset,
+            silent_secs
+        );
+        self.feed_stalled = true;
+        self.publish_status();
+    }
+
+    fn on_feed_backlog(&mut self, backlog: FeedBacklog) {
+        self.feed_backlog = backlog;
+    }
+
+    fn on_precision_update(&mut self, asset: &str, precision: AssetPrecision) -> Vec<OrderRequest> {
+        let orders = self.strategy.on_precision_update(asset, precision);
+        self.forward_events();
+        let orders = self.release_orders(orders);
+        self.publish_status();
+        orders
+    }
+
+    fn on_reconnect(&mut self) {
+        self.reconnects_total += 1;
+    }
+
+    fn take_commands(&mut self) -> Vec<MarketCommand> {
+        for order_id in self.strategy.drain_cancels() {
+            // A held order is dropped instead, and what it was to replace cancelled
+            let order_id = match self.held_orders.iter().position(|o| o.order_id == order_id) {
+                Some(i) => match self.held_orders.remove(i).replaces {
+                    Some(replaced) => replaced,
+                    None => continue,
+                },
+                None => order_id,
+            };
+            self.open_orders.remove(&order_id);
+            self.commands.push(MarketCommand::Cancel(order_id));
+        }
+        if self.strategy.take_open_orders_request() {
+            self.commands.push(MarketCommand::SyncOpenOrders);
+        }
+        std::mem::take(&mut self.commands)
+    }
+
+    fn on_stop(&mut self) {
+        // A bot stopped on range exit has already said so
+        let notified = self.bot_status == BotStatus::Stopped;
+        info!("Bot[{}]: stopped", self.strategy.name());
+        self.bot_status = BotStatus::Stopped;
+        self.held_orders.clear();
+        self.open_orders.clear();
+        // The market has stopped; there's nowhere to send final orders
+        let _ = self.stop();
+        self.forward_events();
+        if !notified {
+            self.notify(NotificationEvent::Stopped {
+                bot_status: self.bot_status,
+            });
+        }
+        self.persist();
+        self.publish_status();
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::market::AssetPrecision;
+    use crate::strategy::spot_grid::{GridMode, SpotGridStrategy};
     use crate::strategy::NoOpStrategy;
 
+    /// Three-level SOL grid from 100 to 120, one unit per level, started at 110
+    fn test_grid() -> SpotGridStrategy {
+        SpotGridStrategy::new(
+            "SOL-USDC".to_string(),
+            100.0,
+            120.0,
+            3,
+            GridMode::Arithmetic,
+            Some(1.0),
+            None,
+            AssetPrecision::for_perp(2),
+            110.0,
+        )
+    }
+
     #[test]
     fn test_bot_new() {
         let bot = Bot::new(NoOpStrategy);
@@ -231,6 +1015,468 @@ mod tests {
         assert_eq!(json["name"], "noop");
     }
 
+    #[test]
+    fn test_bot_status_reports_increasing_uptime() {
+        let bot = Bot::new(NoOpStrategy);
+
+        let first = bot.status_json();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let second = bot.status_json();
+
+        let uptime = |json: &serde_json::Value| json["custom"]["uptime_secs"].as_f64().unwrap();
+        assert!(uptime(&second) > uptime(&first));
+        assert!(first["custom"]["start_time"].as_u64().unwrap() > 0);
+        assert_eq!(
+            first["custom"]["start_time"],
+            second["custom"]["start_time"]
+        );
+    }
+
+    #[test]
+    fn test_bot_flags_stalled_feed_until_next_price() {
+        let mut bot = Bot::new(NoOpStrategy);
+        assert_eq!(bot.status_json()["custom"]["feed_stalled"], false);
+
+        bot.on_feed_stalled("BTC", 61);
+        assert!(bot.feed_stalled());
+        assert_eq!(bot.status_json()["custom"]["feed_stalled"], true);
+
+        bot.on_price_update("BTC", 50000.0);
+        assert!(!bot.feed_stalled());
+    }
+
+    #[test]
+    fn test_bot_publishes_status_on_events() {
+        let mut bot = Bot::new(NoOpStrategy);
+        let mut updates = bot.subscribe_status();
+
+        bot.on_price_update("BTC", 50000.0);
+        bot.on_order_filled(OrderFill::new(1, "BTC", 1.0, 50000.0));
+
+        for _ in 0..2 {
+            let status: serde_json::Value =
+                serde_json::from_str(&updates.try_recv().unwrap()).unwrap();
+            assert_eq!(status["name"], "noop");
+        }
+        assert!(updates.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_bot_forwards_strategy_events() {
+        let mut bot = Bot::new(test_grid());
+        let mut events = bot.subscribe_events();
+
+        let orders = bot.on_price_update("SOL-USDC", 110.0);
+        let buy = orders.iter().find(|o| o.is_buy()).unwrap().order_id;
+        let sells = bot.on_order_filled(OrderFill::new(buy, "SOL-USDC", 1.0, 100.0));
+        assert!(events.try_recv().is_err());
+
+        bot.on_order_filled(OrderFill::new(sells[0].order_id, "SOL-USDC", 1.0, 110.0));
+        let StrategyEvent::RoundtripClosed(rt) = events.try_recv().unwrap() else {
+            panic!("expected a roundtrip");
+        };
+        assert_eq!(rt.pnl, 10.0);
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_bot_status_follows_range_exit() {
+        let mut bot = Bot::new(test_grid());
+        let mut events = bot.subscribe_events();
+        let orders = bot.on_price_update("SOL-USDC", 110.0);
+        let sell = orders.iter().find(|o| !o.is_buy()).unwrap().order_id;
+
+        // Above the top with both zones sold: the lower one never bought, the upper sold at 120
+        bot.on_price_update("SOL-USDC", 125.0);
+        let orders = bot.on_order_filled(OrderFill::new(sell, "SOL-USDC", 1.0, 120.0));
+        let exited = BotStatus::RangeExited {
+            direction: RangeDirection::Above,
+        };
+        assert_eq!(bot.bot_status(), exited);
+        assert_eq!(
+            bot.status_json()["custom"]["bot_status"]["range_exited"]["direction"],
+            "above"
+        );
+        assert!(matches!(
+            events.try_recv(),
+            Ok(StrategyEvent::RoundtripClosed(_))
+        ));
+        assert!(matches!(
+            events.try_recv(),
+            Ok(StrategyEvent::RangeExited {
+                direction: RangeDirection::Above
+            })
+        ));
+        // Orders still go out, so the grid trades again when price returns
+        assert_eq!(orders.len(), 1);
+
+        bot.on_price_update("SOL-USDC", 118.0);
+        assert_eq!(bot.bot_status(), BotStatus::Running);
+        assert!(matches!(
+            events.try_recv(),
+            Ok(StrategyEvent::RangeReentered)
+        ));
+    }
+
+    #[test]
+    fn test_breakout_flattens_and_stops() {
+        let mut bot = Bot::new(test_grid()).with_flatten_on_range_exit(true);
+        let orders = bot.on_price_update("SOL-USDC", 110.0);
+        let sell = orders.iter().find(|o| !o.is_buy()).unwrap().order_id;
+        assert!(bot.take_commands().is_empty());
+
+        // The breakout sells the top level; its counter-order is dropped
+        bot.on_price_update("SOL-USDC", 125.0);
+        let orders = bot.on_order_filled(OrderFill::new(sell, "SOL-USDC", 1.0, 120.0));
+        assert!(orders.is_empty());
+        assert_eq!(
+            bot.take_commands(),
+            [MarketCommand::Flatten, MarketCommand::Stop]
+        );
+        assert_eq!(bot.bot_status(), BotStatus::Stopped);
+
+        // Coming back into range doesn't restart it
+        assert!(bot.on_price_update("SOL-USDC", 110.0).is_empty());
+        assert_eq!(bot.bot_status(), BotStatus::Stopped);
+    }
+
+    #[test]
+    fn test_range_exit_stop_notifies_once_and_stops_strategy_once() {
+        use crate::bot::notifier::{Notification, NotificationEvent, Notifier};
+        use std::sync::{Arc, Mutex};
+
+        struct Recorder(Arc<Mutex<Vec<NotificationEvent>>>);
+
+        impl Notifier for Recorder {
+            fn notify(&self, notification: &Notification) {
+                self.0.lock().unwrap().push(notification.event.clone());
+            }
+        }
+
+        /// Counts `on_stop` calls
+        struct Stops(SpotGridStrategy, Arc<Mutex<u32>>);
+
+        impl Strategy for Stops {
+            fn on_price_update(&mut self, asset: &str, price: f64) -> Vec<OrderRequest> {
+                self.0.on_price_update(asset, price)
+            }
+
+            fn on_order_filled(&mut self, fill: &OrderFill) -> Vec<OrderRequest> {
+                self.0.on_order_filled(fill)
+            }
+
+            fn on_stop(&mut self) -> Vec<OrderRequest> {
+                *self.1.lock().unwrap() += 1;
+                vec![]
+            }
+
+            fn drain_events(&mut self) -> Vec<StrategyEvent> {
+                self.0.drain_events()
+            }
+
+            fn name(&self) -> &str {
+                "stops"
+            }
+        }
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let stops = Arc::new(Mutex::new(0));
+        let mut bot = Bot::new(Stops(test_grid(), stops.clone()))
+            .with_flatten_on_range_exit(true)
+            .with_notifier(Recorder(events.clone()));
+        let orders = bot.on_price_update("SOL-USDC", 110.0);
+        let sell = orders.iter().find(|o| !o.is_buy()).unwrap().order_id;
+        bot.on_price_update("SOL-USDC", 125.0);
+        bot.on_order_filled(OrderFill::new(sell, "SOL-USDC", 1.0, 120.0));
+
+        // The market carries out the stop, then the runner shuts the bot down
+        MarketListener::on_stop(&mut bot);
+        bot.shutdown();
+
+        assert_eq!(*stops.lock().unwrap(), 1);
+        let stopped: Vec<_> = events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| matches!(e, NotificationEvent::Stopped { .. }))
+            .cloned()
+            .collect();
+        assert_eq!(
+            stopped,
+            [NotificationEvent::Stopped {
+                bot_status: BotStatus::Stopped
+            }]
+        );
+    }
+
+    #[test]
+    fn test_status_line_is_compact_json() {
+        let mut bot = Bot::new(test_grid());
+        bot.on_price_update("SOL-USDC", 110.0);
+
+        let line = bot.status_line();
+        assert!(!line.contains('\n'));
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(json["name"], "spot_grid");
+        assert_eq!(json["asset"], "SOL-USDC");
+        assert_eq!(json["current_price"], 110.0);
+        assert_eq!(json["position"], 1.0);
+        assert_eq!(json["active_orders"], 2);
+        assert!(json["realized_pnl"].is_number());
+        assert_eq!(
+            json["custom"],
+            serde_json::json!({ "bot_status": "running" })
+        );
+        assert!(json.get("book").is_none());
+        assert!(serde_json::from_str::<StrategyStatus>(&line).is_ok());
+    }
+
+    #[test]
+    fn test_bot_status_includes_market_book() {
+        let mut bot = Bot::new(NoOpStrategy);
+        assert!(bot.status_json()["custom"].get("market_book").is_none());
+
+        let book = MarketBook {
+            time: 1,
+            bids: vec![crate::market::PriceLevel {
+                price: 99.0,
+                size: 1.0,
+            }],
+            asks: vec![crate::market::PriceLevel {
+                price: 101.0,
+                size: 2.0,
+            }],
+        };
+        bot.on_book_update("BTC", &book);
+
+        let json = bot.status_json();
+        assert_eq!(json["custom"]["market_book"]["bids"][0]["price"], 99.0);
+        assert_eq!(json["custom"]["market_book"]["asks"][0]["size"], 2.0);
+    }
+
+    #[test]
+    fn test_bot_logs_fill_sides() {
+        let path = std::env::temp_dir().join(format!("bot_trades_{}.csv", uuid::Uuid::new_v4()));
+        let mut bot =
+            Bot::new(TestStrategy::new(true)).with_trade_log(TradeLog::open(&path).unwrap());
+
+        let orders = bot.on_price_update("BTC", 50000.0);
+        assert_eq!(orders.len(), 1);
+        let buy_id = orders[0].order_id;
+        // Partial then final fill of the tracked buy
+        let sell = bot.on_order_filled(OrderFill::new(buy_id, "BTC", 0.4, 50000.0));
+        bot.on_order_filled(OrderFill::new(buy_id, "BTC", 0.6, 50000.0));
+        assert!(!bot.open_orders.contains_key(&buy_id));
+        // The sell the strategy placed in response, then an order the bot never saw
+        bot.on_order_filled(OrderFill::new(sell[0].order_id, "BTC", 0.4, 50500.0));
+        bot.on_order_filled(OrderFill::new(99, "BTC", 1.0, 50000.0));
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let sides: Vec<&str> = contents
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').nth(1).unwrap())
+            .collect();
+        assert_eq!(sides, ["buy", "buy", "sell", ""]);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_shutdown_stops_and_persists_trade_log() {
+        let path = std::env::temp_dir().join(format!("bot_trades_{}.csv", uuid::Uuid::new_v4()));
+        let mut bot =
+            Bot::new(TestStrategy::new(true)).with_trade_log(TradeLog::open(&path).unwrap());
+        let orders = bot.on_price_update("BTC", 50000.0);
+        bot.on_order_filled(OrderFill::new(orders[0].order_id, "BTC", 1.0, 50000.0));
+
+        // The market ended without stopping the bot
+        bot.shutdown();
+        assert_eq!(bot.bot_status(), BotStatus::Stopped);
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().count(), 2);
+        assert!(contents.lines().last().unwrap().contains(",buy,50000,1,"));
+
+        // Again after the market's own stop: nothing else happens
+        bot.shutdown();
+        assert!(bot.on_price_update("BTC", 50000.0).is_empty());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_bot_metrics_counters() {
+        let mut bot = Bot::new(TestStrategy::new(true));
+        bot.on_price_update("BTC", 50000.0);
+        bot.on_price_update("BTC", 50100.0);
+        // Fill one buy completely; the strategy answers with a sell
+        bot.on_order_filled(OrderFill::new(1, "BTC", 1.0, 50000.0));
+        bot.on_reconnect();
+
+        let metrics = bot.metrics();
+        assert_eq!(metrics.strategy, "unnamed_strategy");
+        assert_eq!(metrics.active_buys, 1);
+        assert_eq!(metrics.active_sells, 1);
+        assert_eq!(metrics.fills_total, 1);
+        assert_eq!(metrics.reconnects_total, 1);
+        assert!(metrics.uptime_seconds >= 0.0);
+    }
+
+    #[test]
+    fn test_bot_pause_holds_orders_until_resume() {
+        let mut bot = Bot::new(TestStrategy::new(true));
+        assert!(!bot.resume());
+        assert!(bot.pause());
+        assert!(!bot.pause());
+        assert_eq!(bot.bot_status(), BotStatus::Paused);
+        assert_eq!(bot.status_json()["custom"]["bot_status"], "paused");
+
+        // Fills still reach the strategy, but nothing is placed
+        assert!(bot.on_price_update("BTC", 50000.0).is_empty());
+        assert!(bot
+            .on_order_filled(OrderFill::new(1, "BTC", 1.0, 50000.0))
+            .is_empty());
+        assert_eq!(bot.metrics().fills_total, 1);
+
+        assert!(bot.resume());
+        let orders = bot.on_price_update("BTC", 50100.0);
+        let ids: Vec<u64> = orders.iter().map(|o| o.order_id).collect();
+        assert_eq!(ids, [1, 2, 3]);
+        assert_eq!(bot.metrics().active_buys, 2);
+    }
+
+    #[test]
+    fn test_cancels_and_replaces_while_paused_apply_to_held_orders() {
+        /// Returns the orders and cancels it's scripted with
+        #[derive(Default)]
+        struct Scripted {
+            orders: Vec<OrderRequest>,
+            cancels: Vec<u64>,
+        }
+
+        impl Strategy for Scripted {
+            fn on_price_update(&mut self, _asset: &str, _price: f64) -> Vec<OrderRequest> {
+                std::mem::take(&mut self.orders)
+            }
+
+            fn on_order_filled(&mut self, _fill: &OrderFill) -> Vec<OrderRequest> {
+                vec![]
+            }
+
+            fn drain_cancels(&mut self) -> Vec<u64> {
+                std::mem::take(&mut self.cancels)
+            }
+
+            fn name(&self) -> &str {
+                "scripted"
+            }
+        }
+
+        let buy = |id| OrderRequest::buy(id, "BTC", 1.0, 100.0);
+        let mut bot = Bot::new(Scripted::default());
+        bot.strategy_mut().orders = vec![buy(1)];
+        assert_eq!(bot.on_price_update("BTC", 100.0).len(), 1);
+
+        // 2 replaces the resting 1, then 4 replaces the held 2; 3 is cancelled
+        assert!(bot.pause());
+        bot.strategy_mut().orders = vec![buy(2).replacing(1), buy(3)];
+        bot.on_price_update("BTC", 100.0);
+        bot.strategy_mut().orders = vec![buy(4).replacing(2)];
+        bot.strategy_mut().cancels = vec![3];
+        bot.on_price_update("BTC", 100.0);
+        assert!(bot.take_commands().is_empty());
+
+        assert!(bot.resume());
+        let orders = bot.on_price_update("BTC", 100.0);
+        let released: Vec<_> = orders.iter().map(|o| (o.order_id, o.replaces)).collect();
+        assert_eq!(released, [(4, Some(1))]);
+
+        // Cancelling a held replacement cancels the order it was to replace
+        assert!(bot.pause());
+        bot.strategy_mut().orders = vec![buy(5).replacing(4)];
+        bot.on_price_update("BTC", 100.0);
+        bot.strategy_mut().cancels = vec![5];
+        assert_eq!(bot.take_commands(), [MarketCommand::Cancel(4)]);
+        assert!(bot.resume());
+        assert!(bot.on_price_update("BTC", 100.0).is_empty());
+
+        // A stopped bot can't be flattened
+        bot.shutdown();
+        assert!(!bot.flatten());
+        assert!(bot.take_commands().is_empty());
+    }
+
+    #[test]
+    fn test_resume_restores_range_exited() {
+        let mut bot = Bot::new(test_grid());
+        let orders = bot.on_price_update("SOL-USDC", 110.0);
+        let sell = orders.iter().find(|o| !o.is_buy()).unwrap().order_id;
+        bot.on_price_update("SOL-USDC", 125.0);
+        bot.on_order_filled(OrderFill::new(sell, "SOL-USDC", 1.0, 120.0));
+        let exited = BotStatus::RangeExited {
+            direction: RangeDirection::Above,
+        };
+        assert_eq!(bot.bot_status(), exited);
+
+        assert!(bot.pause());
+        assert!(bot.resume());
+        assert_eq!(bot.bot_status(), exited);
+
+        // Price comes back while paused: resuming finds it running again
+        assert!(bot.pause());
+        bot.on_price_update("SOL-USDC", 118.0);
+        assert_eq!(bot.bot_status(), BotStatus::Paused);
+        assert!(bot.resume());
+        assert_eq!(bot.bot_status(), BotStatus::Running);
+    }
+
+    #[test]
+    fn test_bot_flatten_queues_command() {
+        let mut bot = Bot::new(TestStrategy::new(true));
+        bot.on_price_update("BTC", 50000.0);
+        assert!(bot.take_commands().is_empty());
+
+        assert!(bot.flatten());
+        assert!(!bot.flatten());
+        assert!(!bot.resume());
+        assert_eq!(bot.take_commands(), [MarketCommand::Flatten]);
+        assert!(bot.take_commands().is_empty());
+
+        // Strategy output is dropped from now on
+        assert!(bot.on_price_update("BTC", 49000.0).is_empty());
+        assert_eq!(bot.metrics().active_buys, 0);
+        assert_eq!(bot.status_json()["custom"]["bot_status"], "flattened");
+    }
+
+    #[test]
+    fn test_bot_refreshes_precision_after_repeated_rejections() {
+        let mut bot = Bot::new(NoOpStrategy);
+        bot.on_order_rejected(1, "Insufficient margin to place order.".to_string());
+        bot.on_order_rejected(2, "Price must be divisible by tick size.".to_string());
+        bot.on_order_rejected(3, "Order has invalid size.".to_string());
+        assert!(bot.take_commands().is_empty());
+
+        bot.on_order_rejected(4, "Order has invalid price.".to_string());
+        assert_eq!(bot.take_commands(), [MarketCommand::RefreshPrecision]);
+
+        // The count starts over after each refresh
+        bot.on_order_rejected(5, "Order has invalid price.".to_string());
+        assert!(bot.take_commands().is_empty());
+    }
+
+    #[test]
+    fn test_bot_on_stop_drops_later_orders() {
+        let mut bot = Bot::new(TestStrategy::new(true));
+        assert_eq!(bot.on_price_update("BTC", 50000.0).len(), 1);
+
+        bot.on_stop();
+
+        assert_eq!(bot.bot_status(), BotStatus::Stopped);
+        assert!(bot.on_price_update("BTC", 49000.0).is_empty());
+        assert_eq!(bot.metrics().active_buys, 0);
+        assert_eq!(bot.status_json()["custom"]["bot_status"], "stopped");
+    }
+
     #[test]
     fn test_bot_render_dashboard() {
         let bot = Bot::new(NoOpStrategy);
@@ -285,4 +1531,123 @@ mod tests {
         assert!((status.net_profit() - 99.0).abs() < 0.001);
         assert_eq!(status.custom["custom_field"], "test_value");
     }
+
+    // Strategy holding one unit bought at 100 out of a 100 investment
+    struct HoldStrategy {
+        price: f64,
+    }
+
+    impl Strategy for HoldStrategy {
+        fn on_price_update(&mut self, _asset: &str, price: f64) -> Vec<OrderRequest> {
+            self.price = price;
+            vec![]
+        }
+
+        fn on_order_filled(&mut self, _fill: &OrderFill) -> Vec<OrderRequest> {
+            vec![]
+        }
+
+        fn status(&self) -> StrategyStatus {
+            StrategyStatus::new("hold", "BTC")
+                .with_position(1.0)
+                .with_pnl(0.0, self.price - 100.0, 0.0)
+                .with_investment(100.0)
+        }
+    }
+
+    #[test]
+    fn test_price_decline_tracks_drawdown_and_alerts_once() {
+        let mut bot = Bot::new(HoldStrategy { price: 100.0 }).with_drawdown_alert(10.0);
+        let mut events = bot.subscribe_events();
+
+        bot.on_price_update("BTC", 110.0);
+        // Down 15 from a peak equity of 110: 13.6%
+        bot.on_price_update("BTC", 95.0);
+        let status = bot.status();
+        assert!((status.current_drawdown - 15.0).abs() < 1e-9);
+        assert!((status.max_drawdown - 15.0).abs() < 1e-9);
+        assert!((status.drawdown_pct().unwrap() - 15.0 / 110.0 * 100.0).abs() < 1e-9);
+        let Ok(StrategyEvent::DrawdownExceeded {
+            drawdown,
+            drawdown_pct,
+        }) = events.try_recv()
+        else {
+            panic!("expected a drawdown alert");
+        };
+        assert!((drawdown - 15.0).abs() < 1e-9);
+        assert!(drawdown_pct > 10.0);
+
+        // Deeper in the same drawdown: no second alert
+        bot.on_price_update("BTC", 90.0);
+        assert!(events.try_recv().is_err());
+
+        // Partial recovery re-arms it; the maximum is kept
+        bot.on_price_update("BTC", 105.0);
+        let status = bot.status();
+        assert!((status.current_drawdown - 5.0).abs() < 1e-9);
+        assert!((status.max_drawdown - 20.0).abs() < 1e-9);
+        bot.on_price_update("BTC", 95.0);
+        assert!(matches!(
+            events.try_recv(),
+            Ok(StrategyEvent::DrawdownExceeded { .. })
+        ));
+
+        // A new high resets the current drawdown
+        bot.on_price_update("BTC", 120.0);
+        assert_eq!(bot.status().current_drawdown, 0.0);
+    }
+
+    // Strategy that audits its own orders against the exchange
+    #[derive(Default)]
+    struct AuditStrategy {
+        context: Option<StrategyContext>,
+    }
+
+    impl Strategy for AuditStrategy {
+        fn on_price_update(&mut self, _asset: &str, _price: f64) -> Vec<OrderRequest> {
+            vec![]
+        }
+
+        fn on_order_filled(&mut self, _fill: &OrderFill) -> Vec<OrderRequest> {
+            vec![]
+        }
+
+        fn set_context(&mut self, context: StrategyContext) {
+            self.context = Some(context);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_strategy_queries_exchange_through_context() {
+        use axum::{routing::post, Json, Router};
+        use serde_json::{json, Value};
+
+        async fn info(Json(body): Json<Value>) -> Json<Value> {
+            assert_eq!(body["type"], "openOrders");
+            Json(json!([{
+                "coin": "BTC", "limitPx": "49000.0", "oid": 7, "side": "B",
+                "sz": "0.1", "timestamp": 0, "cloid": null
+            }]))
+        }
+        let app = Router::new().route("/info", post(info));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let mut info_client = crate::InfoClient::new(None, None)
+            .await
+            .unwrap()
+            .with_rate_limiter(None);
+        info_client.http_client.base_url = format!("http://{addr}");
+        let context = StrategyContext::new(std::sync::Arc::new(info_client), Default::default());
+
+        // Strategies that don't care are unaffected
+        let _ = Bot::new(NoOpStrategy).with_context(context.clone());
+
+        let bot = Bot::new(AuditStrategy::default()).with_context(context);
+        let context = bot.strategy().context.as_ref().unwrap();
+        let orders = context.info().open_orders(context.user).await.unwrap();
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].oid, 7);
+    }
 }
@@ -1,6 +1,29 @@
 use crate::strategy::StrategyStatus;
 
+/// How often the dashboard refreshes and which candles it charts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DashboardView {
+    /// Seconds between status polls while the live socket is down
+    pub refresh_secs: u64,
+    /// Candle interval of the price chart (e.g. "15m", "1h")
+    pub candle_interval: String,
+}
+
+impl Default for DashboardView {
+    fn default() -> Self {
+        Self {
+            refresh_secs: 1,
+            candle_interval: "15m".to_string(),
+        }
+    }
+}
+
 pub fn render_dashboard(status: &StrategyStatus) -> String {
+    render_dashboard_with(status, &DashboardView::default())
+}
+
+/// Render the dashboard with the given refresh and candle settings
+pub fn render_dashboard_with(status: &StrategyStatus, view: &DashboardView) -> String {
     // defaults
     let p_dec = status
         .custom
@@ -321,14 +344,18 @@ pub fn render_dashboard(status: &StrategyStatus) -> String {
                 <!-- Branding Header -->
                 <div style="grid-column: 1 / -1; display: flex; justify-content: space-between; align-items: center; margin-bottom: 10px; padding-bottom: 10px; border-bottom: 1px solid var(--border);">
                     <div class="brand" style="font-size: 16px;"><span>HELIX</span> // {name} <span class="stat-label"> // {grid_type}</span></div>
+                    <div class="stat-label">Uptime: <span id="disp_uptime">--</span> | Updated <span id="disp_updated">--</span></div>
                     <div class="stat-value" style="color: var(--brand); font-size: 16px;">{asset}</div>
                 </div>
 
+                <!-- Shown while price is outside the grid range -->
+                <div id="rangeBanner" style="grid-column: 1 / -1; display: none; padding: 8px 12px; margin-bottom: 10px; border-radius: 4px; background: var(--sell-bg); color: var(--sell); font-weight: 600;"></div>
+
                 <!-- Row 1: Total Profit & Invested Margin -->
                 <div class="stat-group row-large">
                     <div class="stat-label">Total Profit ({quote_asset})</div>
                     <div class="stat-value" id="disp_total_profit">--</div>
-                    <!-- <div class="stat-sub val-green" id="disp_total_profit_pct">--%</div> -->
+                    <div class="stat-sub" id="disp_roi">ROI --</div>
                 </div>
                  <div class="stat-group"></div> <!-- Spacer -->
                 <div class="stat-group row-large" style="text-align: right;">
@@ -405,6 +432,16 @@ pub fn render_dashboard(status: &StrategyStatus) -> String {
                  </div>
             </div>
 
+            <!-- Venue L2 Book (only shown when the market streams it) -->
+            <div id="marketBookPanel" class="book-scroll-area" style="display: none; border-top: 1px solid var(--border);">
+                <div class="clob-header">
+                    <div class="col">Market Price</div>
+                    <div class="col">Size ({base_asset})</div>
+                    <div class="col" style="text-align: right">Size ({quote_asset})</div>
+                </div>
+                <div id="marketBookContainer" class="book-container"></div>
+            </div>
+
             
         </div>
     </div>
@@ -431,6 +468,7 @@ pub fn render_dashboard(status: &StrategyStatus) -> String {
                 </tbody>
             </table>
             
+            <div id="roundtripStats" style="display: none; padding: 8px 12px; color: var(--text-secondary);"></div>
             <table class="trades-table" id="roundtripTable" style="display: none;">
                 <thead>
                     <tr>
@@ -454,7 +492,20 @@ pub fn render_dashboard(status: &StrategyStatus) -> String {
         // Init with safe defaults
         let P_DEC = {p_dec};
         let S_DEC = {s_dec};
+        const REFRESH_MS = {refresh_ms};
+        const CANDLE_INTERVAL = '{candle_interval}';
         let firstLoad = true;
+
+        // Token from the page URL, forwarded to the API when the server requires one
+        const AUTH_TOKEN = new URLSearchParams(location.search).get('token');
+        // API paths are relative to the page, which sits under /bots/<n>/ when
+        // the server runs several bots
+        const BASE = location.pathname.endsWith('/') ? location.pathname : location.pathname + '/';
+        function withToken(url) {{
+            url = url.startsWith('/') ? BASE + url.slice(1) : url;
+            if (!AUTH_TOKEN) return url;
+            return url + (url.includes('?') ? '&' : '?') + 'token=' + encodeURIComponent(AUTH_TOKEN);
+        }}
         
         let chart;
         let candleSeries;
@@ -465,10 +516,12 @@ pub fn render_dashboard(status: &StrategyStatus) -> String {
 
 
 
-        async function updateDashboard() {{
+        async function renderStatus(data) {{
+            lastStatusAt = Date.now();
             try {{
-                const res = await fetch('/api/status');
-                const data = await res.json();
+                if (typeof data.custom.uptime_secs === 'number') {{
+                    uptimeSecs = data.custom.uptime_secs;
+                }}
                 
                 // Update Precision
                 if (data.custom.asset_precision) {{
@@ -542,9 +595,9 @@ pub fn render_dashboard(status: &StrategyStatus) -> String {
                             candleStartTime = now - (24 * 60 * 60 * 1000);
                         }}
                         
-                        const url = `/api/candles?coin=${{encodeURIComponent(coin)}}&interval=15m&start=${{candleStartTime}}&end=${{now}}`;
+                        const url = `/api/candles?coin=${{encodeURIComponent(coin)}}&interval=${{encodeURIComponent(CANDLE_INTERVAL)}}&start=${{candleStartTime}}&end=${{now}}`;
                         
-                        const cRes = await fetch(url);
+                        const cRes = await fetch(withToken(url));
                         if (!cRes.ok) {{ throw new Error("HTTP " + cRes.status); }}
                         const candles = await cRes.json();
                         
@@ -598,12 +651,12 @@ pub fn render_dashboard(status: &StrategyStatus) -> String {
                     let currentPrice = null;
                     
                     // Try to get current price from data
-                    if (data.custom && data.custom.current_price && data.custom.current_price > 0) {{
-                        currentPrice = data.custom.current_price;
+                    if (data.current_price && data.current_price > 0) {{
+                        currentPrice = data.current_price;
                     }}
                     // Fallback: calculate mid-price from order book
-                    else if (data.custom && data.custom.book) {{
-                        const book = data.custom.book;
+                    else if (data.book) {{
+                        const book = data.book;
                         if (book.asks && book.asks.length > 0 && book.bids && book.bids.length > 0) {{
                             const bestAsk = book.asks[book.asks.length - 1].price;
                             const bestBid = book.bids[0].price;
@@ -700,7 +753,7 @@ pub fn render_dashboard(status: &StrategyStatus) -> String {
                 }}
                 
                 // --- Draw Grid Level Lines ---
-                if (candleSeries && data.custom && data.custom.book) {{
+                if (candleSeries && data.book) {{
                     // Clear existing price lines (if any)
                     if (!window.gridPriceLines) {{
                         window.gridPriceLines = [];
@@ -712,7 +765,7 @@ pub fn render_dashboard(status: &StrategyStatus) -> String {
                     }});
                     window.gridPriceLines = [];
                     
-                    const book = data.custom.book;
+                    const book = data.book;
                     
                     // Draw Buy levels (green)
                     if (book.bids && Array.isArray(book.bids)) {{
@@ -774,13 +827,20 @@ pub fn render_dashboard(status: &StrategyStatus) -> String {
                 
                 // SET VALUES
                 elText('disp_qty_order', fmt(data.custom.qty_order, S_DEC));
-                elText('disp_trade_count', data.custom.total_roundtrips || 0);
-                elText('disp_last_price', fmt(data.custom.current_price, P_DEC));
+                elText('disp_trade_count', data.trade_count || 0);
+                elText('disp_last_price', fmt(data.current_price, P_DEC));
                 
                 elText('disp_total_profit', fmt(totalProfit));
                 elColor('disp_total_profit', totalProfit);
                 
                 elText('disp_invested', fmt(invested));
+
+                // ROI: net realized profit over the configured investment
+                if (data.total_investment > 0) {{
+                    const roi = (matchedPnl - fees) / data.total_investment * 100;
+                    elText('disp_roi', 'ROI ' + roi.toFixed(2) + '%');
+                    elColor('disp_roi', roi);
+                }}
                 
                 elText('disp_matched_pnl', fmt(matchedPnl));
                 // elColor('disp_matched_pnl', matchedPnl); // Always green implied by design? or dynamic? layout says green. dynamic serves better.
@@ -789,14 +849,28 @@ pub fn render_dashboard(status: &StrategyStatus) -> String {
                 elColor('disp_unmatched_pnl', unmatchedPnl);
                 
                 elText('disp_funding', fmt(fees));
-                elText('disp_trade_count', data.custom.total_roundtrips || 0);
+                elText('disp_trade_count', data.trade_count || 0);
                 
                 // Last Price
-                const lp = data.custom.current_price || 0;
-                elText('disp_last_price', lp.toFixed(P_DEC));
+                const lp = data.current_price || 0;
+                const stalled = data.custom.feed_stalled === true;
+                elText('disp_last_price', lp.toFixed(P_DEC) + (stalled ? ' (feed stalled)' : ''));
+                document.getElementById('disp_last_price').style.color = stalled ? 'var(--sell)' : '#fff';
+
+                // Range exit: every level waits on one side until price returns
+                const rangeExit = data.custom.bot_status && data.custom.bot_status.range_exited;
+                const banner = document.getElementById('rangeBanner');
+                if (rangeExit) {{
+                    const sold = rangeExit.direction === 'above';
+                    banner.textContent = 'Price left the range ' + rangeExit.direction + ': ' +
+                        (sold ? 'every level has sold, waiting to buy back' : 'every level has bought, waiting to sell');
+                    banner.style.display = 'block';
+                }} else {{
+                    banner.style.display = 'none';
+                }}
 
                 // --- 2. Render Order Book (Sidebar) ---
-                const book = data.custom.book;
+                const book = data.book;
                 const container = document.getElementById('bookContainer');
                 
                 if (!book) {{
@@ -827,7 +901,7 @@ pub fn render_dashboard(status: &StrategyStatus) -> String {
                     }}
 
                     // Spread & Current Price
-                    let currentPrice = data.custom.current_price || 0;
+                    let currentPrice = data.current_price || 0;
                     let spreadHtml = '<div class="col" style="color: var(--text-secondary);">--</div>'; // Default to --
 
                     // Try to calc spread if we have both sides
@@ -892,6 +966,36 @@ pub fn render_dashboard(status: &StrategyStatus) -> String {
                     }}
                 }}
                 
+                // --- 2b. Render Venue L2 Book (Sidebar) ---
+                const marketBook = data.custom.market_book;
+                const marketBookPanel = document.getElementById('marketBookPanel');
+                if (!marketBook) {{
+                    marketBookPanel.style.display = 'none';
+                }} else {{
+                    const levelRow = (lvl, cls) => `<div class="row">
+                        <div class="col ${{cls}}">${{lvl.price.toFixed(P_DEC)}}</div>
+                        <div class="col">${{lvl.size.toFixed(S_DEC)}}</div>
+                        <div class="col" style="text-align: right">${{(lvl.price * lvl.size).toFixed(2)}}</div>
+                    </div>`;
+
+                    let mbHtml = '';
+                    // Asks are stored best-first; show them with the best ask nearest the spread
+                    for (const ask of [...marketBook.asks].reverse()) {{
+                        mbHtml += levelRow(ask, 'ask-price');
+                    }}
+                    if (marketBook.asks.length > 0 && marketBook.bids.length > 0) {{
+                        const spread = marketBook.asks[0].price - marketBook.bids[0].price;
+                        mbHtml += `<div class="spread-row">
+                            <div class="col" style="color: var(--text-secondary);">Spread ${{spread.toFixed(P_DEC)}}</div>
+                        </div>`;
+                    }}
+                    for (const bid of marketBook.bids) {{
+                        mbHtml += levelRow(bid, 'bid-price');
+                    }}
+                    document.getElementById('marketBookContainer').innerHTML = mbHtml;
+                    marketBookPanel.style.display = 'block';
+                }}
+
                 // --- 3. Render Trades (Sidebar & Bottom Panel) ---
                 const trades = data.custom.recent_trades || [];
 
@@ -950,10 +1054,39 @@ pub fn render_dashboard(status: &StrategyStatus) -> String {
                     rtBody.innerHTML = rtHtml;
                 }}
 
+                const stats = data.custom.roundtrip_stats;
+                const statsEl = document.getElementById('roundtripStats');
+                if (stats && statsEl) {{
+                    statsEl.textContent = `Win rate ${{(stats.win_rate * 100).toFixed(1)}}% (${{stats.wins}}W / ${{stats.losses}}L)`
+                        + ` | Avg PnL ${{stats.avg_pnl.toFixed(4)}} | Avg fees ${{stats.avg_fees.toFixed(4)}}`
+                        + ` | Avg net ${{stats.avg_net_pnl.toFixed(4)}} | Best ${{stats.best.toFixed(4)}} | Worst ${{stats.worst.toFixed(4)}}`;
+                }}
+
+            }} catch (e) {{
+                console.error("Render error:", e);
+            }}
+        }}
+
+        async function updateDashboard() {{
+            try {{
+                const res = await fetch(withToken('/api/status'));
+                await renderStatus(await res.json());
             }} catch (e) {{
                 console.error("Fetch error:", e);
             }}
         }}
+
+        // Live updates pushed by the bot on every fill and price update
+        let liveSocket = null;
+        function connectLive() {{
+            const proto = location.protocol === 'https:' ? 'wss' : 'ws';
+            liveSocket = new WebSocket(`${{proto}}://${{location.host}}` + withToken('/ws'));
+            liveSocket.onmessage = (event) => renderStatus(JSON.parse(event.data));
+            liveSocket.onclose = () => {{
+                liveSocket = null;
+                setTimeout(connectLive, 3000);
+            }};
+        }}
         
         // Tab Switching for Bottom Panel
         function switchBottomTab(tabName) {{
@@ -962,15 +1095,49 @@ pub fn render_dashboard(status: &StrategyStatus) -> String {
                 document.querySelector('.bottom-panel .panel-tab:nth-child(1)').classList.add('active');
                 document.getElementById('historyTable').style.display = 'table';
                 document.getElementById('roundtripTable').style.display = 'none';
+                document.getElementById('roundtripStats').style.display = 'none';
             }} else {{
                 document.querySelector('.bottom-panel .panel-tab:nth-child(2)').classList.add('active');
                 document.getElementById('historyTable').style.display = 'none';
                 document.getElementById('roundtripTable').style.display = 'table';
+                document.getElementById('roundtripStats').style.display = 'block';
             }}
         }}
 
-        setInterval(updateDashboard, 1000);
+        // Uptime and staleness, ticking between status updates
+        const STALE_AFTER_SECS = 10;
+        let lastStatusAt = null;
+        let uptimeSecs = null;
+        function formatDuration(secs) {{
+            secs = Math.floor(secs);
+            const d = Math.floor(secs / 86400);
+            const h = Math.floor(secs % 86400 / 3600);
+            const m = Math.floor(secs % 3600 / 60);
+            const s = secs % 60;
+            if (d > 0) return `${{d}}d ${{h}}h ${{m}}m`;
+            if (h > 0) return `${{h}}h ${{m}}m ${{s}}s`;
+            return m > 0 ? `${{m}}m ${{s}}s` : `${{s}}s`;
+        }}
+        function renderClock() {{
+            if (lastStatusAt === null) return;
+            const ago = (Date.now() - lastStatusAt) / 1000;
+            if (uptimeSecs !== null) {{
+                document.getElementById('disp_uptime').textContent = formatDuration(uptimeSecs + ago);
+            }}
+            const updated = document.getElementById('disp_updated');
+            updated.textContent = `${{Math.floor(ago)}}s ago`;
+            updated.className = ago > STALE_AFTER_SECS ? 'val-red' : '';
+        }}
+        setInterval(renderClock, 1000);
+
+        // Fall back to polling while the live socket is down
+        setInterval(() => {{
+            if (!liveSocket || liveSocket.readyState !== WebSocket.OPEN) {{
+                updateDashboard();
+            }}
+        }}, REFRESH_MS);
         updateDashboard();
+        connectLive();
     </script>
 </body>
         "##,
@@ -983,6 +1150,58 @@ pub fn render_dashboard(status: &StrategyStatus) -> String {
         query_base_asset = base_asset, // Hack for {base_asset} re-use
         p_dec = p_dec,
         s_dec = s_dec,
-        grid_type = grid_type
+        grid_type = grid_type,
+        refresh_ms = view.refresh_secs.max(1) * 1000,
+        candle_interval = view.candle_interval,
+    )
+}
+
+/// Index page listing every bot a multi-strategy server runs
+pub fn render_bot_list(statuses: &[StrategyStatus]) -> String {
+    let rows: String = statuses
+        .iter()
+        .enumerate()
+        .map(|(index, status)| {
+            format!(
+                "<tr><td><a href=\"bots/{index}/\">{asset}</a></td><td>{name}</td>\
+                 <td>{state}</td><td>{price}</td><td>{position}</td><td>{pnl:.2}</td></tr>",
+                index = index,
+                asset = status.asset,
+                name = status.name,
+                state = status.status,
+                price = status.current_price,
+                position = status.position,
+                pnl = status.net_profit(),
+            )
+        })
+        .collect();
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta http-equiv="refresh" content="5">
+    <title>Bots</title>
+    <style>
+        body {{ font-family: sans-serif; background: #0b0e11; color: #eaecef; }}
+        table {{ border-collapse: collapse; }}
+        th, td {{ padding: 6px 14px; text-align: left; border-bottom: 1px solid #2b3139; }}
+        a {{ color: #f0b90b; }}
+    </style>
+</head>
+<body>
+    <h2>{count} bots</h2>
+    <table>
+        <tr><th>Asset</th><th>Strategy</th><th>Status</th><th>Price</th><th>Position</th><th>Net PnL</th></tr>
+        {rows}
+    </table>
+    <script>
+        // Keep the dashboard token on the links to each bot
+        for (const link of document.querySelectorAll('a')) link.href += location.search;
+    </script>
+</body>
+</html>"#,
+        count = statuses.len(),
+        rows = rows,
     )
 }
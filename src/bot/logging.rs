@@ -0,0 +1,173 @@
+//! Log formatting for bots
+//!
+//! Every line is tagged with the bot's asset and strategy, so logs of several
+//! bots can be merged and still told apart. Colors are only written to a
+//! terminal; ANSI codes embedded in messages are stripped everywhere else.
+//! The JSON format writes one object per line for machine parsing.
+
+use std::io::{IsTerminal, Write};
+
+use log::{Level, Record};
+use serde_json::json;
+
+/// Identifies the bot a log line came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogContext {
+    /// Traded asset (e.g., "HYPE/USDC")
+    pub asset: String,
+    /// Strategy type name (e.g., "spot_grid")
+    pub strategy: String,
+}
+
+impl LogContext {
+    pub fn new(asset: impl Into<String>, strategy: impl Into<String>) -> Self {
+        Self {
+            asset: asset.into(),
+            strategy: strategy.into(),
+        }
+    }
+
+    /// One human-readable line
+    ///
+    /// With `color` off, any ANSI escape codes in `message` are removed.
+    pub fn format_text(
+        &self,
+        timestamp: &str,
+        level: Level,
+        target: &str,
+        message: &str,
+        color: bool,
+    ) -> String {
+        if color {
+            format!(
+                "{} {}{:<5}\x1b[0m [{} {}] {}: {}",
+                timestamp,
+                level_color(level),
+                level,
+                self.asset,
+                self.strategy,
+                target,
+                message
+            )
+        } else {
+            format!(
+                "{} {:<5} [{} {}] {}: {}",
+                timestamp,
+                level,
+                self.asset,
+                self.strategy,
+                target,
+                strip_ansi(message)
+            )
+        }
+    }
+
+    /// One JSON object with the context as separate fields
+    pub fn format_json(
+        &self,
+        timestamp: &str,
+        level: Level,
+        target: &str,
+        message: &str,
+    ) -> String {
+        json!({
+            "ts": timestamp,
+            "level": level.as_str(),
+            "asset": self.asset,
+            "strategy": self.strategy,
+            "target": target,
+            "msg": strip_ansi(message),
+        })
+        .to_string()
+    }
+
+    /// Install the global logger, writing to stderr at `RUST_LOG`'s level
+    ///
+    /// Does nothing if a logger is already installed.
+    pub fn init(self, json: bool) {
+        let color = !json && std::io::stderr().is_terminal();
+        env_logger::Builder::from_default_env()
+            .format(move |buf, record: &Record| {
+                let timestamp =
+                    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+                let message = record.args().to_string();
+                let line = if json {
+                    self.format_json(&timestamp, record.level(), record.target(), &message)
+                } else {
+                    self.format_text(&timestamp, record.level(), record.target(), &message, color)
+                };
+                writeln!(buf, "{}", line)
+            })
+            .try_init()
+            .ok();
+    }
+}
+
+fn level_color(level: Level) -> &'static str {
+    match level {
+        Level::Error => "\x1b[31m",
+        Level::Warn => "\x1b[33m",
+        Level::Info => "\x1b[32m",
+        Level::Debug => "\x1b[34m",
+        Level::Trace => "\x1b[35m",
+    }
+}
+
+/// Remove ANSI escape sequences (`ESC [ ... letter`)
+pub fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        if chars.peek() == Some(&'[') {
+            chars.next();
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MESSAGE: &str = "\x1b[32mZone 03 | BUY  | 10.5 | 2.0   <<< BOUGHT @ Lower\x1b[0m";
+
+    #[test]
+    fn test_plain_text_omits_color_codes() {
+        let ctx = LogContext::new("HYPE/USDC", "spot_grid");
+        let line = ctx.format_text("t", Level::Info, "grid", MESSAGE, false);
+
+        assert!(!line.contains('\x1b'));
+        assert_eq!(
+            line,
+            "t INFO  [HYPE/USDC spot_grid] grid: Zone 03 | BUY  | 10.5 | 2.0   <<< BOUGHT @ Lower"
+        );
+
+        // A terminal keeps them
+        let colored = ctx.format_text("t", Level::Info, "grid", MESSAGE, true);
+        assert!(colored.contains("\x1b[32m"));
+    }
+
+    #[test]
+    fn test_json_has_context_fields() {
+        let ctx = LogContext::new("BTC", "dca");
+        let line = ctx.format_json("t", Level::Warn, "dca", MESSAGE);
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+
+        assert_eq!(value["asset"], "BTC");
+        assert_eq!(value["strategy"], "dca");
+        assert_eq!(value["level"], "WARN");
+        assert_eq!(
+            value["msg"],
+            "Zone 03 | BUY  | 10.5 | 2.0   <<< BOUGHT @ Lower"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! Prometheus text exposition of bot metrics
+
+use std::fmt::Write;
+
+use crate::strategy::StrategyStatus;
+
+/// Snapshot of the values exported on `/metrics`
+#[derive(Debug, Clone, Default)]
+pub struct BotMetrics {
+    pub strategy: String,
+    pub asset: String,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub position: f64,
+    pub active_buys: usize,
+    pub active_sells: usize,
+    pub fills_total: u64,
+    pub reconnects_total: u64,
+    pub feed_queue_depth: usize,
+    pub feed_dropped_total: u64,
+    pub uptime_seconds: f64,
+}
+
+impl BotMetrics {
+    /// Take the PnL and position gauges from a strategy status
+    pub fn from_status(status: &StrategyStatus) -> Self {
+        Self {
+            strategy: status.name.clone(),
+            asset: status.asset.clone(),
+            realized_pnl: status.realized_pnl,
+            unrealized_pnl: status.unrealized_pnl,
+            position: status.position,
+            ..Default::default()
+        }
+    }
+
+    /// Render in the Prometheus text exposition format (version 0.0.4)
+    pub fn render(&self) -> String {
+        let labels = format!(
+            "strategy=\"{}\",asset=\"{}\"",
+            escape_label(&self.strategy),
+            escape_label(&self.asset)
+        );
+        let metrics: [(&str, &str, &str, f64); 10] = [
+            (
+                "realized_pnl",
+                "gauge",
+                "Realized PnL in quote currency",
+                self.realized_pnl,
+            ),
+            (
+                "unrealized_pnl",
+                "gauge",
+                "Unrealized PnL in quote currency",
+                self.unrealized_pnl,
+            ),
+            (
+                "position",
+                "gauge",
+                "Current position in base units",
+                self.position,
+            ),
+            (
+                "active_buys",
+                "gauge",
+                "Resting buy orders",
+                self.active_buys as f64,
+            ),
+            (
+                "active_sells",
+                "gauge",
+                "Resting sell orders",
+                self.active_sells as f64,
+            ),
+            (
+                "fills_total",
+                "counter",
+                "Fills processed since start",
+                self.fills_total as f64,
+            ),
+            (
+                "reconnects_total",
+                "counter",
+                "WebSocket reconnects since start",
+                self.reconnects_total as f64,
+            ),
+            (
+                "feed_queue_depth",
+                "gauge",
+                "WebSocket messages waiting for the event loop",
+                self.feed_queue_depth as f64,
+            ),
+            (
+                "feed_dropped_total",
+                "counter",
+                "Stale price updates skipped to keep up with the feed",
+                self.feed_dropped_total as f64,
+            ),
+            (
+                "uptime_seconds",
+                "gauge",
+                "Seconds since the bot started",
+                self.uptime_seconds,
+            ),
+        ];
+
+        let mut out = String::new();
+        for (name, kind, help, value) in metrics {
+            // Writing to a String cannot fail
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+            let _ = writeln!(out, "{name}{{{labels}}} {value}");
+        }
+        out
+    }
+}
+
+/// Escape a label value per the exposition format
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_exposition_format() {
+        let metrics = BotMetrics {
+            strategy: "grid".to_string(),
+            asset: "HYPE/USDC".to_string(),
+            realized_pnl: 12.5,
+            position: -0.25,
+            active_buys: 3,
+            active_sells: 2,
+            fills_total: 7,
+            reconnects_total: 1,
+            uptime_seconds: 42.0,
+            ..Default::default()
+        };
+        let text = metrics.render();
+
+        let mut samples = std::collections::HashMap::new();
+        for line in text.lines() {
+            if let Some(comment) = line.strip_prefix("# ") {
+                let mut parts = comment.splitn(3, ' ');
+                assert!(matches!(parts.next(), Some("HELP") | Some("TYPE")));
+                assert!(parts.next().is_some() && parts.next().is_some());
+                continue;
+            }
+            let (series, value) = line.rsplit_once(' ').unwrap();
+            let (name, labels) = series.split_once('{').unwrap();
+            assert_eq!(labels, "strategy=\"grid\",asset=\"HYPE/USDC\"}");
+            samples.insert(name.to_string(), value.parse::<f64>().unwrap());
+        }
+
+        for name in [
+            "realized_pnl",
+            "unrealized_pnl",
+            "position",
+            "active_buys",
+            "active_sells",
+            "fills_total",
+            "reconnects_total",
+            "feed_queue_depth",
+            "feed_dropped_total",
+            "uptime_seconds",
+        ] {
+            assert!(samples.contains_key(name), "missing {name}");
+            assert!(text.contains(&format!("# TYPE {name} ")));
+        }
+        assert_eq!(samples["realized_pnl"], 12.5);
+        assert_eq!(samples["position"], -0.25);
+        assert_eq!(samples["active_buys"], 3.0);
+        assert_eq!(samples["fills_total"], 7.0);
+        assert!(text.contains("# TYPE fills_total counter"));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -25,10 +25,19 @@
 //! market.start().await;
 //! ```
 
+#[allow(clippy::module_inception)]
 mod bot;
 
-pub use bot::Bot;
+pub use bot::{Bot, BotStatus};
 pub mod dashboard;
+mod logging;
+mod metrics;
+mod notifier;
 pub mod runner;
 mod server; // Internal module
+mod trade_log;
+pub use logging::{strip_ansi, LogContext};
+pub use metrics::BotMetrics;
+pub use notifier::{Notification, NotificationEvent, Notifier, WebhookFormat, WebhookNotifier};
 pub use runner::BotRunner;
+pub use trade_log::{TradeLog, TRADE_LOG_HEADER};
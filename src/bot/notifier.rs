@@ -0,0 +1,245 @@
+//! Notifier - push notifications on fills, stops, range exits and errors
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use super::bot::BotStatus;
+use crate::market::OrderSide;
+use crate::strategy::RangeDirection;
+
+/// Something an operator wants to hear about, from one bot
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    /// Strategy name
+    pub strategy: String,
+    /// Asset the strategy trades
+    pub asset: String,
+    /// When it happened, in Unix seconds
+    pub time: u64,
+    #[serde(flatten)]
+    pub event: NotificationEvent,
+}
+
+/// What happened, serialized as an `event` field next to its data
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum NotificationEvent {
+    /// An order filled, in part or in full
+    Fill {
+        order_id: u64,
+        /// None for orders the bot didn't hand to the market
+        side: Option<OrderSide>,
+        qty: f64,
+        price: f64,
+        fee: f64,
+    },
+    /// The bot stopped trading, flattened or not
+    Stopped { bot_status: BotStatus },
+    /// Price left the strategy's range
+    RangeExited { direction: RangeDirection },
+    /// Drawdown reached the bot's alert threshold
+    DrawdownExceeded { drawdown: f64, drawdown_pct: f64 },
+    /// An order was rejected or the bot's market failed
+    Error { message: String },
+}
+
+impl Notification {
+    /// Stamp `event` with the current time
+    pub fn new(
+        strategy: impl Into<String>,
+        asset: impl Into<String>,
+        event: NotificationEvent,
+    ) -> Self {
+        Self {
+            strategy: strategy.into(),
+            asset: asset.into(),
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            event,
+        }
+    }
+
+    /// One human-readable line, for chat webhooks
+    pub fn summary(&self) -> String {
+        let what = match &self.event {
+            NotificationEvent::Fill {
+                order_id,
+                side,
+                qty,
+                price,
+                ..
+            } => {
+                let side = side.map_or("fill".to_string(), |side| {
+                    format!("{side:?}").to_lowercase()
+                });
+                format!("{side} {qty} @ {price} (order {order_id})")
+            }
+            NotificationEvent::Stopped { bot_status } => format!("stopped ({bot_status:?})"),
+            NotificationEvent::RangeExited { direction } => {
+                format!("price left the range ({direction:?})")
+            }
+            NotificationEvent::DrawdownExceeded {
+                drawdown,
+                drawdown_pct,
+            } => format!("drawdown {drawdown:.4} ({drawdown_pct:.2}%)"),
+            NotificationEvent::Error { message } => format!("error: {message}"),
+        };
+        format!("[{} {}] {}", self.strategy, self.asset, what)
+    }
+}
+
+/// Receives a bot's notifications
+///
+/// Called from the bot's callbacks, so implementations must return at once
+/// and do any slow delivery in a spawned task.
+pub trait Notifier: Send + Sync {
+    fn notify(&self, notification: &Notification);
+}
+
+/// Body a `WebhookNotifier` posts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+    /// The `Notification` as JSON
+    #[default]
+    Json,
+    /// `{"text": summary}`, for Slack incoming webhooks
+    Slack,
+    /// `{"content": summary}`, for Discord webhooks
+    Discord,
+}
+
+/// POSTs every notification to a URL from a spawned task
+///
+/// A slow or failing webhook only costs a warning in the log; trading never
+/// waits on it. Notifications outside a Tokio runtime are dropped.
+#[derive(Debug, Clone)]
+pub struct WebhookNotifier {
+    url: String,
+    format: WebhookFormat,
+    client: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: impl Into<String>, format: WebhookFormat) -> Self {
+        Self {
+            url: url.into(),
+            format,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn body(&self, notification: &Notification) -> serde_json::Value {
+        match self.format {
+            WebhookFormat::Json => serde_json::to_value(notification).unwrap_or_default(),
+            WebhookFormat::Slack => serde_json::json!({ "text": notification.summary() }),
+            WebhookFormat::Discord => serde_json::json!({ "content": notification.summary() }),
+        }
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn notify(&self, notification: &Notification) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            warn!("Webhook: no runtime to deliver {}", notification.summary());
+            return;
+        };
+        let request = self
+            .client
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .body(self.body(notification).to_string());
+        runtime.spawn(async move {
+            match request.send().await.and_then(|r| r.error_for_status()) {
+                Ok(_) => {}
+                Err(e) => warn!("Webhook: delivery failed: {}", e),
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::Bot;
+    use crate::market::{MarketListener, OrderFill, OrderRequest};
+    use crate::strategy::Strategy;
+    use axum::{routing::post, Json, Router};
+    use serde_json::Value;
+    use tokio::sync::mpsc;
+
+    struct BuyStrategy;
+
+    impl Strategy for BuyStrategy {
+        fn on_start(&mut self) -> Vec<OrderRequest> {
+            vec![OrderRequest::buy(7, "BTC", 0.5, 50000.0)]
+        }
+
+        fn on_price_update(&mut self, _asset: &str, _price: f64) -> Vec<OrderRequest> {
+            vec![]
+        }
+
+        fn on_order_filled(&mut self, _fill: &OrderFill) -> Vec<OrderRequest> {
+            vec![]
+        }
+
+        fn name(&self) -> &str {
+            "buyer"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fill_posts_webhook() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let app = Router::new().route(
+            "/hook",
+            post(move |Json(body): Json<Value>| async move {
+                tx.send(body).unwrap();
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let notifier = WebhookNotifier::new(format!("http://{addr}/hook"), WebhookFormat::Json);
+        let mut bot = Bot::new(BuyStrategy).with_notifier(notifier);
+        bot.start();
+        let mut fill = OrderFill::new(7, "BTC", 0.5, 49990.0);
+        fill.fee = 0.25;
+        bot.on_order_filled(fill);
+
+        let body = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+            .await
+            .expect("no webhook within 5s")
+            .unwrap();
+        assert_eq!(body["event"], "fill");
+        assert_eq!(body["strategy"], "buyer");
+        assert_eq!(body["order_id"], 7);
+        assert_eq!(body["side"], "Buy");
+        assert_eq!(body["qty"], 0.5);
+        assert_eq!(body["price"], 49990.0);
+        assert_eq!(body["fee"], 0.25);
+        assert!(body["time"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_chat_formats_post_summary() {
+        let notification = Notification::new(
+            "grid",
+            "HYPE/USDC",
+            NotificationEvent::RangeExited {
+                direction: RangeDirection::Below,
+            },
+        );
+        let slack = WebhookNotifier::new("http://localhost", WebhookFormat::Slack);
+        assert_eq!(
+            slack.body(&notification)["text"],
+            "[grid HYPE/USDC] price left the range (Below)"
+        );
+        let discord = WebhookNotifier::new("http://localhost", WebhookFormat::Discord);
+        assert!(discord.body(&notification)["content"].is_string());
+    }
+}
@@ -1,14 +1,21 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use log::{info, warn};
+use log::{error, info, warn};
+use alloy::primitives::Address;
 use alloy::signers::local::PrivateKeySigner;
 
-use crate::config::{self, Settings};
-use crate::strategy::StrategyRegistry;
-use crate::bot::Bot;
+use crate::config::{self, NetworkConfig, Settings, StrategyConfig};
+use crate::strategy::spot_grid::{GridConfig, SizingMode};
+use crate::strategy::{CapitalRequirement, StrategyContext, StrategyRegistry, StrategyStatus};
+use crate::bot::{Bot, LogContext, TradeLog, WebhookNotifier};
+use super::server::BotState;
 use crate::market::{HyperliquidMarket, HyperliquidMarketInput, PaperTradingMarket, PaperTradingMarketInput};
-use crate::BaseUrl;
+use crate::market::MIN_ORDER_NOTIONAL;
+use crate::{AssetResolver, BaseUrl, ExchangeResponseStatus, MarketType};
+
+/// Share of a perp account's value kept free when checking a strategy's capital
+const PERP_MARGIN_BUFFER: f64 = 0.1;
 
 /// Runner for the trading bot
 pub struct BotRunner {
@@ -20,68 +27,171 @@ impl BotRunner {
     /// Create a new runner from a configuration file
     pub fn new(config_path: impl AsRef<Path>, registry: StrategyRegistry) -> Result<Self, config::ConfigError> {
         let config = Settings::new(config_path.as_ref().to_str().unwrap())?;
+        config.network.base_url()?;
         Ok(Self { config, registry })
     }
 
-    /// Run the bot
+    /// Run the bot, or one bot per strategy when several are configured
+    ///
+    /// The bots share the wallet, one info client and the dashboard; each
+    /// trades from its own market task.
     pub async fn run(self) -> Result<(), Box<dyn std::error::Error>> {
+        let strategies = self.config.strategies();
+        if strategies.is_empty() {
+            return Err("No [strategy] or [[grids]] configured".into());
+        }
+
         // 1. Setup Logging
         if std::env::var("RUST_LOG").is_err() {
             std::env::set_var("RUST_LOG", &self.config.log.level);
         }
-        env_logger::try_init().ok();
+        let assets: Vec<&str> = strategies.iter().map(|s| s.asset.as_str()).collect();
+        let context = match strategies.as_slice() {
+            [single] => LogContext::new(&single.asset, &single.type_name),
+            _ => LogContext::new(assets.join("+"), "multi"),
+        };
+        context.init(self.config.log.json);
 
-        info!("Starting BotRunner...");
+        info!("Starting BotRunner with {} strategies...", strategies.len());
 
         // 2. Setup Network & Client
         let network_config = &self.config.network;
-        let is_mainnet = network_config.env.to_lowercase() == "mainnet";
-        let base_url = if is_mainnet { BaseUrl::Mainnet } else { BaseUrl::Testnet };
+        let base_url = network_config.base_url()?;
         let wallet: PrivateKeySigner = network_config.wallet_private_key.parse()?;
-        
+
+        // We need an InfoClient to fetch meta; the strategies and dashboard share it
+        let info_client = Arc::new(crate::InfoClient::new(None, Some(base_url.clone())).await?);
+
+        // 3-5. One bot per strategy
+        let mut bots = Vec::with_capacity(strategies.len());
+        for (index, strategy_config) in strategies.iter().enumerate() {
+            let trade_log = self.trade_log_path(index, strategy_config);
+            let prepared = self
+                .prepare_bot(strategy_config, trade_log, info_client.clone(), wallet.address())
+                .await?;
+            bots.push(prepared);
+        }
+
+        // 5.5. Start Dashboard Server
+        if self.config.server.enabled {
+            let server_bots = bots.iter().map(|prepared| prepared.bot.clone()).collect();
+            let port = self.config.server.port;
+            let host = self.config.server.host.clone();
+            let view = self.config.server.dashboard_view();
+            let server_info_client = info_client.clone();
+
+            tokio::spawn(async move {
+                super::server::start_server(server_bots, server_info_client, view, port, host)
+                    .await;
+            });
+        }
+
+        // 5.6. Print status lines for log-based monitoring
+        if self.config.log.status_interval_secs > 0 {
+            let status_bots = bots.iter().map(|prepared| prepared.bot.clone()).collect();
+            let every = std::time::Duration::from_secs(self.config.log.status_interval_secs);
+            tokio::spawn(print_status_lines(status_bots, every));
+        }
+
+        // 6. Create one market task per bot
+        let handles: Vec<_> = bots
+            .into_iter()
+            .map(|prepared| {
+                let network = network_config.clone();
+                let wallet = wallet.clone();
+                let base_url = base_url.clone();
+                tokio::spawn(run_market(network, wallet, base_url, prepared))
+            })
+            .collect();
+        let mut failed = None;
+        for (asset, handle) in assets.iter().zip(handles) {
+            let result = match handle.await {
+                Ok(result) => result,
+                Err(e) => Err(e.to_string().into()),
+            };
+            if let Err(e) = result {
+                error!("Bot for {} stopped with an error: {}", asset, e);
+                failed = Some(e.to_string());
+            }
+        }
+
+        match failed {
+            Some(e) => Err(e.into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Print the levels each strategy would start with and the capital they
+    /// need, without placing any orders
+    ///
+    /// Precision, the initial price and the range are resolved as for `run`.
+    pub async fn preview(self) -> Result<(), Box<dyn std::error::Error>> {
+        let strategies = self.config.strategies();
+        if strategies.is_empty() {
+            return Err("No [strategy] or [[grids]] configured".into());
+        }
+        if std::env::var("RUST_LOG").is_err() {
+            std::env::set_var("RUST_LOG", &self.config.log.level);
+        }
+        let assets: Vec<&str> = strategies.iter().map(|s| s.asset.as_str()).collect();
+        LogContext::new(assets.join("+"), "preview").init(self.config.log.json);
+
+        let wallet: PrivateKeySigner = self.config.network.wallet_private_key.parse()?;
+        let base_url = self.config.network.base_url()?;
+        let info_client = Arc::new(crate::InfoClient::new(None, Some(base_url)).await?);
+        for strategy_config in &strategies {
+            let prepared = self
+                .prepare_bot(strategy_config, None, info_client.clone(), wallet.address())
+                .await?;
+            let bot = prepared.bot.read().await;
+            let required = bot.strategy().required_capital();
+            println!("{}", format_preview(&prepared.asset, &bot.status(), required));
+        }
+        Ok(())
+    }
+
+    /// Where a strategy's fills are logged: its own `trade_log`, else
+    /// `[log].trade_log`, numbered per strategy when there are several
+    fn trade_log_path(&self, index: usize, strategy_config: &StrategyConfig) -> Option<PathBuf> {
+        if let Some(path) = &strategy_config.trade_log {
+            return Some(PathBuf::from(path));
+        }
+        let path = Path::new(self.config.log.trade_log.as_ref()?);
+        if self.config.strategies().len() == 1 {
+            return Some(path.to_path_buf());
+        }
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match path.extension() {
+            Some(ext) => format!("{}_{}.{}", stem, index, ext.to_string_lossy()),
+            None => format!("{}_{}", stem, index),
+        };
+        Some(path.with_file_name(name))
+    }
+
+    /// Resolve a strategy's precision, initial price and range, and wrap it in a bot
+    async fn prepare_bot(
+        &self,
+        strategy_config: &StrategyConfig,
+        trade_log: Option<PathBuf>,
+        info_client: Arc<crate::InfoClient>,
+        user: Address,
+    ) -> Result<PreparedBot, Box<dyn std::error::Error>> {
         // 3. Resolve Asset Precision
-        let strategy_config = &self.config.strategy;
         let asset = &strategy_config.asset;
         
         info!("Fetching metadata for {}...", asset);
         let mut params = strategy_config.params.clone();
         
-        // We need an InfoClient to fetch meta
-        let info_client = crate::InfoClient::new(None, Some(base_url)).await?;
-        
-        // Try Spot first (common for grid bots here)
-        let precision = if let Ok(spot_meta) = info_client.spot_meta().await {
-            let base_name = asset.split('/').next().unwrap_or(asset);
-            let index_to_name: std::collections::HashMap<usize, &str> = spot_meta.tokens.iter().map(|t| (t.index, t.name.as_str())).collect();
-            
-            let mut found = None;
-            for spot_asset in &spot_meta.universe {
-                if let Some(t1) = index_to_name.get(&spot_asset.tokens[0]) {
-                    if *t1 == base_name || *asset == spot_asset.name {
-                        if let Some(base_token) = spot_meta.tokens.iter().find(|t| t.index == spot_asset.tokens[0]) {
-                            found = Some(crate::market::AssetPrecision::for_spot(base_token.sz_decimals as u32));
-                        }
-                        break;
-                    }
-                }
-            }
-            found
-        } else {
-            None
-        };
-        
-        // If not Spot, try Perp
-        let precision = if precision.is_some() {
-            precision
-        } else if let Ok(meta) = info_client.meta().await {
-             if let Some(asset_meta) = meta.universe.iter().find(|a| a.name == *asset) {
-                Some(crate::market::AssetPrecision::for_perp(asset_meta.sz_decimals))
-            } else {
+        let resolver = match AssetResolver::new(&info_client).await {
+            Ok(resolver) => Some(resolver),
+            Err(e) => {
+                warn!("Failed to fetch asset metadata: {}", e);
                 None
             }
-        } else {
-            None
         };
+        let precision = resolver
+            .as_ref()
+            .and_then(|resolver| resolver.precision(asset, MarketType::of(asset)).ok());
         
         if let Some(p) = precision {
             info!("Resolved precision: sz_decimals={}, price_decimals={}", p.sz_decimals, p.price_decimals);
@@ -136,6 +246,80 @@ impl BotRunner {
         info!("Starting strategy with initial price: {}", initial_price);
         params.insert("initial_price".to_string(), serde_json::Value::from(initial_price));
 
+        // 3.6. Derive the grid range from recent candles if asked to
+        let auto_range = params.get("auto_range").and_then(|v| v.as_bool()).unwrap_or(false);
+        let has_range = params.contains_key("lower_price") || params.contains_key("upper_price");
+        if auto_range && !has_range {
+            let lookback =
+                params.get("auto_range_lookback").and_then(|v| v.as_u64()).unwrap_or(168);
+            let interval = params
+                .get("auto_range_interval")
+                .and_then(|v| v.as_str())
+                .unwrap_or("1h")
+                .to_string();
+            let (lower, upper) =
+                GridConfig::auto_range(&info_client, asset, lookback as usize, &interval).await?;
+            info!("Auto range from last {} {} candles: {} - {}", lookback, interval, lower, upper);
+            params.insert("lower_price".to_string(), serde_json::Value::from(lower));
+            params.insert("upper_price".to_string(), serde_json::Value::from(upper));
+        }
+
+        // 3.7. Refuse grids whose levels collapse at the asset's tick size
+        if let (Some(grid), Some(p)) = (GridConfig::from_params(&params), precision) {
+            grid.check_precision(&p)?;
+        }
+
+        // 3.8. Hold leverage to the asset's cap before the strategy sizes margin for it
+        let max_leverage = resolver.as_ref().and_then(|resolver| resolver.max_leverage(asset).ok());
+        if let (Some(leverage), Some(max)) =
+            (params.get("leverage").and_then(|v| v.as_f64()), max_leverage)
+        {
+            if leverage > max as f64 {
+                let clamp = params.get("clamp_leverage").and_then(|v| v.as_bool());
+                if !clamp.unwrap_or(false) {
+                    return Err(format!(
+                        "{} allows at most {}x leverage but {}x is configured \
+                         (set clamp_leverage = true to use {}x)",
+                        asset, max, leverage, max
+                    )
+                    .into());
+                }
+                warn!(
+                    "{} allows at most {}x leverage, using {}x instead of {}x",
+                    asset, max, max, leverage
+                );
+                params.insert("leverage".to_string(), serde_json::Value::from(max));
+            }
+        }
+
+        let leverage = params.get("leverage").and_then(|v| v.as_f64());
+
+        // 3.9. Refuse grids whose orders fall below the exchange's minimum value
+        if let Some(grid) = GridConfig::from_params(&params) {
+            let sizing = params
+                .get("sizing_mode")
+                .and_then(|v| v.as_str())
+                .and_then(SizingMode::parse)
+                .unwrap_or_else(|| SizingMode::default_for(grid.mode));
+            let order_size = params.get("order_size").and_then(|v| v.as_f64());
+            let notional = params
+                .get("total_investment")
+                .and_then(|v| v.as_f64())
+                .map(|inv| inv * leverage.unwrap_or(1.0));
+            let min_notional =
+                params.get("min_notional").and_then(|v| v.as_f64()).unwrap_or(MIN_ORDER_NOTIONAL);
+            match GridConfig::level_sizes_from_params(&params) {
+                Some(sizes) => grid.check_level_sizes(&sizes, min_notional)?,
+                None => {
+                    grid.check_min_notional(sizing, order_size, notional, min_notional)?;
+                    // Whole-unit assets truncate fractional sizes to nothing
+                    if let Some(p) = precision {
+                        grid.check_size_precision(sizing, order_size, notional, &p)?;
+                    }
+                }
+            }
+        }
+
         // 4. Instantiate Strategy
         let strategy = self.registry
             .create_strategy(&strategy_config.type_name, asset, params)
@@ -143,48 +327,341 @@ impl BotRunner {
         
         info!("Strategy '{}' initialized for {}", strategy.name(), asset);
 
-        // 5. Create Bot Wrapper
-        let bot = Arc::new(RwLock::new(Bot::new(strategy)));
+        // 5. Create Bot Wrapper; the strategy and dashboard share the info client
+        let context = StrategyContext::new(info_client, user);
+        let mut bot = Bot::new(strategy)
+            .with_context(context)
+            .with_flatten_on_range_exit(strategy_config.flatten_on_range_exit);
+        if let Some(path) = trade_log {
+            info!("Appending {} fills to trade log {}", asset, path.display());
+            bot = bot.with_trade_log(TradeLog::open(path)?);
+        }
+        if let Some(path) = &strategy_config.checkpoint {
+            bot = bot.with_checkpoint(path);
+        }
+        if let Some(pct) = strategy_config.drawdown_alert_pct {
+            bot = bot.with_drawdown_alert(pct);
+        }
+        if let Some(url) = &self.config.notify.webhook_url {
+            bot = bot.with_notifier(WebhookNotifier::new(url, self.config.notify.webhook_format));
+        }
 
-        // 5.5. Start Dashboard Server
-        if self.config.server.enabled {
-            let server_bot = bot.clone();
-            let port = self.config.server.port;
-            let host = self.config.server.host.clone();
-            // Wrap info_client in Arc to share with server
-            // We recreate it or clone it? 
-            // Since info_client is not Clone, and we might have used it above.
-            // Actually, we can just arc it here since we don't need it below in run() anymore.
-            let server_info_client = Arc::new(info_client);
-            
-            tokio::spawn(async move {
-                super::server::start_server(server_bot, server_info_client, port, host).await;
-            });
+        Ok(PreparedBot {
+            asset: asset.clone(),
+            leverage,
+            bot: Arc::new(RwLock::new(bot)),
+        })
+    }
+}
+
+/// A configured strategy in its bot, waiting for a market
+struct PreparedBot {
+    asset: String,
+    /// Perp leverage the strategy sized its margin for
+    leverage: Option<f64>,
+    bot: BotState,
+}
+
+/// Print every bot's `status_line` to stdout each `every`
+async fn print_status_lines(bots: Vec<BotState>, every: std::time::Duration) {
+    let mut ticker = tokio::time::interval(every);
+    loop {
+        ticker.tick().await;
+        for bot in &bots {
+            println!("{}", bot.read().await.status_line());
         }
+    }
+}
 
-        // 6. Create Market based on mode
-        match network_config.mode.as_str() {
-            "live" => {
-                info!("Initializing LIVE market on {}...", if is_mainnet { "Mainnet" } else { "Testnet" });
-                let input = HyperliquidMarketInput {
-                    asset: asset.clone(),
-                    wallet,
-                    base_url: Some(base_url),
-                };
-                let mut market = HyperliquidMarket::new(input, bot.clone()).await?;
-                info!("Live market ready. Starting event loop...");
-                market.start().await;
-            },
-            "paper" => {
-                info!("Initializing PAPER market...");
-                let input = PaperTradingMarketInput::new(asset, 10_000.0);
-                let mut market = PaperTradingMarket::new(input, bot.clone()).await?;
-                info!("Paper market ready. Starting event loop...");
-                market.start().await;
-            },
-            _ => return Err(format!("Unknown mode: {}", network_config.mode).into()),
+/// A strategy's starting ladder, highest price first, and its capital needs
+fn format_preview(asset: &str, status: &StrategyStatus, required: CapitalRequirement) -> String {
+    let book = status.book.clone().unwrap_or_default();
+    let mut out = format!(
+        "{} {} at {}: {} levels\n",
+        asset,
+        status.name,
+        status.current_price,
+        book.asks.len() + book.bids.len()
+    );
+    for level in book.asks.iter().chain(&book.bids) {
+        let side = match level.side {
+            crate::market::OrderSide::Buy => "BUY",
+            crate::market::OrderSide::Sell => "SELL",
+        };
+        out.push_str(&format!("  {:<4} {:>14} x {}\n", side, level.price, level.size));
+    }
+    out.push_str(&format!(
+        "Capital required: {:.2} USDC, {} {}",
+        required.quote, required.base, asset
+    ));
+    out
+}
+
+/// Trade one bot on the market `network.mode` selects until it stops
+///
+/// However the market ends, the bot is shut down so its trade log is flushed;
+/// a failure is passed to the bot's notifiers first.
+async fn run_market(
+    network_config: NetworkConfig,
+    wallet: PrivateKeySigner,
+    base_url: BaseUrl,
+    prepared: PreparedBot,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let bot = prepared.bot.clone();
+    let result = trade_market(network_config, wallet, base_url, prepared).await;
+    if let Err(e) = &result {
+        bot.read().await.notify_error(format!("market stopped: {}", e));
+    }
+    bot.write().await.shutdown();
+    result
+}
+
+async fn trade_market(
+    network_config: NetworkConfig,
+    wallet: PrivateKeySigner,
+    base_url: BaseUrl,
+    prepared: PreparedBot,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let PreparedBot { asset, leverage, bot } = prepared;
+    let asset = &asset;
+    let is_mainnet = base_url == BaseUrl::Mainnet;
+
+    // 6. Create Market based on mode
+    match network_config.mode.as_str() {
+        "live" => {
+            info!("Initializing LIVE market on {}...", if is_mainnet { "Mainnet" } else { "Testnet" });
+            let input = HyperliquidMarketInput {
+                asset: asset.clone(),
+                extra_assets: Vec::new(),
+                wallet,
+                base_url: Some(base_url),
+                max_slippage_bps: network_config.max_slippage_bps,
+                feed_capacity: network_config.feed_capacity,
+                builder: network_config.builder.as_ref().map(|builder| builder.info()),
+                dry_run: network_config.dry_run,
+                price_source: network_config.price_source,
+            };
+            let request_timeout =
+                std::time::Duration::from_secs(network_config.request_timeout_secs);
+            let mut market = HyperliquidMarket::new(input, bot.clone())
+                .await?
+                .with_request_timeout(request_timeout);
+
+            // The strategy sizes its margin for this leverage, so the account must match
+            let is_perp = MarketType::of(asset) == MarketType::Perp;
+            if network_config.dry_run {
+                warn!("Dry run: orders are logged, not sent to the exchange");
+            }
+            if let Some(leverage) = leverage.filter(|_| is_perp && !network_config.dry_run) {
+                info!("Setting {} to {}x isolated leverage", asset, leverage);
+                let response = market
+                    .exchange_client
+                    .update_leverage(leverage.round() as u32, asset, false, None)
+                    .await?;
+                if let ExchangeResponseStatus::Err(e) = response {
+                    return Err(format!("Failed to set leverage: {}", e).into());
+                }
+            }
+
+            // Hold back spot sells the base balance can't cover; they're
+            // placed once it does, instead of being rejected now
+            if !is_perp {
+                market.refresh_balances().await?;
+                let base = market.asset_info().balance;
+                let orders = bot.write().await.strategy_mut().on_balance_update(asset, base);
+                if !orders.is_empty() {
+                    warn!("Dropping {} orders returned before the start", orders.len());
+                }
+            }
+
+            // Refuse to start a strategy the account can't fund, rather than
+            // finding out one rejected order at a time
+            let required = bot.read().await.strategy().required_capital();
+            required.check(market.asset_info(), PERP_MARGIN_BUFFER)?;
+
+            info!("Live market ready. Starting event loop...");
+            market.start().await;
+        },
+        "paper" => {
+            info!("Initializing PAPER market...");
+            let mut input = PaperTradingMarketInput::new(asset, 10_000.0)
+                .with_fee_user(wallet.address());
+            if let Some(capacity) = network_config.feed_capacity {
+                input = input.with_feed_capacity(capacity);
+            }
+            let mut market = PaperTradingMarket::new(input, bot.clone())
+                .await?
+                .with_strict_balance(network_config.strict_balance);
+            info!("Paper market ready. Starting event loop...");
+            market.start().await;
+        },
+        _ => return Err(format!("Unknown mode: {}", network_config.mode).into()),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::{MarketListener, OrderFill};
+    use crate::strategy::spot_grid::SpotGridStrategyFactory;
+    use axum::{http::StatusCode, routing::post, Json, Router};
+    use serde_json::{json, Value};
+
+    const NETWORK: &str = r#"
+[network]
+env = "testnet"
+mode = "paper"
+wallet_private_key = "0000000000000000000000000000000000000000000000000000000000000001"
+"#;
+
+    /// Info client for a mock `/info` serving mids, and `meta` unless it's null
+    async fn mock_info_client(meta: Value) -> Arc<crate::InfoClient> {
+        let app = Router::new().route(
+            "/info",
+            post(move |Json(body): Json<Value>| async move {
+                match body["type"].as_str() {
+                    Some("allMids") => Ok(Json(json!({ "BTC": "50000", "ETH": "3000" }))),
+                    Some("meta") if !meta.is_null() => Ok(Json(meta)),
+                    Some("spotMeta") if !meta.is_null() => {
+                        Ok(Json(json!({ "universe": [], "tokens": [] })))
+                    }
+                    // A client error isn't retried, so failing metadata fails fast
+                    _ => Err(StatusCode::UNPROCESSABLE_ENTITY),
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let mut info_client = crate::InfoClient::new(None, None)
+            .await
+            .unwrap()
+            .with_rate_limiter(None);
+        info_client.http_client.base_url = format!("http://{addr}");
+        Arc::new(info_client)
+    }
+
+    fn runner_for(config: &str) -> BotRunner {
+        let path = std::env::temp_dir().join(format!("grids_{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, format!("{NETWORK}{config}")).unwrap();
+        let mut registry = StrategyRegistry::new();
+        registry.register("spot_grid", SpotGridStrategyFactory);
+        let runner = BotRunner::new(&path, registry);
+        std::fs::remove_file(&path).unwrap();
+        runner.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_over_leverage_config_is_rejected() {
+        let meta = json!({ "universe": [{ "name": "BTC", "szDecimals": 5, "maxLeverage": 20 }] });
+        let info_client = mock_info_client(meta).await;
+        let grid = |extra: &str| {
+            runner_for(&format!(
+                r#"
+[strategy]
+type = "spot_grid"
+asset = "BTC"
+[strategy.params]
+lower_price = 45000.0
+upper_price = 55000.0
+grid_levels = 5
+total_investment = 1000.0
+leverage = 50
+{extra}
+"#
+            ))
+        };
+
+        let runner = grid("");
+        let config = &runner.config.strategies()[0];
+        let err = runner
+            .prepare_bot(config, None, info_client.clone(), Address::ZERO)
+            .await
+            .err()
+            .expect("50x on a 20x asset must not start");
+        assert!(err.to_string().contains("at most 20x"), "{err}");
+
+        // Clamped on request, and the strategy sizes its margin for the cap
+        let runner = grid("clamp_leverage = true");
+        let config = &runner.config.strategies()[0];
+        let prepared = runner
+            .prepare_bot(config, None, info_client, Address::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(prepared.leverage, Some(20.0));
+        assert_eq!(prepared.bot.read().await.status().custom["leverage"], 20.0);
+    }
+
+    #[tokio::test]
+    async fn test_two_grid_config_prepares_independent_bots() {
+        // Metadata fails, so the configured precision is used
+        let info_client = mock_info_client(Value::Null).await;
+        let runner = runner_for(
+            r#"
+[log]
+trade_log = "logs/trades.csv"
+
+[[grids]]
+type = "spot_grid"
+asset = "BTC"
+[grids.params]
+lower_price = 45000.0
+upper_price = 55000.0
+grid_levels = 5
+order_size = 0.01
+sz_decimals = 4
+
+[[grids]]
+type = "spot_grid"
+asset = "ETH"
+trade_log = "eth.csv"
+[grids.params]
+lower_price = 2500.0
+upper_price = 3500.0
+grid_levels = 3
+order_size = 0.1
+sz_decimals = 4
+"#,
+        );
+
+        let strategies = runner.config.strategies();
+        assert_eq!(strategies.len(), 2);
+        assert_eq!(
+            runner.trade_log_path(0, &strategies[0]),
+            Some(PathBuf::from("logs/trades_0.csv"))
+        );
+        assert_eq!(runner.trade_log_path(1, &strategies[1]), Some(PathBuf::from("eth.csv")));
+
+        let mut bots = Vec::new();
+        for config in &strategies {
+            let prepared = runner
+                .prepare_bot(config, None, info_client.clone(), Address::ZERO)
+                .await
+                .unwrap();
+            bots.push(prepared);
         }
+        assert_eq!(bots[0].asset, "BTC");
+        assert_eq!(bots[1].asset, "ETH");
 
-        Ok(())
+        // Each bot has its own strategy, started at its own price
+        let btc = bots[0].bot.read().await.status();
+        let eth = bots[1].bot.read().await.status();
+        assert_eq!(btc.current_price, 50000.0);
+        assert_eq!(eth.current_price, 3000.0);
+        assert_eq!(btc.custom["levels"], 5);
+        assert_eq!(eth.custom["levels"], 3);
+
+        let orders = bots[1].bot.write().await.on_price_update("ETH", 3000.0);
+        let buy = orders.iter().find(|o| o.is_buy()).unwrap();
+        bots[1]
+            .bot
+            .write()
+            .await
+            .on_order_filled(OrderFill::new(buy.order_id, "ETH", buy.qty, buy.limit_price));
+        assert_eq!(bots[1].bot.read().await.metrics().fills_total, 1);
+        assert_eq!(bots[0].bot.read().await.metrics().fills_total, 0);
     }
 }
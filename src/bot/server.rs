@@ -1,34 +1,57 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 use axum::{
-    routing::get,
+    routing::{get, post},
     Router,
-    extract::{State, Query},
-    response::{Html, Json},
+    extract::{
+        ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
+        Request, State, Query,
+    },
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Json, Response},
 };
-use log::info;
+use log::{info, warn};
 use serde::Deserialize;
+use crate::bot::dashboard::{self, DashboardView};
 use crate::bot::Bot;
 use crate::InfoClient;
 
-type BotState = Arc<RwLock<Bot<Box<dyn crate::strategy::Strategy + Send + Sync>>>>;
+pub(super) type BotState = Arc<RwLock<Bot<Box<dyn crate::strategy::Strategy + Send + Sync>>>>;
+
+/// Environment variable holding the token required by every route
+const TOKEN_ENV: &str = "DASHBOARD_TOKEN";
 
 #[derive(Clone)]
 struct ServerState {
     bot: BotState,
     info_client: Arc<InfoClient>,
+    /// Required bearer token; routes are open when unset
+    token: Option<Arc<str>>,
+    /// Dashboard refresh and candle settings
+    view: Arc<DashboardView>,
 }
 
 /// Start the dashboard server
-pub(crate) async fn start_server(bot: BotState, info_client: Arc<InfoClient>, port: u16, host: String) {
-    let state = ServerState { bot, info_client };
-
-    let app = Router::new()
-        .route("/", get(dashboard_handler))
-        .route("/api/status", get(status_handler))
-        .route("/api/candles", get(candles_handler))
-        .with_state(state);
+///
+/// With several bots, `/` lists them and each one's dashboard and routes are
+/// served under `/bots/<n>/`, numbered in config order.
+pub(crate) async fn start_server(
+    bots: Vec<BotState>,
+    info_client: Arc<InfoClient>,
+    view: DashboardView,
+    port: u16,
+    host: String,
+) {
+    let token = std::env::var(TOKEN_ENV)
+        .ok()
+        .filter(|token| !token.is_empty())
+        .map(Arc::from);
+    if token.is_none() {
+        warn!("{} is not set; dashboard and control routes are unauthenticated", TOKEN_ENV);
+    }
+    let app = app(bots, info_client, token, view);
 
     let addr_str = format!("{}:{}", host, port);
     let addr: SocketAddr = addr_str.parse().expect("Invalid address");
@@ -39,9 +62,107 @@ pub(crate) async fn start_server(bot: BotState, info_client: Arc<InfoClient>, po
     axum::serve(listener, app).await.unwrap();
 }
 
+/// Routes for one bot at the root, or for every bot under `/bots/<n>`
+fn app(
+    bots: Vec<BotState>,
+    info_client: Arc<InfoClient>,
+    token: Option<Arc<str>>,
+    view: DashboardView,
+) -> Router {
+    let view = Arc::new(view);
+    let state = |bot: BotState| ServerState {
+        bot,
+        info_client: info_client.clone(),
+        token: token.clone(),
+        view: view.clone(),
+    };
+    if let [bot] = bots.as_slice() {
+        return router(state(bot.clone()));
+    }
+    let mut app = Router::new()
+        .route("/", get(bot_list_handler))
+        .route("/api/bots", get(bots_handler))
+        .route_layer(middleware::from_fn_with_state(token.clone(), require_token))
+        .with_state(Arc::<[BotState]>::from(bots.clone()));
+    for (index, bot) in bots.into_iter().enumerate() {
+        app = app.nest(&format!("/bots/{}", index), router(state(bot)));
+    }
+    app
+}
+
+fn router(state: ServerState) -> Router {
+    Router::new()
+        .route("/", get(dashboard_handler))
+        .route("/ws", get(ws_handler))
+        .route("/api/status", get(status_handler))
+        .route("/api/candles", get(candles_handler))
+        .route("/api/trades.csv", get(trades_csv_handler))
+        .route("/metrics", get(metrics_handler))
+        .route("/api/pause", post(pause_handler))
+        .route("/api/resume", post(resume_handler))
+        .route("/api/flatten", post(flatten_handler))
+        .layer(middleware::from_fn_with_state(state.token.clone(), require_token))
+        .with_state(state)
+}
+
+#[derive(Deserialize)]
+struct TokenParams {
+    token: Option<String>,
+}
+
+/// Reject requests without the configured token
+///
+/// The token is accepted as `Authorization: Bearer <token>` or as a `?token=`
+/// query parameter, since browsers can't set headers on WebSocket upgrades.
+async fn require_token(State(token): State<Option<Arc<str>>>, request: Request, next: Next) -> Response {
+    let Some(expected) = token.as_deref() else {
+        return next.run(request).await;
+    };
+
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string);
+    let provided = bearer.or_else(|| {
+        Query::<TokenParams>::try_from_uri(request.uri())
+            .ok()
+            .and_then(|Query(params)| params.token)
+    });
+
+    if provided.as_deref() == Some(expected) {
+        next.run(request).await
+    } else {
+        (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            "Unauthorized",
+        )
+            .into_response()
+    }
+}
+
 async fn dashboard_handler(State(state): State<ServerState>) -> Html<String> {
-    let bot = state.bot.read().await;
-    Html(bot.render_dashboard())
+    let status = state.bot.read().await.status();
+    Html(dashboard::render_dashboard_with(&status, &state.view))
+}
+
+/// Every bot's status, in config order
+async fn statuses(bots: &[BotState]) -> Vec<crate::strategy::StrategyStatus> {
+    let mut statuses = Vec::with_capacity(bots.len());
+    for bot in bots {
+        statuses.push(bot.read().await.status());
+    }
+    statuses
+}
+
+async fn bot_list_handler(State(bots): State<Arc<[BotState]>>) -> Html<String> {
+    Html(crate::bot::dashboard::render_bot_list(&statuses(&bots).await))
+}
+
+async fn bots_handler(State(bots): State<Arc<[BotState]>>) -> Json<serde_json::Value> {
+    Json(serde_json::to_value(statuses(&bots).await).unwrap_or_default())
 }
 
 async fn status_handler(State(state): State<ServerState>) -> Json<serde_json::Value> {
@@ -49,6 +170,102 @@ async fn status_handler(State(state): State<ServerState>) -> Json<serde_json::Va
     Json(bot.status_json())
 }
 
+/// Stop placing new orders; fills keep being tracked
+async fn pause_handler(State(state): State<ServerState>) -> Response {
+    control(&state, |bot| bot.pause()).await
+}
+
+/// Re-place the orders held back while paused
+async fn resume_handler(State(state): State<ServerState>) -> Response {
+    control(&state, |bot| bot.resume()).await
+}
+
+/// Cancel all orders and close the position at market
+async fn flatten_handler(State(state): State<ServerState>) -> Response {
+    control(&state, |bot| bot.flatten()).await
+}
+
+/// Apply a run-state transition, answering 409 if it isn't allowed from the current state
+async fn control(
+    state: &ServerState,
+    transition: impl FnOnce(&mut Bot<Box<dyn crate::strategy::Strategy + Send + Sync>>) -> bool,
+) -> Response {
+    let mut bot = state.bot.write().await;
+    let applied = transition(&mut bot);
+    let body = Json(serde_json::json!({ "status": bot.bot_status() }));
+    if applied {
+        body.into_response()
+    } else {
+        (StatusCode::CONFLICT, body).into_response()
+    }
+}
+
+/// Prometheus scrape endpoint
+async fn metrics_handler(State(state): State<ServerState>) -> impl IntoResponse {
+    let bot = state.bot.read().await;
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        bot.metrics().render(),
+    )
+}
+
+/// Download the full CSV trade log
+async fn trades_csv_handler(State(state): State<ServerState>) -> Response {
+    let path = {
+        let bot = state.bot.read().await;
+        match bot.trade_log() {
+            Some(trade_log) => trade_log.path().to_path_buf(),
+            None => return (StatusCode::NOT_FOUND, "Trade log is not enabled").into_response(),
+        }
+    };
+
+    match tokio::fs::read(&path).await {
+        Ok(contents) => (
+            [
+                (header::CONTENT_TYPE, "text/csv"),
+                (header::CONTENT_DISPOSITION, "attachment; filename=\"trades.csv\""),
+            ],
+            contents,
+        )
+            .into_response(),
+        Err(e) => {
+            log::error!("Failed to read trade log {}: {}", path.display(), e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Live status feed: sends the current status, then every update the bot publishes
+async fn ws_handler(ws: WebSocketUpgrade, State(state): State<ServerState>) -> Response {
+    let (initial, updates) = {
+        let bot = state.bot.read().await;
+        (bot.status_json().to_string(), bot.subscribe_status())
+    };
+    ws.on_upgrade(move |socket| push_status(socket, initial, updates))
+}
+
+async fn push_status(
+    mut socket: WebSocket,
+    initial: String,
+    mut updates: broadcast::Receiver<String>,
+) {
+    if socket.send(WsMessage::Text(initial)).await.is_err() {
+        return;
+    }
+    loop {
+        match updates.recv().await {
+            Ok(status) => {
+                if socket.send(WsMessage::Text(status)).await.is_err() {
+                    break;
+                }
+            }
+            // Only the latest status matters, skip whatever we fell behind on
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
 #[derive(Deserialize)]
 struct CandlesParams {
     coin: String,
@@ -62,7 +279,9 @@ async fn candles_handler(
     State(state): State<ServerState>,
     Query(params): Query<CandlesParams>,
 ) -> Json<serde_json::Value> {
-    let interval = params.interval.unwrap_or_else(|| "15m".to_string());
+    let interval = params
+        .interval
+        .unwrap_or_else(|| state.view.candle_interval.clone());
     
     let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_millis() as u64;
     let end = params.end.unwrap_or(now);
@@ -85,3 +304,283 @@ async fn candles_handler(
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures_util::StreamExt;
+    use tokio_tungstenite::{connect_async, tungstenite};
+
+    use super::*;
+    use crate::market::{MarketListener, OrderFill};
+    use crate::strategy::{NoOpStrategy, Strategy};
+
+    async fn test_state(bot: BotState, token: Option<&str>) -> ServerState {
+        ServerState {
+            bot,
+            info_client: Arc::new(InfoClient::new(None, None).await.unwrap()),
+            token: token.map(Arc::from),
+            view: Arc::new(DashboardView::default()),
+        }
+    }
+
+    /// Serve the dashboard router on an ephemeral port
+    async fn spawn_server(bot: BotState) -> SocketAddr {
+        serve(test_state(bot, None).await).await
+    }
+
+    async fn serve(state: ServerState) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, router(state)).await.unwrap();
+        });
+        addr
+    }
+
+    async fn next_status<S>(ws: &mut S) -> serde_json::Value
+    where
+        S: futures_util::Stream<Item = Result<tungstenite::Message, tungstenite::Error>> + Unpin,
+    {
+        let frame = tokio::time::timeout(Duration::from_secs(5), ws.next())
+            .await
+            .expect("no frame received")
+            .unwrap()
+            .unwrap();
+        serde_json::from_str(&frame.into_text().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_fill_produces_ws_broadcast() {
+        let strategy: Box<dyn Strategy + Send + Sync> = Box::new(NoOpStrategy);
+        let bot = Arc::new(RwLock::new(Bot::new(strategy)));
+        let addr = spawn_server(bot.clone()).await;
+
+        let (mut ws, _) = connect_async(format!("ws://{addr}/ws")).await.unwrap();
+
+        // Snapshot on connect
+        assert_eq!(next_status(&mut ws).await["name"], "noop");
+
+        // A fill processed by the bot is pushed immediately
+        bot.write()
+            .await
+            .on_order_filled(OrderFill::new(1, "BTC", 1.0, 50000.0));
+        assert_eq!(next_status(&mut ws).await["name"], "noop");
+    }
+
+    #[tokio::test]
+    async fn test_trades_csv_download() {
+        let path = std::env::temp_dir().join(format!("trades_{}.csv", uuid::Uuid::new_v4()));
+        let strategy: Box<dyn Strategy + Send + Sync> = Box::new(NoOpStrategy);
+        let bot = Bot::new(strategy).with_trade_log(crate::bot::TradeLog::open(&path).unwrap());
+        let bot = Arc::new(RwLock::new(bot));
+        let addr = spawn_server(bot.clone()).await;
+
+        for i in 0..2 {
+            bot.write()
+                .await
+                .on_order_filled(OrderFill::new(i, "BTC", 1.0, 50000.0));
+        }
+
+        let response = reqwest::get(format!("http://{addr}/api/trades.csv"))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["content-type"], "text/csv");
+        let body = response.text().await.unwrap();
+        assert_eq!(body.lines().next(), Some(crate::bot::TRADE_LOG_HEADER));
+        assert_eq!(body.lines().count(), 3);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_trades_csv_not_found_without_log() {
+        let strategy: Box<dyn Strategy + Send + Sync> = Box::new(NoOpStrategy);
+        let addr = spawn_server(Arc::new(RwLock::new(Bot::new(strategy)))).await;
+
+        let response = reqwest::get(format!("http://{addr}/api/trades.csv"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint() {
+        let strategy: Box<dyn Strategy + Send + Sync> = Box::new(NoOpStrategy);
+        let bot = Arc::new(RwLock::new(Bot::new(strategy)));
+        let addr = spawn_server(bot.clone()).await;
+        bot.write()
+            .await
+            .on_order_filled(OrderFill::new(1, "BTC", 1.0, 50000.0));
+
+        let response = reqwest::get(format!("http://{addr}/metrics")).await.unwrap();
+        assert!(response.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain"));
+        let body = response.text().await.unwrap();
+        assert!(body.contains("# TYPE fills_total counter"));
+        assert!(body.contains("fills_total{strategy=\"noop\",asset=\"\"} 1"));
+    }
+
+    #[tokio::test]
+    async fn test_control_handlers_transition_status() {
+        use crate::bot::BotStatus;
+
+        let strategy: Box<dyn Strategy + Send + Sync> = Box::new(NoOpStrategy);
+        let bot = Arc::new(RwLock::new(Bot::new(strategy)));
+        let state = test_state(bot.clone(), None).await;
+
+        let response = resume_handler(State(state.clone())).await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = pause_handler(State(state.clone())).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(bot.read().await.bot_status(), BotStatus::Paused);
+
+        let response = resume_handler(State(state.clone())).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(bot.read().await.bot_status(), BotStatus::Running);
+
+        let response = flatten_handler(State(state.clone())).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(bot.read().await.bot_status(), BotStatus::Flattened);
+        assert_eq!(
+            bot.write().await.take_commands(),
+            [crate::market::MarketCommand::Flatten]
+        );
+
+        // Flatten is terminal
+        assert_eq!(
+            pause_handler(State(state.clone())).await.status(),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(
+            resume_handler(State(state)).await.status(),
+            StatusCode::CONFLICT
+        );
+    }
+
+    #[tokio::test]
+    async fn test_token_required_when_configured() {
+        let strategy: Box<dyn Strategy + Send + Sync> = Box::new(NoOpStrategy);
+        let bot = Arc::new(RwLock::new(Bot::new(strategy)));
+        let addr = serve(test_state(bot, Some("s3cret")).await).await;
+        let client = reqwest::Client::new();
+        let status_url = format!("http://{addr}/api/status");
+
+        let missing = client.get(&status_url).send().await.unwrap();
+        assert_eq!(missing.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let wrong = client
+            .get(&status_url)
+            .bearer_auth("wrong")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(wrong.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let wrong_query = client
+            .post(format!("http://{addr}/api/pause?token=wrong"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(wrong_query.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let bearer = client
+            .get(&status_url)
+            .bearer_auth("s3cret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(bearer.status(), reqwest::StatusCode::OK);
+
+        let query = client
+            .get(format!("http://{addr}/?token=s3cret"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(query.status(), reqwest::StatusCode::OK);
+
+        let (mut ws, _) = connect_async(format!("ws://{addr}/ws?token=s3cret"))
+            .await
+            .unwrap();
+        assert_eq!(next_status(&mut ws).await["name"], "noop");
+        assert!(connect_async(format!("ws://{addr}/ws")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_several_bots_served_under_their_index() {
+        let bot = || -> BotState {
+            let strategy: Box<dyn Strategy + Send + Sync> = Box::new(NoOpStrategy);
+            Arc::new(RwLock::new(Bot::new(strategy)))
+        };
+        let bots = vec![bot(), bot()];
+        let info_client = Arc::new(InfoClient::new(None, None).await.unwrap());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = app(bots.clone(), info_client, None, DashboardView::default());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let all = reqwest::get(format!("http://{addr}/api/bots"))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        let all: serde_json::Value = serde_json::from_str(&all).unwrap();
+        assert_eq!(all.as_array().unwrap().len(), 2);
+
+        let index = reqwest::get(format!("http://{addr}/")).await.unwrap();
+        assert!(index.text().await.unwrap().contains("href=\"bots/1/\""));
+
+        // Controls only reach the bot they're addressed to
+        let client = reqwest::Client::new();
+        let paused = client
+            .post(format!("http://{addr}/bots/1/api/pause"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(paused.status(), reqwest::StatusCode::OK);
+        assert_eq!(bots[0].read().await.bot_status(), crate::bot::BotStatus::Running);
+        assert_eq!(bots[1].read().await.bot_status(), crate::bot::BotStatus::Paused);
+    }
+
+    #[tokio::test]
+    async fn test_dashboard_uses_configured_intervals() {
+        let strategy: Box<dyn Strategy + Send + Sync> = Box::new(NoOpStrategy);
+        let bot: BotState = Arc::new(RwLock::new(Bot::new(strategy)));
+        let mut state = test_state(bot, None).await;
+        state.view = Arc::new(DashboardView {
+            refresh_secs: 5,
+            candle_interval: "1h".to_string(),
+        });
+        let addr = serve(state).await;
+
+        let html = reqwest::get(format!("http://{addr}/"))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(html.contains("const REFRESH_MS = 5000;"));
+        assert!(html.contains("const CANDLE_INTERVAL = '1h';"));
+        assert!(!html.contains("interval=15m"));
+
+        // Defaults match the old hardcoded values
+        let html = dashboard::render_dashboard(&Bot::new(NoOpStrategy).status());
+        assert!(html.contains("const REFRESH_MS = 1000;"));
+        assert!(html.contains("const CANDLE_INTERVAL = '15m';"));
+    }
+
+    #[tokio::test]
+    async fn test_routes_open_without_token() {
+        let strategy: Box<dyn Strategy + Send + Sync> = Box::new(NoOpStrategy);
+        let addr = spawn_server(Arc::new(RwLock::new(Bot::new(strategy)))).await;
+
+        let response = reqwest::get(format!("http://{addr}/api/status"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+    }
+}
@@ -0,0 +1,131 @@
+//! TradeLog - append-only CSV record of every fill
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::market::{OrderFill, OrderSide};
+
+/// Column header written at the top of a new trade log
+pub const TRADE_LOG_HEADER: &str = "time,side,price,size,fee,oid";
+
+/// Appends every fill to a CSV file as it happens
+///
+/// Unlike the in-memory `recent_trades` shown on the dashboard, the log keeps
+/// the full fill history across restarts, so it can be used for record-keeping.
+/// Rows are written unbuffered so the file is complete even if the process dies.
+#[derive(Debug)]
+pub struct TradeLog {
+    path: PathBuf,
+    file: File,
+}
+
+impl TradeLog {
+    /// Open (or create) the trade log at `path`
+    ///
+    /// The header is only written when the file is new or empty; an existing
+    /// log is appended to.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        if file.metadata()?.len() == 0 {
+            writeln!(file, "{TRADE_LOG_HEADER}")?;
+        }
+        Ok(Self { path, file })
+    }
+
+    /// Path of the CSV file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append one fill
+    ///
+    /// `side` is left blank when the originating order is unknown.
+    pub fn append(
+        &mut self,
+        fill: &OrderFill,
+        side: Option<OrderSide>,
+        fee: f64,
+    ) -> io::Result<()> {
+        let side = match side {
+            Some(OrderSide::Buy) => "buy",
+            Some(OrderSide::Sell) => "sell",
+            None => "",
+        };
+        writeln!(
+            self.file,
+            "{},{},{},{},{},{}",
+            fill_time(fill).to_rfc3339(),
+            side,
+            fill.price,
+            fill.qty,
+            fee,
+            fill.order_id
+        )
+    }
+
+    /// Flush buffered rows to disk
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.sync_data()
+    }
+}
+
+/// When a fill happened, or now if its market didn't say
+fn fill_time(fill: &OrderFill) -> chrono::DateTime<chrono::Utc> {
+    match fill.time_ms {
+        0 => chrono::Utc::now(),
+        ms => chrono::DateTime::from_timestamp_millis(ms as i64).unwrap_or_else(chrono::Utc::now),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("trade_log_{}.csv", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_fills_produce_csv_rows() {
+        let path = temp_path();
+        let mut log = TradeLog::open(&path).unwrap();
+        for i in 0..3 {
+            let fill = OrderFill::new(i, "HYPE/USDC", 1.5, 10.0 + i as f64);
+            log.append(&fill, Some(OrderSide::Buy), 0.01).unwrap();
+        }
+        log.append(&OrderFill::new(9, "HYPE/USDC", 2.0, 12.5), None, 0.0)
+            .unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], TRADE_LOG_HEADER);
+        for (i, line) in lines[1..4].iter().enumerate() {
+            let cols: Vec<&str> = line.split(',').collect();
+            assert_eq!(cols.len(), 6);
+            assert!(chrono::DateTime::parse_from_rfc3339(cols[0]).is_ok());
+            assert_eq!(
+                cols[1..],
+                ["buy", &(10 + i).to_string(), "1.5", "0.01", &i.to_string()]
+            );
+        }
+        assert!(lines[4].ends_with(",,12.5,2,0,9"));
+
+        // Reopening appends without repeating the header
+        drop(log);
+        let mut log = TradeLog::open(&path).unwrap();
+        log.append(
+            &OrderFill::new(10, "HYPE/USDC", 1.0, 11.0),
+            Some(OrderSide::Sell),
+            0.0,
+        )
+        .unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().count(), 6);
+        assert_eq!(contents.matches(TRADE_LOG_HEADER).count(), 1);
+
+        std::fs::remove_file(&path).ok();
+    }
+}
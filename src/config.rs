@@ -3,33 +3,119 @@ pub use config::ConfigError;
 use serde::Deserialize;
 use serde_json::Value; // Add this import
 
+use crate::bot::dashboard::DashboardView;
+use crate::bot::WebhookFormat;
+use crate::market::PriceSource;
+use crate::{BaseUrl, BuilderInfo};
+
 /// Main configuration struct
 #[derive(Debug, Deserialize)]
 pub struct Settings {
     /// Network configuration (env, mode, wallet)
     pub network: NetworkConfig,
     /// Strategy configuration (type, asset, params)
-    pub strategy: StrategyConfig,
+    ///
+    /// Optional when the strategies are listed as `[[grids]]` instead.
+    #[serde(default)]
+    pub strategy: Option<StrategyConfig>,
+    /// More strategies run side by side in this process
+    #[serde(flatten)]
+    pub multi: MultiGridConfig,
     /// Logging configuration
     #[serde(default)]
     pub log: LogConfig,
     /// Server configuration
     #[serde(default)]
     pub server: ServerConfig,
+    /// Push notifications
+    #[serde(default)]
+    pub notify: NotifyConfig,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct NetworkConfig {
-    /// Environment: "mainnet" or "testnet"
+    /// Environment: "mainnet", "testnet", "localhost" or an http(s) URL
     pub env: String,
     /// Mode: "live" or "paper"
     pub mode: String,
     /// Wallet private key (hex string)
     /// In production, consider loading this from ENV variables only
     pub wallet_private_key: String,
+    /// Worst price accepted when flattening, in BPS from mid (default 500)
+    #[serde(default = "default_max_slippage_bps")]
+    pub max_slippage_bps: u32,
+    /// Queued feed messages beyond which stale price updates are skipped
+    /// (unbounded if unset)
+    #[serde(default)]
+    pub feed_capacity: Option<usize>,
+    /// Builder code attached to every live order (none if unset)
+    #[serde(default)]
+    pub builder: Option<BuilderConfig>,
+    /// Live mode only: follow the exchange but log orders instead of sending them
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Live mode only: trade on the "mid" (default), "mark" or "oracle" price
+    #[serde(default)]
+    pub price_source: PriceSource,
+    /// Live mode only: seconds to wait for an exchange request before
+    /// abandoning it (default 10)
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+    /// Paper mode only: reject buys the balance left after resting buys
+    /// can't cover
+    #[serde(default)]
+    pub strict_balance: bool,
 }
 
-#[derive(Debug, Deserialize)]
+/// Builder that receives a share of the fees on the bot's orders
+///
+/// The wallet must approve the fee first (`ExchangeClient::approve_builder_fee`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct BuilderConfig {
+    /// Builder's address
+    pub address: String,
+    /// Fee in tenths of a basis point (10 = 1 bps)
+    pub fee_tenths_bps: u64,
+}
+
+impl NetworkConfig {
+    /// The API endpoint named by `env`; unknown names are an error rather
+    /// than silently falling back to a network
+    pub fn base_url(&self) -> Result<BaseUrl, ConfigError> {
+        match self.env.to_lowercase().as_str() {
+            "mainnet" => Ok(BaseUrl::Mainnet),
+            "testnet" => Ok(BaseUrl::Testnet),
+            "localhost" => Ok(BaseUrl::Localhost),
+            _ if self.env.starts_with("http://") || self.env.starts_with("https://") => {
+                Ok(BaseUrl::Custom(self.env.trim_end_matches('/').to_string()))
+            }
+            _ => Err(ConfigError::Message(format!(
+                "unknown network env {:?}: expected mainnet, testnet, localhost or a URL",
+                self.env
+            ))),
+        }
+    }
+}
+
+impl BuilderConfig {
+    /// The builder as sent with orders; the exchange expects a lowercase address
+    pub fn info(&self) -> BuilderInfo {
+        BuilderInfo {
+            builder: self.address.to_lowercase(),
+            fee: self.fee_tenths_bps,
+        }
+    }
+}
+
+fn default_max_slippage_bps() -> u32 {
+    500
+}
+
+fn default_request_timeout_secs() -> u64 {
+    10
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct StrategyConfig {
     /// Strategy type name (e.g., "grid", "market_maker")
     #[serde(rename = "type")]
@@ -39,6 +125,30 @@ pub struct StrategyConfig {
     /// Strategy-specific parameters
     #[serde(default)]
     pub params: std::collections::HashMap<String, Value>,
+    /// CSV file this strategy's fills are appended to, instead of `[log]`'s
+    #[serde(default)]
+    pub trade_log: Option<String>,
+    /// Close the position and stop once price leaves the grid's range
+    #[serde(default)]
+    pub flatten_on_range_exit: bool,
+    /// Raise a drawdown alert once total PnL falls this many percent of peak
+    /// equity below its high
+    #[serde(default)]
+    pub drawdown_alert_pct: Option<f64>,
+    /// JSON file the strategy's lifetime totals are kept in across restarts
+    #[serde(default)]
+    pub checkpoint: Option<String>,
+}
+
+/// Several strategies for one process, each a `[[grids]]` table laid out
+/// like `[strategy]`
+///
+/// Every entry gets its own bot, market and trade log; they share the
+/// wallet, the info client and the dashboard.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MultiGridConfig {
+    #[serde(default)]
+    pub grids: Vec<StrategyConfig>,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -46,6 +156,15 @@ pub struct LogConfig {
     /// Log level: "error", "warn", "info", "debug", "trace"
     #[serde(default = "default_log_level")]
     pub level: String,
+    /// CSV file every fill is appended to (disabled if unset)
+    #[serde(default)]
+    pub trade_log: Option<String>,
+    /// Write one JSON object per line instead of plain text
+    #[serde(default)]
+    pub json: bool,
+    /// Print each bot's status as a JSON line to stdout this often (0 never does)
+    #[serde(default)]
+    pub status_interval_secs: u64,
 }
 
 fn default_log_level() -> String {
@@ -61,8 +180,16 @@ pub struct ServerConfig {
     #[serde(default = "default_server_port")]
     pub port: u16,
     /// Server host (default 127.0.0.1)
+    ///
+    /// Set the `DASHBOARD_TOKEN` env var before binding beyond localhost.
     #[serde(default = "default_server_host")]
     pub host: String,
+    /// Seconds between dashboard polls when its live socket is down (default 1)
+    #[serde(default = "default_refresh_secs")]
+    pub refresh_secs: u64,
+    /// Candle interval of the dashboard chart (default "15m")
+    #[serde(default = "default_candle_interval")]
+    pub candle_interval: String,
 }
 
 impl Default for ServerConfig {
@@ -71,6 +198,29 @@ impl Default for ServerConfig {
             enabled: default_server_enabled(),
             port: default_server_port(),
             host: default_server_host(),
+            refresh_secs: default_refresh_secs(),
+            candle_interval: default_candle_interval(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct NotifyConfig {
+    /// URL every bot POSTs its fills, stops, range exits and errors to
+    /// (disabled if unset)
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Body posted: "json" (default), "slack" or "discord"
+    #[serde(default)]
+    pub webhook_format: WebhookFormat,
+}
+
+impl ServerConfig {
+    /// Refresh and candle settings for the dashboard
+    pub fn dashboard_view(&self) -> DashboardView {
+        DashboardView {
+            refresh_secs: self.refresh_secs,
+            candle_interval: self.candle_interval.clone(),
         }
     }
 }
@@ -87,6 +237,14 @@ fn default_server_host() -> String {
     "127.0.0.1".to_string()
 }
 
+fn default_refresh_secs() -> u64 {
+    1
+}
+
+fn default_candle_interval() -> String {
+    "15m".to_string()
+}
+
 impl Settings {
     /// Load settings from a configuration file
     pub fn new(config_path: &str) -> Result<Self, ConfigError> {
@@ -101,4 +259,74 @@ impl Settings {
 
         s.try_deserialize()
     }
+
+    /// Every strategy to run: `[strategy]` first, then each `[[grids]]` entry
+    pub fn strategies(&self) -> Vec<StrategyConfig> {
+        self.strategy
+            .iter()
+            .chain(&self.multi.grids)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multi_grid_config() {
+        let path = std::env::temp_dir().join(format!("multi_{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+[network]
+env = "testnet"
+mode = "paper"
+wallet_private_key = "01"
+
+[[grids]]
+type = "spot_grid"
+asset = "HYPE/USDC"
+trade_log = "hype.csv"
+params = { lower_price = 10.0, upper_price = 20.0 }
+
+[[grids]]
+type = "spot_grid"
+asset = "BTC"
+"#,
+        )
+        .unwrap();
+        let settings = Settings::new(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        let settings = settings.unwrap();
+
+        let strategies = settings.strategies();
+        assert_eq!(strategies.len(), 2);
+        assert_eq!(strategies[0].asset, "HYPE/USDC");
+        assert_eq!(strategies[0].params["upper_price"], 20.0);
+        assert_eq!(strategies[0].trade_log.as_deref(), Some("hype.csv"));
+        assert_eq!(strategies[1].asset, "BTC");
+        assert!(strategies[1].params.is_empty());
+    }
+
+    #[test]
+    fn test_network_env_is_parsed_explicitly() {
+        let network = |env: &str| -> NetworkConfig {
+            serde_json::from_value(serde_json::json!({
+                "env": env,
+                "mode": "paper",
+                "wallet_private_key": "01",
+            }))
+            .unwrap()
+        };
+        assert_eq!(network("Mainnet").base_url().unwrap(), BaseUrl::Mainnet);
+        assert_eq!(network("testnet").base_url().unwrap(), BaseUrl::Testnet);
+        assert_eq!(network("localhost").base_url().unwrap(), BaseUrl::Localhost);
+        assert_eq!(
+            network("http://127.0.0.1:3001/").base_url().unwrap(),
+            BaseUrl::Custom("http://127.0.0.1:3001".to_string())
+        );
+        assert!(network("mainet").base_url().is_err());
+    }
 }
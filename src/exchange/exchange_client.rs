@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use alloy::{
     primitives::{keccak256, Address, Signature, B256},
@@ -13,7 +13,7 @@ use crate::{
         actions::{
             ApproveAgent, ApproveBuilderFee, BulkCancel, BulkModify, BulkOrder, ClaimRewards,
             EvmUserModify, ScheduleCancel, SendAsset, SetReferrer, UpdateIsolatedMargin,
-            UpdateLeverage, UsdSend,
+            UpdateLeverage, UsdClassTransfer, UsdSend,
         },
         cancel::{CancelRequest, CancelRequestCloid, ClientCancelRequestCloid},
         modify::{ClientModifyRequest, ModifyRequest},
@@ -24,6 +24,7 @@ use crate::{
     info::info_client::InfoClient,
     meta::Meta,
     prelude::*,
+    rate_limiter::RateLimiter,
     req::HttpClient,
     signature::{sign_l1_action, sign_typed_data},
     BaseUrl, BulkCancelCloid, ClassTransfer, Error, ExchangeResponseStatus, SpotSend, SpotUser,
@@ -74,6 +75,7 @@ pub enum Actions {
     ApproveAgent(ApproveAgent),
     Withdraw3(Withdraw3),
     SpotUser(SpotUser),
+    UsdClassTransfer(UsdClassTransfer),
     SendAsset(SendAsset),
     VaultTransfer(VaultTransfer),
     SpotSend(SpotSend),
@@ -110,7 +112,7 @@ impl ExchangeClient {
         let client = client.unwrap_or_default();
         let base_url = base_url.unwrap_or(BaseUrl::Mainnet);
 
-        let info = InfoClient::new(None, Some(base_url)).await?;
+        let info = InfoClient::new(None, Some(base_url.clone())).await?;
         let meta = if let Some(meta) = meta {
             meta
         } else {
@@ -134,11 +136,27 @@ impl ExchangeClient {
             http_client: HttpClient {
                 client,
                 base_url: base_url.get_url(),
+                mainnet: base_url.is_mainnet(),
+                rate_limiter: Some(RateLimiter::shared()),
             },
             coin_to_asset,
         })
     }
 
+    /// Info client for the same API host
+    async fn info_client(&self) -> Result<InfoClient> {
+        let base_url = BaseUrl::Custom(self.http_client.base_url.clone());
+        InfoClient::new(None, Some(base_url)).await
+    }
+
+    /// Use a different rate limiter for REST calls, or `None` to disable throttling
+    ///
+    /// By default all clients share `RateLimiter::shared()`.
+    pub fn with_rate_limiter(mut self, rate_limiter: Option<Arc<RateLimiter>>) -> Self {
+        self.http_client.rate_limiter = rate_limiter;
+        self
+    }
+
     async fn post(
         &self,
         action: serde_json::Value,
@@ -239,6 +257,42 @@ impl ExchangeClient {
         self.post(action, signature, timestamp).await
     }
 
+    /// Move USDC between the spot and perp balances (`to_perp` picks the way)
+    ///
+    /// Signed as a user action, which replaces the older `spotUser` class
+    /// transfer. From a vault or subaccount, its balances are moved instead.
+    pub async fn usd_class_transfer(
+        &self,
+        amount: f64,
+        to_perp: bool,
+        wallet: Option<&PrivateKeySigner>,
+    ) -> Result<ExchangeResponseStatus> {
+        let wallet = wallet.unwrap_or(&self.wallet);
+        let hyperliquid_chain = if self.http_client.is_mainnet() {
+            "Mainnet".to_string()
+        } else {
+            "Testnet".to_string()
+        };
+
+        let timestamp = next_nonce();
+        let amount = match self.vault_address {
+            Some(vault_address) => format!("{amount} subaccount:{vault_address:?}"),
+            None => amount.to_string(),
+        };
+        let transfer = UsdClassTransfer {
+            signature_chain_id: 421614,
+            hyperliquid_chain,
+            amount,
+            to_perp,
+            nonce: timestamp,
+        };
+        let signature = sign_typed_data(&transfer, wallet)?;
+        let action = serde_json::to_value(Actions::UsdClassTransfer(transfer))
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        self.post(action, signature, timestamp).await
+    }
+
     pub async fn send_asset(
         &self,
         destination: &str,
@@ -366,12 +420,7 @@ impl ExchangeClient {
         let slippage = params.slippage.unwrap_or(0.05); // Default 5% slippage
         let wallet = params.wallet.unwrap_or(&self.wallet);
 
-        let base_url = match self.http_client.base_url.as_str() {
-            "https://api.hyperliquid.xyz" => BaseUrl::Mainnet,
-            "https://api.hyperliquid-testnet.xyz" => BaseUrl::Testnet,
-            _ => return Err(Error::GenericRequest("Invalid base URL".to_string())),
-        };
-        let info_client = InfoClient::new(None, Some(base_url)).await?;
+        let info_client = self.info_client().await?;
         let user_state = info_client.user_state(wallet.address()).await?;
 
         let position = user_state
@@ -414,12 +463,7 @@ impl ExchangeClient {
         slippage: f64,
         px: Option<f64>,
     ) -> Result<(f64, u32)> {
-        let base_url = match self.http_client.base_url.as_str() {
-            "https://api.hyperliquid.xyz" => BaseUrl::Mainnet,
-            "https://api.hyperliquid-testnet.xyz" => BaseUrl::Testnet,
-            _ => return Err(Error::GenericRequest("Invalid base URL".to_string())),
-        };
-        let info_client = InfoClient::new(None, Some(base_url)).await?;
+        let info_client = self.info_client().await?;
         let meta = info_client.meta().await?;
 
         let asset_meta = meta
@@ -613,6 +657,29 @@ impl ExchangeClient {
         self.post(action, signature, timestamp).await
     }
 
+    /// Move a resting limit order to a new price and size in one action
+    ///
+    /// Avoids the cancel-then-place round trip. The order is looked up by
+    /// `oid` so its coin, side, reduce-only flag, tif and cloid are preserved.
+    /// On success the exchange assigns a new oid, returned in the response
+    /// status.
+    pub async fn modify_order(
+        &self,
+        oid: u64,
+        new_px: f64,
+        new_sz: f64,
+    ) -> Result<ExchangeResponseStatus> {
+        let info_client = self.info_client().await?;
+        let user = self.vault_address.unwrap_or_else(|| self.wallet.address());
+        let status = info_client.query_order_by_oid(user, oid).await?;
+        let order = status
+            .order
+            .ok_or_else(|| Error::GenericRequest(format!("Order {oid} not found")))?;
+
+        let modify = ClientModifyRequest::amend(&order.order, new_px, new_sz)?;
+        self.modify(modify, None).await
+    }
+
     pub async fn cancel_by_cloid(
         &self,
         cancel: ClientCancelRequestCloid,
@@ -703,13 +770,43 @@ impl ExchangeClient {
         self.post(action, signature, timestamp).await
     }
 
+    /// Approve a freshly generated agent and return its private key
     pub async fn approve_agent(
         &self,
         wallet: Option<&PrivateKeySigner>,
     ) -> Result<(B256, ExchangeResponseStatus)> {
-        let wallet = wallet.unwrap_or(&self.wallet);
         let agent = PrivateKeySigner::random();
+        let response = self
+            .approve_agent_address(agent.address(), None, wallet)
+            .await?;
+        Ok((agent.to_bytes(), response))
+    }
 
+    /// Let an API wallet (agent) trade for this account
+    ///
+    /// Signed by the main wallet. The agent can place and cancel orders but
+    /// cannot transfer or withdraw. Approving another agent under the same
+    /// `name` replaces it; unnamed agents replace the previous unnamed one.
+    ///
+    /// To trade through the agent, build a client from the agent's key and no
+    /// `vault_address`: the exchange applies its orders to the account that
+    /// approved it. Info queries (open orders, fills, balances) still need the
+    /// main account's address, not the agent's.
+    ///
+    /// ```ignore
+    /// let agent: PrivateKeySigner = agent_key.parse()?;
+    /// main_client
+    ///     .approve_agent_address(agent.address(), Some("grid-bot"), None)
+    ///     .await?;
+    /// let bot_client = ExchangeClient::new(None, agent, Some(BaseUrl::Mainnet), None, None).await?;
+    /// ```
+    pub async fn approve_agent_address(
+        &self,
+        agent_address: Address,
+        name: Option<&str>,
+        wallet: Option<&PrivateKeySigner>,
+    ) -> Result<ExchangeResponseStatus> {
+        let wallet = wallet.unwrap_or(&self.wallet);
         let hyperliquid_chain = if self.http_client.is_mainnet() {
             "Mainnet".to_string()
         } else {
@@ -720,14 +817,14 @@ impl ExchangeClient {
         let approve_agent = ApproveAgent {
             signature_chain_id: 421614,
             hyperliquid_chain,
-            agent_address: agent.address(),
-            agent_name: None,
+            agent_address,
+            agent_name: name.map(str::to_string),
             nonce,
         };
         let signature = sign_typed_data(&approve_agent, wallet)?;
         let action = serde_json::to_value(Actions::ApproveAgent(approve_agent))
             .map_err(|e| Error::JsonParse(e.to_string()))?;
-        Ok((agent.to_bytes(), self.post(action, signature, nonce).await?))
+        self.post(action, signature, nonce).await
     }
 
     pub async fn withdraw_from_bridge(
@@ -1164,4 +1261,372 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_builder_serialized_only_when_set() -> Result<()> {
+        let order = |builder| {
+            let action = Actions::Order(BulkOrder {
+                orders: vec![OrderRequest {
+                    asset: 1,
+                    is_buy: true,
+                    limit_px: "2000.0".to_string(),
+                    sz: "3.5".to_string(),
+                    reduce_only: false,
+                    order_type: Order::Limit(Limit {
+                        tif: "Gtc".to_string(),
+                    }),
+                    cloid: None,
+                }],
+                grouping: "na".to_string(),
+                builder,
+            });
+            (
+                serde_json::to_value(&action).unwrap(),
+                action.hash(1583838, None).unwrap(),
+            )
+        };
+
+        let (plain, plain_hash) = order(None);
+        assert!(plain.get("builder").is_none());
+
+        let (with_builder, builder_hash) = order(Some(BuilderInfo {
+            builder: "0x8c967e73e7b15087c42a10d344cff4c96d877f1d".to_string(),
+            fee: 10,
+        }));
+        assert_eq!(
+            with_builder["builder"],
+            serde_json::json!({"b": "0x8c967e73e7b15087c42a10d344cff4c96d877f1d", "f": 10})
+        );
+        // The builder is part of the signed action
+        assert_ne!(plain_hash, builder_hash);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_approve_agent_signing() -> Result<()> {
+        let wallet = get_wallet()?;
+        let approve_agent = ApproveAgent {
+            signature_chain_id: 421614,
+            hyperliquid_chain: "Mainnet".to_string(),
+            agent_address: address!("0x1234567890123456789012345678901234567890"),
+            agent_name: Some("grid-bot".to_string()),
+            nonce: 1583838,
+        };
+
+        let signature = sign_typed_data(&approve_agent, &wallet)?;
+        let recovered = signature
+            .recover_address_from_prehash(&crate::Eip712::eip712_signing_hash(&approve_agent))
+            .unwrap();
+        assert_eq!(recovered, wallet.address());
+        assert_eq!(
+            serde_json::to_value(Actions::ApproveAgent(approve_agent.clone())).unwrap(),
+            serde_json::json!({
+                "type": "approveAgent",
+                "signatureChainId": "0x66eee",
+                "hyperliquidChain": "Mainnet",
+                "agentAddress": "0x1234567890123456789012345678901234567890",
+                "agentName": "grid-bot",
+                "nonce": 1583838,
+            })
+        );
+
+        // The name is part of what the main wallet signs
+        let unnamed = ApproveAgent {
+            agent_name: None,
+            ..approve_agent
+        };
+        assert_ne!(sign_typed_data(&unnamed, &wallet)?, signature);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_transfer_actions_signing() -> Result<()> {
+        let wallet = get_wallet()?;
+        let recover = |action: &dyn crate::Eip712, signature: &Signature| {
+            signature
+                .recover_address_from_prehash(&action.eip712_signing_hash())
+                .unwrap()
+        };
+
+        let to_perp = UsdClassTransfer {
+            signature_chain_id: 421614,
+            hyperliquid_chain: "Mainnet".to_string(),
+            amount: "12.5".to_string(),
+            to_perp: true,
+            nonce: 1583838,
+        };
+        let signature = sign_typed_data(&to_perp, &wallet)?;
+        assert_eq!(recover(&to_perp, &signature), wallet.address());
+        assert_eq!(
+            serde_json::to_value(Actions::UsdClassTransfer(to_perp.clone())).unwrap(),
+            serde_json::json!({
+                "type": "usdClassTransfer",
+                "signatureChainId": "0x66eee",
+                "hyperliquidChain": "Mainnet",
+                "amount": "12.5",
+                "toPerp": true,
+                "nonce": 1583838
+            })
+        );
+        // The direction is part of what's signed
+        let to_spot = UsdClassTransfer {
+            to_perp: false,
+            ..to_perp
+        };
+        assert_ne!(sign_typed_data(&to_spot, &wallet)?, signature);
+
+        let spot_send = SpotSend {
+            signature_chain_id: 421614,
+            hyperliquid_chain: "Testnet".to_string(),
+            destination: "0x1234567890123456789012345678901234567890".to_string(),
+            token: "PURR:0xc4bf3f870c0e9465323c0b6ed28096c2".to_string(),
+            amount: "1".to_string(),
+            time: 1583838,
+        };
+        let signature = sign_typed_data(&spot_send, &wallet)?;
+        assert_eq!(recover(&spot_send, &signature), wallet.address());
+        assert_eq!(
+            serde_json::to_value(Actions::SpotSend(spot_send)).unwrap(),
+            serde_json::json!({
+                "type": "spotSend",
+                "signatureChainId": "0x66eee",
+                "hyperliquidChain": "Testnet",
+                "destination": "0x1234567890123456789012345678901234567890",
+                "token": "PURR:0xc4bf3f870c0e9465323c0b6ed28096c2",
+                "amount": "1",
+                "time": 1583838
+            })
+        );
+
+        let withdraw = Withdraw3 {
+            signature_chain_id: 421614,
+            hyperliquid_chain: "Mainnet".to_string(),
+            destination: "0x1234567890123456789012345678901234567890".to_string(),
+            amount: "100".to_string(),
+            time: 1583838,
+        };
+        let signature = sign_typed_data(&withdraw, &wallet)?;
+        assert_eq!(recover(&withdraw, &signature), wallet.address());
+        assert_eq!(
+            serde_json::to_value(Actions::Withdraw3(withdraw)).unwrap()["type"],
+            "withdraw3"
+        );
+
+        Ok(())
+    }
+
+    fn resting_order(cloid: Option<&str>) -> crate::BasicOrderInfo {
+        crate::BasicOrderInfo {
+            coin: "ETH".to_string(),
+            side: "A".to_string(),
+            limit_px: "2100.0".to_string(),
+            sz: "1.0".to_string(),
+            oid: 77,
+            timestamp: 0,
+            trigger_condition: "N/A".to_string(),
+            is_trigger: false,
+            trigger_px: "0.0".to_string(),
+            is_position_tpsl: false,
+            reduce_only: true,
+            order_type: "Limit".to_string(),
+            orig_sz: "1.0".to_string(),
+            tif: Some("Alo".to_string()),
+            cloid: cloid.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_modify_action_serialization() -> Result<()> {
+        let cloid = "0x1e60610f0b3d420597c88c1fed2ad5ee";
+        let modify = ClientModifyRequest::amend(&resting_order(Some(cloid)), 2000.0, 3.5)?;
+        assert_eq!(modify.oid, 77);
+
+        let coin_to_asset = HashMap::from([("ETH".to_string(), 1)]);
+        let action = Actions::BatchModify(BulkModify {
+            modifies: vec![ModifyRequest {
+                oid: modify.oid,
+                order: modify.order.convert(&coin_to_asset)?,
+            }],
+        });
+        let payload = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
+        assert_eq!(
+            payload,
+            serde_json::json!({
+                "type": "batchModify",
+                "modifies": [{
+                    "oid": 77,
+                    "order": {
+                        "a": 1,
+                        "b": false,
+                        "p": "2000",
+                        "s": "3.5",
+                        "r": true,
+                        "t": {"limit": {"tif": "Alo"}},
+                        "c": cloid,
+                    }
+                }]
+            })
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_modify_order_posts_signed_modify() -> Result<()> {
+        use alloy::primitives::U256;
+        use axum::{extract::State, routing::post, Json, Router};
+        use serde_json::{json, Value};
+        use std::sync::Mutex;
+
+        use crate::meta::AssetMeta;
+
+        type Posted = Arc<Mutex<Vec<Value>>>;
+
+        async fn info(Json(body): Json<Value>) -> Json<Value> {
+            match body["type"].as_str() {
+                Some("spotMeta") => Json(json!({"universe": [], "tokens": []})),
+                Some("orderStatus") => {
+                    assert_eq!(body["oid"], 77);
+                    let order = json!({
+                        "coin": "ETH", "side": "A", "limitPx": "2100.0", "sz": "1.0",
+                        "oid": 77, "timestamp": 0, "triggerCondition": "N/A",
+                        "isTrigger": false, "triggerPx": "0.0", "isPositionTpsl": false,
+                        "reduceOnly": true, "orderType": "Limit", "origSz": "1.0",
+                        "tif": "Alo", "cloid": "0x1e60610f0b3d420597c88c1fed2ad5ee",
+                    });
+                    Json(json!({
+                        "status": "order",
+                        "order": {"order": order, "status": "open", "statusTimestamp": 0},
+                    }))
+                }
+                other => panic!("unexpected info request {other:?}"),
+            }
+        }
+
+        async fn exchange(State(posted): State<Posted>, Json(body): Json<Value>) -> Json<Value> {
+            posted.lock().unwrap().push(body);
+            Json(json!({
+                "status": "ok",
+                "response": {"type": "order", "data": {"statuses": [{"resting": {"oid": 78}}]}}
+            }))
+        }
+
+        let posted = Posted::default();
+        let app = Router::new()
+            .route("/info", post(info))
+            .route("/exchange", post(exchange))
+            .with_state(posted.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let wallet = get_wallet()?;
+        let meta = Meta {
+            universe: vec![AssetMeta {
+                name: "ETH".to_string(),
+                sz_decimals: 4,
+                max_leverage: 25,
+                only_isolated: None,
+            }],
+        };
+        let exchange = ExchangeClient::new(
+            None,
+            wallet.clone(),
+            Some(BaseUrl::Custom(url)),
+            Some(meta),
+            None,
+        )
+        .await?
+        .with_rate_limiter(None);
+        let status = exchange.modify_order(77, 2000.0, 3.5).await?;
+        assert!(matches!(status, ExchangeResponseStatus::Ok(_)));
+
+        let body = posted.lock().unwrap().pop().unwrap();
+        assert_eq!(
+            body["action"],
+            json!({
+                "type": "batchModify",
+                "modifies": [{
+                    "oid": 77,
+                    "order": {
+                        "a": 0,
+                        "b": false,
+                        "p": "2000",
+                        "s": "3.5",
+                        "r": true,
+                        "t": {"limit": {"tif": "Alo"}},
+                        "c": "0x1e60610f0b3d420597c88c1fed2ad5ee",
+                    }
+                }]
+            })
+        );
+        assert_eq!(body["vaultAddress"], Value::Null);
+
+        // Verify the signature the way the exchange does: rehash the posted
+        // action with the posted nonce and recover the signer
+        let action: Actions = serde_json::from_value(body["action"].clone())
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let nonce = body["nonce"].as_u64().unwrap();
+        let scalar = |key: &str| U256::from_str(body["signature"][key].as_str().unwrap()).unwrap();
+        let v = body["signature"]["v"].as_u64().unwrap();
+        let signature = Signature::new(scalar("r"), scalar("s"), v == 28);
+        let agent = crate::signature::agent::l1::Agent {
+            source: "b".to_string(),
+            connectionId: action.hash(nonce, None)?,
+        };
+        let signer = signature
+            .recover_address_from_prehash(&crate::Eip712::eip712_signing_hash(&agent))
+            .unwrap();
+        assert_eq!(signer, wallet.address());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_modify_rejects_trigger_orders() {
+        let mut order = resting_order(None);
+        order.is_trigger = true;
+        assert!(ClientModifyRequest::amend(&order, 2000.0, 1.0).is_err());
+
+        let modify = ClientModifyRequest::amend(&resting_order(None), 2000.0, 1.0).unwrap();
+        assert!(modify.order.cloid.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_clients_against_custom_url() -> Result<()> {
+        use axum::{routing::post, Json, Router};
+        use serde_json::{json, Value};
+
+        async fn info(Json(body): Json<Value>) -> Json<Value> {
+            match body["type"].as_str() {
+                Some("allMids") => Json(json!({"BTC": "50000"})),
+                Some("spotMeta") => Json(json!({"universe": [], "tokens": []})),
+                other => panic!("unexpected info request {other:?}"),
+            }
+        }
+
+        let app = Router::new().route("/info", post(info));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let base_url = BaseUrl::Custom(format!("{url}/"));
+        let info = InfoClient::new(None, Some(base_url.clone()))
+            .await?
+            .with_rate_limiter(None);
+        assert_eq!(info.http_client.base_url, url);
+        assert_eq!(info.all_mids().await?["BTC"], "50000");
+
+        let meta = Meta { universe: vec![] };
+        let exchange =
+            ExchangeClient::new(None, get_wallet()?, Some(base_url), Some(meta), None).await?;
+        assert_eq!(exchange.http_client.base_url, url);
+        // A custom host is never signed for as mainnet
+        assert!(!exchange.http_client.is_mainnet());
+        assert!(BaseUrl::Mainnet.is_mainnet());
+
+        Ok(())
+    }
 }
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-use super::{order::OrderRequest, ClientOrderRequest};
+use super::{order::OrderRequest, ClientLimit, ClientOrder, ClientOrderRequest};
+use crate::{prelude::*, BasicOrderInfo, Error};
 
 #[derive(Debug)]
 pub struct ClientModifyRequest {
@@ -8,6 +10,41 @@ pub struct ClientModifyRequest {
     pub order: ClientOrderRequest,
 }
 
+impl ClientModifyRequest {
+    /// Build a modify that moves a resting limit order to a new price and size
+    ///
+    /// Coin, side, reduce-only, time in force and cloid are carried over from
+    /// the resting order so only price and size change.
+    pub(crate) fn amend(order: &BasicOrderInfo, new_px: f64, new_sz: f64) -> Result<Self> {
+        if order.is_trigger {
+            return Err(Error::GenericRequest(
+                "modify_order only supports limit orders".to_string(),
+            ));
+        }
+        let cloid = order
+            .cloid
+            .as_deref()
+            .map(|cloid| Uuid::parse_str(cloid.trim_start_matches("0x")))
+            .transpose()
+            .map_err(|e| Error::GenericParse(e.to_string()))?;
+
+        Ok(Self {
+            oid: order.oid,
+            order: ClientOrderRequest {
+                asset: order.coin.clone(),
+                is_buy: order.side == "B",
+                reduce_only: order.reduce_only,
+                limit_px: new_px,
+                sz: new_sz,
+                cloid,
+                order_type: ClientOrder::Limit(ClientLimit {
+                    tif: order.tif.clone().unwrap_or_else(|| "Gtc".to_string()),
+                }),
+            },
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ModifyRequest {
     pub oid: u64,
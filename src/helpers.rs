@@ -1,11 +1,12 @@
+use std::future::Future;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 use chrono::prelude::Utc;
 use lazy_static::lazy_static;
-use log::info;
+use log::{info, warn};
 use uuid::Uuid;
 
-use crate::consts::*;
+use crate::{consts::*, Error, ReconnectPolicy};
 
 fn now_timestamp_ms() -> u64 {
     let now = Utc::now();
@@ -70,11 +71,15 @@ pub fn bps_diff(x: f64, y: f64) -> u16 {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BaseUrl {
     Localhost,
     Testnet,
     Mainnet,
+    /// Any other API host, e.g. a proxy or mock server ("http://127.0.0.1:3001")
+    ///
+    /// Actions are signed for testnet, as with `Localhost`.
+    Custom(String),
 }
 
 impl BaseUrl {
@@ -83,8 +88,47 @@ impl BaseUrl {
             BaseUrl::Localhost => LOCAL_API_URL.to_string(),
             BaseUrl::Mainnet => MAINNET_API_URL.to_string(),
             BaseUrl::Testnet => TESTNET_API_URL.to_string(),
+            BaseUrl::Custom(url) => url.trim_end_matches('/').to_string(),
         }
     }
+
+    /// Whether actions are signed for mainnet
+    pub fn is_mainnet(&self) -> bool {
+        matches!(self, BaseUrl::Mainnet)
+    }
+}
+
+/// Run `call` until it succeeds, backing off between attempts as `policy` says
+///
+/// Only failures that may pass are retried: 5xx responses and requests that
+/// never got an answer. Other errors, and the last failure once
+/// `policy.max_attempts` is used up, are returned with `what` in the message.
+pub(crate) async fn with_retry<T, F, Fut>(
+    what: &str,
+    policy: &ReconnectPolicy,
+    mut call: F,
+) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let mut failures = 0;
+    loop {
+        let e = match call().await {
+            Ok(value) => return Ok(value),
+            Err(e) => e,
+        };
+        failures += 1;
+        let transient = matches!(e, Error::ServerRequest { .. } | Error::GenericRequest(_));
+        if !transient || !policy.should_retry(failures) {
+            return Err(Error::GenericRequest(format!(
+                "{what} failed after {failures} attempt(s): {e}"
+            )));
+        }
+        let delay = policy.delay(failures - 1);
+        warn!("{what} failed ({e}), retrying in {delay:?}");
+        tokio::time::sleep(delay).await;
+    }
 }
 
 lazy_static! {
@@ -0,0 +1,327 @@
+//! Resolution of user-friendly asset names to exchange keys and precision
+//!
+//! Spot pairs are written as "BASE/QUOTE" (e.g. "HYPE/USDC") and perps by
+//! coin name (e.g. "BTC"). The exchange keys spot books by "@{index}", except
+//! for the few pairs whose universe name is the pair itself (e.g. "PURR/USDC").
+//! A base token can trade against several quotes, so both sides of the name
+//! pick the pair.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::{
+    helpers::with_retry, market::AssetPrecision, Error, InfoClient, Meta, ReconnectPolicy,
+    SpotAssetMeta, SpotMeta,
+};
+
+/// Retries for the metadata fetched at startup: 0.5s, 1s, 2s, 4s apart
+const METADATA_RETRY: ReconnectPolicy = ReconnectPolicy {
+    base_delay: Duration::from_millis(500),
+    max_delay: Duration::from_secs(8),
+    jitter: 0.0,
+    max_attempts: Some(5),
+};
+
+/// Which exchange an asset trades on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarketType {
+    Spot,
+    Perp,
+}
+
+impl MarketType {
+    /// Spot for "BASE/QUOTE" names, perp otherwise
+    pub fn of(asset: &str) -> Self {
+        if asset.contains('/') {
+            MarketType::Spot
+        } else {
+            MarketType::Perp
+        }
+    }
+}
+
+/// Caches `meta` and `spot_meta` and answers asset lookups from them
+#[derive(Debug, Clone)]
+pub struct AssetResolver {
+    meta: Meta,
+    spot_meta: SpotMeta,
+}
+
+impl AssetResolver {
+    /// Fetch perp and spot metadata once
+    ///
+    /// A 5xx or dropped request is retried a few times with backoff, so a
+    /// brief exchange hiccup doesn't stop a bot from starting.
+    pub async fn new(info_client: &InfoClient) -> Result<Self, Error> {
+        Self::fetch(info_client, &METADATA_RETRY).await
+    }
+
+    async fn fetch(info_client: &InfoClient, retry: &ReconnectPolicy) -> Result<Self, Error> {
+        let meta = with_retry("meta", retry, || info_client.meta()).await?;
+        let spot_meta = with_retry("spotMeta", retry, || info_client.spot_meta()).await?;
+        Ok(Self::from_meta(meta, spot_meta))
+    }
+
+    /// Build a resolver from metadata fetched elsewhere
+    pub fn from_meta(meta: Meta, spot_meta: SpotMeta) -> Self {
+        Self { meta, spot_meta }
+    }
+
+    /// Exchange key for an asset: "@{index}" for spot pairs, the name for perps
+    ///
+    /// Spot pairs match either by universe name ("PURR/USDC") or by base token
+    /// name ("HYPE/USDC" -> the pair whose base token is "HYPE").
+    pub fn resolve_key(&self, asset: &str) -> Result<String, Error> {
+        match MarketType::of(asset) {
+            MarketType::Spot => {
+                let pair = self.spot_pair(asset)?;
+                Ok(format!("@{}", pair.index))
+            }
+            MarketType::Perp => Ok(asset.to_string()),
+        }
+    }
+
+    /// Size and price precision of an asset on the given exchange
+    pub fn precision(&self, asset: &str, market_type: MarketType) -> Result<AssetPrecision, Error> {
+        match market_type {
+            MarketType::Spot => {
+                let pair = self.spot_pair(asset)?;
+                let base = self
+                    .spot_meta
+                    .tokens
+                    .iter()
+                    .find(|t| t.index == pair.tokens[0])
+                    .ok_or(Error::AssetNotFound)?;
+                Ok(AssetPrecision::for_spot(base.sz_decimals as u32))
+            }
+            MarketType::Perp => {
+                let asset_meta = self
+                    .meta
+                    .universe
+                    .iter()
+                    .find(|a| a.name == asset)
+                    .ok_or(Error::AssetNotFound)?;
+                Ok(AssetPrecision::for_perp(asset_meta.sz_decimals))
+            }
+        }
+    }
+
+    /// Token the asset is quoted (and its balance held) in
+    ///
+    /// The pair's second token for spot, USDC for perps.
+    pub fn quote_token(&self, asset: &str) -> Result<String, Error> {
+        match MarketType::of(asset) {
+            MarketType::Spot => {
+                let pair = self.spot_pair(asset)?;
+                self.token_name(pair.tokens[1])
+                    .map(str::to_string)
+                    .ok_or(Error::AssetNotFound)
+            }
+            MarketType::Perp => Ok("USDC".to_string()),
+        }
+    }
+
+    /// Highest leverage a perp allows
+    ///
+    /// Spot assets can't be traded with leverage and aren't found.
+    pub fn max_leverage(&self, asset: &str) -> Result<u32, Error> {
+        self.meta
+            .universe
+            .iter()
+            .find(|a| a.name == asset && MarketType::of(asset) == MarketType::Perp)
+            .map(|a| a.max_leverage as u32)
+            .ok_or(Error::AssetNotFound)
+    }
+
+    /// Friendly "BASE/QUOTE" name of every spot pair, by exchange key
+    pub fn spot_names(&self) -> HashMap<String, String> {
+        self.spot_meta
+            .universe
+            .iter()
+            .filter_map(|pair| {
+                let base = self.token_name(pair.tokens[0])?;
+                let quote = self.token_name(pair.tokens[1])?;
+                Some((format!("@{}", pair.index), format!("{}/{}", base, quote)))
+            })
+            .collect()
+    }
+
+    fn token_name(&self, index: usize) -> Option<&str> {
+        self.spot_meta
+            .tokens
+            .iter()
+            .find(|t| t.index == index)
+            .map(|t| t.name.as_str())
+    }
+
+    fn spot_pair(&self, asset: &str) -> Result<&SpotAssetMeta, Error> {
+        let (base_name, quote_name) = asset.split_once('/').unwrap_or((asset, "USDC"));
+
+        // An exact pair name wins over a token match
+        self.spot_meta
+            .universe
+            .iter()
+            .find(|pair| pair.name == asset)
+            .or_else(|| {
+                self.spot_meta.universe.iter().find(|pair| {
+                    self.token_name(pair.tokens[0]) == Some(base_name)
+                        && self.token_name(pair.tokens[1]) == Some(quote_name)
+                })
+            })
+            .ok_or(Error::AssetNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn resolver() -> AssetResolver {
+        let meta: Meta = serde_json::from_value(json!({
+            "universe": [
+                {"name": "BTC", "szDecimals": 5, "maxLeverage": 40},
+                {"name": "HYPE", "szDecimals": 2, "maxLeverage": 10}
+            ]
+        }))
+        .unwrap();
+        let token = |name: &str, index: usize, sz_decimals: u8| {
+            json!({
+                "name": name, "szDecimals": sz_decimals, "weiDecimals": 8, "index": index,
+                "tokenId": format!("0x{:032x}", index), "isCanonical": true
+            })
+        };
+        let spot_meta: SpotMeta = serde_json::from_value(json!({
+            "universe": [
+                {"tokens": [1, 0], "name": "PURR/USDC", "index": 0, "isCanonical": true},
+                {"tokens": [150, 0], "name": "@107", "index": 107, "isCanonical": false},
+                {"tokens": [150, 268], "name": "@166", "index": 166, "isCanonical": false}
+            ],
+            "tokens": [
+                token("USDC", 0, 8), token("PURR", 1, 0), token("HYPE", 150, 2),
+                token("USDT0", 268, 2)
+            ]
+        }))
+        .unwrap();
+        AssetResolver::from_meta(meta, spot_meta)
+    }
+
+    #[test]
+    fn test_spot_by_base_token_name() {
+        let resolver = resolver();
+
+        assert_eq!(resolver.resolve_key("HYPE/USDC").unwrap(), "@107");
+        assert_eq!(
+            resolver.precision("HYPE/USDC", MarketType::Spot).unwrap(),
+            AssetPrecision::for_spot(2)
+        );
+    }
+
+    #[test]
+    fn test_spot_quote_token_picks_pair() {
+        let resolver = resolver();
+
+        assert_eq!(resolver.resolve_key("HYPE/USDT0").unwrap(), "@166");
+        assert_eq!(resolver.quote_token("HYPE/USDT0").unwrap(), "USDT0");
+        assert_eq!(resolver.quote_token("HYPE/USDC").unwrap(), "USDC");
+        assert_eq!(resolver.quote_token("PURR/USDC").unwrap(), "USDC");
+        assert_eq!(resolver.quote_token("BTC").unwrap(), "USDC");
+        // HYPE isn't quoted in PURR
+        assert!(resolver.resolve_key("HYPE/PURR").is_err());
+    }
+
+    #[test]
+    fn test_spot_by_pair_name() {
+        let resolver = resolver();
+
+        assert_eq!(resolver.resolve_key("PURR/USDC").unwrap(), "@0");
+        assert_eq!(
+            resolver.precision("PURR/USDC", MarketType::Spot).unwrap(),
+            AssetPrecision::for_spot(0)
+        );
+
+        // Unknown pairs are an error rather than a guessed precision
+        assert!(resolver.resolve_key("NOPE/USDC").is_err());
+        assert!(resolver.precision("NOPE/USDC", MarketType::Spot).is_err());
+    }
+
+    #[test]
+    fn test_perp_passes_through() {
+        let resolver = resolver();
+
+        assert_eq!(MarketType::of("BTC"), MarketType::Perp);
+        assert_eq!(resolver.resolve_key("BTC").unwrap(), "BTC");
+        assert_eq!(
+            resolver.precision("BTC", MarketType::Perp).unwrap(),
+            AssetPrecision::for_perp(5)
+        );
+        // Perp HYPE is looked up in the perp universe, not the spot token
+        assert_eq!(
+            resolver.precision("HYPE", MarketType::Perp).unwrap(),
+            AssetPrecision::for_perp(2)
+        );
+        assert!(resolver.precision("DOGE", MarketType::Perp).is_err());
+
+        assert_eq!(resolver.max_leverage("BTC").unwrap(), 40);
+        assert_eq!(resolver.max_leverage("HYPE").unwrap(), 10);
+        assert!(resolver.max_leverage("HYPE/USDC").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_metadata_fetch_survives_transient_errors() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+
+        use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+        use serde_json::Value;
+
+        // meta fails twice with a 502 before answering
+        async fn info(
+            State(calls): State<Arc<AtomicU32>>,
+            Json(body): Json<Value>,
+        ) -> (StatusCode, Json<Value>) {
+            match body["type"].as_str() {
+                Some("meta") if calls.fetch_add(1, Ordering::SeqCst) < 2 => {
+                    (StatusCode::BAD_GATEWAY, Json(json!("bad gateway")))
+                }
+                Some("meta") => (
+                    StatusCode::OK,
+                    Json(
+                        json!({"universe": [{"name": "BTC", "szDecimals": 5, "maxLeverage": 40}]}),
+                    ),
+                ),
+                _ => (StatusCode::OK, Json(json!({"universe": [], "tokens": []}))),
+            }
+        }
+        let calls = Arc::new(AtomicU32::new(0));
+        let app = Router::new()
+            .route("/info", post(info))
+            .with_state(calls.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let info_client = InfoClient::new(None, Some(crate::BaseUrl::Custom(url)))
+            .await
+            .unwrap();
+        let retry = ReconnectPolicy {
+            base_delay: Duration::from_millis(1),
+            ..METADATA_RETRY
+        };
+        let resolver = AssetResolver::fetch(&info_client, &retry).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(
+            resolver.precision("BTC", MarketType::Perp).unwrap(),
+            AssetPrecision::for_perp(5)
+        );
+
+        // Out of attempts, the error says what was being fetched
+        calls.store(0, Ordering::SeqCst);
+        let once = ReconnectPolicy {
+            max_attempts: Some(2),
+            ..retry
+        };
+        let err = AssetResolver::fetch(&info_client, &once).await.unwrap_err();
+        assert!(err.to_string().contains("meta failed after 2 attempt(s)"));
+    }
+}
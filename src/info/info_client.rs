@@ -1,11 +1,13 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use alloy::primitives::Address;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc::UnboundedSender;
+use uuid::Uuid;
 
 use crate::{
+    helpers::uuid_to_hex_string,
     info::{
         ActiveAssetDataResponse, CandlesSnapshotResponse, FundingHistoryResponse,
         L2SnapshotResponse, OpenOrdersResponse, OrderInfo, RecentTradesResponse, UserFillsResponse,
@@ -13,12 +15,16 @@ use crate::{
     },
     meta::{AssetContext, Meta, SpotMeta, SpotMetaAndAssetCtxs},
     prelude::*,
+    rate_limiter::RateLimiter,
     req::HttpClient,
-    ws::{Subscription, WsManager},
+    ws::{ReconnectPolicy, Subscription, TradeInfo, WsManager},
     BaseUrl, Error, Message, OrderStatusResponse, ReferralResponse, UserFeesResponse,
     UserFundingResponse, UserTokenBalanceResponse,
 };
 
+/// Most fills the exchange returns for one `userFillsByTime` request
+const USER_FILLS_PAGE_LIMIT: usize = 2000;
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct CandleSnapshotRequest {
@@ -63,6 +69,12 @@ pub enum InfoRequest {
         user: Address,
     },
     #[serde(rename_all = "camelCase")]
+    UserFillsByTime {
+        user: Address,
+        start_time: u64,
+        end_time: Option<u64>,
+    },
+    #[serde(rename_all = "camelCase")]
     FundingHistory {
         coin: String,
         start_time: u64,
@@ -96,84 +108,127 @@ pub enum InfoRequest {
     },
 }
 
+/// `orderStatus` request addressed by cloid, which the endpoint accepts in place of the oid
+#[derive(Serialize)]
+#[serde(tag = "type", rename = "orderStatus")]
+struct OrderStatusByCloidRequest {
+    user: Address,
+    oid: String,
+}
+
 #[derive(Debug)]
 pub struct InfoClient {
     pub http_client: HttpClient,
     pub(crate) ws_manager: Option<WsManager>,
-    reconnect: bool,
+    reconnect: Option<ReconnectPolicy>,
+    ping_interval: Duration,
+    heartbeat_timeout: Duration,
 }
 
 impl InfoClient {
     pub async fn new(client: Option<Client>, base_url: Option<BaseUrl>) -> Result<InfoClient> {
-        Self::new_internal(client, base_url, false).await
+        Self::new_internal(client, base_url, None).await
     }
 
     pub async fn with_reconnect(
         client: Option<Client>,
         base_url: Option<BaseUrl>,
     ) -> Result<InfoClient> {
-        Self::new_internal(client, base_url, true).await
+        Self::new_internal(client, base_url, Some(ReconnectPolicy::default())).await
     }
 
     async fn new_internal(
         client: Option<Client>,
         base_url: Option<BaseUrl>,
-        reconnect: bool,
+        reconnect: Option<ReconnectPolicy>,
     ) -> Result<InfoClient> {
         let client = client.unwrap_or_default();
-        let base_url = base_url.unwrap_or(BaseUrl::Mainnet).get_url();
+        let base_url = base_url.unwrap_or(BaseUrl::Mainnet);
 
         Ok(InfoClient {
-            http_client: HttpClient { client, base_url },
+            http_client: HttpClient {
+                client,
+                base_url: base_url.get_url(),
+                mainnet: base_url.is_mainnet(),
+                rate_limiter: Some(RateLimiter::shared()),
+            },
             ws_manager: None,
             reconnect,
+            ping_interval: WsManager::SEND_PING_INTERVAL,
+            heartbeat_timeout: WsManager::HEARTBEAT_TIMEOUT,
         })
     }
 
-    pub async fn subscribe(
-        &mut self,
-        subscription: Subscription,
-        sender_channel: UnboundedSender<Message>,
-    ) -> Result<u32> {
+    /// Configure the WebSocket heartbeat
+    ///
+    /// A ping is sent every `ping_interval`. If no message (including the pong)
+    /// arrives within `timeout` the socket is considered dead, subscribers get
+    /// `Message::NoData` and, when created with `with_reconnect`, the client
+    /// reconnects. Must be called before the first `subscribe`.
+    pub fn with_heartbeat(mut self, ping_interval: Duration, timeout: Duration) -> Self {
+        self.ping_interval = ping_interval;
+        self.heartbeat_timeout = timeout;
+        self
+    }
+
+    /// Reconnect a dropped WebSocket following `policy`
+    ///
+    /// Overrides the default policy of `with_reconnect`, and turns
+    /// reconnecting on for clients created with `new`. Once `max_attempts`
+    /// is reached, subscribers get a `Message::HyperliquidError` and their
+    /// channels close. Must be called before the first `subscribe`.
+    pub fn with_reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect = Some(policy);
+        self
+    }
+
+    /// Use a different rate limiter for REST calls, or `None` to disable throttling
+    ///
+    /// By default all clients share `RateLimiter::shared()`.
+    pub fn with_rate_limiter(mut self, rate_limiter: Option<Arc<RateLimiter>>) -> Self {
+        self.http_client.rate_limiter = rate_limiter;
+        self
+    }
+
+    async fn ws_manager(&mut self) -> Result<&mut WsManager> {
         if self.ws_manager.is_none() {
             let ws_manager = WsManager::new(
                 format!("ws{}/ws", &self.http_client.base_url[4..]),
                 self.reconnect,
+                self.ping_interval,
+                self.heartbeat_timeout,
             )
             .await?;
             self.ws_manager = Some(ws_manager);
         }
 
+        self.ws_manager.as_mut().ok_or(Error::WsManagerNotFound)
+    }
+
+    pub async fn subscribe(
+        &mut self,
+        subscription: Subscription,
+        sender_channel: UnboundedSender<Message>,
+    ) -> Result<u32> {
         let identifier =
             serde_json::to_string(&subscription).map_err(|e| Error::JsonParse(e.to_string()))?;
 
-        self.ws_manager
-            .as_mut()
-            .ok_or(Error::WsManagerNotFound)?
+        self.ws_manager()
+            .await?
             .add_subscription(identifier, sender_channel)
             .await
     }
 
     pub async fn unsubscribe(&mut self, subscription_id: u32) -> Result<()> {
-        if self.ws_manager.is_none() {
-            let ws_manager = WsManager::new(
-                format!("ws{}/ws", &self.http_client.base_url[4..]),
-                self.reconnect,
-            )
-            .await?;
-            self.ws_manager = Some(ws_manager);
-        }
-
-        self.ws_manager
-            .as_mut()
-            .ok_or(Error::WsManagerNotFound)?
+        self.ws_manager()
+            .await?
             .remove_subscription(subscription_id)
             .await
     }
 
     async fn send_info_request<T: for<'a> Deserialize<'a>>(
         &self,
-        info_request: InfoRequest,
+        info_request: impl Serialize,
     ) -> Result<T> {
         let data =
             serde_json::to_string(&info_request).map_err(|e| Error::JsonParse(e.to_string()))?;
@@ -237,6 +292,45 @@ impl InfoClient {
         self.send_info_request(input).await
     }
 
+    /// All of a user's fills between `start_time` and `end_time` (ms), oldest first
+    ///
+    /// The exchange returns at most 2000 fills per request, so full pages are
+    /// followed by another request starting at the last fill's time. Fills seen
+    /// on both pages are dropped by `(oid, time)`.
+    pub async fn user_fills_by_time(
+        &self,
+        address: Address,
+        start_time: u64,
+        end_time: Option<u64>,
+    ) -> Result<Vec<TradeInfo>> {
+        let mut fills: Vec<TradeInfo> = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        let mut page_start = start_time;
+        loop {
+            let input = InfoRequest::UserFillsByTime {
+                user: address,
+                start_time: page_start,
+                end_time,
+            };
+            let page: Vec<TradeInfo> = self.send_info_request(input).await?;
+            let full_page = page.len() >= USER_FILLS_PAGE_LIMIT;
+            let last_time = page.iter().map(|fill| fill.time).max();
+
+            fills.extend(
+                page.into_iter()
+                    .filter(|fill| seen.insert((fill.oid, fill.time))),
+            );
+
+            match last_time {
+                // Stop if a full page didn't move forward, rather than loop forever
+                Some(last_time) if full_page && last_time > page_start => page_start = last_time,
+                _ => break,
+            }
+        }
+        fills.sort_by_key(|fill| fill.time);
+        Ok(fills)
+    }
+
     pub async fn funding_history(
         &self,
         coin: String,
@@ -302,6 +396,18 @@ impl InfoClient {
         self.send_info_request(input).await
     }
 
+    pub async fn query_order_by_cloid(
+        &self,
+        address: Address,
+        cloid: Uuid,
+    ) -> Result<OrderStatusResponse> {
+        let input = OrderStatusByCloidRequest {
+            user: address,
+            oid: uuid_to_hex_string(cloid),
+        };
+        self.send_info_request(input).await
+    }
+
     pub async fn query_referral_state(&self, address: Address) -> Result<ReferralResponse> {
         let input = InfoRequest::Referral { user: address };
         self.send_info_request(input).await
@@ -321,3 +427,56 @@ impl InfoClient {
         self.send_info_request(input).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Json, Router};
+    use serde_json::{json, Value};
+
+    fn fill(oid: u64, time: u64) -> Value {
+        json!({
+            "coin": "BTC", "side": "B", "px": "50000", "sz": "0.1", "time": time,
+            "hash": "0x0", "startPosition": "0", "dir": "Open Long", "closedPnl": "0",
+            "oid": oid, "cloid": null, "crossed": false, "fee": "0.5",
+            "feeToken": "USDC", "tid": oid
+        })
+    }
+
+    #[tokio::test]
+    async fn test_user_fills_by_time_follows_pages() {
+        // A full first page, then a page that repeats its last fill
+        async fn info(Json(body): Json<Value>) -> Json<Value> {
+            assert_eq!(body["type"], "userFillsByTime");
+            let fills: Vec<Value> = match body["startTime"].as_u64().unwrap() {
+                1_000 => (0..USER_FILLS_PAGE_LIMIT as u64)
+                    .map(|i| fill(i, 1_000 + i))
+                    .collect(),
+                2_999 => vec![fill(1_999, 2_999), fill(5_000, 3_000), fill(5_001, 3_500)],
+                start => panic!("unexpected page start {start}"),
+            };
+            Json(Value::Array(fills))
+        }
+
+        let app = Router::new().route("/info", post(info));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let mut info_client = InfoClient::new(None, None)
+            .await
+            .unwrap()
+            .with_rate_limiter(None);
+        info_client.http_client.base_url = format!("http://{addr}");
+
+        let fills = info_client
+            .user_fills_by_time(Address::ZERO, 1_000, None)
+            .await
+            .unwrap();
+
+        assert_eq!(fills.len(), USER_FILLS_PAGE_LIMIT + 2);
+        assert_eq!(fills.iter().filter(|f| f.oid == 1_999).count(), 1);
+        assert_eq!(fills.last().unwrap().oid, 5_001);
+        assert!(fills.windows(2).all(|w| w[0].time <= w[1].time));
+    }
+}
@@ -1,6 +1,8 @@
+mod asset_resolver;
 pub(super) mod info_client;
 mod response_structs;
 mod sub_structs;
 
+pub use asset_resolver::{AssetResolver, MarketType};
 pub use response_structs::*;
 pub use sub_structs::*;
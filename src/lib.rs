@@ -1,4 +1,5 @@
 #![deny(unreachable_pub)]
+pub mod backtest;
 pub mod bot;
 pub mod config;
 
@@ -13,6 +14,7 @@ pub mod market;
 mod market_maker;
 mod meta;
 mod prelude;
+mod rate_limiter;
 mod req;
 mod signature;
 pub mod strategy;
@@ -23,6 +25,9 @@ pub use errors::Error;
 pub use exchange::*;
 pub use helpers::{bps_diff, truncate_float, BaseUrl};
 pub use info::{info_client::*, *};
-pub use market_maker::{MarketMaker, MarketMakerInput, MarketMakerRestingOrder};
+pub use market_maker::{
+    MarketMaker, MarketMakerInput, MarketMakerLevel, MarketMakerRestingOrder,
+};
+pub use rate_limiter::{RateLimiter, DEFAULT_BURST, DEFAULT_WEIGHT_PER_SECOND};
 pub use meta::{AssetContext, AssetMeta, Meta, MetaAndAssetCtxs, SpotAssetMeta, SpotMeta};
 pub use ws::*;
@@ -0,0 +1,131 @@
+//! Exact decimal prices and sizes for rounding to ticks and lots
+//!
+//! Strategies work in `f64`, but rounding a float to N decimals by scaling
+//! it (`0.29 * 100.0 == 28.999999999999996`) can land one tick or lot off.
+//! [`ExactDecimal`] takes the float's shortest representation, which is the
+//! number the float was meant to be, rounds that exactly and converts back.
+
+use std::fmt;
+use std::str::FromStr;
+
+use rust_decimal::{Decimal, RoundingStrategy};
+
+/// Float noise tolerated by directed rounding, in units of the last place
+/// kept: a value this close above a tick doesn't round up to the next one
+const NOISE_PLACES: u32 = 9;
+
+/// A price or size held as an exact decimal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ExactDecimal(Decimal);
+
+impl ExactDecimal {
+    /// The decimal `value` prints as, or None if it's not finite or too
+    /// large for 28 digits
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        Decimal::from_str(&value.to_string()).ok().map(Self)
+    }
+
+    /// The nearest `f64`
+    pub fn to_f64(self) -> f64 {
+        self.0.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    /// The underlying decimal
+    pub fn value(self) -> Decimal {
+        self.0
+    }
+
+    /// Round to the nearest multiple of `10^-decimals`, halves away from zero
+    pub fn round(self, decimals: u32) -> Self {
+        Self(
+            self.0
+                .round_dp_with_strategy(decimals, RoundingStrategy::MidpointAwayFromZero),
+        )
+    }
+
+    /// Round up to a multiple of `10^-decimals`, leaving values within float
+    /// noise of the one below there
+    pub fn round_up(self, decimals: u32) -> Self {
+        let noise = Decimal::new(1, decimals + NOISE_PLACES);
+        Self(
+            (self.0 - noise).round_dp_with_strategy(decimals, RoundingStrategy::ToPositiveInfinity),
+        )
+    }
+
+    /// Round down to a multiple of `10^-decimals`, leaving values within
+    /// float noise of the one above there
+    pub fn round_down(self, decimals: u32) -> Self {
+        let noise = Decimal::new(1, decimals + NOISE_PLACES);
+        Self(
+            (self.0 + noise).round_dp_with_strategy(decimals, RoundingStrategy::ToNegativeInfinity),
+        )
+    }
+}
+
+impl fmt::Display for ExactDecimal {
+    /// Without trailing zeros, as the exchange expects on the wire
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.normalize())
+    }
+}
+
+/// Apply `round` to `value` in exact decimal, or leave `value` as it is when
+/// it has no decimal form
+pub(crate) fn round_f64(value: f64, round: impl FnOnce(ExactDecimal) -> ExactDecimal) -> f64 {
+    ExactDecimal::from_f64(value).map_or(value, |exact| round(exact).to_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exact(value: f64) -> ExactDecimal {
+        ExactDecimal::from_f64(value).unwrap()
+    }
+
+    #[test]
+    fn test_problem_values_round_to_intended_ticks() {
+        // Scaling lands just below the lot: a float floor loses a whole one
+        assert_eq!(exact(0.29).round_down(2).to_f64(), 0.29);
+        assert_eq!(exact(1.15).round_down(2).to_f64(), 1.15);
+        // A true halfway value rounds away from zero, not by its float error
+        assert_eq!(exact(1.005).round(2).to_f64(), 1.01);
+        // Arithmetic noise either side of a tick stays on it
+        assert_eq!(exact(0.1 + 0.2).round_up(1).to_f64(), 0.3);
+        assert_eq!(exact(0.7 - 0.1).round_down(1).to_f64(), 0.6);
+        assert_eq!(exact(100.0 * 1.01).round_up(2).to_f64(), 101.0);
+        // Real distance from a tick still rounds
+        assert_eq!(exact(101.001).round_up(2).to_f64(), 101.01);
+        assert_eq!(exact(0.299).round_down(2).to_f64(), 0.29);
+    }
+
+    #[test]
+    fn test_rounding_is_stable_when_repeated() {
+        for i in 1..5_000 {
+            let value = i as f64 * 0.013 * 1.07;
+            for decimals in 0..6 {
+                for round in [
+                    ExactDecimal::round,
+                    ExactDecimal::round_up,
+                    ExactDecimal::round_down,
+                ] {
+                    let once = round(exact(value), decimals).to_f64();
+                    let twice = round(exact(once), decimals).to_f64();
+                    assert_eq!(once, twice, "{value} at {decimals} decimals");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_display_and_edge_values() {
+        assert_eq!(exact(2.50).to_string(), "2.5");
+        assert_eq!(exact(1e-7).round(8).to_string(), "0.0000001");
+        assert!(ExactDecimal::from_f64(f64::NAN).is_none());
+        assert!(ExactDecimal::from_f64(1e300).is_none());
+        assert_eq!(round_f64(1e300, |d| d.round(2)), 1e300);
+    }
+}
@@ -0,0 +1,104 @@
+//! Errors returned by market order operations
+
+use thiserror::Error;
+
+/// Why an order operation on a market failed
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum MarketError {
+    /// The exchange doesn't list the asset
+    #[error("Asset not found")]
+    AssetNotFound,
+    /// The exchange refused the order for a reason not covered below
+    #[error("Order rejected: {reason}")]
+    OrderRejected { reason: String },
+    /// Not enough margin or spot balance for the order
+    #[error("Insufficient balance: {0}")]
+    InsufficientBalance(String),
+    /// Price or size doesn't fit the asset's tick or lot size
+    #[error("Precision violation: {0}")]
+    PrecisionViolation(String),
+    /// The exchange couldn't be reached
+    #[error("Disconnected: {0}")]
+    Disconnected(String),
+    /// No active order with this id is tracked by the market
+    #[error("Unknown or inactive order {0}")]
+    UnknownOrder(u64),
+}
+
+impl MarketError {
+    /// Classify an error status string returned by the exchange
+    pub fn from_exchange(reason: &str) -> Self {
+        let lower = reason.to_lowercase();
+        if lower.contains("insufficient") {
+            MarketError::InsufficientBalance(reason.to_string())
+        } else if lower.contains("invalid price")
+            || lower.contains("invalid size")
+            || lower.contains("tick size")
+        {
+            MarketError::PrecisionViolation(reason.to_string())
+        } else if lower.contains("unknown asset") || lower.contains("asset not found") {
+            MarketError::AssetNotFound
+        } else {
+            MarketError::OrderRejected {
+                reason: reason.to_string(),
+            }
+        }
+    }
+}
+
+impl From<crate::Error> for MarketError {
+    fn from(e: crate::Error) -> Self {
+        match e {
+            crate::Error::AssetNotFound => MarketError::AssetNotFound,
+            crate::Error::GenericRequest(_)
+            | crate::Error::ServerRequest { .. }
+            | crate::Error::Websocket(_) => MarketError::Disconnected(e.to_string()),
+            e => MarketError::OrderRejected {
+                reason: e.to_string(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exchange_errors_map_to_variants() {
+        assert!(matches!(
+            MarketError::from_exchange("Insufficient margin to place order. asset=0"),
+            MarketError::InsufficientBalance(_)
+        ));
+        assert!(matches!(
+            MarketError::from_exchange("Insufficient spot balance asset=10107"),
+            MarketError::InsufficientBalance(_)
+        ));
+        assert!(matches!(
+            MarketError::from_exchange("Order has invalid price."),
+            MarketError::PrecisionViolation(_)
+        ));
+        assert!(matches!(
+            MarketError::from_exchange("Order has invalid size."),
+            MarketError::PrecisionViolation(_)
+        ));
+        assert_eq!(
+            MarketError::from_exchange("Post only order would have immediately matched"),
+            MarketError::OrderRejected {
+                reason: "Post only order would have immediately matched".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_client_errors_map_to_variants() {
+        assert_eq!(
+            MarketError::from(crate::Error::AssetNotFound),
+            MarketError::AssetNotFound
+        );
+        assert!(matches!(
+            MarketError::from(crate::Error::GenericRequest("timed out".to_string())),
+            MarketError::Disconnected(_)
+        ));
+    }
+}
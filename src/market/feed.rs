@@ -0,0 +1,177 @@
+//! Price and fill sources for the market event loops
+//!
+//! `HyperliquidMarket` and `PaperTradingMarket` read everything that drives
+//! the listener from one channel of [`Message`]s. A [`PriceFeed`] fills it
+//! with `AllMids` (and optionally `L2Book`) messages, a [`FillFeed`] with
+//! `User` fill messages. `InfoClient` implements both over the Hyperliquid
+//! websocket and is what the markets use unless given another feed.
+//!
+//! # Other venues
+//!
+//! To trade on prices from elsewhere, implement the traits and translate the
+//! venue's updates into the same messages. Prices are keyed by exchange key
+//! (e.g., "BTC", "@107"); fills name the exchange order id in `oid` and the
+//! client order id, if any, in `cloid`. The event loop stops once every
+//! sender is dropped, so a feed that never ends must keep one alive.
+//!
+//! # Replay
+//!
+//! [`ReplayPriceFeed`] and [`ReplayFillFeed`] send a recorded sequence and
+//! then end the feed, so a market run against them is deterministic and
+//! returns from `start` once the recording has been processed.
+
+use std::collections::HashMap;
+
+use alloy::primitives::Address;
+use async_trait::async_trait;
+use log::warn;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{AllMids, AllMidsData, InfoClient, Message, Subscription, TradeInfo, User, UserData};
+
+/// Source of the price messages a market's event loop trades on
+#[async_trait]
+pub trait PriceFeed: Send + Sync {
+    /// Start sending mid prices, and the books of `coins` (exchange keys,
+    /// e.g. "@107") if available, to `sender`
+    async fn subscribe_prices(
+        &mut self,
+        coins: &[String],
+        sender: UnboundedSender<Message>,
+    ) -> Result<(), crate::Error>;
+}
+
+/// Source of the fills on a user's orders
+#[async_trait]
+pub trait FillFeed: Send + Sync {
+    /// Start sending `user`'s fills to `sender`
+    async fn subscribe_fills(
+        &mut self,
+        user: Address,
+        sender: UnboundedSender<Message>,
+    ) -> Result<(), crate::Error>;
+}
+
+#[async_trait]
+impl PriceFeed for InfoClient {
+    async fn subscribe_prices(
+        &mut self,
+        coins: &[String],
+        sender: UnboundedSender<Message>,
+    ) -> Result<(), crate::Error> {
+        self.subscribe(Subscription::AllMids, sender.clone())
+            .await?;
+
+        // The venue book is informational, so carry on without it
+        for coin in coins {
+            let subscription = Subscription::L2Book { coin: coin.clone() };
+            if let Err(e) = self.subscribe(subscription, sender.clone()).await {
+                warn!("Failed to subscribe to L2Book: {e}");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FillFeed for InfoClient {
+    async fn subscribe_fills(
+        &mut self,
+        user: Address,
+        sender: UnboundedSender<Message>,
+    ) -> Result<(), crate::Error> {
+        self.subscribe(Subscription::UserEvents { user }, sender)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Replays recorded mid prices, one `AllMids` message per snapshot
+#[derive(Debug, Clone, Default)]
+pub struct ReplayPriceFeed {
+    snapshots: Vec<HashMap<String, f64>>,
+}
+
+impl ReplayPriceFeed {
+    /// Replay `prices` for a single exchange key, in order
+    pub fn new(coin: &str, prices: impl IntoIterator<Item = f64>) -> Self {
+        let snapshots = prices
+            .into_iter()
+            .map(|price| HashMap::from([(coin.to_string(), price)]))
+            .collect();
+        Self { snapshots }
+    }
+
+    /// Append a snapshot of several prices, by exchange key
+    pub fn with_snapshot(mut self, mids: HashMap<String, f64>) -> Self {
+        self.snapshots.push(mids);
+        self
+    }
+
+    /// Number of snapshots to replay
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /// Whether there is nothing to replay
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+}
+
+#[async_trait]
+impl PriceFeed for ReplayPriceFeed {
+    /// Queue every snapshot at once; no books are replayed
+    async fn subscribe_prices(
+        &mut self,
+        _coins: &[String],
+        sender: UnboundedSender<Message>,
+    ) -> Result<(), crate::Error> {
+        for mids in self.snapshots.drain(..) {
+            let mids = mids
+                .into_iter()
+                .map(|(coin, price)| (coin, price.to_string()))
+                .collect();
+            let message = Message::AllMids(AllMids {
+                data: AllMidsData { mids },
+            });
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replays recorded fills, one `User` message per fill
+#[derive(Debug, Clone, Default)]
+pub struct ReplayFillFeed {
+    fills: Vec<TradeInfo>,
+}
+
+impl ReplayFillFeed {
+    /// Replay `fills` in order, whoever the subscribing user is
+    pub fn new(fills: Vec<TradeInfo>) -> Self {
+        Self { fills }
+    }
+}
+
+#[async_trait]
+impl FillFeed for ReplayFillFeed {
+    /// Queue every fill at once, ahead of anything sent later
+    async fn subscribe_fills(
+        &mut self,
+        _user: Address,
+        sender: UnboundedSender<Message>,
+    ) -> Result<(), crate::Error> {
+        for fill in self.fills.drain(..) {
+            let message = Message::User(User {
+                data: UserData::Fills(vec![fill]),
+            });
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+//! Metered queue between the WebSocket task and a market's event loop
+//!
+//! The WebSocket task never waits on the market, so when the event loop falls
+//! behind (e.g. a burst of fills each placing orders) messages pile up in the
+//! channel. `FeedQueue` tracks how deep that backlog gets, warns when it
+//! crosses `FEED_BACKLOG_WARN`, and with a capacity set keeps it bounded by
+//! skipping price updates that newer ones already supersede. Fills and other
+//! events are never dropped.
+
+use std::collections::VecDeque;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::Message;
+
+/// Queued messages at which markets warn that they lag the feed
+pub const FEED_BACKLOG_WARN: usize = 100;
+
+/// How far a market's event loop lags its WebSocket feed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedBacklog {
+    /// Messages still queued when the last one was taken
+    pub depth: usize,
+    /// Deepest the queue has been since start
+    pub max_depth: usize,
+    /// Price updates skipped because newer ones were queued behind them
+    pub dropped: u64,
+}
+
+/// Receives feed messages, recording the backlog behind each one
+#[derive(Debug)]
+pub(super) struct FeedQueue {
+    receiver: UnboundedReceiver<Message>,
+    /// Messages kept after coalescing an over-capacity backlog
+    pending: VecDeque<Message>,
+    capacity: Option<usize>,
+    backlog: FeedBacklog,
+    lagging: bool,
+}
+
+impl FeedQueue {
+    /// Wrap a feed receiver; with `capacity`, coalesce price updates beyond it
+    pub(super) fn new(receiver: UnboundedReceiver<Message>, capacity: Option<usize>) -> Self {
+        Self {
+            receiver,
+            pending: VecDeque::new(),
+            capacity,
+            backlog: FeedBacklog::default(),
+            lagging: false,
+        }
+    }
+
+    /// The next message, or `None` once the feed closed
+    ///
+    /// Cancel safe, so it can be used in `tokio::select!`.
+    pub(super) async fn recv(&mut self) -> Option<Message> {
+        if let Some(message) = self.pending.pop_front() {
+            self.record(self.pending.len() + self.receiver.len());
+            return Some(message);
+        }
+        let message = self.receiver.recv().await?;
+        let depth = self.receiver.len();
+        if self.capacity.is_some_and(|capacity| depth > capacity) {
+            self.backlog.max_depth = self.backlog.max_depth.max(depth);
+            self.coalesce(message);
+            let message = self.pending.pop_front();
+            self.record(self.pending.len());
+            return message;
+        }
+        self.record(depth);
+        Some(message)
+    }
+
+    /// Backlog statistics so far
+    pub(super) fn backlog(&self) -> FeedBacklog {
+        self.backlog
+    }
+
+    /// Move everything queued into `pending`, keeping only the last AllMids
+    fn coalesce(&mut self, first: Message) {
+        let mut queued = vec![first];
+        while let Ok(message) = self.receiver.try_recv() {
+            queued.push(message);
+        }
+        let last_mids = queued
+            .iter()
+            .rposition(|message| matches!(message, Message::AllMids(_)));
+        for (i, message) in queued.into_iter().enumerate() {
+            if matches!(message, Message::AllMids(_)) && Some(i) != last_mids {
+                self.backlog.dropped += 1;
+            } else {
+                self.pending.push_back(message);
+            }
+        }
+    }
+
+    fn record(&mut self, depth: usize) {
+        self.backlog.depth = depth;
+        self.backlog.max_depth = self.backlog.max_depth.max(depth);
+        if !self.lagging && depth >= FEED_BACKLOG_WARN {
+            self.lagging = true;
+            warn!(
+                "{} feed messages queued, the event loop is falling behind",
+                depth
+            );
+        } else if self.lagging && depth < FEED_BACKLOG_WARN / 2 {
+            self.lagging = false;
+            info!("Feed backlog cleared");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AllMids, AllMidsData};
+    use std::collections::HashMap;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    fn mids(price: &str) -> Message {
+        Message::AllMids(AllMids {
+            data: AllMidsData {
+                mids: HashMap::from([("BTC".to_string(), price.to_string())]),
+            },
+        })
+    }
+
+    #[tokio::test]
+    async fn test_slow_consumer_drops_stale_prices() {
+        let (sender, receiver) = unbounded_channel();
+        let mut queue = FeedQueue::new(receiver, Some(4));
+
+        // The consumer falls ten messages behind, with a reconnect among them
+        for i in 0..10 {
+            sender.send(mids(&i.to_string())).unwrap();
+            if i == 3 {
+                sender.send(Message::Reconnected).unwrap();
+            }
+        }
+
+        // Only the reconnect and the newest prices survive
+        assert!(matches!(queue.recv().await, Some(Message::Reconnected)));
+        let backlog = queue.backlog();
+        assert_eq!(backlog.dropped, 9);
+        assert_eq!(backlog.max_depth, 10);
+        match queue.recv().await {
+            Some(Message::AllMids(all_mids)) => assert_eq!(all_mids.data.mids["BTC"], "9"),
+            other => panic!("expected the latest mids, got {:?}", other),
+        }
+        assert_eq!(queue.backlog().depth, 0);
+    }
+
+    #[tokio::test]
+    async fn test_unbounded_queue_keeps_every_message() {
+        let (sender, receiver) = unbounded_channel();
+        let mut queue = FeedQueue::new(receiver, None);
+        for i in 0..5 {
+            sender.send(mids(&i.to_string())).unwrap();
+        }
+
+        for remaining in (0..5).rev() {
+            assert!(queue.recv().await.is_some());
+            assert_eq!(queue.backlog().depth, remaining);
+        }
+        assert_eq!(queue.backlog().max_depth, 4);
+        assert_eq!(queue.backlog().dropped, 0);
+    }
+}
@@ -0,0 +1,156 @@
+//! Drive a strategy through a paper market from a test
+//!
+//! [`MarketTestHarness`] wires a strategy into a [`Bot`] on an offline
+//! [`PaperTradingMarket`]. Prices and fills are pushed in one call each and
+//! everything they trigger (strategy callbacks, order placement, simulated
+//! fills, queued cancels) has happened by the time the call returns, so tests
+//! can read orders and status straight away.
+//!
+//! Available in this crate's tests and, with the `test-util` feature, in
+//! downstream ones.
+
+use std::sync::Arc;
+
+use tokio::sync::RwLock;
+
+use super::paper_trading_market::PaperTradingMarket;
+use super::types::{AssetInfo, OrderFill, OrderRequest};
+use crate::bot::Bot;
+use crate::strategy::{Strategy, StrategyStatus};
+
+/// A strategy trading on an offline paper market, driven by hand
+pub struct MarketTestHarness<S: Strategy> {
+    market: PaperTradingMarket<Bot<S>>,
+    bot: Arc<RwLock<Bot<S>>>,
+}
+
+impl<S: Strategy> MarketTestHarness<S> {
+    /// Trade `strategy` on `asset_info`'s asset, starting with its
+    /// `usdc_balance` and no fees
+    pub async fn new(asset_info: AssetInfo, strategy: S) -> Result<Self, crate::Error> {
+        let bot = Arc::new(RwLock::new(Bot::new(strategy)));
+        let balance = asset_info.usdc_balance;
+        let market = PaperTradingMarket::offline(asset_info, balance, bot.clone()).await?;
+        Ok(Self { market, bot })
+    }
+
+    /// Move the price, filling every resting order it crosses
+    pub fn push_price(&mut self, price: f64) {
+        let asset = self.market.asset.clone();
+        self.market.update_price(&asset, price);
+        self.market.run_listener_commands();
+    }
+
+    /// Fill a resting order in full at its limit price, whatever the price
+    ///
+    /// Returns false if no such order is resting.
+    pub fn fill(&mut self, order_id: u64) -> bool {
+        let Some(order) = self.open_order(order_id) else {
+            return false;
+        };
+        let fill = OrderFill::new(order_id, &order.asset, order.qty, order.limit_price);
+        self.market.execute_fill(fill);
+        self.market.run_listener_commands();
+        true
+    }
+
+    /// Deliver a timer tick at `now`, in Unix seconds
+    pub fn tick(&mut self, now: u64) {
+        self.market.tick(now);
+        self.market.run_listener_commands();
+    }
+
+    /// Orders resting on the paper book, by ascending order id
+    pub fn open_orders(&self) -> Vec<OrderRequest> {
+        self.market
+            .open_orders()
+            .into_iter()
+            .map(|(_, order, _)| order)
+            .collect()
+    }
+
+    /// A resting order by id
+    pub fn open_order(&self, order_id: u64) -> Option<OrderRequest> {
+        self.open_orders()
+            .into_iter()
+            .find(|order| order.order_id == order_id)
+    }
+
+    /// The bot's current status
+    pub fn status(&self) -> StrategyStatus {
+        self.bot
+            .try_read()
+            .expect("the harness holds the only lock")
+            .status()
+    }
+
+    /// The bot, as shared with the market
+    pub fn bot(&self) -> Arc<RwLock<Bot<S>>> {
+        self.bot.clone()
+    }
+
+    /// The paper market, for balances, positions and anything else
+    pub fn market(&mut self) -> &mut PaperTradingMarket<Bot<S>> {
+        &mut self.market
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::{AssetPrecision, OrderSide};
+    use crate::strategy::spot_grid::{GridMode, SpotGridStrategy};
+
+    #[tokio::test]
+    async fn test_grid_roundtrip_through_paper_market() {
+        let precision = AssetPrecision {
+            sz_decimals: 2,
+            price_decimals: 2,
+            max_decimals: 8,
+        };
+        // Lines 100, 110, 120: buy 100 below the start, sell the held unit at 120
+        let strategy = SpotGridStrategy::new(
+            "SOL/USDC".to_string(),
+            100.0,
+            120.0,
+            3,
+            GridMode::Arithmetic,
+            Some(1.0),
+            None,
+            precision,
+            110.0,
+        );
+        let asset_info = AssetInfo::new("SOL/USDC", 0.0, 1000.0, 2, 2);
+        let mut harness = MarketTestHarness::new(asset_info, strategy).await.unwrap();
+
+        harness.push_price(110.0);
+        let sides: Vec<(OrderSide, f64)> = harness
+            .open_orders()
+            .iter()
+            .map(|o| (o.side, o.limit_price))
+            .collect();
+        assert_eq!(
+            sides,
+            vec![(OrderSide::Buy, 100.0), (OrderSide::Sell, 120.0)]
+        );
+
+        // Inject a fill of the buy the price never reached; its counter-sell at 110 rests
+        harness.push_price(104.0);
+        let buy = harness.open_orders()[0].order_id;
+        assert!(harness.fill(buy));
+        assert!(!harness.fill(buy));
+        let counter = harness
+            .open_orders()
+            .into_iter()
+            .find(|o| o.side == OrderSide::Sell && o.limit_price == 110.0)
+            .unwrap();
+
+        // Price crossing the counter-sell fills it on the paper book
+        harness.push_price(111.0);
+        assert!(harness.open_order(counter.order_id).is_none());
+        let status = harness.status();
+        assert_eq!(status.realized_pnl, 10.0);
+        assert_eq!(status.trade_count, 1);
+        assert_eq!(harness.market().current_price("SOL/USDC"), Some(111.0));
+    }
+}
@@ -2,18 +2,32 @@
 //!
 //! Connects to the Hyperliquid exchange and implements the Market interface.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 
 use alloy::{primitives::Address, signers::local::PrivateKeySigner};
-use log::{debug, error, info};
+use futures_util::StreamExt;
+use log::{debug, error, info, warn};
 use tokio::sync::{mpsc::unbounded_channel, RwLock};
+use uuid::Uuid;
 
-use super::listener::MarketListener;
-use super::types::{AssetInfo, OrderFill, OrderRequest, OrderStatus};
+use super::error::MarketError;
+use super::feed::{FillFeed, PriceFeed};
+use super::feed_queue::{FeedBacklog, FeedQueue};
+use super::listener::{
+    unix_now, unix_now_ms, FeedWatchdog, MarketListener, ORDER_EXPIRED, TICK_INTERVAL,
+};
+use super::types::{
+    AssetInfo, AssetPrecision, MarketBook, MarketCommand, OpenOrderDrift, OrderFill, OrderRequest,
+    OrderStatus, PriceSource, TimeInForce,
+};
 use crate::{
-    BaseUrl, ClientCancelRequest, ClientLimit, ClientOrder, ClientOrderRequest, ExchangeClient,
-    ExchangeDataStatus, ExchangeResponseStatus, InfoClient, Message, Subscription, UserData,
+    helpers::uuid_to_hex_string, AssetCtx, AssetResolver, BaseUrl, BuilderInfo,
+    ClientCancelRequest, ClientLimit, ClientModifyRequest, ClientOrder, ClientOrderRequest,
+    ExchangeClient, ExchangeDataStatus, ExchangeResponseStatus, FilledOrder, InfoClient,
+    MarketType, Message, RestingOrder, Subscription, UserData,
 };
 
 /// Input configuration for creating a HyperliquidMarket
@@ -21,10 +35,25 @@ use crate::{
 pub struct HyperliquidMarketInput {
     /// Asset to trade (e.g., "BTC", "ETH")
     pub asset: String,
+    /// Further assets traded through the same listener (e.g., "ETH" next to "BTC")
+    pub extra_assets: Vec<String>,
     /// Wallet containing private key for signing
     pub wallet: PrivateKeySigner,
     /// Base URL (Mainnet or Testnet)
     pub base_url: Option<BaseUrl>,
+    /// Worst price accepted when flattening, in BPS from mid (widened once if unfilled)
+    pub max_slippage_bps: u32,
+    /// Queued feed messages beyond which stale price updates are skipped
+    /// (default: unbounded)
+    pub feed_capacity: Option<usize>,
+    /// Builder code attached to every order for fee sharing; the wallet
+    /// must have approved its fee with `ExchangeClient::approve_builder_fee`
+    pub builder: Option<BuilderInfo>,
+    /// Log orders, modifies and cancels instead of sending them; orders rest
+    /// immediately under a made-up oid. Market data is still live.
+    pub dry_run: bool,
+    /// Price the listener trades on: mid, mark or oracle
+    pub price_source: PriceSource,
 }
 
 /// Internal order tracking for Hyperliquid
@@ -32,6 +61,8 @@ pub struct HyperliquidMarketInput {
 struct TrackedOrder {
     /// Exchange order ID (oid) - internal to Hyperliquid
     exchange_oid: Option<u64>,
+    /// Client order ID sent with the order
+    cloid: Uuid,
     /// Original request (contains user's order_id)
     request: OrderRequest,
     /// Current status
@@ -40,16 +71,20 @@ struct TrackedOrder {
     filled_qty: f64,
     /// Average fill price
     avg_fill_price: f64,
+    /// Fees paid across fills, in quote currency
+    fees: f64,
 }
 
 impl TrackedOrder {
-    fn new(request: OrderRequest) -> Self {
+    fn new(request: OrderRequest, cloid: Uuid) -> Self {
         Self {
             exchange_oid: None,
+            cloid,
             request,
             status: OrderStatus::Pending,
             filled_qty: 0.0,
             avg_fill_price: 0.0,
+            fees: 0.0,
         }
     }
 
@@ -70,6 +105,40 @@ impl TrackedOrder {
     }
 }
 
+/// Recently processed fills, so ones redelivered after a reconnect are skipped
+///
+/// Fills are keyed by (oid, time, tid). Only the last `capacity` are kept.
+#[derive(Debug)]
+struct SeenFills {
+    keys: HashSet<(u64, u64, u64)>,
+    order: VecDeque<(u64, u64, u64)>,
+    capacity: usize,
+}
+
+impl SeenFills {
+    fn new(capacity: usize) -> Self {
+        Self {
+            keys: HashSet::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Record a fill; false if it was already seen
+    fn insert(&mut self, key: (u64, u64, u64)) -> bool {
+        if !self.keys.insert(key) {
+            return false;
+        }
+        self.order.push_back(key);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.keys.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
 /// Hyperliquid Market implementation
 ///
 /// Connects to the Hyperliquid exchange via WebSocket and REST APIs.
@@ -80,14 +149,20 @@ impl TrackedOrder {
 /// ```ignore
 /// use std::sync::Arc;
 /// use tokio::sync::RwLock;
-/// use hyperliquid_rust_sdk::market::{HyperliquidMarket, HyperliquidMarketInput};
+/// use hyperliquid_rust_sdk::market::{HyperliquidMarket, HyperliquidMarketInput, PriceSource};
 /// use hyperliquid_rust_sdk::bot::Bot;
 ///
 /// let bot = Arc::new(RwLock::new(Bot::new(my_strategy)));
 /// let input = HyperliquidMarketInput {
 ///     asset: "BTC".to_string(),
+///     extra_assets: vec!["ETH".to_string()],
 ///     wallet: wallet,
 ///     base_url: Some(BaseUrl::Testnet),
+///     max_slippage_bps: 100,
+///     feed_capacity: None,
+///     builder: None,
+///     dry_run: false,
+///     price_source: PriceSource::Mid,
 /// };
 ///
 /// let mut market = HyperliquidMarket::new(input, bot.clone()).await?;
@@ -95,14 +170,22 @@ impl TrackedOrder {
 /// market.start().await;
 /// ```
 pub struct HyperliquidMarket<L: MarketListener> {
-    /// Asset being traded
+    /// Primary asset being traded
     pub asset: String,
-    /// Cached asset info (balances and precision)
-    asset_info: AssetInfo,
+    /// Cached info (balances and precision) for every traded asset, by name
+    assets: HashMap<String, AssetInfo>,
+    /// Traded asset names by exchange key ("@107" -> "HYPE/USDC")
+    asset_keys: HashMap<String, String>,
+    /// Token each traded asset is quoted in, by name ("HYPE/USDT0" -> "USDT0")
+    quote_tokens: HashMap<String, String>,
     /// Shared listener instance for external access
     listener: Arc<RwLock<L>>,
     /// Info client for market data
     pub info_client: InfoClient,
+    /// Prices to trade on instead of the info client's websocket
+    price_feed: Option<Box<dyn PriceFeed>>,
+    /// Fills to act on instead of the info client's websocket
+    fill_feed: Option<Box<dyn FillFeed>>,
     /// Exchange client for order management
     pub exchange_client: ExchangeClient,
     /// User's wallet address
@@ -113,9 +196,50 @@ pub struct HyperliquidMarket<L: MarketListener> {
     orders: HashMap<u64, TrackedOrder>,
     /// Maps exchange OID to user's order_id
     exchange_oid_to_order_id: HashMap<u64, u64>,
+    /// Maps hex-encoded cloid to user's order_id
+    cloid_to_order_id: HashMap<String, u64>,
+    /// Random high half of every cloid, so ids never repeat across runs
+    cloid_prefix: u64,
+    /// Worst price accepted when flattening, in BPS from mid
+    max_slippage_bps: u32,
+    /// Fees paid on our fills since start, in quote currency
+    total_fees: f64,
+    /// Notices when the feed stops carrying the main asset's price
+    feed_watchdog: FeedWatchdog,
+    /// Queued feed messages beyond which stale price updates are skipped
+    feed_capacity: Option<usize>,
+    /// How far the event loop lagged the feed at the last message
+    feed_backlog: FeedBacklog,
+    /// Shut down; the event loop ends
+    stopped: bool,
+    /// Builder code sent with every order
+    builder: Option<BuilderInfo>,
+    /// Fills already applied, to ignore redeliveries
+    seen_fills: SeenFills,
+    /// Log instead of sending orders, modifies and cancels
+    dry_run: bool,
+    /// Price the listener's `on_price_update` receives
+    price_source: PriceSource,
+    /// Longest wait for one exchange request before it is abandoned
+    request_timeout: Duration,
 }
 
 impl<L: MarketListener> HyperliquidMarket<L> {
+    /// Levels per side forwarded to the listener from L2 book updates
+    const BOOK_DEPTH: usize = 10;
+
+    /// IOC attempts made to close a position, each with twice the slippage
+    const CLOSE_ATTEMPTS: u32 = 2;
+
+    /// Order requests `place_orders` keeps in flight at once
+    const MAX_CONCURRENT_ORDERS: usize = 8;
+
+    /// Fills remembered for deduplication
+    const SEEN_FILLS: usize = 10_000;
+
+    /// Default wait for an exchange request
+    pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
     /// Create a new HyperliquidMarket
     ///
     /// # Arguments
@@ -128,36 +252,99 @@ impl<L: MarketListener> HyperliquidMarket<L> {
         let user_address = input.wallet.address();
         let base_url = input.base_url.unwrap_or(BaseUrl::Mainnet);
 
-        let info_client = InfoClient::with_reconnect(None, Some(base_url)).await?;
+        let info_client = InfoClient::with_reconnect(None, Some(base_url.clone())).await?;
         let exchange_client =
             ExchangeClient::new(None, input.wallet, Some(base_url), None, None).await?;
 
         // Fetch and cache asset info (precision is static)
-        let asset_info = Self::fetch_asset_info(&info_client, &input.asset, user_address).await?;
+        let resolver = AssetResolver::new(&info_client).await?;
+        let mids = info_client.all_mids().await?;
+        let mut assets = HashMap::new();
+        let mut asset_keys = HashMap::new();
+        let mut quote_tokens = HashMap::new();
+        for asset in std::iter::once(&input.asset).chain(&input.extra_assets) {
+            let quote = resolver.quote_token(asset)?;
+            let asset_info =
+                Self::fetch_asset_info(&info_client, &resolver, asset, &quote, user_address)
+                    .await?;
+            quote_tokens.insert(asset.clone(), quote);
+            assets.insert(asset.clone(), asset_info);
+
+            // A key missing from the feed would leave the strategy without prices
+            let key = resolver.resolve_key(asset)?;
+            if !mids.contains_key(&key) {
+                error!("{} ({}) is not in the AllMids feed", asset, key);
+                return Err(crate::Error::AssetNotFound);
+            }
+            asset_keys.insert(key, asset.clone());
+            let is_spot = MarketType::of(asset) == MarketType::Spot;
+            if input.price_source == PriceSource::Oracle && is_spot {
+                warn!("{} is spot and has no oracle price, trading on its mark price", asset);
+            }
+        }
 
         Ok(Self {
             asset: input.asset,
-            asset_info,
+            assets,
+            asset_keys,
+            quote_tokens,
             listener,
             info_client,
+            price_feed: None,
+            fill_feed: None,
             exchange_client,
             user_address,
             prices: HashMap::new(),
             orders: HashMap::new(),
             exchange_oid_to_order_id: HashMap::new(),
+            cloid_to_order_id: HashMap::new(),
+            cloid_prefix: Uuid::new_v4().as_u64_pair().0,
+            max_slippage_bps: input.max_slippage_bps,
+            total_fees: 0.0,
+            feed_watchdog: FeedWatchdog::new(unix_now()),
+            feed_capacity: input.feed_capacity,
+            feed_backlog: FeedBacklog::default(),
+            stopped: false,
+            builder: input.builder,
+            seen_fills: SeenFills::new(Self::SEEN_FILLS),
+            dry_run: input.dry_run,
+            price_source: input.price_source,
+            request_timeout: Self::REQUEST_TIMEOUT,
         })
     }
 
     /// Fetch asset info from exchange (internal helper)
     async fn fetch_asset_info(
         info_client: &InfoClient,
+        resolver: &AssetResolver,
         asset: &str,
+        quote: &str,
         user_address: Address,
     ) -> Result<AssetInfo, crate::Error> {
-        let is_spot = asset.contains('/');
+        let (base_balance, usdc_balance) =
+            Self::fetch_balances(info_client, asset, quote, user_address).await?;
+        let precision = resolver.precision(asset, MarketType::of(asset))?;
+
+        Ok(AssetInfo::new(
+            asset,
+            base_balance,
+            usdc_balance,
+            precision.sz_decimals,
+            precision.price_decimals,
+        ))
+    }
 
-        // Get balances
-        let (base_balance, usdc_balance) = if is_spot {
+    /// Fetch an asset's base balance and its quote token's balance (internal helper)
+    ///
+    /// For perps the base balance is the signed position size and the quote
+    /// balance is the account value.
+    async fn fetch_balances(
+        info_client: &InfoClient,
+        asset: &str,
+        quote: &str,
+        user_address: Address,
+    ) -> Result<(f64, f64), crate::Error> {
+        if MarketType::of(asset) == MarketType::Spot {
             let balances = info_client.user_token_balances(user_address).await?;
             let base_name = asset.split('/').next().unwrap_or(asset);
 
@@ -168,14 +355,14 @@ impl<L: MarketListener> HyperliquidMarket<L> {
                 .and_then(|b| b.total.parse::<f64>().ok())
                 .unwrap_or(0.0);
 
-            let usdc_bal = balances
+            let quote_bal = balances
                 .balances
                 .iter()
-                .find(|b| b.coin == "USDC")
+                .find(|b| b.coin == quote)
                 .and_then(|b| b.total.parse::<f64>().ok())
                 .unwrap_or(0.0);
 
-            (base_bal, usdc_bal)
+            Ok((base_bal, quote_bal))
         } else {
             let state = info_client.user_state(user_address).await?;
 
@@ -192,105 +379,148 @@ impl<L: MarketListener> HyperliquidMarket<L> {
                 .parse::<f64>()
                 .unwrap_or(0.0);
 
-            (position, margin)
-        };
-
-        // Get precision
-        let (sz_decimals, price_decimals) = if is_spot {
-            let spot_meta = info_client.spot_meta().await?;
-            let base_name = asset.split('/').next().unwrap_or(asset);
-
-            let index_to_token: std::collections::HashMap<_, _> = spot_meta
-                .tokens
-                .iter()
-                .map(|t| (t.index, t))
-                .collect();
-
-            let mut found_sz = 4u32;
-            for spot_asset in &spot_meta.universe {
-                if let Some(token) = index_to_token.get(&spot_asset.tokens[0]) {
-                    if token.name == base_name || asset == spot_asset.name {
-                        found_sz = token.sz_decimals as u32;
-                        break;
-                    }
-                }
-            }
+            Ok((position, margin))
+        }
+    }
 
-            (found_sz, 6u32)
-        } else {
-            let meta = info_client.meta().await?;
-            let asset_meta = meta
-                .universe
-                .iter()
-                .find(|a| a.name == asset)
-                .ok_or_else(|| crate::Error::AssetNotFound)?;
+    /// Trade on prices from `feed` instead of the Hyperliquid websocket
+    pub fn with_price_feed(mut self, feed: impl PriceFeed + 'static) -> Self {
+        self.price_feed = Some(Box::new(feed));
+        self
+    }
 
-            (asset_meta.sz_decimals, 5u32)
-        };
+    /// Abandon exchange requests (orders, cancels, modifies and order
+    /// lookups) that take longer than `timeout` (default `REQUEST_TIMEOUT`)
+    ///
+    /// A hung connection would otherwise stall the event loop. An order
+    /// whose request timed out is looked up by cloid before it is sent
+    /// again, so the retry never places it twice.
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
 
-        Ok(AssetInfo::new(
-            asset,
-            base_balance,
-            usdc_balance,
-            sz_decimals,
-            price_decimals,
-        ))
+    /// Act on fills from `feed` instead of the Hyperliquid websocket
+    ///
+    /// Orders are still placed on the exchange; the feed's fills must name
+    /// them by exchange oid or cloid.
+    pub fn with_fill_feed(mut self, feed: impl FillFeed + 'static) -> Self {
+        self.fill_feed = Some(Box::new(feed));
+        self
     }
 
     /// Start the market event loop
     ///
-    /// Subscribes to AllMids (price updates) and UserEvents (fills)
-    /// and processes them in a loop. Orders returned by the listener
-    /// are automatically placed.
+    /// Subscribes to AllMids (price updates) and UserEvents (fills), plus
+    /// every asset's context when trading on mark or oracle prices, and
+    /// processes them in a loop. Orders returned by the listener
+    /// are automatically placed. The loop ends if both feeds do.
     pub async fn start(&mut self) {
-        let (sender, mut receiver) = unbounded_channel();
+        let (sender, receiver) = unbounded_channel();
+        let mut feed = FeedQueue::new(receiver, self.feed_capacity);
+        self.feed_watchdog = FeedWatchdog::new(unix_now());
 
         // Subscribe to UserEvents for fills
-        if let Err(e) = self
-            .info_client
-            .subscribe(
-                Subscription::UserEvents {
-                    user: self.user_address,
-                },
-                sender.clone(),
-            )
-            .await
-        {
+        let user = self.user_address;
+        let subscribed = match self.fill_feed.as_mut() {
+            Some(fill_feed) => fill_feed.subscribe_fills(user, sender.clone()).await,
+            None => self.info_client.subscribe_fills(user, sender.clone()).await,
+        };
+        if let Err(e) = subscribed {
             error!("Failed to subscribe to UserEvents: {e}");
             return;
         }
 
-        // Subscribe to AllMids for price updates
-        if let Err(e) = self
-            .info_client
-            .subscribe(Subscription::AllMids, sender)
-            .await
-        {
+        // Mark and oracle prices come with the asset context; a custom feed sends its own
+        if self.price_source != PriceSource::Mid && self.price_feed.is_none() {
+            for coin in self.coins() {
+                let subscription = Subscription::ActiveAssetCtx { coin: coin.clone() };
+                if let Err(e) = self.info_client.subscribe(subscription, sender.clone()).await {
+                    error!("Failed to subscribe to ActiveAssetCtx for {coin}: {e}");
+                    return;
+                }
+            }
+        }
+
+        // Subscribe to AllMids for price updates, and L2Book for every asset
+        let coins = self.coins();
+        let subscribed = match self.price_feed.as_mut() {
+            Some(price_feed) => price_feed.subscribe_prices(&coins, sender).await,
+            None => self.info_client.subscribe_prices(&coins, sender).await,
+        };
+        if let Err(e) = subscribed {
             error!("Failed to subscribe to AllMids: {e}");
             return;
         }
 
-        info!("HyperliquidMarket started for asset {}", self.asset);
+        info!(
+            "HyperliquidMarket started for {}",
+            self.assets.keys().cloned().collect::<Vec<_>>().join(", ")
+        );
 
-        loop {
-            match receiver.recv().await {
-                Some(message) => {
-                    // Process message and get orders to place
-                    let pending_orders = self.handle_message(message);
+        let mut tick_timer = tokio::time::interval(TICK_INTERVAL);
+        let ctrl_c = tokio::signal::ctrl_c();
+        tokio::pin!(ctrl_c);
 
-                    // Place orders returned by listener
-                    for order in pending_orders {
-                        self.place_order(order).await;
-                    }
+        while !self.stopped {
+            tokio::select! {
+                _ = &mut ctrl_c => {
+                    info!("Interrupted, shutting down");
+                    self.shutdown().await;
                 }
-                None => {
-                    error!("Channel closed");
-                    break;
+                message = feed.recv() => match message {
+                    Some(message) => {
+                        self.feed_backlog = feed.backlog();
+                        // Process message and get orders to place
+                        let pending_orders = self.handle_message(message);
+
+                        // Place orders returned by listener (failures are logged and
+                        // reported to the listener)
+                        self.place_orders(pending_orders).await;
+
+                        self.run_listener_commands().await;
+                    }
+                    None => {
+                        error!("Channel closed");
+                        break;
+                    }
+                },
+                _ = tick_timer.tick() => {
+                    self.tick(unix_now()).await;
+                    self.run_listener_commands().await;
                 }
             }
         }
     }
 
+    /// Exchange keys of the traded assets ("@107" for "HYPE/USDC"), which the
+    /// websocket subscriptions and messages name them by
+    fn coins(&self) -> Vec<String> {
+        self.asset_keys.keys().cloned().collect()
+    }
+
+    /// Forward a traded asset's mark or oracle price, if that is what the
+    /// listener trades on
+    fn on_asset_ctx(&mut self, coin: &str, ctx: &AssetCtx) -> Vec<OrderRequest> {
+        let Some(asset) = self.asset_keys.get(coin).cloned() else {
+            return vec![];
+        };
+        let price = match (self.price_source, ctx) {
+            (PriceSource::Mid, _) => return vec![],
+            (PriceSource::Oracle, AssetCtx::Perps(perp)) => &perp.oracle_px,
+            (_, AssetCtx::Perps(perp)) => &perp.shared.mark_px,
+            (_, AssetCtx::Spot(spot)) => &spot.shared.mark_px,
+        };
+        let Ok(price) = price.parse::<f64>() else {
+            warn!("Unreadable {:?} price {} for {}", self.price_source, price, asset);
+            return vec![];
+        };
+        match self.listener.try_write() {
+            Ok(mut listener) => listener.on_price_update(&asset, price),
+            Err(_) => vec![],
+        }
+    }
+
     /// Handle incoming WebSocket messages
     /// Returns orders that need to be placed (from listener callbacks)
     fn handle_message(&mut self, message: Message) -> Vec<OrderRequest> {
@@ -299,12 +529,19 @@ impl<L: MarketListener> HyperliquidMarket<L> {
         match message {
             Message::AllMids(all_mids) => {
                 let mids = all_mids.data.mids;
-                for (asset, price_str) in mids {
+                for (key, price_str) in mids {
                     if let Ok(price) = price_str.parse::<f64>() {
                         // Update internal price state (M1)
-                        self.prices.insert(asset.clone(), price);
-                        // Only notify listener for our configured asset
-                        if asset == self.asset {
+                        self.prices.insert(key.clone(), price);
+                        // Only notify listener for the assets we trade, by their own names
+                        if let Some(asset) = self.asset_keys.get(&key).cloned() {
+                            self.prices.insert(asset.clone(), price);
+                            if asset == self.asset {
+                                self.feed_watchdog.seen(unix_now());
+                            }
+                            if self.price_source != PriceSource::Mid {
+                                continue;
+                            }
                             if let Ok(mut listener) = self.listener.try_write() {
                                 let orders = listener.on_price_update(&asset, price);
                                 pending_orders.extend(orders);
@@ -313,25 +550,51 @@ impl<L: MarketListener> HyperliquidMarket<L> {
                     }
                 }
             }
+            Message::ActiveAssetCtx(ctx) => {
+                pending_orders.extend(self.on_asset_ctx(&ctx.data.coin, &ctx.data.ctx));
+            }
+            Message::ActiveSpotAssetCtx(ctx) => {
+                let spot = AssetCtx::Spot(ctx.data.ctx);
+                pending_orders.extend(self.on_asset_ctx(&ctx.data.coin, &spot));
+            }
             Message::User(user_events) => {
                 let user_data = user_events.data;
                 if let UserData::Fills(fills) = user_data {
                     for fill in fills {
                         let oid = fill.oid;
+                        if !self.seen_fills.insert((oid, fill.time, fill.tid)) {
+                            debug!("Skipping duplicate fill oid={} tid={}", oid, fill.tid);
+                            continue;
+                        }
                         let qty: f64 = fill.sz.parse().unwrap_or(0.0);
                         let price: f64 = fill.px.parse().unwrap_or(0.0);
                         let closed_pnl: f64 = fill.closed_pnl.parse().unwrap_or(0.0);
+                        let fee: f64 = fill.fee.parse().unwrap_or(0.0);
 
                         debug!(
                             "Fill received: oid={}, qty={}, price={}, side={}, closed_pnl={}",
                             oid, qty, price, fill.side, closed_pnl
                         );
 
-                        // Find order by exchange OID and update
-                        if let Some(&user_order_id) = self.exchange_oid_to_order_id.get(&oid) {
+                        // Find order by exchange OID (or cloid) and update
+                        let user_order_id = self.exchange_oid_to_order_id.get(&oid).or_else(|| {
+                            fill.cloid
+                                .as_ref()
+                                .and_then(|cloid| self.cloid_to_order_id.get(cloid))
+                        });
+                        if let Some(&user_order_id) = user_order_id {
                             if let Some(order) = self.orders.get_mut(&user_order_id) {
+                                // Matched by cloid ahead of the placement response
+                                if order.exchange_oid.is_none() {
+                                    order.exchange_oid = Some(oid);
+                                    self.exchange_oid_to_order_id.insert(oid, user_order_id);
+                                }
                                 let was_active = order.status.is_active();
                                 order.fill(qty, price);
+                                let fee =
+                                    fee_in_quote(&order.request.asset, fee, &fill.fee_token, price);
+                                order.fees += fee;
+                                self.total_fees += fee;
 
                                 if fill.side == "B" {
                                     info!("Fill: bought {} {} at {}", qty, fill.coin, price);
@@ -343,10 +606,13 @@ impl<L: MarketListener> HyperliquidMarket<L> {
                                 if was_active && matches!(order.status, OrderStatus::Filled(_)) {
                                     let order_fill = OrderFill::new(
                                         user_order_id,          // User's order_id
-                                        &fill.coin,
+                                        &order.request.asset,
                                         order.request.qty,      // Total order qty
                                         order.avg_fill_price,   // Average fill price
-                                    );
+                                    )
+                                    .with_fee(order.fees)
+                                    .with_tag(order.request.tag.clone())
+                                    .with_time(fill.time);
 
                                     info!(
                                         "Order {} fully filled: {} {} at avg price {}",
@@ -367,6 +633,24 @@ impl<L: MarketListener> HyperliquidMarket<L> {
                     }
                 }
             }
+            Message::L2Book(l2_book) => {
+                // Books arrive under the exchange key; report them by the asset's own name
+                if let Some(asset) = self.asset_keys.get(&l2_book.data.coin) {
+                    let book = MarketBook::from_l2(&l2_book.data, Self::BOOK_DEPTH);
+                    if let Ok(mut listener) = self.listener.try_write() {
+                        listener.on_book_update(asset, &book);
+                    }
+                }
+            }
+            Message::HyperliquidError(e) => {
+                error!("Feed error: {}", e);
+            }
+            Message::Reconnected => {
+                warn!("WebSocket reconnected; fills during the outage may have been missed");
+                if let Ok(mut listener) = self.listener.try_write() {
+                    listener.on_reconnect();
+                }
+            }
             _ => {
                 debug!("Received unhandled message type");
             }
@@ -375,6 +659,134 @@ impl<L: MarketListener> HyperliquidMarket<L> {
         pending_orders
     }
 
+    /// Carry out commands queued by the listener
+    async fn run_listener_commands(&mut self) {
+        let commands = match self.listener.try_write() {
+            Ok(mut listener) => listener.take_commands(),
+            Err(_) => return,
+        };
+        for command in commands {
+            match command {
+                MarketCommand::Flatten => self.flatten().await,
+                MarketCommand::RefreshPrecision => self.refresh_precision().await,
+                MarketCommand::Cancel(order_id) => {
+                    if let Err(e) = self.cancel_order(order_id).await {
+                        debug!("Order {} not cancelled: {}", order_id, e);
+                    }
+                }
+                MarketCommand::SyncOpenOrders => self.sync_open_orders().await,
+                MarketCommand::Stop => {
+                    info!("Listener asked to stop, shutting down");
+                    self.shutdown().await;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Check the tracked open orders against the exchange and pass the ones
+    /// still open to the listener, placing the orders it returns
+    ///
+    /// A tracked order missing from the exchange's list is looked up by
+    /// cloid. One that was cancelled or rejected is marked cancelled and
+    /// reported through `on_order_rejected`; one that filled stays open until
+    /// its fill arrives, as does one that can't be looked up.
+    async fn sync_open_orders(&mut self) {
+        let mut gone = Vec::new();
+        if !self.dry_run {
+            let request = self.info_client.open_orders(self.user_address);
+            let exchange_orders: Vec<_> = match self.with_timeout("open orders", request).await {
+                Ok(orders) => orders
+                    .into_iter()
+                    .filter(|o| self.asset_keys.contains_key(&o.coin))
+                    .collect(),
+                Err(e) => {
+                    warn!("Failed to fetch open orders: {}", e);
+                    return;
+                }
+            };
+            for (order_id, _, _) in self.open_orders() {
+                let tracked = &self.orders[&order_id];
+                let cloid = tracked.cloid;
+                let cloid_hex = uuid_to_hex_string(cloid);
+                let on_book = exchange_orders.iter().any(|o| {
+                    Some(o.oid) == tracked.exchange_oid
+                        || o.cloid.as_deref() == Some(cloid_hex.as_str())
+                });
+                if on_book {
+                    continue;
+                }
+                if let Some(Err(MarketError::OrderRejected { reason })) =
+                    self.find_order_by_cloid(cloid).await
+                {
+                    gone.push((order_id, reason));
+                }
+            }
+            for order in &exchange_orders {
+                if !self.exchange_oid_to_order_id.contains_key(&order.oid) {
+                    warn!("Exchange order {} on {} is not tracked", order.oid, order.coin);
+                }
+            }
+        }
+
+        let mut orders = Vec::new();
+        for (order_id, reason) in gone {
+            warn!("Order {} is no longer on the exchange: {}", order_id, reason);
+            if let Some(order) = self.orders.get_mut(&order_id) {
+                order.status = OrderStatus::Cancelled;
+            }
+            if let Ok(mut listener) = self.listener.try_write() {
+                orders.extend(listener.on_order_rejected(order_id, reason));
+            }
+        }
+        self.place_orders(orders).await;
+
+        let open: Vec<u64> = self.open_orders().into_iter().map(|(id, ..)| id).collect();
+        let orders = match self.listener.try_write() {
+            Ok(mut listener) => listener.on_open_orders(&open),
+            Err(_) => vec![],
+        };
+        self.place_orders(orders).await;
+    }
+
+    /// Re-fetch asset metadata and pass every traded asset's precision to
+    /// the listener, placing the orders it returns
+    async fn refresh_precision(&mut self) {
+        let resolver = match AssetResolver::new(&self.info_client).await {
+            Ok(resolver) => resolver,
+            Err(e) => {
+                warn!("Failed to re-fetch asset metadata: {}", e);
+                return;
+            }
+        };
+        let mut assets: Vec<String> = self.assets.keys().cloned().collect();
+        assets.sort();
+        let mut orders = vec![];
+        for asset in assets {
+            let precision = match resolver.precision(&asset, MarketType::of(&asset)) {
+                Ok(precision) => precision,
+                Err(e) => {
+                    warn!("No precision for {} in re-fetched metadata: {}", asset, e);
+                    continue;
+                }
+            };
+            if let Some(info) = self.assets.get_mut(&asset) {
+                if info.sz_decimals != precision.sz_decimals {
+                    info!(
+                        "{} sz_decimals changed from {} to {}",
+                        asset, info.sz_decimals, precision.sz_decimals
+                    );
+                }
+                info.sz_decimals = precision.sz_decimals;
+                info.price_decimals = precision.price_decimals;
+            }
+            if let Ok(mut listener) = self.listener.try_write() {
+                orders.extend(listener.on_precision_update(&asset, precision));
+            }
+        }
+        self.place_orders(orders).await;
+    }
+
     /// Update the price for an asset (M7)
     ///
     /// Manually updates internal price state and notifies the listener.
@@ -397,94 +809,356 @@ impl<L: MarketListener> HyperliquidMarket<L> {
         }
     }
 
+    /// Deliver a timer tick to the listener and place the orders it returns
+    ///
+    /// Called once per `TICK_INTERVAL` by the event loop. Orders whose
+    /// `expires_at_ms` has passed are cancelled first.
+    ///
+    /// # Arguments
+    /// * `now` - Current Unix time in seconds
+    pub async fn tick(&mut self, now: u64) {
+        let mut orders = self.expire_orders(now * 1000).await;
+        let stalled = self.feed_watchdog.check(now);
+        if let Ok(mut listener) = self.listener.try_write() {
+            if let Some(silent) = stalled {
+                warn!("No price for {} in {}s, is it still listed?", self.asset, silent);
+                listener.on_feed_stalled(&self.asset, silent);
+            }
+            listener.on_feed_backlog(self.feed_backlog);
+            orders.extend(listener.on_tick(now));
+        }
+        self.place_orders(orders).await;
+    }
+
+    /// Cancel resting orders past their expiry and report them to the listener
+    ///
+    /// An order whose cancel fails (e.g. it filled in the meantime) is left
+    /// alone and its fill is reported as usual.
+    async fn expire_orders(&mut self, now_ms: u64) -> Vec<OrderRequest> {
+        let mut expired: Vec<u64> = self
+            .orders
+            .iter()
+            .filter(|(_, o)| o.status.is_active() && o.request.is_expired(now_ms))
+            .map(|(&id, _)| id)
+            .collect();
+        expired.sort_unstable();
+
+        let mut orders = Vec::new();
+        for order_id in expired {
+            if self.cancel_order(order_id).await.is_err() {
+                continue;
+            }
+            if let Some(order) = self.orders.get_mut(&order_id) {
+                order.status = OrderStatus::Expired;
+            }
+            info!("Order {} expired", order_id);
+            if let Ok(mut listener) = self.listener.try_write() {
+                orders.extend(listener.on_order_rejected(order_id, ORDER_EXPIRED.to_string()));
+            }
+        }
+        orders
+    }
+
     /// Place a new order on Hyperliquid (M8)
     ///
+    /// Each order carries a cloid derived from its `order_id`. If the request
+    /// fails in transit, the exchange is asked whether that cloid already
+    /// exists before the order is sent again, so a retry never double-places.
+    ///
+    /// A rejected order is also reported to the listener through
+    /// `on_order_rejected`, and any orders it returns are placed.
+    ///
     /// # Arguments
     /// * `order` - The order request (contains user-provided order_id, side, reduce_only, tif)
-    pub async fn place_order(&mut self, order: OrderRequest) {
-        let user_order_id = order.order_id;
-        let mut tracked_order = TrackedOrder::new(order.clone());
+    ///
+    /// # Returns
+    /// `Ok` once the order is resting or filled, otherwise why it was refused
+    pub async fn place_order(&mut self, order: OrderRequest) -> Result<(), MarketError> {
+        if let Some(old_id) = order.replaces {
+            return Box::pin(self.replace_order(old_id, order)).await;
+        }
+        let (tracked_order, exchange_order) = self.prepare_order(order);
+        let result = self
+            .submit_order(exchange_order, tracked_order.cloid)
+            .await;
+        self.apply_order_result(tracked_order, result).await
+    }
+
+    /// Place several orders, up to `MAX_CONCURRENT_ORDERS` requests at a time
+    ///
+    /// Requests go out concurrently (still through the client's rate limiter);
+    /// responses are then applied one by one in order, so tracking and
+    /// listener callbacks behave exactly as with `place_order`. Orders that
+    /// replace a resting one are placed sequentially afterwards.
+    pub async fn place_orders(&mut self, orders: Vec<OrderRequest>) {
+        let (replacements, orders): (Vec<_>, Vec<_>) =
+            orders.into_iter().partition(|o| o.replaces.is_some());
+
+        let prepared: Vec<_> = orders.into_iter().map(|o| self.prepare_order(o)).collect();
+        let this = &*self;
+        let mut results: Vec<_> = futures_util::stream::iter(prepared.into_iter().enumerate())
+            .map(|(i, (tracked, exchange_order))| async move {
+                let result = this.submit_order(exchange_order, tracked.cloid).await;
+                (i, tracked, result)
+            })
+            .buffer_unordered(Self::MAX_CONCURRENT_ORDERS)
+            .collect()
+            .await;
+        results.sort_by_key(|(i, _, _)| *i);
+
+        for (_, tracked, result) in results {
+            let _ = self.apply_order_result(tracked, result).await;
+        }
+        for order in replacements {
+            let _ = self.place_order(order).await;
+        }
+    }
+
+    /// Register an order's cloid and build its exchange request
+    ///
+    /// The order is tracked from before it is sent, so a fill that beats the
+    /// placement response is still matched to it by cloid.
+    fn prepare_order(&mut self, order: OrderRequest) -> (TrackedOrder, ClientOrderRequest) {
+        let cloid = self.cloid_for(order.order_id);
+        self.cloid_to_order_id
+            .insert(uuid_to_hex_string(cloid), order.order_id);
+        self.orders
+            .insert(order.order_id, TrackedOrder::new(order.clone(), cloid));
 
-        // Place order on exchange
         let exchange_order = ClientOrderRequest {
             asset: order.asset.clone(),
             is_buy: order.side.is_buy(),
             reduce_only: order.reduce_only,
             limit_px: order.limit_price,
             sz: order.qty,
-            cloid: None,
+            cloid: Some(cloid),
             order_type: ClientOrder::Limit(ClientLimit {
-                tif: "Gtc".to_string(),
+                tif: order.tif.as_str().to_string(),
             }),
         };
+        (TrackedOrder::new(order, cloid), exchange_order)
+    }
 
-        match self.exchange_client.order(exchange_order, None).await {
-            Ok(response) => match response {
-                ExchangeResponseStatus::Ok(resp) => {
-                    if let Some(data) = resp.data {
-                        if !data.statuses.is_empty() {
-                            match &data.statuses[0] {
-                                ExchangeDataStatus::Filled(filled) => {
-                                    tracked_order.exchange_oid = Some(filled.oid);
-                                    tracked_order.status = OrderStatus::Filled(order.limit_price);
-                                    self.exchange_oid_to_order_id.insert(filled.oid, user_order_id);
-
-                                    info!("Order {} filled immediately, oid={}", user_order_id, filled.oid);
-
-                                    // Create fill notification with user's order_id
-                                    let fill = OrderFill::new(
-                                        user_order_id,
-                                        &order.asset,
-                                        order.qty,
-                                        order.limit_price,
-                                    );
-
-                                    // Store order before notifying
-                                    self.orders.insert(user_order_id, tracked_order);
-
-                                    // M6: Synchronous notification, place returned orders
-                                    let pending_orders = if let Ok(mut listener) = self.listener.try_write() {
-                                        listener.on_order_filled(fill)
-                                    } else {
-                                        vec![]
-                                    };
-                                    for pending in pending_orders {
-                                        // Recursive call for orders returned by listener
-                                        Box::pin(self.place_order(pending)).await;
-                                    }
+    /// Record the exchange's answer to an order and notify the listener
+    ///
+    /// Orders the listener returns in response are placed right away.
+    async fn apply_order_result(
+        &mut self,
+        mut tracked_order: TrackedOrder,
+        result: Result<ExchangeDataStatus, MarketError>,
+    ) -> Result<(), MarketError> {
+        let order = tracked_order.request.clone();
+        let user_order_id = order.order_id;
 
-                                    return;
-                                }
-                                ExchangeDataStatus::Resting(resting) => {
-                                    tracked_order.exchange_oid = Some(resting.oid);
-                                    tracked_order.status = OrderStatus::Pending;
-                                    self.exchange_oid_to_order_id.insert(resting.oid, user_order_id);
+        // Fills that arrived first were already applied and reported; the
+        // response only adds the oid
+        if let Some(early) = self
+            .orders
+            .get_mut(&user_order_id)
+            .filter(|o| o.filled_qty > 0.0)
+        {
+            let oid = match &result {
+                Ok(ExchangeDataStatus::Resting(resting)) => Some(resting.oid),
+                Ok(ExchangeDataStatus::Filled(filled)) => Some(filled.oid),
+                _ => None,
+            };
+            info!(
+                "Order {} filled before its placement response, oid={:?}",
+                user_order_id, oid
+            );
+            if let Some(oid) = oid.filter(|_| early.exchange_oid.is_none()) {
+                early.exchange_oid = Some(oid);
+                self.exchange_oid_to_order_id.insert(oid, user_order_id);
+            }
+            return Ok(());
+        }
 
-                                    info!("Order {} resting, oid={}", user_order_id, resting.oid);
-                                }
-                                ExchangeDataStatus::Error(e) => {
-                                    error!("Order {} error: {}", user_order_id, e);
-                                    tracked_order.status = OrderStatus::Cancelled;
-                                }
-                                _ => {
-                                    debug!("Order {} unknown status", user_order_id);
-                                }
-                            }
-                        }
-                    }
-                }
-                ExchangeResponseStatus::Err(e) => {
-                    error!("Order {} exchange error: {}", user_order_id, e);
-                    tracked_order.status = OrderStatus::Cancelled;
+        let mut rejection = None;
+        match result {
+            Ok(ExchangeDataStatus::Filled(filled)) => {
+                tracked_order.exchange_oid = Some(filled.oid);
+                tracked_order.status = OrderStatus::Filled(order.limit_price);
+                self.exchange_oid_to_order_id.insert(filled.oid, user_order_id);
+
+                info!("Order {} filled immediately, oid={}", user_order_id, filled.oid);
+
+                // Create fill notification with user's order_id
+                let fill = OrderFill::new(
+                    user_order_id,
+                    &order.asset,
+                    order.qty,
+                    order.limit_price,
+                )
+                .with_tag(order.tag.clone())
+                .with_time(unix_now_ms());
+
+                // Store order before notifying
+                self.orders.insert(user_order_id, tracked_order);
+
+                // M6: Synchronous notification, place returned orders
+                let pending_orders = if let Ok(mut listener) = self.listener.try_write() {
+                    listener.on_order_filled(fill)
+                } else {
+                    vec![]
+                };
+                for pending in pending_orders {
+                    // Recursive call for orders returned by listener
+                    let _ = Box::pin(self.place_order(pending)).await;
                 }
-            },
+
+                return Ok(());
+            }
+            Ok(ExchangeDataStatus::Resting(resting)) => {
+                tracked_order.exchange_oid = Some(resting.oid);
+                tracked_order.status = OrderStatus::Pending;
+                self.exchange_oid_to_order_id.insert(resting.oid, user_order_id);
+
+                info!("Order {} resting, oid={}", user_order_id, resting.oid);
+            }
+            Ok(ExchangeDataStatus::Error(e)) => {
+                error!("Order {} error: {}", user_order_id, e);
+                tracked_order.status = OrderStatus::Rejected(e.clone());
+                rejection = Some((e.clone(), MarketError::from_exchange(&e)));
+            }
+            Ok(_) => {
+                debug!("Order {} unknown status", user_order_id);
+            }
             Err(e) => {
-                error!("Order {} request error: {}", user_order_id, e);
-                tracked_order.status = OrderStatus::Cancelled;
+                error!("Order {} failed: {}", user_order_id, e);
+                tracked_order.status = OrderStatus::Rejected(e.to_string());
+                rejection = Some((e.to_string(), e));
             }
         }
 
         self.orders.insert(user_order_id, tracked_order);
+
+        // Let the listener know the order never made it, so it can retry or adjust
+        let Some((reason, err)) = rejection else {
+            return Ok(());
+        };
+        let retry_orders = if let Ok(mut listener) = self.listener.try_write() {
+            listener.on_order_rejected(user_order_id, reason)
+        } else {
+            vec![]
+        };
+        for pending in retry_orders {
+            let _ = Box::pin(self.place_order(pending)).await;
+        }
+        Err(err)
+    }
+
+    /// Deterministic cloid for a user order id, unique to this market instance
+    fn cloid_for(&self, order_id: u64) -> Uuid {
+        Uuid::from_u64_pair(self.cloid_prefix, order_id)
+    }
+
+    /// Send an order, retrying once if the request fails in transit
+    ///
+    /// Before retrying, the order is looked up by cloid: if the first attempt
+    /// reached the exchange, its current state is returned instead.
+    async fn submit_order(
+        &self,
+        order: ClientOrderRequest,
+        cloid: Uuid,
+    ) -> Result<ExchangeDataStatus, MarketError> {
+        if self.dry_run {
+            info!(
+                "Dry run: would {} {} {} at {} (cloid {})",
+                if order.is_buy { "buy" } else { "sell" },
+                order.sz,
+                order.asset,
+                order.limit_px,
+                cloid
+            );
+            // The cloid's low half is the user order_id, unique per order
+            let oid = cloid.as_u64_pair().1;
+            return Ok(ExchangeDataStatus::Resting(RestingOrder { oid }));
+        }
+
+        let response = match self.send_order(order.clone()).await {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("Order request with cloid {} failed: {}", cloid, e);
+                if let Some(status) = self.find_order_by_cloid(cloid).await {
+                    info!("Order with cloid {} already reached the exchange", cloid);
+                    return status;
+                }
+                self.send_order(order).await?
+            }
+        };
+
+        match response {
+            ExchangeResponseStatus::Ok(resp) => resp
+                .data
+                .and_then(|data| data.statuses.into_iter().next())
+                .ok_or_else(|| MarketError::OrderRejected {
+                    reason: "empty order response".to_string(),
+                }),
+            ExchangeResponseStatus::Err(e) => Err(MarketError::from_exchange(&e)),
+        }
+    }
+
+    /// Sign and send one order, with the builder code if one is set
+    async fn send_order(
+        &self,
+        order: ClientOrderRequest,
+    ) -> Result<ExchangeResponseStatus, crate::Error> {
+        match &self.builder {
+            Some(builder) => {
+                let request = self.exchange_client.order_with_builder(order, None, builder.clone());
+                self.with_timeout("order", request).await
+            }
+            None => self.with_timeout("order", self.exchange_client.order(order, None)).await,
+        }
+    }
+
+    /// Await an exchange request, failing it once `request_timeout` passes
+    ///
+    /// The abandoned request may still reach the exchange, so a timeout is
+    /// only safe to retry where the retry is deduplicated.
+    async fn with_timeout<T>(
+        &self,
+        what: &str,
+        request: impl Future<Output = Result<T, crate::Error>>,
+    ) -> Result<T, crate::Error> {
+        match tokio::time::timeout(self.request_timeout, request).await {
+            Ok(result) => result,
+            Err(_) => Err(crate::Error::GenericRequest(format!(
+                "{} request timed out after {:?}",
+                what, self.request_timeout
+            ))),
+        }
+    }
+
+    /// Look up an order by cloid, mapped onto the status the order endpoint would return
+    ///
+    /// Returns `None` if the exchange doesn't know the cloid (or can't be asked).
+    async fn find_order_by_cloid(
+        &self,
+        cloid: Uuid,
+    ) -> Option<Result<ExchangeDataStatus, MarketError>> {
+        let lookup = self.info_client.query_order_by_cloid(self.user_address, cloid);
+        let response = match self.with_timeout("order status", lookup).await {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("Order status lookup for cloid {} failed: {}", cloid, e);
+                return None;
+            }
+        };
+        let order_info = response.order?;
+        let order = order_info.order;
+
+        Some(match order_info.status.as_str() {
+            "open" => Ok(ExchangeDataStatus::Resting(RestingOrder { oid: order.oid })),
+            "filled" => Ok(ExchangeDataStatus::Filled(FilledOrder {
+                total_sz: order.orig_sz,
+                avg_px: order.limit_px,
+                oid: order.oid,
+            })),
+            status => Err(MarketError::OrderRejected {
+                reason: format!("order {} is {}", order.oid, status),
+            }),
+        })
     }
 
     /// Inject an external fill (M9)
@@ -511,7 +1185,9 @@ impl<L: MarketListener> HyperliquidMarket<L> {
                     &order.request.asset,
                     order.request.qty,      // Total order qty
                     order.avg_fill_price,   // Average fill price
-                );
+                )
+                .with_tag(order.request.tag.clone())
+                .with_time(fill.time_ms);
 
                 // M6: Synchronous notification, return orders to place
                 if let Ok(mut listener) = self.listener.try_write() {
@@ -541,7 +1217,62 @@ impl<L: MarketListener> HyperliquidMarket<L> {
     /// # Returns
     /// The current order status if the order exists
     pub fn order_status(&self, order_id: u64) -> Option<OrderStatus> {
-        self.orders.get(&order_id).map(|o| o.status)
+        self.orders.get(&order_id).map(|o| o.status.clone())
+    }
+
+    /// Orders the market believes are open, by user-provided order id
+    ///
+    /// This is the local view; use `reconcile_open_orders` to check it
+    /// against the exchange.
+    pub fn open_orders(&self) -> Vec<(u64, OrderRequest, OrderStatus)> {
+        let mut open: Vec<_> = self
+            .orders
+            .iter()
+            .filter(|(_, o)| o.status.is_active())
+            .map(|(&id, o)| (id, o.request.clone(), o.status.clone()))
+            .collect();
+        open.sort_by_key(|(id, _, _)| *id);
+        open
+    }
+
+    /// Compare the tracked open orders with the exchange's open orders
+    ///
+    /// Orders are matched by exchange oid, or by cloid while the oid is still
+    /// unknown. Exchange orders on assets this market doesn't trade are
+    /// ignored. Nothing is cancelled or re-placed; the caller decides.
+    pub async fn reconcile_open_orders(&self) -> Result<OpenOrderDrift, crate::Error> {
+        let exchange_orders: Vec<_> = self
+            .info_client
+            .open_orders(self.user_address)
+            .await?
+            .into_iter()
+            .filter(|o| self.asset_keys.contains_key(&o.coin))
+            .collect();
+
+        let mut drift = OpenOrderDrift::default();
+        for (order_id, request, _) in self.open_orders() {
+            let tracked = &self.orders[&order_id];
+            let cloid = uuid_to_hex_string(tracked.cloid);
+            let on_book = exchange_orders.iter().any(|o| {
+                Some(o.oid) == tracked.exchange_oid || o.cloid.as_deref() == Some(cloid.as_str())
+            });
+            if !on_book {
+                warn!("Order {} on {} is not open on the exchange", order_id, request.asset);
+                drift.missing.push(order_id);
+            }
+        }
+        for order in &exchange_orders {
+            let known = self.exchange_oid_to_order_id.contains_key(&order.oid)
+                || order.cloid.as_deref().is_some_and(|cloid| {
+                    self.orders.values().any(|o| uuid_to_hex_string(o.cloid) == cloid)
+                });
+            if !known {
+                warn!("Exchange order {} on {} is not tracked", order.oid, order.coin);
+                drift.untracked.push(order.oid);
+            }
+        }
+        drift.untracked.sort_unstable();
+        Ok(drift)
     }
 
     /// Get the shared listener reference
@@ -558,14 +1289,15 @@ impl<L: MarketListener> HyperliquidMarket<L> {
     /// * `order_id` - The user-provided order ID to cancel
     ///
     /// # Returns
-    /// `true` if the order was cancelled successfully
-    pub async fn cancel_order(&mut self, order_id: u64) -> bool {
+    /// `Ok` if the order was cancelled, otherwise why it wasn't (e.g. it
+    /// already filled)
+    pub async fn cancel_order(&mut self, order_id: u64) -> Result<(), MarketError> {
         let Some(order) = self.orders.get(&order_id) else {
-            return false;
+            return Err(MarketError::UnknownOrder(order_id));
         };
 
         if !order.status.is_active() {
-            return false;
+            return Err(MarketError::UnknownOrder(order_id));
         }
 
         let Some(exchange_oid) = order.exchange_oid else {
@@ -573,15 +1305,24 @@ impl<L: MarketListener> HyperliquidMarket<L> {
             if let Some(order) = self.orders.get_mut(&order_id) {
                 order.status = OrderStatus::Cancelled;
             }
-            return true;
+            return Ok(());
         };
 
+        if self.dry_run {
+            info!("Dry run: would cancel order {} (oid {})", order_id, exchange_oid);
+            if let Some(order) = self.orders.get_mut(&order_id) {
+                order.status = OrderStatus::Cancelled;
+            }
+            return Ok(());
+        }
+
         let cancel_request = ClientCancelRequest {
             asset: order.request.asset.clone(),
             oid: exchange_oid,
         };
 
-        match self.exchange_client.cancel(cancel_request, None).await {
+        let request = self.exchange_client.cancel(cancel_request, None);
+        match self.with_timeout("cancel", request).await {
             Ok(response) => match response {
                 ExchangeResponseStatus::Ok(resp) => {
                     if let Some(data) = resp.data {
@@ -592,10 +1333,11 @@ impl<L: MarketListener> HyperliquidMarket<L> {
                                         order.status = OrderStatus::Cancelled;
                                     }
                                     info!("Order {} cancelled", order_id);
-                                    return true;
+                                    return Ok(());
                                 }
                                 ExchangeDataStatus::Error(e) => {
                                     error!("Cancel error: {}", e);
+                                    return Err(MarketError::from_exchange(e));
                                 }
                                 _ => {}
                             }
@@ -604,46 +1346,466 @@ impl<L: MarketListener> HyperliquidMarket<L> {
                 }
                 ExchangeResponseStatus::Err(e) => {
                     error!("Cancel exchange error: {}", e);
+                    return Err(MarketError::from_exchange(&e));
                 }
             },
             Err(e) => {
                 error!("Cancel request error: {}", e);
+                return Err(e.into());
             }
         }
 
-        false
+        Err(MarketError::OrderRejected {
+            reason: "unexpected cancel response".to_string(),
+        })
     }
 
-    /// Get the exchange OID for a user-provided order ID
-    pub fn get_exchange_oid(&self, order_id: u64) -> Option<u64> {
+    /// Move an active order to a new price and quantity without cancelling it
+    ///
+    /// Uses the exchange's modify action, keeping the order's cloid. The
+    /// exchange assigns a new oid, which is remapped so fills keep resolving
+    /// to the same user `order_id`.
+    ///
+    /// # Errors
+    /// `UnknownOrder` if no active order has this id, otherwise the reason the
+    /// exchange refused the modification
+    pub async fn modify_order(
+        &mut self,
+        order_id: u64,
+        new_price: f64,
+        new_qty: f64,
+    ) -> Result<(), MarketError> {
+        let Some(order) = self.orders.get(&order_id).filter(|o| o.status.is_active()) else {
+            return Err(MarketError::UnknownOrder(order_id));
+        };
+        let Some(exchange_oid) = order.exchange_oid else {
+            return Err(MarketError::OrderRejected {
+                reason: format!("order {} is not on the exchange yet", order_id),
+            });
+        };
+
+        let mut request = order.request.clone();
+        request.limit_price = new_price;
+        request.qty = new_qty;
+        let new_oid = self.send_modify(exchange_oid, &request, order.cloid).await?;
+
+        self.exchange_oid_to_order_id.remove(&exchange_oid);
+        self.exchange_oid_to_order_id.insert(new_oid, order_id);
+        if let Some(order) = self.orders.get_mut(&order_id) {
+            order.exchange_oid = Some(new_oid);
+            order.request = request;
+        }
+        info!(
+            "Order {} modified to {} @ {}, oid {} -> {}",
+            order_id, new_qty, new_price, exchange_oid, new_oid
+        );
+        Ok(())
+    }
+
+    /// Swap the resting order `order.replaces` for `order`
+    ///
+    /// The resting order is modified in place when it's on the same asset, so
+    /// the level stays quoted throughout; the new cloid keeps fills resolving
+    /// to the new `order_id`. Otherwise, or if the modify fails, it is
+    /// cancelled and the new order placed once the cancel is confirmed.
+    ///
+    /// What the old order filled is reported to the listener before it's
+    /// retired. If the old order filled completely, or its cancel fails
+    /// (e.g. because it filled meanwhile), `order` is rejected instead of
+    /// placed, so the level never trades twice.
+    async fn replace_order(
+        &mut self,
+        old_id: u64,
+        mut order: OrderRequest,
+    ) -> Result<(), MarketError> {
+        order.replaces = None;
+        let resting = self
+            .orders
+            .get(&old_id)
+            .filter(|old| old.status.is_active())
+            .map(|old| (old.exchange_oid, old.request.asset == order.asset));
+
+        match resting {
+            None => {
+                let filled = self
+                    .orders
+                    .get(&old_id)
+                    .is_some_and(|old| matches!(old.status, OrderStatus::Filled(_)));
+                if filled {
+                    let reason = format!("order {} it replaces already filled", old_id);
+                    return self.refuse_replacement(order, reason).await;
+                }
+                debug!("Order {} to replace is no longer active", old_id);
+            }
+            Some((Some(exchange_oid), true)) => {
+                let cloid = self.cloid_for(order.order_id);
+                match self.send_modify(exchange_oid, &order, cloid).await {
+                    Ok(new_oid) => {
+                        self.exchange_oid_to_order_id.remove(&exchange_oid);
+                        self.exchange_oid_to_order_id.insert(new_oid, order.order_id);
+                        self.cloid_to_order_id
+                            .insert(uuid_to_hex_string(cloid), order.order_id);
+
+                        let mut tracked = TrackedOrder::new(order.clone(), cloid);
+                        tracked.exchange_oid = Some(new_oid);
+                        self.orders.insert(order.order_id, tracked);
+                        info!(
+                            "Order {} replaced by {} ({} @ {}), oid {} -> {}",
+                            old_id,
+                            order.order_id,
+                            order.qty,
+                            order.limit_price,
+                            exchange_oid,
+                            new_oid
+                        );
+                        let orders = self.retire_replaced(old_id);
+                        Box::pin(self.place_orders(orders)).await;
+                        return Ok(());
+                    }
+                    Err(e) => warn!(
+                        "Modify for replacing order {} failed ({}), cancelling instead",
+                        old_id, e
+                    ),
+                }
+                self.cancel_replaced(old_id, &order).await?;
+            }
+            Some(_) => self.cancel_replaced(old_id, &order).await?,
+        }
+        self.place_order(order).await
+    }
+
+    /// Cancel the order `order` replaces and retire it
+    ///
+    /// # Returns
+    /// `Err` with `order` rejected if the cancel wasn't confirmed: the old
+    /// order may have filled, so `order` must not be placed
+    async fn cancel_replaced(
+        &mut self,
+        old_id: u64,
+        order: &OrderRequest,
+    ) -> Result<(), MarketError> {
+        if let Err(e) = self.cancel_order(old_id).await {
+            warn!("Could not cancel order {}, not placing its replacement: {}", old_id, e);
+            let reason = format!("order {} it replaces could not be cancelled: {}", old_id, e);
+            return self.refuse_replacement(order.clone(), reason).await;
+        }
+        let orders = self.retire_replaced(old_id);
+        Box::pin(self.place_orders(orders)).await;
+        Ok(())
+    }
+
+    /// Reject a replacement without sending it, as the exchange would have
+    async fn refuse_replacement(
+        &mut self,
+        order: OrderRequest,
+        reason: String,
+    ) -> Result<(), MarketError> {
+        let (tracked, _) = self.prepare_order(order);
+        let result = Err(MarketError::OrderRejected { reason });
+        self.apply_order_result(tracked, result).await
+    }
+
+    /// Mark a replaced order cancelled, first reporting what of it filled
+    ///
+    /// Fills are otherwise only reported once an order completes, so a
+    /// partial fill on a replaced order would never reach the listener.
+    ///
+    /// # Returns
+    /// Orders the listener wants placed in answer to the partial fill
+    fn retire_replaced(&mut self, old_id: u64) -> Vec<OrderRequest> {
+        let Some(old) = self.orders.get_mut(&old_id) else {
+            return vec![];
+        };
+        old.status = OrderStatus::Cancelled;
+        if old.filled_qty <= 0.0 {
+            return vec![];
+        }
+        info!(
+            "Replaced order {} had filled {} at {}",
+            old_id, old.filled_qty, old.avg_fill_price
+        );
+        let fill = OrderFill::new(old_id, &old.request.asset, old.filled_qty, old.avg_fill_price)
+            .with_fee(old.fees)
+            .with_tag(old.request.tag.clone())
+            .with_time(unix_now_ms());
+        match self.listener.try_write() {
+            Ok(mut listener) => listener.on_order_filled(fill),
+            Err(_) => vec![],
+        }
+    }
+
+    /// Send a modify moving the resting order `exchange_oid` to `request`
+    ///
+    /// # Returns
+    /// The order's new oid, or `None` if the exchange refused
+    async fn send_modify(
+        &self,
+        exchange_oid: u64,
+        request: &OrderRequest,
+        cloid: Uuid,
+    ) -> Result<u64, MarketError> {
+        if self.dry_run {
+            info!(
+                "Dry run: would modify oid {} to {} @ {}",
+                exchange_oid, request.qty, request.limit_price
+            );
+            return Ok(exchange_oid);
+        }
+
+        let modify = ClientModifyRequest {
+            oid: exchange_oid,
+            order: ClientOrderRequest {
+                asset: request.asset.clone(),
+                is_buy: request.side.is_buy(),
+                reduce_only: request.reduce_only,
+                limit_px: request.limit_price,
+                sz: request.qty,
+                cloid: Some(cloid),
+                order_type: ClientOrder::Limit(ClientLimit {
+                    tif: request.tif.as_str().to_string(),
+                }),
+            },
+        };
+
+        match self.with_timeout("modify", self.exchange_client.modify(modify, None)).await {
+            Ok(ExchangeResponseStatus::Ok(resp)) => {
+                match resp.data.and_then(|data| data.statuses.into_iter().next()) {
+                    Some(ExchangeDataStatus::Resting(resting)) => Ok(resting.oid),
+                    Some(ExchangeDataStatus::Filled(filled)) => Ok(filled.oid),
+                    Some(ExchangeDataStatus::Error(e)) => {
+                        error!("Modify error for oid {}: {}", exchange_oid, e);
+                        Err(MarketError::from_exchange(&e))
+                    }
+                    _ => {
+                        debug!("Modify of oid {} returned unknown status", exchange_oid);
+                        Err(MarketError::OrderRejected {
+                            reason: "unexpected modify response".to_string(),
+                        })
+                    }
+                }
+            }
+            Ok(ExchangeResponseStatus::Err(e)) => {
+                error!("Modify exchange error: {}", e);
+                Err(MarketError::from_exchange(&e))
+            }
+            Err(e) => {
+                error!("Modify request error: {}", e);
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Cancel every active order placed through this market
+    ///
+    /// Returns the number of orders the exchange confirmed as cancelled.
+    pub async fn cancel_all_orders(&mut self) -> usize {
+        let mut active: Vec<u64> = self
+            .orders
+            .iter()
+            .filter(|(_, order)| order.status.is_active())
+            .map(|(&order_id, _)| order_id)
+            .collect();
+        active.sort_unstable();
+
+        let mut cancelled = 0;
+        for order_id in active {
+            if self.cancel_order(order_id).await.is_ok() {
+                cancelled += 1;
+            }
+        }
+        cancelled
+    }
+
+    /// Cancel all open orders and tell the listener the market has stopped
+    ///
+    /// Called by the event loop on Ctrl-C or `MarketCommand::Stop` so no
+    /// orders are left resting on the exchange, and ends the loop. Positions
+    /// are left as they are.
+    pub async fn shutdown(&mut self) {
+        self.stopped = true;
+        let cancelled = self.cancel_all_orders().await;
+        info!("Shutdown: cancelled {} order(s)", cancelled);
+        if let Ok(mut listener) = self.listener.try_write() {
+            listener.on_stop();
+        }
+    }
+
+    /// Cancel all open orders and close every asset's position at market
+    ///
+    /// Perp positions are closed with a reduce-only IOC order and spot base
+    /// balances are sold with one. Each IOC is limited to `max_slippage_bps`
+    /// from the last mid; if it doesn't fill, it's retried once with twice
+    /// the slippage.
+    pub async fn flatten(&mut self) {
+        self.cancel_all_orders().await;
+
+        let mut assets: Vec<String> = self.assets.keys().cloned().collect();
+        assets.sort();
+        for asset in assets {
+            self.close_position(&asset).await;
+        }
+    }
+
+    /// Close one asset's position at market (see `flatten`)
+    async fn close_position(&mut self, asset: &str) {
+        if self.dry_run {
+            info!("Dry run: would close any {} position at market", asset);
+            return;
+        }
+        let market_type = MarketType::of(asset);
+        for attempt in 0..Self::CLOSE_ATTEMPTS {
+            if let Err(e) = self.refresh_balances().await {
+                error!("Flatten: failed to fetch {} position: {}", asset, e);
+                return;
+            }
+            let asset_info = &self.assets[asset];
+            let precision = asset_info.precision();
+            let position = asset_info.balance;
+            let sz = precision.round_size(position.abs());
+            if sz <= 0.0 {
+                if attempt == 0 {
+                    info!("Flatten: no open {} position", asset);
+                }
+                return;
+            }
+            let Some(mid) = self.current_price(asset) else {
+                warn!("Flatten: no price for {}, position left open", asset);
+                return;
+            };
+
+            // Spot only ever sells the base balance; perps trade against the position
+            let is_buy = market_type == MarketType::Perp && position < 0.0;
+            let slippage_bps = self.max_slippage_bps << attempt;
+            let limit_px = close_limit_price(mid, is_buy, slippage_bps, &precision);
+            let order = ClientOrderRequest {
+                asset: asset.to_string(),
+                is_buy,
+                reduce_only: market_type == MarketType::Perp,
+                limit_px,
+                sz,
+                cloid: None,
+                order_type: ClientOrder::Limit(ClientLimit {
+                    tif: TimeInForce::Ioc.as_str().to_string(),
+                }),
+            };
+
+            match self.send_order(order).await {
+                Ok(ExchangeResponseStatus::Ok(response)) => {
+                    let filled = response
+                        .data
+                        .and_then(|data| data.statuses.into_iter().next())
+                        .and_then(|status| match status {
+                            ExchangeDataStatus::Filled(order) => order.total_sz.parse::<f64>().ok(),
+                            _ => None,
+                        })
+                        .unwrap_or(0.0);
+                    if filled >= sz {
+                        info!("Flatten: closed {} position at <= {} bps", asset, slippage_bps);
+                        return;
+                    }
+                    warn!(
+                        "Flatten: {} closed {} of {} within {} bps",
+                        asset, filled, sz, slippage_bps
+                    );
+                }
+                Ok(ExchangeResponseStatus::Err(e)) => {
+                    error!("Flatten: close rejected: {}", e);
+                    return;
+                }
+                Err(e) => {
+                    error!("Flatten: close request error: {}", e);
+                    return;
+                }
+            }
+        }
+        error!(
+            "Flatten: {} position still open after {} attempts",
+            asset,
+            Self::CLOSE_ATTEMPTS
+        );
+    }
+
+    /// Get the exchange OID for a user-provided order ID
+    pub fn get_exchange_oid(&self, order_id: u64) -> Option<u64> {
         self.orders.get(&order_id).and_then(|o| o.exchange_oid)
     }
 
+    /// Fees paid on this market's fills since start, in quote currency
+    ///
+    /// Maker rebates are negative, so this can go below zero.
+    pub fn total_fees(&self) -> f64 {
+        self.total_fees
+    }
+
+    /// Get the cloid sent with a user-provided order ID
+    pub fn get_cloid(&self, order_id: u64) -> Option<Uuid> {
+        self.orders.get(&order_id).map(|o| o.cloid)
+    }
+
     /// Get all current prices
     pub fn all_prices(&self) -> &HashMap<String, f64> {
         &self.prices
     }
 
-    /// Get cached asset information (balances and precision)
+    /// Get cached asset information (balances and precision) for the primary asset
     ///
     /// Returns the cached AssetInfo. Precision is static, but balances
     /// may be stale. Use `refresh_balances()` to update balances.
     pub fn asset_info(&self) -> &AssetInfo {
-        &self.asset_info
+        &self.assets[&self.asset]
+    }
+
+    /// Get cached asset information for any traded asset
+    pub fn asset_info_for(&self, asset: &str) -> Option<&AssetInfo> {
+        self.assets.get(asset)
+    }
+
+    /// Names of all traded assets
+    pub fn assets(&self) -> impl Iterator<Item = &str> {
+        self.assets.keys().map(String::as_str)
     }
 
     /// Refresh cached balances from the exchange
     ///
-    /// Updates the balance and usdc_balance fields in the cached AssetInfo.
+    /// Updates the balance and usdc_balance fields of every cached AssetInfo.
     /// Precision fields remain unchanged (they are static).
     pub async fn refresh_balances(&mut self) -> Result<(), crate::Error> {
-        let updated = Self::fetch_asset_info(&self.info_client, &self.asset, self.user_address).await?;
-        self.asset_info.balance = updated.balance;
-        self.asset_info.usdc_balance = updated.usdc_balance;
+        for (asset, asset_info) in self.assets.iter_mut() {
+            let quote = self.quote_tokens.get(asset).map_or("USDC", String::as_str);
+            let (balance, usdc_balance) =
+                Self::fetch_balances(&self.info_client, asset, quote, self.user_address).await?;
+            asset_info.balance = balance;
+            asset_info.usdc_balance = usdc_balance;
+        }
         Ok(())
     }
 }
 
+/// Value of a fill's fee in the asset's quote currency
+///
+/// Perp fees and spot sell fees are charged in the quote token. Spot buys pay
+/// the fee in the base token received, so it's valued at the fill price.
+fn fee_in_quote(asset: &str, fee: f64, fee_token: &str, price: f64) -> f64 {
+    let quote = asset.split('/').nth(1).unwrap_or("USDC");
+    if fee_token == quote {
+        fee
+    } else {
+        fee * price
+    }
+}
+
+/// IOC limit price for closing at most `slippage_bps` away from `mid`
+///
+/// Rounded toward mid, so the tick rounding never adds to the slippage.
+fn close_limit_price(mid: f64, is_buy: bool, slippage_bps: u32, precision: &AssetPrecision) -> f64 {
+    let slippage = slippage_bps as f64 / 10_000.0;
+    if is_buy {
+        precision.round_price_down(mid * (1.0 + slippage))
+    } else {
+        precision.round_price(mid * (1.0 - slippage), true)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -655,7 +1817,7 @@ mod tests {
     #[test]
     fn test_tracked_order_fill() {
         let request = OrderRequest::buy(100, "BTC", 2.0, 50000.0);
-        let mut order = TrackedOrder::new(request);
+        let mut order = TrackedOrder::new(request, Uuid::nil());
 
         assert_eq!(order.status, OrderStatus::Pending);
 
@@ -685,5 +1847,1008 @@ mod tests {
         assert_eq!(order.side, OrderSide::Buy);
         assert!(!order.reduce_only);
     }
-}
 
+    /// Minimal stand-in for the exchange's `/exchange` and `/info` endpoints
+    mod mock_exchange {
+        use std::net::SocketAddr;
+        use std::sync::{Arc, Mutex};
+
+        use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+        use serde_json::{json, Value};
+
+        #[derive(Default)]
+        pub(super) struct MockState {
+            /// Fail the first order request after (or before) accepting it
+            pub(super) fail_first: bool,
+            /// Whether the failed first request still reached the book
+            pub(super) accept_failed: bool,
+            pub(super) order_requests: usize,
+            /// Cloids of orders on the book
+            pub(super) resting: Vec<String>,
+            /// Reject the first order with an error status
+            pub(super) reject_first: bool,
+            /// Modify requests received, as sent on the wire
+            pub(super) modifies: Vec<Value>,
+            /// Oids of cancel requests received
+            pub(super) cancels: Vec<u64>,
+            /// Orders received, as sent on the wire
+            pub(super) orders: Vec<Value>,
+            /// Builder field of every order action (null when absent)
+            pub(super) builders: Vec<Value>,
+            /// Oids of open orders placed outside the market
+            pub(super) foreign: Vec<u64>,
+            /// Never answer the first order request
+            pub(super) hang_first: bool,
+            /// Oids that filled, so modifies and cancels of them fail
+            pub(super) filled: Vec<u64>,
+        }
+
+        pub(super) type Shared = Arc<Mutex<MockState>>;
+
+        pub(super) async fn spawn(state: MockState) -> (SocketAddr, Shared) {
+            let state = Arc::new(Mutex::new(state));
+            let app = Router::new()
+                .route("/exchange", post(exchange))
+                .route("/info", post(info))
+                .with_state(state.clone());
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+            (addr, state)
+        }
+
+        async fn exchange(
+            State(state): State<Shared>,
+            Json(body): Json<Value>,
+        ) -> (StatusCode, Json<Value>) {
+            let hang = {
+                let mut state = state.lock().unwrap();
+                let hang = state.hang_first
+                    && body["action"]["type"] == "order"
+                    && state.order_requests == 0;
+                if hang {
+                    state.order_requests += 1;
+                }
+                hang
+            };
+            if hang {
+                std::future::pending::<()>().await;
+            }
+
+            let mut state = state.lock().unwrap();
+            if body["action"]["type"] == "cancel" {
+                let oid = body["action"]["cancels"][0]["o"].as_u64().unwrap();
+                if state.filled.contains(&oid) {
+                    let error = "Order was never placed, already canceled, or filled.";
+                    return (
+                        StatusCode::OK,
+                        Json(json!({
+                            "status": "ok",
+                            "response": {"type": "cancel", "data": {"statuses": [{"error": error}]}}
+                        })),
+                    );
+                }
+                state.cancels.push(oid);
+                return (
+                    StatusCode::OK,
+                    Json(json!({
+                        "status": "ok",
+                        "response": {"type": "cancel", "data": {"statuses": ["success"]}}
+                    })),
+                );
+            }
+            if body["action"]["type"] == "batchModify" {
+                let oid = body["action"]["modifies"][0]["oid"].as_u64().unwrap();
+                if state.filled.contains(&oid) {
+                    let error = "Cannot modify canceled or filled order";
+                    return (
+                        StatusCode::OK,
+                        Json(json!({
+                            "status": "ok",
+                            "response": {"type": "order", "data": {"statuses": [{"error": error}]}}
+                        })),
+                    );
+                }
+                state.modifies.push(body["action"]["modifies"][0].clone());
+                let oid = 200 + state.modifies.len() as u64;
+                return (
+                    StatusCode::OK,
+                    Json(json!({
+                        "status": "ok",
+                        "response": {"type": "order", "data": {"statuses": [{"resting": {"oid": oid}}]}}
+                    })),
+                );
+            }
+            state.order_requests += 1;
+            state.orders.push(body["action"]["orders"][0].clone());
+            state.builders.push(body["action"]["builder"].clone());
+            let cloid = body["action"]["orders"][0]["c"].as_str().unwrap().to_string();
+
+            if state.reject_first && state.order_requests == 1 {
+                return (
+                    StatusCode::OK,
+                    Json(json!({
+                        "status": "ok",
+                        "response": {"type": "order", "data": {"statuses": [
+                            {"error": "Order has invalid price."}
+                        ]}}
+                    })),
+                );
+            }
+
+            if state.fail_first && state.order_requests == 1 {
+                if state.accept_failed {
+                    state.resting.push(cloid);
+                }
+                return (StatusCode::BAD_GATEWAY, Json(json!("upstream timeout")));
+            }
+
+            state.resting.push(cloid);
+            let oid = 100 + state.resting.len() as u64;
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "ok",
+                    "response": {"type": "order", "data": {"statuses": [{"resting": {"oid": oid}}]}}
+                })),
+            )
+        }
+
+        async fn info(State(state): State<Shared>, Json(body): Json<Value>) -> Json<Value> {
+            if body["type"] == "spotClearinghouseState" {
+                let balance = |coin: &str, total: &str| {
+                    json!({"coin": coin, "token": 0, "hold": "0", "total": total, "entryNtl": "0"})
+                };
+                return Json(json!({"balances": [
+                    balance("USDC", "1000"), balance("HYPE", "3"), balance("USDT0", "250")
+                ]}));
+            }
+            if body["type"] == "openOrders" {
+                let state = state.lock().unwrap();
+                let order = |oid: u64, cloid: Option<&String>| {
+                    json!({"coin": "BTC", "limitPx": "50000", "oid": oid, "side": "B",
+                           "sz": "1", "timestamp": 0, "cloid": cloid})
+                };
+                // Cancelled orders leave the book
+                let open: Vec<Value> = (101..)
+                    .zip(&state.resting)
+                    .filter(|(oid, _)| !state.cancels.contains(oid))
+                    .map(|(oid, cloid)| order(oid, Some(cloid)))
+                    .chain(state.foreign.iter().map(|&oid| order(oid, None)))
+                    .collect();
+                return Json(Value::Array(open));
+            }
+            assert_eq!(body["type"], "orderStatus");
+            let state = state.lock().unwrap();
+            let cloid = body["oid"].as_str().unwrap();
+            match state.resting.iter().position(|c| c == cloid) {
+                Some(index) => {
+                    let oid = 101 + index as u64;
+                    let status = if state.cancels.contains(&oid) { "canceled" } else { "open" };
+                    Json(json!({
+                    "status": "order",
+                    "order": {
+                        "order": {
+                            "coin": "BTC", "side": "B", "limitPx": "50000", "sz": "1",
+                            "oid": oid, "timestamp": 0, "triggerCondition": "N/A",
+                            "isTrigger": false, "triggerPx": "0", "isPositionTpsl": false,
+                            "reduceOnly": false, "orderType": "Limit", "origSz": "1",
+                            "tif": "Gtc", "cloid": cloid
+                        },
+                        "status": status,
+                        "statusTimestamp": 0
+                    }
+                }))
+                }
+                None => Json(json!({"status": "unknownOid"})),
+            }
+        }
+    }
+
+    async fn mock_market(
+        addr: std::net::SocketAddr,
+    ) -> HyperliquidMarket<crate::market::NoOpListener> {
+        mock_market_with(addr, Arc::new(RwLock::new(crate::market::NoOpListener))).await
+    }
+
+    async fn mock_market_with<L: MarketListener>(
+        addr: std::net::SocketAddr,
+        listener: Arc<RwLock<L>>,
+    ) -> HyperliquidMarket<L> {
+        let base_url = format!("http://{addr}");
+        let wallet: PrivateKeySigner =
+            "0000000000000000000000000000000000000000000000000000000000000001"
+                .parse()
+                .unwrap();
+        let user_address = wallet.address();
+
+        let mut info_client = InfoClient::new(None, None)
+            .await
+            .unwrap()
+            .with_rate_limiter(None);
+        info_client.http_client.base_url = base_url.clone();
+        let exchange_client = ExchangeClient {
+            http_client: crate::req::HttpClient {
+                client: reqwest::Client::new(),
+                base_url,
+                mainnet: false,
+                rate_limiter: None,
+            },
+            wallet,
+            meta: crate::Meta { universe: vec![] },
+            vault_address: None,
+            coin_to_asset: HashMap::from([("BTC".to_string(), 0)]),
+        };
+
+        HyperliquidMarket {
+            asset: "BTC".to_string(),
+            assets: HashMap::from([
+                ("BTC".to_string(), AssetInfo::new("BTC", 0.0, 10_000.0, 5, 5)),
+                ("ETH".to_string(), AssetInfo::new("ETH", 0.0, 10_000.0, 4, 5)),
+            ]),
+            asset_keys: HashMap::from([
+                ("BTC".to_string(), "BTC".to_string()),
+                ("ETH".to_string(), "ETH".to_string()),
+            ]),
+            quote_tokens: HashMap::new(),
+            listener,
+            info_client,
+            price_feed: None,
+            fill_feed: None,
+            exchange_client,
+            user_address,
+            prices: HashMap::new(),
+            orders: HashMap::new(),
+            exchange_oid_to_order_id: HashMap::new(),
+            cloid_to_order_id: HashMap::new(),
+            cloid_prefix: 7,
+            max_slippage_bps: 100,
+            total_fees: 0.0,
+            feed_watchdog: FeedWatchdog::new(unix_now()),
+            feed_capacity: None,
+            feed_backlog: FeedBacklog::default(),
+            stopped: false,
+            builder: None,
+            seen_fills: SeenFills::new(16),
+            dry_run: false,
+            price_source: PriceSource::Mid,
+            request_timeout: HyperliquidMarket::<L>::REQUEST_TIMEOUT,
+        }
+    }
+
+    #[test]
+    fn test_close_limit_price_respects_slippage() {
+        let precision = AssetPrecision::for_perp(5);
+
+        // Closing a short buys at most 1% above mid, rounded down to a tick
+        let buy = close_limit_price(43_210.7, true, 100, &precision);
+        assert!(buy <= 43_210.7 * 1.01);
+        assert!(buy > 43_210.7 * 1.01 - precision.tick_size(buy));
+
+        // Closing a long sells at least 1% below mid, rounded up to a tick
+        let sell = close_limit_price(43_210.7, false, 100, &precision);
+        assert!(sell >= 43_210.7 * 0.99);
+        assert!(sell < 43_210.7 * 0.99 + precision.tick_size(sell));
+
+        // The retry's wider bound still holds
+        let wider = close_limit_price(43_210.7, false, 200, &precision);
+        assert!(wider >= 43_210.7 * 0.98 && wider < sell);
+
+        // Just under a power of ten the nearest tick is above the bound
+        assert_eq!(close_limit_price(99.9996, true, 0, &AssetPrecision::for_perp(0)), 99.999);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_placement_registers_every_oid() {
+        let (addr, mock) = mock_exchange::spawn(mock_exchange::MockState::default()).await;
+        let mut market = mock_market(addr).await;
+
+        let orders: Vec<OrderRequest> = (1..=20)
+            .map(|id| OrderRequest::buy(id, "BTC", 1.0, 50000.0 - id as f64))
+            .collect();
+        market.place_orders(orders).await;
+
+        // Responses arrive in any order; each oid still maps back to its own order
+        let mock = mock.lock().unwrap();
+        assert_eq!(mock.order_requests, 20);
+        for id in 1..=20 {
+            assert_eq!(market.order_status(id), Some(OrderStatus::Pending));
+            let cloid = uuid_to_hex_string(market.get_cloid(id).unwrap());
+            let position = mock.resting.iter().position(|c| *c == cloid).unwrap();
+            assert_eq!(market.get_exchange_oid(id), Some(101 + position as u64));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_spot_balances_read_in_pair_quote() {
+        let (addr, _) = mock_exchange::spawn(mock_exchange::MockState::default()).await;
+        let market = mock_market(addr).await;
+
+        let fetch = |asset, quote| {
+            HyperliquidMarket::<crate::market::NoOpListener>::fetch_balances(
+                &market.info_client,
+                asset,
+                quote,
+                market.user_address,
+            )
+        };
+        assert_eq!(fetch("HYPE/USDT0", "USDT0").await.unwrap(), (3.0, 250.0));
+        assert_eq!(fetch("HYPE/USDC", "USDC").await.unwrap(), (3.0, 1000.0));
+    }
+
+    #[tokio::test]
+    async fn test_builder_code_sent_with_orders() {
+        let (addr, mock) = mock_exchange::spawn(mock_exchange::MockState::default()).await;
+        let mut market = mock_market(addr).await;
+        market.place_order(OrderRequest::buy(1, "BTC", 1.0, 50000.0)).await.unwrap();
+
+        market.builder = Some(BuilderInfo {
+            builder: "0x8c967e73e7b15087c42a10d344cff4c96d877f1d".to_string(),
+            fee: 10,
+        });
+        market.place_order(OrderRequest::buy(2, "BTC", 1.0, 49000.0)).await.unwrap();
+
+        let mock = mock.lock().unwrap();
+        assert!(mock.builders[0].is_null());
+        assert_eq!(
+            mock.builders[1],
+            serde_json::json!({"b": "0x8c967e73e7b15087c42a10d344cff4c96d877f1d", "f": 10})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_open_orders_reports_drift() {
+        let (addr, mock) = mock_exchange::spawn(mock_exchange::MockState::default()).await;
+        let mut market = mock_market(addr).await;
+
+        market.place_order(OrderRequest::buy(1, "BTC", 1.0, 50000.0)).await.unwrap();
+        market.place_order(OrderRequest::buy(2, "BTC", 1.0, 49000.0)).await.unwrap();
+        let open = market.open_orders();
+        assert_eq!(open.iter().map(|(id, _, _)| *id).collect::<Vec<_>>(), vec![1, 2]);
+        assert!(market.reconcile_open_orders().await.unwrap().is_empty());
+
+        // Order 2 is cancelled behind the market's back, and another appears
+        {
+            let mut mock = mock.lock().unwrap();
+            mock.cancels.push(market.get_exchange_oid(2).unwrap());
+            mock.foreign.push(999);
+        }
+        let drift = market.reconcile_open_orders().await.unwrap();
+        assert_eq!(drift.missing, vec![2]);
+        assert_eq!(drift.untracked, vec![999]);
+    }
+
+    #[tokio::test]
+    async fn test_sync_reports_exchange_open_orders() {
+        use crate::market::listener::RecordingListener;
+
+        let (addr, mock) = mock_exchange::spawn(mock_exchange::MockState::default()).await;
+        let listener = Arc::new(RwLock::new(RecordingListener::default()));
+        let mut market = mock_market_with(addr, listener.clone()).await;
+        market.place_order(OrderRequest::buy(1, "BTC", 1.0, 50000.0)).await.unwrap();
+        market.place_order(OrderRequest::buy(2, "BTC", 1.0, 49000.0)).await.unwrap();
+
+        // Order 2 is cancelled behind the market's back
+        mock.lock().unwrap().cancels.push(market.get_exchange_oid(2).unwrap());
+        market.sync_open_orders().await;
+
+        let listener = listener.read().await;
+        assert_eq!(listener.rejections.len(), 1);
+        assert_eq!(listener.rejections[0].0, 2);
+        assert!(listener.rejections[0].1.contains("canceled"));
+        assert_eq!(listener.open_orders, vec![vec![1]]);
+        assert_eq!(market.order_status(2), Some(OrderStatus::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_never_reaches_exchange() {
+        let (addr, mock) = mock_exchange::spawn(mock_exchange::MockState::default()).await;
+        let mut market = mock_market(addr).await;
+        market.dry_run = true;
+
+        market.place_order(OrderRequest::buy(1, "BTC", 1.0, 50000.0)).await.unwrap();
+        market.place_orders(vec![OrderRequest::buy(2, "BTC", 1.0, 49000.0)]).await;
+        assert_eq!(market.order_status(1), Some(OrderStatus::Pending));
+        assert_eq!(market.get_exchange_oid(2), Some(2));
+        market.modify_order(2, 48000.0, 1.0).await.unwrap();
+        market.cancel_order(1).await.unwrap();
+        assert_eq!(market.order_status(1), Some(OrderStatus::Cancelled));
+
+        let mock = mock.lock().unwrap();
+        assert_eq!(mock.order_requests, 0);
+        assert!(mock.modifies.is_empty());
+        assert!(mock.cancels.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_retry_does_not_double_place_same_cloid() {
+        let (addr, mock) = mock_exchange::spawn(mock_exchange::MockState {
+            fail_first: true,
+            accept_failed: true,
+            ..Default::default()
+        })
+        .await;
+        let mut market = mock_market(addr).await;
+
+        market.place_order(OrderRequest::buy(1, "BTC", 1.0, 50000.0)).await.unwrap();
+
+        let mock = mock.lock().unwrap();
+        assert_eq!(mock.order_requests, 1);
+        assert_eq!(mock.resting.len(), 1);
+        let cloid = market.get_cloid(1).unwrap();
+        assert_eq!(mock.resting[0], uuid_to_hex_string(cloid));
+        assert_eq!(market.order_status(1), Some(OrderStatus::Pending));
+        assert_eq!(market.get_exchange_oid(1), Some(101));
+    }
+
+    #[tokio::test]
+    async fn test_retry_ignores_same_order_id_from_a_previous_run() {
+        let (addr, mock) = mock_exchange::spawn(mock_exchange::MockState {
+            fail_first: true,
+            accept_failed: false,
+            ..Default::default()
+        })
+        .await;
+        // A restarted bot hands out order id 1 again; the last run's order 1
+        // still rests under that run's prefix
+        let previous_run = uuid_to_hex_string(Uuid::from_u64_pair(8, 1));
+        mock.lock().unwrap().resting.push(previous_run);
+        let mut market = mock_market(addr).await;
+
+        market.place_order(OrderRequest::buy(1, "BTC", 1.0, 50000.0)).await.unwrap();
+
+        let mock = mock.lock().unwrap();
+        assert_eq!(mock.order_requests, 2);
+        assert_eq!(mock.resting.len(), 2);
+        assert_eq!(market.get_exchange_oid(1), Some(102));
+    }
+
+    #[tokio::test]
+    async fn test_retry_places_order_that_never_arrived() {
+        let (addr, mock) = mock_exchange::spawn(mock_exchange::MockState {
+            fail_first: true,
+            accept_failed: false,
+            ..Default::default()
+        })
+        .await;
+        let mut market = mock_market(addr).await;
+
+        market.place_order(OrderRequest::buy(1, "BTC", 1.0, 50000.0)).await.unwrap();
+
+        let mock = mock.lock().unwrap();
+        assert_eq!(mock.order_requests, 2);
+        assert_eq!(mock.resting.len(), 1);
+        assert_eq!(market.order_status(1), Some(OrderStatus::Pending));
+        assert_eq!(market.get_exchange_oid(1), Some(101));
+    }
+
+    #[tokio::test]
+    async fn test_hung_order_request_is_abandoned_after_timeout() {
+        let (addr, mock) = mock_exchange::spawn(mock_exchange::MockState {
+            hang_first: true,
+            ..Default::default()
+        })
+        .await;
+        let mut market = mock_market(addr).await.with_request_timeout(Duration::from_millis(200));
+
+        // The first request never answers; the cloid lookup finds nothing and
+        // the order is sent again
+        let order = market.place_order(OrderRequest::buy(1, "BTC", 1.0, 50000.0));
+        tokio::time::timeout(Duration::from_secs(5), order)
+            .await
+            .expect("a hung request must not stall placement")
+            .unwrap();
+
+        let mock = mock.lock().unwrap();
+        assert_eq!(mock.order_requests, 2);
+        assert_eq!(mock.resting.len(), 1);
+        assert_eq!(market.order_status(1), Some(OrderStatus::Pending));
+        assert_eq!(market.get_exchange_oid(1), Some(101));
+    }
+
+    #[tokio::test]
+    async fn test_cloid_is_deterministic_per_order_id() {
+        let market = mock_market("127.0.0.1:9".parse().unwrap()).await;
+        assert_eq!(market.cloid_for(5), market.cloid_for(5));
+        assert_ne!(market.cloid_for(5), market.cloid_for(6));
+        assert_eq!(market.cloid_for(5).as_u64_pair(), (7, 5));
+    }
+
+    #[tokio::test]
+    async fn test_modify_keeps_order_id_mapping() {
+        let (addr, mock) = mock_exchange::spawn(Default::default()).await;
+        let mut market = mock_market(addr).await;
+
+        market.place_order(OrderRequest::buy(1, "BTC", 1.0, 50000.0)).await.unwrap();
+        assert_eq!(market.get_exchange_oid(1), Some(101));
+
+        market.modify_order(1, 49500.0, 2.0).await.unwrap();
+        assert_eq!(market.get_exchange_oid(1), Some(201));
+        assert_eq!(market.exchange_oid_to_order_id.get(&201), Some(&1));
+        assert!(!market.exchange_oid_to_order_id.contains_key(&101));
+        assert_eq!(market.orders[&1].request.limit_price, 49500.0);
+        assert_eq!(market.orders[&1].request.qty, 2.0);
+
+        let mock = mock.lock().unwrap();
+        assert_eq!(mock.order_requests, 1);
+        let modify = &mock.modifies[0];
+        assert_eq!(modify["oid"], 101);
+        assert_eq!(modify["order"]["p"], "49500");
+        assert_eq!(modify["order"]["s"], "2");
+        assert_eq!(modify["order"]["c"], uuid_to_hex_string(market.get_cloid(1).unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_replace_reports_partial_fill_of_the_old_order() {
+        use crate::market::listener::RecordingListener;
+
+        let (addr, mock) = mock_exchange::spawn(Default::default()).await;
+        let listener = Arc::new(RwLock::new(RecordingListener::default()));
+        let mut market = mock_market_with(addr, listener.clone()).await;
+        market.place_order(OrderRequest::buy(1, "BTC", 1.0, 50000.0)).await.unwrap();
+        market.execute_fill(OrderFill::new(1, "BTC", 0.4, 50000.0));
+        assert!(listener.read().await.fills.is_empty());
+
+        let replacement = OrderRequest::buy(2, "BTC", 1.0, 49500.0).replacing(1);
+        market.place_order(replacement).await.unwrap();
+
+        assert_eq!(mock.lock().unwrap().modifies.len(), 1);
+        assert_eq!(market.order_status(1), Some(OrderStatus::Cancelled));
+        assert_eq!(market.order_status(2), Some(OrderStatus::Pending));
+        let fills = &listener.read().await.fills;
+        assert_eq!(fills.len(), 1);
+        assert_eq!((fills[0].order_id, fills[0].qty), (1, 0.4));
+    }
+
+    #[tokio::test]
+    async fn test_replacement_not_placed_when_old_order_cannot_be_cancelled() {
+        use crate::market::listener::RecordingListener;
+
+        let (addr, mock) = mock_exchange::spawn(Default::default()).await;
+        let listener = Arc::new(RwLock::new(RecordingListener::default()));
+        let mut market = mock_market_with(addr, listener.clone()).await;
+        market.place_order(OrderRequest::buy(1, "BTC", 1.0, 50000.0)).await.unwrap();
+
+        // The old order fills before the modify and the cancel arrive
+        mock.lock().unwrap().filled.push(101);
+        let replacement = OrderRequest::buy(2, "BTC", 1.0, 49500.0).replacing(1);
+        assert!(market.place_order(replacement).await.is_err());
+
+        assert_eq!(mock.lock().unwrap().order_requests, 1);
+        assert!(matches!(market.order_status(2), Some(OrderStatus::Rejected(_))));
+        assert_eq!(market.order_status(1), Some(OrderStatus::Pending));
+        let rejections = &listener.read().await.rejections;
+        assert_eq!(rejections.len(), 1);
+        assert_eq!(rejections[0].0, 2);
+        assert!(rejections[0].1.contains("could not be cancelled"));
+    }
+
+    #[tokio::test]
+    async fn test_modify_unknown_order_is_rejected() {
+        let mut market = mock_market("127.0.0.1:9".parse().unwrap()).await;
+        assert!(matches!(
+            market.modify_order(42, 1.0, 1.0).await,
+            Err(MarketError::UnknownOrder(42))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_refused_modify_returns_the_exchange_error() {
+        let (addr, mock) = mock_exchange::spawn(mock_exchange::MockState::default()).await;
+        let mut market = mock_market(addr).await;
+        market.place_order(OrderRequest::buy(1, "BTC", 1.0, 50000.0)).await.unwrap();
+        mock.lock().unwrap().filled.push(101);
+
+        let err = market.modify_order(1, 49500.0, 1.0).await.unwrap_err();
+        assert!(err.to_string().contains("Cannot modify canceled or filled order"));
+        // The order keeps its oid and terms
+        assert_eq!(market.get_exchange_oid(1), Some(101));
+        assert_eq!(market.orders[&1].request.limit_price, 50000.0);
+    }
+
+    #[tokio::test]
+    async fn test_expired_order_cancelled_on_tick() {
+        let (addr, mock) = mock_exchange::spawn(mock_exchange::MockState::default()).await;
+        let mut market = mock_market(addr).await;
+
+        market
+            .place_order(OrderRequest::buy(1, "BTC", 1.0, 50000.0).expires_at(5_000))
+            .await
+            .unwrap();
+        market
+            .place_order(OrderRequest::buy(2, "BTC", 1.0, 50000.0).expires_at(20_000))
+            .await
+            .unwrap();
+
+        market.tick(10).await;
+
+        assert_eq!(mock.lock().unwrap().cancels, vec![101]);
+        assert_eq!(market.order_status(1), Some(OrderStatus::Expired));
+        assert_eq!(market.order_status(2), Some(OrderStatus::Pending));
+
+        // Order 2 expires later; order 1 isn't cancelled twice
+        market.tick(30).await;
+        assert_eq!(mock.lock().unwrap().cancels, vec![101, 102]);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_cancels_all_and_stops_listener() {
+        use crate::bot::{Bot, BotStatus, TradeLog};
+        use crate::strategy::NoOpStrategy;
+
+        let (addr, mock) = mock_exchange::spawn(mock_exchange::MockState::default()).await;
+        let path = std::env::temp_dir().join(format!("shutdown_{}.csv", Uuid::new_v4()));
+        let bot = Bot::new(NoOpStrategy).with_trade_log(TradeLog::open(&path).unwrap());
+        let listener = Arc::new(RwLock::new(bot));
+        let mut market = mock_market_with(addr, listener.clone()).await;
+        market.place_order(OrderRequest::buy(1, "BTC", 1.0, 50000.0)).await.unwrap();
+        market.place_order(OrderRequest::sell(2, "BTC", 1.0, 51000.0)).await.unwrap();
+
+        market.shutdown().await;
+
+        assert_eq!(mock.lock().unwrap().cancels, vec![101, 102]);
+        assert_eq!(market.order_status(1), Some(OrderStatus::Cancelled));
+        assert_eq!(market.order_status(2), Some(OrderStatus::Cancelled));
+        assert_eq!(listener.read().await.bot_status(), BotStatus::Stopped);
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_order_tif_reaches_exchange() {
+        let (addr, mock) = mock_exchange::spawn(mock_exchange::MockState::default()).await;
+        let mut market = mock_market(addr).await;
+
+        market.place_order(OrderRequest::buy(1, "BTC", 1.0, 50000.0)).await.unwrap();
+        market
+            .place_order(OrderRequest::buy(2, "BTC", 1.0, 49000.0).tif(TimeInForce::Alo))
+            .await
+            .unwrap();
+
+        let orders = mock.lock().unwrap().orders.clone();
+        assert_eq!(orders[0]["t"]["limit"]["tif"], "Gtc");
+        assert_eq!(orders[1]["t"]["limit"]["tif"], "Alo");
+    }
+
+    #[tokio::test]
+    async fn test_price_updates_forwarded_for_every_asset() {
+        #[derive(Default)]
+        struct PriceListener {
+            prices: Vec<(String, f64)>,
+        }
+
+        impl MarketListener for PriceListener {
+            fn on_order_filled(&mut self, _fill: OrderFill) -> Vec<OrderRequest> {
+                vec![]
+            }
+
+            fn on_price_update(&mut self, asset: &str, price: f64) -> Vec<OrderRequest> {
+                self.prices.push((asset.to_string(), price));
+                vec![]
+            }
+        }
+
+        let listener = Arc::new(RwLock::new(PriceListener::default()));
+        let mut market = mock_market_with("127.0.0.1:9".parse().unwrap(), listener.clone()).await;
+        let message: Message = serde_json::from_value(serde_json::json!({
+            "channel": "allMids",
+            "data": {"mids": {"BTC": "50000", "ETH": "3000", "SOL": "150"}}
+        }))
+        .unwrap();
+
+        market.handle_message(message);
+
+        let mut prices = listener.read().await.prices.clone();
+        prices.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            prices,
+            vec![("BTC".to_string(), 50000.0), ("ETH".to_string(), 3000.0)]
+        );
+        // Untraded assets are still priced, just not forwarded
+        assert_eq!(market.current_price("SOL"), Some(150.0));
+        assert_eq!(market.asset_info_for("ETH").unwrap().sz_decimals, 4);
+    }
+
+    #[tokio::test]
+    async fn test_mark_price_source_feeds_mark_price() {
+        #[derive(Default)]
+        struct PriceListener {
+            prices: Vec<f64>,
+        }
+
+        impl MarketListener for PriceListener {
+            fn on_order_filled(&mut self, _fill: OrderFill) -> Vec<OrderRequest> {
+                vec![]
+            }
+
+            fn on_price_update(&mut self, _asset: &str, price: f64) -> Vec<OrderRequest> {
+                self.prices.push(price);
+                vec![]
+            }
+        }
+
+        let listener = Arc::new(RwLock::new(PriceListener::default()));
+        let mut market = mock_market_with("127.0.0.1:9".parse().unwrap(), listener.clone()).await;
+        market.price_source = PriceSource::Mark;
+        let mids: Message = serde_json::from_value(serde_json::json!({
+            "channel": "allMids",
+            "data": {"mids": {"BTC": "50000"}}
+        }))
+        .unwrap();
+        let ctx: Message = serde_json::from_value(serde_json::json!({
+            "channel": "activeAssetCtx",
+            "data": {"coin": "BTC", "ctx": {
+                "dayNtlVlm": "1000000", "prevDayPx": "49000", "markPx": "50100",
+                "midPx": "50000", "funding": "0.0001", "openInterest": "100",
+                "oraclePx": "50050"
+            }}
+        }))
+        .unwrap();
+
+        // Mids still price orders but no longer drive the listener
+        market.handle_message(mids);
+        market.handle_message(ctx.clone());
+        assert_eq!(listener.read().await.prices, vec![50100.0]);
+        assert_eq!(market.current_price("BTC"), Some(50000.0));
+
+        market.price_source = PriceSource::Oracle;
+        market.handle_message(ctx);
+        assert_eq!(listener.read().await.prices, vec![50100.0, 50050.0]);
+    }
+
+    #[tokio::test]
+    async fn test_spot_book_reported_by_name() {
+        #[derive(Default)]
+        struct BookListener {
+            books: Vec<(String, MarketBook)>,
+        }
+
+        impl MarketListener for BookListener {
+            fn on_order_filled(&mut self, _fill: OrderFill) -> Vec<OrderRequest> {
+                vec![]
+            }
+
+            fn on_price_update(&mut self, _asset: &str, _price: f64) -> Vec<OrderRequest> {
+                vec![]
+            }
+
+            fn on_book_update(&mut self, asset: &str, book: &MarketBook) {
+                self.books.push((asset.to_string(), book.clone()));
+            }
+        }
+
+        let listener = Arc::new(RwLock::new(BookListener::default()));
+        let mut market = mock_market_with("127.0.0.1:9".parse().unwrap(), listener.clone()).await;
+        let hype = AssetInfo::new("HYPE/USDC", 0.0, 10_000.0, 2, 4);
+        market.assets.insert("HYPE/USDC".to_string(), hype);
+        market.asset_keys.insert("@107".to_string(), "HYPE/USDC".to_string());
+
+        let book_message = |coin: &str| -> Message {
+            serde_json::from_value(serde_json::json!({
+                "channel": "l2Book",
+                "data": {
+                    "coin": coin,
+                    "time": 1,
+                    "levels": [[{"px": "25", "sz": "1", "n": 1}], [{"px": "26", "sz": "2", "n": 1}]]
+                }
+            }))
+            .unwrap()
+        };
+        // The venue never names spot books by pair
+        market.handle_message(book_message("HYPE/USDC"));
+        market.handle_message(book_message("@107"));
+
+        let books = &listener.read().await.books;
+        assert_eq!(books.len(), 1);
+        assert_eq!(books[0].0, "HYPE/USDC");
+        assert_eq!(books[0].1.best_bid(), Some(25.0));
+    }
+
+    #[tokio::test]
+    async fn test_spot_mark_price_reaches_listener_by_name() {
+        use crate::market::listener::RecordingListener;
+
+        let listener = Arc::new(RwLock::new(RecordingListener::default()));
+        let mut market = mock_market_with("127.0.0.1:9".parse().unwrap(), listener.clone()).await;
+        market.price_source = PriceSource::Oracle;
+        let hype = AssetInfo::new("HYPE/USDC", 0.0, 10_000.0, 2, 4);
+        market.assets.insert("HYPE/USDC".to_string(), hype);
+        market.asset_keys.insert("@107".to_string(), "HYPE/USDC".to_string());
+
+        // Contexts are subscribed and delivered under the exchange key
+        assert!(market.coins().contains(&"@107".to_string()));
+        let ctx: Message = serde_json::from_value(serde_json::json!({
+            "channel": "activeSpotAssetCtx",
+            "data": {"coin": "@107", "ctx": {
+                "dayNtlVlm": "1000000", "prevDayPx": "24", "markPx": "25.5",
+                "midPx": "25.4", "circulatingSupply": "1000000"
+            }}
+        }))
+        .unwrap();
+        market.handle_message(ctx);
+
+        // Spot has no oracle price, so it trades on the mark
+        assert_eq!(
+            listener.read().await.price_updates,
+            vec![("HYPE/USDC".to_string(), 25.5)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fill_fees_accumulate() {
+        #[derive(Default)]
+        struct RecordingFills {
+            fills: Vec<OrderFill>,
+        }
+
+        impl MarketListener for RecordingFills {
+            fn on_order_filled(&mut self, fill: OrderFill) -> Vec<OrderRequest> {
+                self.fills.push(fill);
+                vec![]
+            }
+
+            fn on_price_update(&mut self, _asset: &str, _price: f64) -> Vec<OrderRequest> {
+                vec![]
+            }
+        }
+
+        let listener = Arc::new(RwLock::new(RecordingFills::default()));
+        let mut market = mock_market_with("127.0.0.1:9".parse().unwrap(), listener.clone()).await;
+        let mut order = TrackedOrder::new(OrderRequest::buy(1, "BTC", 2.0, 50000.0), Uuid::nil());
+        order.exchange_oid = Some(555);
+        market.orders.insert(1, order);
+        market.exchange_oid_to_order_id.insert(555, 1);
+
+        let fill = |sz: &str, fee: &str, tid: u64| {
+            serde_json::json!({
+                "coin": "BTC", "side": "B", "px": "50000", "sz": sz, "time": 0,
+                "hash": "0x0", "startPosition": "0", "dir": "Open Long", "closedPnl": "0",
+                "oid": 555, "cloid": null, "crossed": false, "fee": fee,
+                "feeToken": "USDC", "tid": tid
+            })
+        };
+        for (sz, fee, tid) in [("1", "17.5", 1), ("1", "-2.5", 2)] {
+            let message: Message = serde_json::from_value(serde_json::json!({
+                "channel": "user",
+                "data": {"fills": [fill(sz, fee, tid)]}
+            }))
+            .unwrap();
+            market.handle_message(message);
+        }
+
+        // The rebate on the second fill offsets part of the first fee
+        assert!((market.total_fees() - 15.0).abs() < 1e-9);
+        let fills = &listener.read().await.fills;
+        assert_eq!(fills.len(), 1);
+        assert!((fills[0].fee - 15.0).abs() < 1e-9);
+
+        // A spot buy's fee is charged in the base token
+        assert!((fee_in_quote("HYPE/USDC", 0.01, "HYPE", 20.0) - 0.2).abs() < 1e-9);
+        assert_eq!(fee_in_quote("HYPE/USDC", 0.3, "USDC", 20.0), 0.3);
+    }
+
+    #[tokio::test]
+    async fn test_redelivered_fill_applied_once() {
+        #[derive(Default)]
+        struct CounterOrders {
+            fills: Vec<OrderFill>,
+        }
+
+        impl MarketListener for CounterOrders {
+            fn on_order_filled(&mut self, fill: OrderFill) -> Vec<OrderRequest> {
+                self.fills.push(fill.clone());
+                vec![OrderRequest::sell(fill.order_id + 1, "BTC", fill.qty, 51000.0)]
+            }
+
+            fn on_price_update(&mut self, _asset: &str, _price: f64) -> Vec<OrderRequest> {
+                vec![]
+            }
+        }
+
+        let listener = Arc::new(RwLock::new(CounterOrders::default()));
+        let mut market = mock_market_with("127.0.0.1:9".parse().unwrap(), listener.clone()).await;
+        let mut order = TrackedOrder::new(OrderRequest::buy(1, "BTC", 2.0, 50000.0), Uuid::nil());
+        order.exchange_oid = Some(555);
+        market.orders.insert(1, order);
+        market.exchange_oid_to_order_id.insert(555, 1);
+
+        let fill = |tid: u64| {
+            serde_json::from_value::<Message>(serde_json::json!({
+                "channel": "user",
+                "data": {"fills": [{
+                    "coin": "BTC", "side": "B", "px": "50000", "sz": "1", "time": 1000,
+                    "hash": "0x0", "startPosition": "0", "dir": "Open Long", "closedPnl": "0",
+                    "oid": 555, "cloid": null, "crossed": false, "fee": "5",
+                    "feeToken": "USDC", "tid": tid
+                }]}
+            }))
+            .unwrap()
+        };
+
+        // Half the order, then the same half again as a resubscribe would replay it
+        assert!(market.handle_message(fill(1)).is_empty());
+        assert!(market.handle_message(fill(1)).is_empty());
+        assert_eq!(market.order_status(1), Some(OrderStatus::PartiallyFilled(1.0)));
+
+        let counter = market.handle_message(fill(2));
+        assert_eq!(counter.len(), 1);
+        assert!(market.handle_message(fill(2)).is_empty());
+        assert_eq!(listener.read().await.fills.len(), 1);
+        assert!((market.total_fees() - 10.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_fill_before_resting_response_is_matched() {
+        use crate::market::listener::RecordingListener;
+
+        let listener = Arc::new(RwLock::new(RecordingListener::default()));
+        let mut market = mock_market_with("127.0.0.1:9".parse().unwrap(), listener.clone()).await;
+        let (tracked, _) = market.prepare_order(OrderRequest::buy(1, "BTC", 1.0, 50000.0));
+
+        // The fill overtakes the response that would have recorded oid 777
+        let fill: Message = serde_json::from_value(serde_json::json!({
+            "channel": "user",
+            "data": {"fills": [{
+                "coin": "BTC", "side": "B", "px": "50000", "sz": "1", "time": 1000,
+                "hash": "0x0", "startPosition": "0", "dir": "Open Long", "closedPnl": "0",
+                "oid": 777, "cloid": uuid_to_hex_string(tracked.cloid), "crossed": false,
+                "fee": "5", "feeToken": "USDC", "tid": 1
+            }]}
+        }))
+        .unwrap();
+        market.handle_message(fill);
+        assert_eq!(listener.read().await.fills.len(), 1);
+        assert_eq!(market.order_status(1), Some(OrderStatus::Filled(50000.0)));
+
+        // The late response doesn't reopen the order or report it again
+        let resting = Ok(ExchangeDataStatus::Resting(RestingOrder { oid: 777 }));
+        market.apply_order_result(tracked, resting).await.unwrap();
+        assert_eq!(market.order_status(1), Some(OrderStatus::Filled(50000.0)));
+        assert_eq!(market.get_exchange_oid(1), Some(777));
+        assert_eq!(listener.read().await.fills.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_order_is_reported_and_retried() {
+        #[derive(Default)]
+        struct RetryListener {
+            rejected: Vec<(u64, String)>,
+        }
+
+        impl MarketListener for RetryListener {
+            fn on_order_filled(&mut self, _fill: OrderFill) -> Vec<OrderRequest> {
+                vec![]
+            }
+
+            fn on_price_update(&mut self, _asset: &str, _price: f64) -> Vec<OrderRequest> {
+                vec![]
+            }
+
+            fn on_order_rejected(&mut self, order_id: u64, reason: String) -> Vec<OrderRequest> {
+                self.rejected.push((order_id, reason));
+                vec![OrderRequest::buy(order_id + 1, "BTC", 1.0, 49000.0)]
+            }
+        }
+
+        let (addr, mock) = mock_exchange::spawn(mock_exchange::MockState {
+            reject_first: true,
+            ..Default::default()
+        })
+        .await;
+        let listener = Arc::new(RwLock::new(RetryListener::default()));
+        let mut market = mock_market_with(addr, listener.clone()).await;
+
+        let result = market.place_order(OrderRequest::buy(1, "BTC", 1.0, 50000.0)).await;
+
+        assert!(matches!(result, Err(MarketError::PrecisionViolation(_))));
+        assert_eq!(
+            listener.read().await.rejected,
+            vec![(1, "Order has invalid price.".to_string())]
+        );
+        assert_eq!(
+            market.order_status(1),
+            Some(OrderStatus::Rejected("Order has invalid price.".to_string()))
+        );
+        assert_eq!(market.order_status(2), Some(OrderStatus::Pending));
+        assert_eq!(mock.lock().unwrap().order_requests, 2);
+    }
+}
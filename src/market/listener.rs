@@ -2,7 +2,69 @@
 //!
 //! Defines how external components receive notifications from the Market.
 
-use super::types::{OrderFill, OrderRequest};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::feed_queue::FeedBacklog;
+use super::types::{AssetPrecision, MarketBook, MarketCommand, OrderFill, OrderRequest};
+
+/// How often markets call `MarketListener::on_tick`
+pub const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a market waits for a mid price of its asset before reporting
+/// the feed as stalled
+pub const STALE_FEED_AFTER: Duration = Duration::from_secs(60);
+
+/// Reason passed to `MarketListener::on_order_rejected` when an order's
+/// `expires_at_ms` passes before it fills
+pub const ORDER_EXPIRED: &str = "Order expired";
+
+/// Current Unix time in seconds, as passed to `on_tick`
+pub(super) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Current Unix time in milliseconds, as stamped on simulated fills
+pub(super) fn unix_now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Tracks when the traded asset's price was last seen in the feed
+#[derive(Debug, Clone, Copy)]
+pub(super) struct FeedWatchdog {
+    last_seen: u64,
+    stalled: bool,
+}
+
+impl FeedWatchdog {
+    pub(super) fn new(now: u64) -> Self {
+        Self {
+            last_seen: now,
+            stalled: false,
+        }
+    }
+
+    /// A price for the asset arrived
+    pub(super) fn seen(&mut self, now: u64) {
+        self.last_seen = now;
+        self.stalled = false;
+    }
+
+    /// Seconds without a price, the first time that exceeds `STALE_FEED_AFTER`
+    pub(super) fn check(&mut self, now: u64) -> Option<u64> {
+        let silent = now.saturating_sub(self.last_seen);
+        if self.stalled || silent < STALE_FEED_AFTER.as_secs() {
+            return None;
+        }
+        self.stalled = true;
+        Some(silent)
+    }
+}
 
 /// MarketListener interface for receiving market notifications
 ///
@@ -27,6 +89,21 @@ pub trait MarketListener {
     /// Orders to place in response to this fill
     fn on_order_filled(&mut self, fill: OrderFill) -> Vec<OrderRequest>;
 
+    /// Called when the exchange rejects an order or it could not be sent
+    ///
+    /// Also called with `ORDER_EXPIRED` when the market cancels an order
+    /// whose expiry passed. The order is marked cancelled; it will never fill.
+    ///
+    /// # Arguments
+    /// * `order_id` - The user-provided ID of the rejected order
+    /// * `reason` - The exchange's error message
+    ///
+    /// # Returns
+    /// Orders to place instead, e.g. a corrected retry
+    fn on_order_rejected(&mut self, _order_id: u64, _reason: String) -> Vec<OrderRequest> {
+        vec![]
+    }
+
     /// Called when an asset's price is updated
     ///
     /// This notification is invoked synchronously when:
@@ -39,6 +116,84 @@ pub trait MarketListener {
     /// # Returns
     /// Orders to place in response to this price update
     fn on_price_update(&mut self, asset: &str, price: f64) -> Vec<OrderRequest>;
+
+    /// Called on a timer, about once per `TICK_INTERVAL`
+    ///
+    /// Lets listeners act on a schedule instead of only reacting to market
+    /// events.
+    ///
+    /// # Arguments
+    /// * `now` - Current Unix time in seconds
+    ///
+    /// # Returns
+    /// Orders to place
+    fn on_tick(&mut self, _now: u64) -> Vec<OrderRequest> {
+        vec![]
+    }
+
+    /// Called when a new L2 order book snapshot arrives for the traded asset
+    ///
+    /// Informational only; markets that don't stream the book never call it.
+    fn on_book_update(&mut self, _asset: &str, _book: &MarketBook) {}
+
+    /// Called once when no price for `asset` has arrived for `STALE_FEED_AFTER`
+    ///
+    /// The next `on_price_update` for the asset means the feed is back.
+    ///
+    /// # Arguments
+    /// * `asset` - The traded asset
+    /// * `silent_secs` - Seconds since its last price
+    fn on_feed_stalled(&mut self, _asset: &str, _silent_secs: u64) {}
+
+    /// Called on every tick with how far the market lags its WebSocket feed
+    ///
+    /// Informational; the market already warns when the backlog grows.
+    fn on_feed_backlog(&mut self, _backlog: FeedBacklog) {}
+
+    /// Called with `asset`'s precision from freshly fetched metadata
+    ///
+    /// Only sent in answer to `MarketCommand::RefreshPrecision`, whether or
+    /// not the precision changed. Returned orders are placed like those from
+    /// any other callback.
+    fn on_precision_update(
+        &mut self,
+        _asset: &str,
+        _precision: AssetPrecision,
+    ) -> Vec<OrderRequest> {
+        vec![]
+    }
+
+    /// Called with the ids of the orders open on the exchange
+    ///
+    /// Only sent in answer to `MarketCommand::SyncOpenOrders`. Orders the
+    /// exchange no longer has and that didn't fill are reported through
+    /// `on_order_rejected` first; orders whose fate can't be confirmed, or
+    /// whose fill is still on its way, count as open.
+    ///
+    /// # Returns
+    /// Orders to place
+    fn on_open_orders(&mut self, _open: &[u64]) -> Vec<OrderRequest> {
+        vec![]
+    }
+
+    /// Called after the market's WebSocket reconnected and resubscribed
+    ///
+    /// Fills that happened during the outage may not have been reported.
+    fn on_reconnect(&mut self) {}
+
+    /// Called once when the market shuts down, after it cancelled its orders
+    ///
+    /// No further events follow, and orders can no longer be placed. Save or
+    /// flush any state here.
+    fn on_stop(&mut self) {}
+
+    /// Take the commands queued since the last call
+    ///
+    /// Markets poll this after handling each event and carry the commands out
+    /// in order.
+    fn take_commands(&mut self) -> Vec<MarketCommand> {
+        vec![]
+    }
 }
 
 /// A no-op listener for testing or when notifications aren't needed
@@ -55,6 +210,49 @@ impl MarketListener for NoOpListener {
     }
 }
 
+/// A listener that records every event it receives, for assertions in tests
+///
+/// Never returns orders.
+#[derive(Debug, Default)]
+pub struct RecordingListener {
+    /// Price updates received, as (asset, price)
+    pub price_updates: Vec<(String, f64)>,
+    /// Fills received
+    pub fills: Vec<OrderFill>,
+    /// Rejections received, as (order_id, reason)
+    pub rejections: Vec<(u64, String)>,
+    /// Stalled feeds reported, as (asset, silent_secs)
+    pub stalls: Vec<(String, u64)>,
+    /// Open-order snapshots received, as order ids
+    pub open_orders: Vec<Vec<u64>>,
+}
+
+impl MarketListener for RecordingListener {
+    fn on_order_filled(&mut self, fill: OrderFill) -> Vec<OrderRequest> {
+        self.fills.push(fill);
+        vec![]
+    }
+
+    fn on_order_rejected(&mut self, order_id: u64, reason: String) -> Vec<OrderRequest> {
+        self.rejections.push((order_id, reason));
+        vec![]
+    }
+
+    fn on_price_update(&mut self, asset: &str, price: f64) -> Vec<OrderRequest> {
+        self.price_updates.push((asset.to_string(), price));
+        vec![]
+    }
+
+    fn on_feed_stalled(&mut self, asset: &str, silent_secs: u64) {
+        self.stalls.push((asset.to_string(), silent_secs));
+    }
+
+    fn on_open_orders(&mut self, open: &[u64]) -> Vec<OrderRequest> {
+        self.open_orders.push(open.to_vec());
+        vec![]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
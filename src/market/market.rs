@@ -17,7 +17,6 @@ use super::types::{OrderFill, OrderRequest, OrderStatus};
 /// Internal order tracking - simple status only
 #[derive(Debug, Clone)]
 struct InternalOrder {
-    #[allow(dead_code)]
     request: OrderRequest,
     status: OrderStatus,
 }
@@ -118,10 +117,13 @@ impl<L: MarketListener> Market<L> {
     ///
     /// # Arguments
     /// * `fill` - The complete fill details (qty = total filled, price = final price)
-    pub fn execute_fill(&mut self, fill: OrderFill) {
+    pub fn execute_fill(&mut self, mut fill: OrderFill) {
         // Update order state if it exists
         if let Some(internal_order) = self.orders.get_mut(&fill.order_id) {
             if internal_order.status.is_active() {
+                if fill.tag.is_none() {
+                    fill.tag = internal_order.request.tag.clone();
+                }
                 // Mark as filled with the provided price
                 internal_order.status = OrderStatus::Filled(fill.price);
 
@@ -158,7 +160,7 @@ impl<L: MarketListener> Market<L> {
     /// # Returns
     /// The current order status if the order exists
     pub fn order_status(&self, order_id: u64) -> Option<OrderStatus> {
-        self.orders.get(&order_id).map(|o| o.status)
+        self.orders.get(&order_id).map(|o| o.status.clone())
     }
 
     /// Get the shared listener reference
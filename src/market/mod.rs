@@ -28,15 +28,22 @@
 //! | `HyperliquidMarket` | Live trading on Hyperliquid exchange |
 //! | `PaperTradingMarket` | Paper trading with live price feeds |
 //!
+//! Both live markets read prices (and fills) from a [`PriceFeed`] and a
+//! [`FillFeed`]; the Hyperliquid websocket by default, or e.g. a
+//! [`ReplayPriceFeed`] for offline runs. See the `feed` module docs for
+//! wiring up another venue.
+//!
 //! # Examples
 //!
 //! ## Basic Market (in-memory)
 //!
 //! ```rust
+//! use std::sync::Arc;
+//! use tokio::sync::RwLock;
 //! use hyperliquid_rust_sdk::market::{Market, OrderRequest, OrderSide, NoOpListener};
 //!
 //! // Create a market with a no-op listener
-//! let mut market = Market::new(NoOpListener);
+//! let mut market = Market::new(Arc::new(RwLock::new(NoOpListener)));
 //!
 //! // Update price
 //! market.update_price("BTC", 50000.0);
@@ -55,14 +62,20 @@
 //!
 //! ```ignore
 //! use hyperliquid_rust_sdk::market::{
-//!     HyperliquidMarket, HyperliquidMarketInput, OrderRequest, NoOpListener
+//!     HyperliquidMarket, HyperliquidMarketInput, OrderRequest, NoOpListener, PriceSource
 //! };
 //! use hyperliquid_rust_sdk::BaseUrl;
 //!
 //! let input = HyperliquidMarketInput {
 //!     asset: "HYPE/USDC".to_string(),
+//!     extra_assets: vec![],
 //!     wallet: wallet,
 //!     base_url: Some(BaseUrl::Testnet),
+//!     max_slippage_bps: 100,
+//!     feed_capacity: None,
+//!     builder: None,
+//!     dry_run: false,
+//!     price_source: PriceSource::Mid,
 //! };
 //!
 //! let mut market = HyperliquidMarket::new(input, NoOpListener).await?;
@@ -73,7 +86,7 @@
 //!
 //! // Place a limit buy order
 //! let order = OrderRequest::buy(1, "HYPE/USDC", 10.0, 25.0);
-//! market.place_order(order).await;
+//! market.place_order(order).await?;
 //!
 //! // Start the event loop (runs indefinitely)
 //! market.start().await;
@@ -101,15 +114,35 @@
 //! market.start().await;
 //! ```
 
+mod decimal;
+mod error;
+mod feed;
+mod feed_queue;
+#[cfg(any(test, feature = "test-util"))]
+mod harness;
 mod hyperliquid_market;
 mod listener;
+#[allow(clippy::module_inception)]
 mod market;
 mod paper_trading_market;
 mod types;
 
+pub use decimal::ExactDecimal;
+pub use error::MarketError;
+pub use feed::{FillFeed, PriceFeed, ReplayFillFeed, ReplayPriceFeed};
+pub use feed_queue::{FeedBacklog, FEED_BACKLOG_WARN};
+#[cfg(any(test, feature = "test-util"))]
+pub use harness::MarketTestHarness;
 pub use hyperliquid_market::{HyperliquidMarket, HyperliquidMarketInput};
-pub use listener::{MarketListener, NoOpListener};
+pub use listener::{
+    MarketListener, NoOpListener, RecordingListener, ORDER_EXPIRED, STALE_FEED_AFTER,
+    TICK_INTERVAL,
+};
 pub use market::Market;
 pub use paper_trading_market::{PaperPosition, PaperTradingMarket, PaperTradingMarketInput};
-pub use types::{AssetInfo, AssetPrecision, OrderFill, OrderRequest, OrderSide, OrderStatus};
+pub use types::{
+    AssetInfo, AssetPrecision, FeeRates, MarketBook, MarketCommand, OpenOrderDrift, OrderFill,
+    OrderRequest, OrderSide, OrderStatus, PriceLevel, PriceMap, PriceSource, TimeInForce,
+    MIN_ORDER_NOTIONAL,
+};
 
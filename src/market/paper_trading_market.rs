@@ -5,13 +5,28 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
-use log::{error, info};
+use alloy::primitives::Address;
+use log::{error, info, warn};
 use tokio::sync::{mpsc::unbounded_channel, RwLock};
 
-use super::listener::MarketListener;
-use super::types::{AssetInfo, OrderFill, OrderRequest, OrderSide, OrderStatus};
-use crate::{BaseUrl, InfoClient, Message, Subscription};
+use super::feed::PriceFeed;
+use super::feed_queue::{FeedBacklog, FeedQueue};
+use super::listener::{
+    unix_now, unix_now_ms, FeedWatchdog, MarketListener, ORDER_EXPIRED, TICK_INTERVAL,
+};
+use super::types::{
+    AssetInfo, FeeRates, MarketBook, MarketCommand, OrderFill, OrderRequest, OrderSide,
+    OrderStatus, PriceMap,
+};
+use crate::{AssetResolver, BaseUrl, InfoClient, MarketType, Message};
+
+/// Hyperliquid settles perp funding every hour
+const FUNDING_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Levels per side forwarded to the listener from L2 book updates
+const BOOK_DEPTH: usize = 10;
 
 /// Input configuration for creating a PaperTradingMarket
 #[derive(Debug)]
@@ -20,6 +35,11 @@ pub struct PaperTradingMarketInput {
     pub asset: String,
     /// Initial balance in quote currency (e.g., USDC)
     pub initial_balance: f64,
+    /// Account whose exchange fee rates are charged (default: `FeeRates::default()`)
+    pub fee_user: Option<Address>,
+    /// Queued feed messages beyond which stale price updates are skipped
+    /// (default: unbounded)
+    pub feed_capacity: Option<usize>,
 }
 
 impl PaperTradingMarketInput {
@@ -28,8 +48,22 @@ impl PaperTradingMarketInput {
         Self {
             asset: asset.into(),
             initial_balance,
+            fee_user: None,
+            feed_capacity: None,
         }
     }
+
+    /// Charge fills at this account's maker and taker rates
+    pub fn with_fee_user(mut self, user: Address) -> Self {
+        self.fee_user = Some(user);
+        self
+    }
+
+    /// Skip stale price updates once this many feed messages are queued
+    pub fn with_feed_capacity(mut self, capacity: usize) -> Self {
+        self.feed_capacity = Some(capacity);
+        self
+    }
 }
 
 /// Internal order tracking for paper trading
@@ -106,7 +140,7 @@ pub struct PaperPosition {
 
 impl PaperPosition {
     /// Update position after a fill
-    fn apply_fill(&mut self, qty: f64, price: f64, is_buy: bool) {
+    pub(crate) fn apply_fill(&mut self, qty: f64, price: f64, is_buy: bool) {
         let signed_qty = if is_buy { qty } else { -qty };
 
         if self.size == 0.0 {
@@ -119,7 +153,7 @@ impl PaperPosition {
             self.size += signed_qty;
             self.entry_price = total_value / self.size.abs();
         } else {
-            // Reducing or closing position
+            // Reducing, closing or flipping position
             let close_qty = qty.min(self.size.abs());
             let pnl = if self.size > 0.0 {
                 // Long position being closed
@@ -131,15 +165,25 @@ impl PaperPosition {
             self.realized_pnl += pnl;
             self.size += signed_qty;
 
-            // If position flipped, reset entry price
-            if (self.size > 0.0 && !is_buy) || (self.size < 0.0 && is_buy) {
-                // This shouldn't happen with proper close_qty logic
-            } else if self.size == 0.0 {
+            if self.size == 0.0 {
                 self.entry_price = 0.0;
+            } else if qty > close_qty {
+                // Position flipped: the remainder is a new position opened at this fill
+                self.entry_price = price;
             }
         }
     }
 
+    /// Apply a funding payment at the given mark price
+    ///
+    /// Longs pay shorts when the funding rate is positive. The payment is
+    /// booked against realized PnL and returned (negative when received).
+    pub fn apply_funding(&mut self, mark_price: f64, funding_rate: f64) -> f64 {
+        let payment = self.size * mark_price * funding_rate;
+        self.realized_pnl -= payment;
+        payment
+    }
+
     /// Calculate unrealized PnL at current price
     pub fn unrealized_pnl(&self, current_price: f64) -> f64 {
         if self.size == 0.0 {
@@ -197,8 +241,10 @@ pub struct PaperTradingMarket<L: MarketListener> {
     listener: Arc<RwLock<L>>,
     /// Info client for price feeds
     pub info_client: InfoClient,
-    /// Current prices by asset
-    prices: HashMap<String, f64>,
+    /// Prices to trade on instead of the info client's websocket
+    price_feed: Option<Box<dyn PriceFeed>>,
+    /// Current prices by asset name, also reachable by exchange key
+    prices: PriceMap,
     /// Orders by user-provided order_id
     orders: HashMap<u64, PaperOrder>,
     /// Positions by asset
@@ -207,11 +253,28 @@ pub struct PaperTradingMarket<L: MarketListener> {
     pub balance: f64,
     /// Total fees paid
     pub total_fees: f64,
-    /// Fee rate (e.g., 0.0001 = 0.01%)
+    /// Total funding paid on perp positions (negative when received)
+    pub total_funding_paid: f64,
+    /// Fee rate charged on resting fills (e.g., 0.0001 = 0.01%)
     pub fee_rate: f64,
+    /// Fee rate charged when taking liquidity, as `flatten` does
+    pub taker_fee_rate: f64,
+    /// Notices when the feed stops carrying our asset's price
+    feed_watchdog: FeedWatchdog,
+    /// Queued feed messages beyond which stale price updates are skipped
+    feed_capacity: Option<usize>,
+    /// How far the event loop lagged the feed at the last message
+    feed_backlog: FeedBacklog,
+    /// Shut down; the event loop ends
+    stopped: bool,
+    /// Reject buys whose notional exceeds the free balance
+    strict_balance: bool,
 }
 
 impl<L: MarketListener> PaperTradingMarket<L> {
+    /// Rejection reason for buys the free balance can't cover in strict mode
+    pub const INSUFFICIENT_BALANCE: &'static str = "Insufficient balance to place order.";
+
     /// Create a new PaperTradingMarket
     ///
     /// Always connects to Mainnet for live price feeds.
@@ -227,12 +290,36 @@ impl<L: MarketListener> PaperTradingMarket<L> {
         let info_client = InfoClient::with_reconnect(None, Some(BaseUrl::Mainnet)).await?;
 
         // Resolve asset to exchange key (e.g., "HYPE/USDC" -> "@107")
-        let asset_key = Self::resolve_asset_key(&info_client, &input.asset).await?;
+        let resolver = AssetResolver::new(&info_client).await?;
+        let asset_key = resolver.resolve_key(&input.asset)?;
         info!("Resolved {} -> {}", input.asset, asset_key);
 
+        // A key missing from the feed would leave the strategy without prices
+        let mids = info_client.all_mids().await?;
+        if !mids.contains_key(&asset_key) {
+            error!("{} ({}) is not in the AllMids feed", input.asset, asset_key);
+            return Err(crate::Error::AssetNotFound);
+        }
+
         // Fetch precision from exchange (static data)
-        let asset_info =
-            Self::fetch_precision(&info_client, &input.asset, input.initial_balance).await?;
+        let precision = resolver.precision(&input.asset, MarketType::of(&input.asset))?;
+
+        let mut names = resolver.spot_names();
+        names.insert(asset_key.clone(), input.asset.clone());
+
+        let fees = match input.fee_user {
+            Some(user) => FeeRates::fetch_or_default(&info_client, user).await,
+            None => FeeRates::default(),
+        };
+        info!("Paper fees: maker {}, taker {}", fees.maker, fees.taker);
+        // Paper trading starts with 0 base balance
+        let asset_info = AssetInfo::new(
+            &input.asset,
+            0.0,
+            input.initial_balance,
+            precision.sz_decimals,
+            precision.price_decimals,
+        );
 
         Ok(Self {
             asset: input.asset,
@@ -240,138 +327,196 @@ impl<L: MarketListener> PaperTradingMarket<L> {
             asset_info,
             listener,
             info_client,
-            prices: HashMap::new(),
+            price_feed: None,
+            prices: PriceMap::new(names),
             orders: HashMap::new(),
             positions: HashMap::new(),
             balance: input.initial_balance,
             total_fees: 0.0,
-            fee_rate: 0.0001, // Default 0.01% fee
+            total_funding_paid: 0.0,
+            fee_rate: fees.maker,
+            taker_fee_rate: fees.taker,
+            feed_watchdog: FeedWatchdog::new(unix_now()),
+            feed_capacity: input.feed_capacity,
+            feed_backlog: FeedBacklog::default(),
+            stopped: false,
+            strict_balance: false,
         })
     }
 
-    /// Resolve user-friendly asset name to exchange key
-    async fn resolve_asset_key(
-        info_client: &InfoClient,
-        asset: &str,
-    ) -> Result<String, crate::Error> {
-        let is_spot = asset.contains('/');
-
-        if is_spot {
-            let spot_meta = info_client.spot_meta().await?;
-            let base_name = asset.split('/').next().unwrap_or(asset);
-
-            let index_to_name: std::collections::HashMap<usize, &str> = spot_meta
-                .tokens
-                .iter()
-                .map(|t| (t.index, t.name.as_str()))
-                .collect();
-
-            for spot_asset in &spot_meta.universe {
-                if let Some(token_name) = index_to_name.get(&spot_asset.tokens[0]) {
-                    if *token_name == base_name || asset == spot_asset.name {
-                        return Ok(format!("@{}", spot_asset.index));
-                    }
-                }
-            }
-            Err(crate::Error::AssetNotFound)
-        } else {
-            // Perp assets use the name directly
-            Ok(asset.to_string())
-        }
+    /// Build a market for `asset_info`'s asset without touching the network
+    ///
+    /// Prices only arrive through `update_price`; the asset is keyed by its
+    /// name and trades without fees.
+    #[cfg(any(test, feature = "test-util"))]
+    pub(crate) async fn offline(
+        asset_info: AssetInfo,
+        initial_balance: f64,
+        listener: Arc<RwLock<L>>,
+    ) -> Result<Self, crate::Error> {
+        let asset = asset_info.name.clone();
+        Ok(Self {
+            asset: asset.clone(),
+            asset_key: asset,
+            asset_info,
+            listener,
+            info_client: InfoClient::new(None, None).await?,
+            price_feed: None,
+            prices: PriceMap::default(),
+            orders: HashMap::new(),
+            positions: HashMap::new(),
+            balance: initial_balance,
+            total_fees: 0.0,
+            total_funding_paid: 0.0,
+            fee_rate: 0.0,
+            taker_fee_rate: 0.0,
+            feed_watchdog: FeedWatchdog::new(unix_now()),
+            feed_capacity: None,
+            feed_backlog: FeedBacklog::default(),
+            stopped: false,
+            strict_balance: false,
+        })
     }
 
-    /// Fetch precision from exchange (internal helper)
-    async fn fetch_precision(
-        info_client: &InfoClient,
-        asset: &str,
-        usdc_balance: f64,
-    ) -> Result<AssetInfo, crate::Error> {
-        let is_spot = asset.contains('/');
-
-        let (sz_decimals, price_decimals) = if is_spot {
-            let spot_meta = info_client.spot_meta().await?;
-            let base_name = asset.split('/').next().unwrap_or(asset);
-
-            let index_to_token: std::collections::HashMap<_, _> =
-                spot_meta.tokens.iter().map(|t| (t.index, t)).collect();
-
-            let mut found_sz = 4u32;
-            for spot_asset in &spot_meta.universe {
-                if let Some(token) = index_to_token.get(&spot_asset.tokens[0]) {
-                    if token.name == base_name || asset == spot_asset.name {
-                        found_sz = token.sz_decimals as u32;
-                        break;
-                    }
-                }
-            }
-
-            (found_sz, 6u32)
-        } else {
-            let meta = info_client.meta().await?;
-            let asset_meta = meta
-                .universe
-                .iter()
-                .find(|a| a.name == asset)
-                .ok_or_else(|| crate::Error::AssetNotFound)?;
-
-            (asset_meta.sz_decimals, 5u32)
-        };
+    /// Trade on prices from `feed` instead of the Hyperliquid websocket
+    ///
+    /// The event loop ends once the feed does, as a replay's does.
+    pub fn with_price_feed(mut self, feed: impl PriceFeed + 'static) -> Self {
+        self.price_feed = Some(Box::new(feed));
+        self
+    }
 
-        // Paper trading starts with 0 base balance
-        Ok(AssetInfo::new(
-            asset,
-            0.0,
-            usdc_balance,
-            sz_decimals,
-            price_decimals,
-        ))
+    /// Reject buys whose notional exceeds `free_balance`, as the exchange
+    /// rejects orders the account can't fund
+    ///
+    /// The listener is told through `on_order_rejected` with
+    /// `INSUFFICIENT_BALANCE`. Off by default, so buys can overdraw the
+    /// paper balance.
+    pub fn with_strict_balance(mut self, strict: bool) -> Self {
+        self.strict_balance = strict;
+        self
     }
 
     /// Start the market event loop
     ///
     /// Subscribes to AllMids for live price updates and processes
-    /// pending orders when prices change.
+    /// pending orders when prices change. Perp positions are charged
+    /// funding at the current rate once per funding interval.
     pub async fn start(&mut self) {
-        let (sender, mut receiver) = unbounded_channel();
-
-        // Subscribe to AllMids for price updates
-        if let Err(e) = self
-            .info_client
-            .subscribe(Subscription::AllMids, sender)
-            .await
-        {
+        let (sender, receiver) = unbounded_channel();
+        let mut feed = FeedQueue::new(receiver, self.feed_capacity);
+
+        // Subscribe to AllMids for price updates, and L2Book for our asset
+        let coins = [self.asset_key.clone()];
+        let subscribed = match self.price_feed.as_mut() {
+            Some(price_feed) => price_feed.subscribe_prices(&coins, sender).await,
+            None => self.info_client.subscribe_prices(&coins, sender).await,
+        };
+        if let Err(e) = subscribed {
             error!("Failed to subscribe to AllMids: {e}");
             return;
         }
 
         info!("PaperTradingMarket started with balance: {}", self.balance);
+        self.feed_watchdog = FeedWatchdog::new(unix_now());
+
+        let mut funding_timer = tokio::time::interval(FUNDING_INTERVAL);
+        // The first tick completes immediately; funding is charged at the end of an interval
+        funding_timer.tick().await;
 
-        loop {
-            match receiver.recv().await {
-                Some(message) => self.handle_message(message),
-                None => {
-                    error!("Channel closed");
-                    break;
+        let mut tick_timer = tokio::time::interval(TICK_INTERVAL);
+        let ctrl_c = tokio::signal::ctrl_c();
+        tokio::pin!(ctrl_c);
+
+        while !self.stopped {
+            tokio::select! {
+                _ = &mut ctrl_c => {
+                    info!("Interrupted, shutting down");
+                    self.shutdown();
+                }
+                message = feed.recv() => match message {
+                    Some(message) => {
+                        self.feed_backlog = feed.backlog();
+                        self.handle_message(message);
+                        self.run_listener_commands();
+                    }
+                    None => {
+                        error!("Channel closed");
+                        break;
+                    }
+                },
+                _ = tick_timer.tick() => {
+                    self.tick(unix_now());
+                    self.run_listener_commands();
+                }
+                _ = funding_timer.tick() => {
+                    if let Some(rate) = self.fetch_funding_rate().await {
+                        self.apply_funding(rate);
+                    }
                 }
             }
         }
     }
 
+    /// Fetch the current funding rate for the traded perp asset
+    ///
+    /// Returns `None` for spot assets or when the rate can't be fetched.
+    async fn fetch_funding_rate(&self) -> Option<f64> {
+        if self.asset.contains('/') {
+            return None;
+        }
+
+        let (meta, contexts) = match self.info_client.meta_and_asset_contexts().await {
+            Ok(result) => result,
+            Err(e) => {
+                warn!("Failed to fetch funding rate: {e}");
+                return None;
+            }
+        };
+
+        let index = meta
+            .universe
+            .iter()
+            .position(|a| a.name == self.asset_key)?;
+        contexts.get(index)?.funding.parse().ok()
+    }
+
     /// Handle incoming WebSocket messages
     fn handle_message(&mut self, message: Message) {
+        if let Message::HyperliquidError(e) = &message {
+            error!("Feed error: {}", e);
+            return;
+        }
+
+        if let Message::Reconnected = message {
+            if let Ok(mut listener) = self.listener.try_write() {
+                listener.on_reconnect();
+            }
+            return;
+        }
+
+        if let Message::L2Book(l2_book) = &message {
+            if l2_book.data.coin == self.asset_key {
+                let book = MarketBook::from_l2(&l2_book.data, BOOK_DEPTH);
+                if let Ok(mut listener) = self.listener.try_write() {
+                    listener.on_book_update(&self.asset, &book);
+                }
+            }
+            return;
+        }
+
         if let Message::AllMids(all_mids) = message {
             let mids = all_mids.data.mids;
             let mut pending_orders: Vec<OrderRequest> = Vec::new();
 
             for (asset, price_str) in mids {
                 if let Ok(price) = price_str.parse::<f64>() {
-                    let old_price = self.prices.get(&asset).copied();
-                    self.prices.insert(asset.clone(), price);
+                    // Stored once under the user-friendly name
+                    let old_price = self.prices.insert(&asset, price);
 
                     // Only notify listener for our configured asset (compare with exchange key)
                     if asset == self.asset_key {
-                        // Keep price accessible by user-friendly name too
-                        self.prices.insert(self.asset.clone(), price);
+                        self.feed_watchdog.seen(unix_now());
 
                         if old_price != Some(price) {
                             // M6: Synchronous notification, collect returned orders
@@ -381,14 +526,9 @@ impl<L: MarketListener> PaperTradingMarket<L> {
                                 pending_orders.extend(orders);
                             }
                         }
-
-                        // Check fills for user-friendly asset name
-                        let asset_name = self.asset.clone();
-                        let fill_orders = self.check_and_fill_orders(&asset_name, price);
-                        pending_orders.extend(fill_orders);
                     }
 
-                    // Check pending orders for raw asset key (just in case)
+                    // Orders may name the asset either way; both resolve to one entry
                     let fill_orders = self.check_and_fill_orders(&asset, price);
                     pending_orders.extend(fill_orders);
                 }
@@ -399,15 +539,59 @@ impl<L: MarketListener> PaperTradingMarket<L> {
         }
     }
 
+    /// Carry out commands queued by the listener
+    pub(super) fn run_listener_commands(&mut self) {
+        let commands = match self.listener.try_write() {
+            Ok(mut listener) => listener.take_commands(),
+            Err(_) => return,
+        };
+        for command in commands {
+            match command {
+                MarketCommand::Flatten => self.flatten(),
+                // Paper precision is fetched once and never changes
+                MarketCommand::RefreshPrecision => {
+                    let precision = self.asset_info.precision();
+                    let orders = match self.listener.try_write() {
+                        Ok(mut listener) => listener.on_precision_update(&self.asset, precision),
+                        Err(_) => vec![],
+                    };
+                    self.place_pending_orders(orders);
+                }
+                MarketCommand::Cancel(order_id) => {
+                    self.cancel_order(order_id);
+                }
+                // The simulated book is the exchange
+                MarketCommand::SyncOpenOrders => {
+                    let open: Vec<u64> =
+                        self.open_orders().into_iter().map(|(id, ..)| id).collect();
+                    let orders = match self.listener.try_write() {
+                        Ok(mut listener) => listener.on_open_orders(&open),
+                        Err(_) => vec![],
+                    };
+                    self.place_pending_orders(orders);
+                }
+                MarketCommand::Stop => {
+                    info!("Listener asked to stop, shutting down");
+                    self.shutdown();
+                    return;
+                }
+            }
+        }
+    }
+
     /// Check all pending orders for an asset and fill if conditions are met
     /// Returns any orders the listener wants to place in response to fills
     fn check_and_fill_orders(&mut self, asset: &str, mid_price: f64) -> Vec<OrderRequest> {
+        let name = self.prices.name(asset);
+
         // Collect orders to fill with their limit prices and sides
         // We capture (order_id, limit_price, side)
         let mut orders_to_fill: Vec<(u64, f64, OrderSide)> = self
             .orders
             .iter()
-            .filter(|(_, order)| order.request.asset == asset && order.should_fill(mid_price))
+            .filter(|(_, order)| {
+                self.prices.name(&order.request.asset) == name && order.should_fill(mid_price)
+            })
             .map(|(&id, order)| (id, order.request.limit_price, order.request.side))
             .collect();
 
@@ -483,7 +667,10 @@ impl<L: MarketListener> PaperTradingMarket<L> {
                     &asset,
                     order.request.qty,    // Total order qty
                     order.avg_fill_price, // Average fill price
-                );
+                )
+                .with_fee(fee)
+                .with_tag(order.request.tag.clone())
+                .with_time(unix_now_ms());
 
                 // info!(
                 //     "Paper order {} fully filled: {} {} at avg price {}",
@@ -508,9 +695,9 @@ impl<L: MarketListener> PaperTradingMarket<L> {
             let batch: Vec<OrderRequest> = std::mem::take(&mut pending);
             for order in batch {
                 let order_asset = order.asset.clone();
-                self.place_order_internal(order);
+                pending.extend(self.place_order_internal(order));
                 // Check if this order can fill immediately, collect new orders
-                if let Some(&current_price) = self.prices.get(&order_asset) {
+                if let Some(current_price) = self.prices.get(&order_asset) {
                     let fill_orders = self.check_and_fill_orders(&order_asset, current_price);
                     pending.extend(fill_orders);
                 }
@@ -519,9 +706,34 @@ impl<L: MarketListener> PaperTradingMarket<L> {
     }
 
     /// Internal place order (doesn't trigger immediate fill check cascade)
-    fn place_order_internal(&mut self, order: OrderRequest) {
+    ///
+    /// Returns the orders the listener wants placed if the order is rejected.
+    fn place_order_internal(&mut self, order: OrderRequest) -> Vec<OrderRequest> {
         let user_order_id = order.order_id;
         let paper_order = PaperOrder::new(order.clone());
+        if let Some(replaced) = order.replaces {
+            let filled = |o: &PaperOrder| matches!(o.status, OrderStatus::Filled(_));
+            // Replacing an order that already filled would trade its level twice
+            if !self.cancel_order(replaced) && self.orders.get(&replaced).is_some_and(filled) {
+                warn!(
+                    "Paper order {} rejected: order {} it replaces already filled",
+                    user_order_id, replaced
+                );
+                let reason = format!("order {} it replaces already filled", replaced);
+                return self.reject_order(paper_order, reason);
+            }
+        }
+
+        let notional = order.qty * order.limit_price;
+        if self.strict_balance && order.side.is_buy() && notional > self.free_balance() {
+            warn!(
+                "Paper order {} rejected: {:.2} notional exceeds the free balance {:.2}",
+                user_order_id,
+                notional,
+                self.free_balance()
+            );
+            return self.reject_order(paper_order, Self::INSUFFICIENT_BALANCE.to_string());
+        }
 
         // info!(
         //     "Paper order {}: {:?} {} {} @ {}",
@@ -529,6 +741,18 @@ impl<L: MarketListener> PaperTradingMarket<L> {
         // );
 
         self.orders.insert(user_order_id, paper_order);
+        vec![]
+    }
+
+    /// Record `paper_order` as rejected and tell the listener why
+    fn reject_order(&mut self, mut paper_order: PaperOrder, reason: String) -> Vec<OrderRequest> {
+        let user_order_id = paper_order.request.order_id;
+        paper_order.status = OrderStatus::Rejected(reason.clone());
+        self.orders.insert(user_order_id, paper_order);
+        match self.listener.try_write() {
+            Ok(mut listener) => listener.on_order_rejected(user_order_id, reason),
+            Err(_) => vec![],
+        }
     }
 
     /// Update the price for an asset (M7)
@@ -536,7 +760,7 @@ impl<L: MarketListener> PaperTradingMarket<L> {
     /// Manually updates internal price state and checks for fills.
     /// Note: Prices are also updated automatically via WebSocket subscription.
     pub fn update_price(&mut self, asset: &str, price: f64) {
-        self.prices.insert(asset.to_string(), price);
+        self.prices.insert(asset, price);
 
         // M6: Synchronous notification, collect returned orders
         let mut pending_orders = if let Ok(mut listener) = self.listener.try_write() {
@@ -553,19 +777,69 @@ impl<L: MarketListener> PaperTradingMarket<L> {
         self.place_pending_orders(pending_orders);
     }
 
+    /// Deliver a timer tick to the listener and place the orders it returns
+    ///
+    /// Called once per `TICK_INTERVAL` by the event loop. Orders whose
+    /// `expires_at_ms` has passed are cancelled first.
+    ///
+    /// # Arguments
+    /// * `now` - Current Unix time in seconds
+    pub fn tick(&mut self, now: u64) {
+        let mut orders = self.expire_orders(now * 1000);
+        let stalled = self.feed_watchdog.check(now);
+        if let Ok(mut listener) = self.listener.try_write() {
+            if let Some(silent) = stalled {
+                warn!(
+                    "No price for {} in {}s, is it still listed?",
+                    self.asset, silent
+                );
+                listener.on_feed_stalled(&self.asset, silent);
+            }
+            listener.on_feed_backlog(self.feed_backlog);
+            orders.extend(listener.on_tick(now));
+        }
+        self.place_pending_orders(orders);
+    }
+
+    /// Cancel resting orders past their expiry and report them to the listener
+    ///
+    /// Returns orders that the listener wants to place in their place.
+    fn expire_orders(&mut self, now_ms: u64) -> Vec<OrderRequest> {
+        let mut expired: Vec<u64> = self
+            .orders
+            .iter()
+            .filter(|(_, o)| o.status.is_active() && o.request.is_expired(now_ms))
+            .map(|(&id, _)| id)
+            .collect();
+        expired.sort_unstable();
+
+        let mut orders = Vec::new();
+        for order_id in expired {
+            self.cancel_order(order_id);
+            if let Some(order) = self.orders.get_mut(&order_id) {
+                order.status = OrderStatus::Expired;
+            }
+            info!("Paper order {} expired", order_id);
+            if let Ok(mut listener) = self.listener.try_write() {
+                orders.extend(listener.on_order_rejected(order_id, ORDER_EXPIRED.to_string()));
+            }
+        }
+        orders
+    }
+
     /// Place a new paper order (M8)
     ///
     /// # Arguments
     /// * `order` - The order request (contains user-provided order_id, side, reduce_only, tif)
     pub fn place_order(&mut self, order: OrderRequest) {
         let asset = order.asset.clone();
-        self.place_order_internal(order);
+        let mut pending_orders = self.place_order_internal(order);
 
         // Check if order can be filled immediately, handle any returned orders
-        if let Some(&current_price) = self.prices.get(&asset) {
-            let pending_orders = self.check_and_fill_orders(&asset, current_price);
-            self.place_pending_orders(pending_orders);
+        if let Some(current_price) = self.prices.get(&asset) {
+            pending_orders.extend(self.check_and_fill_orders(&asset, current_price));
         }
+        self.place_pending_orders(pending_orders);
     }
 
     /// Inject an external fill (M9)
@@ -585,7 +859,9 @@ impl<L: MarketListener> PaperTradingMarket<L> {
                     &order.request.asset,
                     order.request.qty,    // Total order qty
                     order.avg_fill_price, // Average fill price
-                );
+                )
+                .with_tag(order.request.tag.clone())
+                .with_time(fill.time_ms);
 
                 // M6: Synchronous notification, collect returned orders
                 let pending_orders = if let Ok(mut listener) = self.listener.try_write() {
@@ -600,12 +876,24 @@ impl<L: MarketListener> PaperTradingMarket<L> {
 
     /// Query current price for an asset (M10)
     pub fn current_price(&self, asset: &str) -> Option<f64> {
-        self.prices.get(asset).copied()
+        self.prices.get(asset)
     }
 
     /// Query order status (M11)
     pub fn order_status(&self, order_id: u64) -> Option<OrderStatus> {
-        self.orders.get(&order_id).map(|o| o.status)
+        self.orders.get(&order_id).map(|o| o.status.clone())
+    }
+
+    /// Orders still resting on the simulated book, by order id
+    pub fn open_orders(&self) -> Vec<(u64, OrderRequest, OrderStatus)> {
+        let mut open: Vec<_> = self
+            .orders
+            .iter()
+            .filter(|(_, o)| o.status.is_active())
+            .map(|(&id, o)| (id, o.request.clone(), o.status.clone()))
+            .collect();
+        open.sort_by_key(|(id, _, _)| *id);
+        open
     }
 
     /// Get the shared listener reference
@@ -628,6 +916,60 @@ impl<L: MarketListener> PaperTradingMarket<L> {
         false
     }
 
+    /// Cancel all pending orders and tell the listener the market has stopped
+    ///
+    /// Called by the event loop on Ctrl-C or `MarketCommand::Stop`, and ends
+    /// the loop. The position is left as it is.
+    pub fn shutdown(&mut self) {
+        self.stopped = true;
+        let pending = self.pending_order_ids();
+        info!("Paper shutdown: cancelling {} order(s)", pending.len());
+        for order_id in pending {
+            self.cancel_order(order_id);
+        }
+        if let Ok(mut listener) = self.listener.try_write() {
+            listener.on_stop();
+        }
+    }
+
+    /// Cancel all pending orders and close the traded asset's position at the current price
+    ///
+    /// The close is simulated as a taker fill at the last mid and pays the normal fee.
+    /// It doesn't notify the listener, since it doesn't fill any of its orders.
+    pub fn flatten(&mut self) {
+        for order_id in self.pending_order_ids() {
+            self.cancel_order(order_id);
+        }
+
+        let Some(price) = self.prices.get(&self.asset) else {
+            warn!(
+                "Paper flatten: no price for {}, position left open",
+                self.asset
+            );
+            return;
+        };
+        let Some(position) = self.positions.get_mut(&self.asset) else {
+            return;
+        };
+        if position.size == 0.0 {
+            return;
+        }
+
+        let qty = position.size.abs();
+        let is_buy = position.size < 0.0;
+        position.apply_fill(qty, price, is_buy);
+
+        let notional = qty * price;
+        let fee = notional * self.taker_fee_rate;
+        if is_buy {
+            self.balance -= notional + fee;
+        } else {
+            self.balance += notional - fee;
+        }
+        self.total_fees += fee;
+        info!("Paper flatten: closed {} {} at {}", qty, self.asset, price);
+    }
+
     /// Get position for an asset
     pub fn position(&self, asset: &str) -> Option<&PaperPosition> {
         self.positions.get(asset)
@@ -640,7 +982,7 @@ impl<L: MarketListener> PaperTradingMarket<L> {
 
     /// Get all current prices
     pub fn all_prices(&self) -> &HashMap<String, f64> {
-        &self.prices
+        self.prices.as_map()
     }
 
     /// Get count of pending orders
@@ -660,6 +1002,21 @@ impl<L: MarketListener> PaperTradingMarket<L> {
             .collect()
     }
 
+    /// Quote tied up in resting buys: the unfilled notional at their limit
+    /// prices
+    pub fn locked_capital(&self) -> f64 {
+        self.orders
+            .values()
+            .filter(|o| o.status.is_active() && o.request.side.is_buy())
+            .map(|o| (o.request.qty - o.filled_qty) * o.request.limit_price)
+            .sum()
+    }
+
+    /// Balance not tied up in resting buys
+    pub fn free_balance(&self) -> f64 {
+        self.balance - self.locked_capital()
+    }
+
     /// Calculate total account value (balance + unrealized PnL)
     pub fn account_value(&self) -> f64 {
         let unrealized_pnl: f64 = self
@@ -668,7 +1025,7 @@ impl<L: MarketListener> PaperTradingMarket<L> {
             .map(|(asset, pos)| {
                 self.prices
                     .get(asset)
-                    .map(|&price| pos.unrealized_pnl(price))
+                    .map(|price| pos.unrealized_pnl(price))
                     .unwrap_or(0.0)
             })
             .sum();
@@ -681,15 +1038,48 @@ impl<L: MarketListener> PaperTradingMarket<L> {
         self.positions.values().map(|p| p.realized_pnl).sum()
     }
 
-    /// Set fee rate (e.g., 0.0001 = 0.01%)
+    /// Charge one funding interval on the traded perp position
+    ///
+    /// The payment is `position_notional * funding_rate`, paid by longs and
+    /// received by shorts when the rate is positive. It is deducted from the
+    /// balance and the position's realized PnL. Spot assets are ignored.
+    pub fn apply_funding(&mut self, funding_rate: f64) {
+        if self.asset.contains('/') {
+            return;
+        }
+        let Some(mark_price) = self.prices.get(&self.asset) else {
+            return;
+        };
+        let Some(position) = self.positions.get_mut(&self.asset) else {
+            return;
+        };
+
+        let payment = position.apply_funding(mark_price, funding_rate);
+        self.balance -= payment;
+        self.total_funding_paid += payment;
+        info!(
+            "Paper funding: rate {} on {} {} -> paid {:.4}",
+            funding_rate, position.size, self.asset, payment
+        );
+    }
+
+    /// Set a single fee rate for maker and taker fills (e.g., 0.0001 = 0.01%)
     pub fn set_fee_rate(&mut self, rate: f64) {
         self.fee_rate = rate;
+        self.taker_fee_rate = rate;
+    }
+
+    /// Set separate maker and taker fee rates
+    pub fn set_fee_rates(&mut self, rates: FeeRates) {
+        self.fee_rate = rates.maker;
+        self.taker_fee_rate = rates.taker;
     }
 
     /// Reset paper trading state
     pub fn reset(&mut self, initial_balance: f64) {
         self.balance = initial_balance;
         self.total_fees = 0.0;
+        self.total_funding_paid = 0.0;
         self.orders.clear();
         self.positions.clear();
         info!("Paper trading reset with balance: {}", initial_balance);
@@ -831,12 +1221,48 @@ mod tests {
         assert_eq!(pos.realized_pnl, 0.0); // 1000 - 1000 = 0
     }
 
+    #[test]
+    fn test_paper_position_flip_long_to_short() {
+        let mut pos = PaperPosition::default();
+
+        // Long 1 unit at 100
+        pos.apply_fill(1.0, 100.0, true);
+
+        // Sell 3 units at 110: close 1 long, open 2 short at 110
+        pos.apply_fill(3.0, 110.0, false);
+        assert_eq!(pos.size, -2.0);
+        assert_eq!(pos.entry_price, 110.0);
+        assert_eq!(pos.realized_pnl, 10.0);
+
+        // Short PnL is measured from the new entry
+        assert_eq!(pos.unrealized_pnl(105.0), 10.0);
+    }
+
+    #[test]
+    fn test_paper_position_flip_short_to_long() {
+        let mut pos = PaperPosition::default();
+
+        // Short 2 units at 100
+        pos.apply_fill(2.0, 100.0, false);
+
+        // Buy 5 units at 90: close 2 short (+20), open 3 long at 90
+        pos.apply_fill(5.0, 90.0, true);
+        assert_eq!(pos.size, 3.0);
+        assert_eq!(pos.entry_price, 90.0);
+        assert_eq!(pos.realized_pnl, 20.0);
+
+        // Closing the new long realizes against 90, not the old short entry
+        pos.apply_fill(3.0, 95.0, false);
+        assert_eq!(pos.size, 0.0);
+        assert_eq!(pos.realized_pnl, 35.0);
+    }
+
     #[tokio::test]
     async fn test_paper_fill_priority_and_price() {
         use crate::market::listener::NoOpListener;
 
         let listener = Arc::new(RwLock::new(NoOpListener));
-        let input = PaperTradingMarketInput::new("HYPE/USDC", 10000.0);
+        let _input = PaperTradingMarketInput::new("HYPE/USDC", 10000.0);
 
         // We can't easily mock InfoClient in this integration-style test without more setup,
         // but we can test the internal logic by creating a market and manipulating it.
@@ -878,12 +1304,20 @@ mod tests {
             asset_info,
             listener,
             info_client: InfoClient::new(None, None).await.unwrap(),
-            prices: HashMap::new(),
+            price_feed: None,
+            prices: PriceMap::default(),
             orders: HashMap::new(),
             positions: HashMap::new(),
             balance: 10000.0,
             total_fees: 0.0,
+            total_funding_paid: 0.0,
             fee_rate: 0.0,
+            taker_fee_rate: 0.0,
+            feed_watchdog: FeedWatchdog::new(unix_now()),
+            feed_capacity: None,
+            feed_backlog: FeedBacklog::default(),
+            stopped: false,
+            strict_balance: false,
         };
 
         // 1. Setup Buy Orders
@@ -901,7 +1335,7 @@ mod tests {
         // Yes, `mod tests` is inside the file so it has access to privates.
 
         // We need to match the signature of `check_and_fill_orders`
-        let filled_orders = market.check_and_fill_orders(&asset, 99.0);
+        let _filled_orders = market.check_and_fill_orders(&asset, 99.0);
 
         // Verify orders are filled!
         // We can inspect `market.orders` directly.
@@ -977,4 +1411,590 @@ mod tests {
 
         // Since we verified prices are strictly limit prices, the requirement is met.
     }
+
+    /// Build a market around `listener` without touching the network
+    async fn offline_market<L: MarketListener>(
+        asset: &str,
+        listener: Arc<RwLock<L>>,
+    ) -> PaperTradingMarket<L> {
+        let asset_info = AssetInfo::new(asset, 0.0, 10000.0, 4, 6);
+        PaperTradingMarket::offline(asset_info, 10000.0, listener)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_paper_position_funding() {
+        let mut long = PaperPosition::default();
+        long.apply_fill(2.0, 100.0, true);
+
+        // Long pays positive funding: 2 * 110 * 0.001 = 0.22
+        let paid = long.apply_funding(110.0, 0.001);
+        assert!((paid - 0.22).abs() < 1e-9);
+        assert!((long.realized_pnl + 0.22).abs() < 1e-9);
+
+        // Short receives positive funding
+        let mut short = PaperPosition::default();
+        short.apply_fill(2.0, 100.0, false);
+        let paid = short.apply_funding(110.0, 0.001);
+        assert!((paid + 0.22).abs() < 1e-9);
+        assert!((short.realized_pnl - 0.22).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_apply_funding_to_long_perp() {
+        use crate::market::listener::NoOpListener;
+
+        let listener = Arc::new(RwLock::new(NoOpListener));
+        let mut market = offline_market("BTC", listener).await;
+
+        market.place_order(OrderRequest::buy(1, "BTC", 0.5, 50000.0));
+        market.update_price("BTC", 50000.0);
+        assert_eq!(market.position("BTC").unwrap().size, 0.5);
+        let balance_after_fill = market.balance;
+
+        // 0.5 BTC * 50000 * 0.0001 = 2.5 USDC paid by the long
+        market.apply_funding(0.0001);
+
+        let position = market.position("BTC").unwrap();
+        assert!((position.realized_pnl + 2.5).abs() < 1e-9);
+        assert!((market.total_funding_paid - 2.5).abs() < 1e-9);
+        assert!((market.balance - (balance_after_fill - 2.5)).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_fill_delivered_to_listener() {
+        use crate::market::listener::RecordingListener;
+
+        let listener = Arc::new(RwLock::new(RecordingListener::default()));
+        let mut market = offline_market("BTC", listener.clone()).await;
+
+        market.place_order(OrderRequest::buy(1, "BTC", 0.5, 50000.0));
+        market.update_price("BTC", 50500.0);
+        assert!(listener.read().await.fills.is_empty());
+        market.update_price("BTC", 49900.0);
+
+        let listener = listener.read().await;
+        assert_eq!(
+            listener.price_updates,
+            vec![("BTC".to_string(), 50500.0), ("BTC".to_string(), 49900.0)]
+        );
+        assert_eq!(listener.fills.len(), 1);
+        assert_eq!(listener.fills[0].order_id, 1);
+        assert_eq!(listener.fills[0].qty, 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_order_tag_carried_to_fill() {
+        use crate::market::listener::RecordingListener;
+
+        let listener = Arc::new(RwLock::new(RecordingListener::default()));
+        let mut market = offline_market("BTC", listener.clone()).await;
+
+        market.place_order(OrderRequest::buy(1, "BTC", 0.5, 50000.0).tag("level-3"));
+        market.place_order(OrderRequest::buy(2, "BTC", 0.5, 49000.0));
+        market.update_price("BTC", 48900.0);
+
+        let fills = &listener.read().await.fills;
+        assert_eq!(fills.len(), 2);
+        let tags: Vec<_> = fills
+            .iter()
+            .map(|f| (f.order_id, f.tag.as_deref()))
+            .collect();
+        assert!(tags.contains(&(1, Some("level-3"))));
+        assert!(tags.contains(&(2, None)));
+    }
+
+    #[tokio::test]
+    async fn test_price_reachable_by_name_and_key() {
+        use crate::market::listener::RecordingListener;
+
+        let listener = Arc::new(RwLock::new(RecordingListener::default()));
+        let mut market = offline_market("HYPE/USDC", listener.clone()).await;
+        market.asset_key = "@107".to_string();
+        market.prices = PriceMap::new(HashMap::from([(
+            "@107".to_string(),
+            "HYPE/USDC".to_string(),
+        )]));
+        market.place_order(OrderRequest::buy(1, "HYPE/USDC", 1.0, 25.0));
+
+        let mids: Message = serde_json::from_value(serde_json::json!({
+            "channel": "allMids",
+            "data": {"mids": {"@107": "24.5", "BTC": "50000"}}
+        }))
+        .unwrap();
+        market.handle_message(mids);
+
+        assert_eq!(market.current_price("HYPE/USDC"), Some(24.5));
+        assert_eq!(market.current_price("@107"), Some(24.5));
+        assert_eq!(market.all_prices().len(), 2);
+
+        let listener = listener.read().await;
+        assert_eq!(
+            listener.price_updates,
+            vec![("HYPE/USDC".to_string(), 24.5)]
+        );
+        assert_eq!(listener.fills.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_missing_asset_in_feed_reports_stall() {
+        use crate::market::listener::{RecordingListener, STALE_FEED_AFTER};
+
+        let listener = Arc::new(RwLock::new(RecordingListener::default()));
+        let mut market = offline_market("BTC", listener.clone()).await;
+        let mids_message = |coin: &str| -> Message {
+            serde_json::from_value(serde_json::json!({
+                "channel": "allMids",
+                "data": { "mids": { coin: "100.0" } }
+            }))
+            .unwrap()
+        };
+        let stale = unix_now() + STALE_FEED_AFTER.as_secs();
+
+        // The feed carries other coins only
+        market.handle_message(mids_message("ETH"));
+        market.tick(stale - 5);
+        assert!(listener.read().await.stalls.is_empty());
+        market.tick(stale + 1);
+        market.tick(stale + 2);
+        {
+            let listener = listener.read().await;
+            assert!(listener.price_updates.is_empty());
+            assert_eq!(listener.stalls.len(), 1);
+            assert_eq!(listener.stalls[0].0, "BTC");
+        }
+
+        // Prices coming back re-arm the watchdog
+        market.handle_message(mids_message("BTC"));
+        market.tick(unix_now() + 1);
+        assert_eq!(listener.read().await.price_updates.len(), 1);
+        assert_eq!(listener.read().await.stalls.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_apply_funding_ignores_spot() {
+        use crate::market::listener::NoOpListener;
+
+        let listener = Arc::new(RwLock::new(NoOpListener));
+        let mut market = offline_market("HYPE/USDC", listener).await;
+
+        market.place_order(OrderRequest::buy(1, "HYPE/USDC", 10.0, 20.0));
+        market.update_price("HYPE/USDC", 20.0);
+        market.apply_funding(0.001);
+
+        assert_eq!(market.total_funding_paid, 0.0);
+        assert_eq!(market.position("HYPE/USDC").unwrap().realized_pnl, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_l2_book_forwarded_to_listener() {
+        #[derive(Default)]
+        struct BookListener {
+            books: Vec<(String, MarketBook)>,
+        }
+
+        impl MarketListener for BookListener {
+            fn on_order_filled(&mut self, _fill: OrderFill) -> Vec<OrderRequest> {
+                vec![]
+            }
+
+            fn on_price_update(&mut self, _asset: &str, _price: f64) -> Vec<OrderRequest> {
+                vec![]
+            }
+
+            fn on_book_update(&mut self, asset: &str, book: &MarketBook) {
+                self.books.push((asset.to_string(), book.clone()));
+            }
+        }
+
+        let listener = Arc::new(RwLock::new(BookListener::default()));
+        let mut market = offline_market("BTC", listener.clone()).await;
+
+        let book_message = |coin: &str| -> Message {
+            serde_json::from_value(serde_json::json!({
+                "channel": "l2Book",
+                "data": {
+                    "coin": coin,
+                    "time": 1,
+                    "levels": [[{"px": "99", "sz": "1", "n": 1}], [{"px": "101", "sz": "2", "n": 1}]]
+                }
+            }))
+            .unwrap()
+        };
+        market.handle_message(book_message("ETH"));
+        market.handle_message(book_message("BTC"));
+
+        let books = &listener.read().await.books;
+        assert_eq!(books.len(), 1);
+        assert_eq!(books[0].0, "BTC");
+        assert_eq!(books[0].1.best_bid(), Some(99.0));
+        assert_eq!(books[0].1.best_ask(), Some(101.0));
+    }
+
+    #[tokio::test]
+    async fn test_stop_command_shuts_down() {
+        #[derive(Default)]
+        struct StopListener {
+            commands: Vec<MarketCommand>,
+            stops: u32,
+        }
+
+        impl MarketListener for StopListener {
+            fn on_order_filled(&mut self, _fill: OrderFill) -> Vec<OrderRequest> {
+                vec![]
+            }
+
+            fn on_price_update(&mut self, _asset: &str, _price: f64) -> Vec<OrderRequest> {
+                vec![]
+            }
+
+            fn on_stop(&mut self) {
+                self.stops += 1;
+            }
+
+            fn take_commands(&mut self) -> Vec<MarketCommand> {
+                std::mem::take(&mut self.commands)
+            }
+        }
+
+        let listener = Arc::new(RwLock::new(StopListener::default()));
+        let mut market = offline_market("BTC", listener.clone()).await;
+        market.update_price("BTC", 100.0);
+        market.place_order(OrderRequest::buy(1, "BTC", 1.0, 90.0));
+        market.place_order(OrderRequest::buy(2, "BTC", 1.0, 80.0));
+
+        listener.write().await.commands = vec![MarketCommand::Stop, MarketCommand::Cancel(2)];
+        market.run_listener_commands();
+
+        assert!(market.stopped);
+        assert_eq!(market.pending_order_count(), 0);
+        assert_eq!(market.order_status(2), Some(OrderStatus::Cancelled));
+        assert_eq!(listener.read().await.stops, 1);
+    }
+
+    #[tokio::test]
+    async fn test_flatten_command_cancels_and_closes() {
+        #[derive(Default)]
+        struct FlattenListener {
+            commands: Vec<MarketCommand>,
+        }
+
+        impl MarketListener for FlattenListener {
+            fn on_order_filled(&mut self, _fill: OrderFill) -> Vec<OrderRequest> {
+                vec![]
+            }
+
+            fn on_price_update(&mut self, _asset: &str, _price: f64) -> Vec<OrderRequest> {
+                vec![]
+            }
+
+            fn take_commands(&mut self) -> Vec<MarketCommand> {
+                std::mem::take(&mut self.commands)
+            }
+        }
+
+        let listener = Arc::new(RwLock::new(FlattenListener::default()));
+        let mut market = offline_market("BTC", listener.clone()).await;
+        market.update_price("BTC", 100.0);
+        market.place_order(OrderRequest::buy(1, "BTC", 2.0, 100.0));
+        market.place_order(OrderRequest::buy(2, "BTC", 1.0, 90.0));
+        market.update_price("BTC", 110.0);
+        assert_eq!(market.position("BTC").unwrap().size, 2.0);
+        assert_eq!(market.pending_order_count(), 1);
+
+        // Nothing queued: no-op
+        market.run_listener_commands();
+        assert_eq!(market.pending_order_count(), 1);
+
+        listener.write().await.commands.push(MarketCommand::Flatten);
+        market.run_listener_commands();
+
+        assert_eq!(market.pending_order_count(), 0);
+        let position = market.position("BTC").unwrap();
+        assert_eq!(position.size, 0.0);
+        assert!((position.realized_pnl - 20.0).abs() < 1e-9);
+        assert!((market.balance - 10_020.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_open_orders_lists_resting_orders() {
+        use crate::market::listener::NoOpListener;
+
+        let listener = Arc::new(RwLock::new(NoOpListener));
+        let mut market = offline_market("BTC", listener).await;
+        market.update_price("BTC", 100.0);
+        market.place_order(OrderRequest::sell(2, "BTC", 1.0, 120.0));
+        market.place_order(OrderRequest::buy(1, "BTC", 1.0, 90.0));
+        market.place_order(OrderRequest::buy(3, "BTC", 1.0, 95.0));
+        market.cancel_order(3);
+
+        let open = market.open_orders();
+        assert_eq!(open.len(), 2);
+        let (id, request, status) = &open[0];
+        assert_eq!((*id, request.limit_price), (1, 90.0));
+        assert_eq!(*status, OrderStatus::Pending);
+        let (id, request, _) = &open[1];
+        assert_eq!((*id, request.side), (2, OrderSide::Sell));
+    }
+
+    #[tokio::test]
+    async fn test_resting_buys_lock_capital() {
+        #[derive(Default)]
+        struct RejectListener {
+            rejected: Vec<(u64, String)>,
+        }
+
+        impl MarketListener for RejectListener {
+            fn on_order_filled(&mut self, _fill: OrderFill) -> Vec<OrderRequest> {
+                vec![]
+            }
+
+            fn on_price_update(&mut self, _asset: &str, _price: f64) -> Vec<OrderRequest> {
+                vec![]
+            }
+
+            fn on_order_rejected(&mut self, order_id: u64, reason: String) -> Vec<OrderRequest> {
+                self.rejected.push((order_id, reason));
+                vec![]
+            }
+        }
+
+        let listener = Arc::new(RwLock::new(RejectListener::default()));
+        let mut market = offline_market("BTC", listener.clone())
+            .await
+            .with_strict_balance(true);
+        market.update_price("BTC", 100.0);
+        market.place_order(OrderRequest::buy(1, "BTC", 10.0, 90.0));
+        market.place_order(OrderRequest::buy(2, "BTC", 20.0, 95.0));
+        // Sells lock no quote
+        market.place_order(OrderRequest::sell(3, "BTC", 1.0, 120.0));
+
+        assert!((market.locked_capital() - 2800.0).abs() < 1e-9);
+        assert!((market.free_balance() - 7200.0).abs() < 1e-9);
+
+        // 8000 of notional against 7200 free: rejected, nothing more locked
+        market.place_order(OrderRequest::buy(4, "BTC", 100.0, 80.0));
+        assert!(matches!(
+            market.order_status(4),
+            Some(OrderStatus::Rejected(_))
+        ));
+        assert_eq!(
+            listener.read().await.rejected,
+            vec![(
+                4,
+                PaperTradingMarket::<RejectListener>::INSUFFICIENT_BALANCE.to_string()
+            )]
+        );
+        assert!((market.locked_capital() - 2800.0).abs() < 1e-9);
+
+        // Cancelling a buy frees its capital
+        market.cancel_order(2);
+        assert!((market.free_balance() - 9100.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_replace_keeps_level_occupied() {
+        /// Trails a single bid 10 below the price, replacing it on every update
+        #[derive(Default)]
+        struct TrailingBid {
+            resting: Option<u64>,
+        }
+
+        impl MarketListener for TrailingBid {
+            fn on_order_filled(&mut self, _fill: OrderFill) -> Vec<OrderRequest> {
+                vec![]
+            }
+
+            fn on_price_update(&mut self, asset: &str, price: f64) -> Vec<OrderRequest> {
+                let order_id = self.resting.map_or(1, |id| id + 1);
+                let mut order = OrderRequest::buy(order_id, asset, 1.0, price - 10.0);
+                if let Some(resting) = self.resting.replace(order_id) {
+                    order = order.replacing(resting);
+                }
+                vec![order]
+            }
+        }
+
+        let listener = Arc::new(RwLock::new(TrailingBid::default()));
+        let mut market = offline_market("BTC", listener).await;
+        for (i, price) in [100.0, 105.0, 103.0, 110.0].into_iter().enumerate() {
+            market.update_price("BTC", price);
+            assert_eq!(market.pending_order_ids(), vec![i as u64 + 1]);
+        }
+        assert!(market.cancel_order(4));
+        assert!(!market.cancel_order(3));
+    }
+
+    #[tokio::test]
+    async fn test_replacement_of_filled_order_is_rejected() {
+        use crate::market::listener::RecordingListener;
+
+        let listener = Arc::new(RwLock::new(RecordingListener::default()));
+        let mut market = offline_market("BTC", listener.clone()).await;
+        market.update_price("BTC", 100.0);
+        market.place_order(OrderRequest::buy(1, "BTC", 1.0, 95.0));
+        market.update_price("BTC", 94.0);
+        assert_eq!(listener.read().await.fills.len(), 1);
+
+        market.place_order(OrderRequest::buy(2, "BTC", 1.0, 90.0).replacing(1));
+        assert!(market.pending_order_ids().is_empty());
+        assert_eq!(listener.read().await.rejections[0].0, 2);
+    }
+
+    #[tokio::test]
+    async fn test_fee_schedule_overrides_default_rate() {
+        use crate::market::listener::NoOpListener;
+
+        let response: crate::UserFeesResponse = serde_json::from_value(serde_json::json!({
+            "activeReferralDiscount": "0.0",
+            "dailyUserVlm": [],
+            "feeSchedule": {
+                "add": "0.0001",
+                "cross": "0.00035",
+                "referralDiscount": "0.04",
+                "tiers": { "mm": [], "vip": [] }
+            },
+            "userAddRate": "0.0002",
+            "userCrossRate": "0.0005"
+        }))
+        .unwrap();
+        let rates = FeeRates::try_from(&response).unwrap();
+        assert_ne!(rates, FeeRates::default());
+        assert_eq!(
+            rates,
+            FeeRates {
+                maker: 0.0002,
+                taker: 0.0005
+            }
+        );
+
+        let listener = Arc::new(RwLock::new(NoOpListener));
+        let mut market = offline_market("BTC", listener).await;
+        market.set_fee_rates(rates);
+        market.place_order(OrderRequest::buy(1, "BTC", 2.0, 100.0));
+        market.update_price("BTC", 99.0);
+
+        // Resting fill at 100 pays maker: 200 * 0.0002 = 0.04
+        assert!((market.total_fees - 0.04).abs() < 1e-9);
+
+        // Flatten takes liquidity at 110: 220 * 0.0005 = 0.11
+        market.update_price("BTC", 110.0);
+        market.flatten();
+        assert!((market.total_fees - 0.15).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_tick_places_listener_orders() {
+        use crate::bot::Bot;
+        use crate::market::AssetPrecision;
+        use crate::strategy::dca::DcaStrategy;
+
+        let strategy = DcaStrategy::new(
+            "BTC".to_string(),
+            200.0,
+            60,
+            None,
+            AssetPrecision::for_perp(4),
+            100.0,
+        )
+        .with_slippage(0.0);
+        let listener = Arc::new(RwLock::new(Bot::new(strategy)));
+        let mut market = offline_market("BTC", listener.clone()).await;
+        market.update_price("BTC", 100.0);
+
+        market.tick(0);
+        market.tick(30);
+        market.tick(60);
+
+        assert_eq!(market.position("BTC").unwrap().size, 4.0);
+        let status = listener.read().await.status();
+        assert_eq!(status.position, 4.0);
+        assert_eq!(status.custom["avg_cost"], 100.0);
+    }
+
+    #[tokio::test]
+    async fn test_grid_roundtrips_against_replayed_prices() {
+        use crate::bot::Bot;
+        use crate::market::{AssetPrecision, ReplayPriceFeed};
+        use crate::strategy::spot_grid::{GridMode, SpotGridStrategy};
+
+        let strategy = SpotGridStrategy::new(
+            "BTC".to_string(),
+            100.0,
+            120.0,
+            3,
+            GridMode::Arithmetic,
+            Some(1.0),
+            None,
+            AssetPrecision::for_perp(2),
+            110.0,
+        );
+        let listener = Arc::new(RwLock::new(Bot::new(strategy)));
+        // Down through the lower line and back, twice, then up through the top
+        let prices = [110.0, 99.0, 111.0, 99.5, 110.5, 121.0];
+        let mut market = offline_market("BTC", listener.clone())
+            .await
+            .with_price_feed(ReplayPriceFeed::new("BTC", prices));
+
+        // Returns once the replay has been processed
+        market.start().await;
+
+        let status = listener.read().await.status();
+        // Two roundtrips on the lower zone and the start inventory sold at the top
+        assert_eq!(status.trade_count, 3);
+        assert!((status.realized_pnl - 30.0).abs() < 1e-9);
+        assert_eq!(market.pending_order_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_expired_order_cancelled_on_tick() {
+        #[derive(Default)]
+        struct ExpiryListener {
+            rejected: Vec<(u64, String)>,
+        }
+
+        impl MarketListener for ExpiryListener {
+            fn on_order_filled(&mut self, _fill: OrderFill) -> Vec<OrderRequest> {
+                vec![]
+            }
+
+            fn on_price_update(&mut self, _asset: &str, _price: f64) -> Vec<OrderRequest> {
+                vec![]
+            }
+
+            fn on_order_rejected(&mut self, order_id: u64, reason: String) -> Vec<OrderRequest> {
+                self.rejected.push((order_id, reason));
+                vec![]
+            }
+        }
+
+        let listener = Arc::new(RwLock::new(ExpiryListener::default()));
+        let mut market = offline_market("BTC", listener.clone()).await;
+        market.update_price("BTC", 50000.0);
+
+        // Resting buy with an expiry already in the past
+        market.place_order(OrderRequest::buy(1, "BTC", 1.0, 49000.0).expires_at(5_000));
+        // Filled before its expiry
+        market.place_order(OrderRequest::buy(2, "BTC", 1.0, 51000.0).expires_at(5_000));
+        // Not expired yet
+        market.place_order(OrderRequest::buy(3, "BTC", 1.0, 49000.0).expires_at(20_000));
+        // Cancelled by us before its expiry
+        market.place_order(OrderRequest::buy(4, "BTC", 1.0, 49000.0).expires_at(5_000));
+        assert!(market.cancel_order(4));
+
+        market.tick(10);
+
+        assert_eq!(market.order_status(1), Some(OrderStatus::Expired));
+        assert_eq!(market.order_status(4), Some(OrderStatus::Cancelled));
+        assert!(matches!(
+            market.order_status(2),
+            Some(OrderStatus::Filled(_))
+        ));
+        assert_eq!(market.order_status(3), Some(OrderStatus::Pending));
+        assert_eq!(
+            listener.read().await.rejected,
+            vec![(1, ORDER_EXPIRED.to_string())]
+        );
+    }
 }
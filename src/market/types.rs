@@ -1,7 +1,15 @@
 //! Core data types for the Market interface
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
+use super::decimal::round_f64;
+use crate::MarketType;
+
+/// Smallest order value Hyperliquid accepts, in quote currency
+pub const MIN_ORDER_NOTIONAL: f64 = 10.0;
+
 /// Order side (buy or sell)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OrderSide {
@@ -24,6 +32,39 @@ impl OrderSide {
     }
 }
 
+/// How long a limit order may rest on the book
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TimeInForce {
+    /// Good til cancelled
+    #[default]
+    Gtc,
+    /// Add liquidity only (post-only): rejected if it would match on arrival
+    Alo,
+    /// Immediate or cancel: whatever doesn't match right away is cancelled
+    Ioc,
+}
+
+impl TimeInForce {
+    /// Name used on the wire ("Gtc", "Alo", "Ioc")
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimeInForce::Gtc => "Gtc",
+            TimeInForce::Alo => "Alo",
+            TimeInForce::Ioc => "Ioc",
+        }
+    }
+
+    /// Parse a wire name, ignoring case
+    pub fn parse(tif: &str) -> Option<Self> {
+        match tif.to_ascii_lowercase().as_str() {
+            "gtc" => Some(TimeInForce::Gtc),
+            "alo" => Some(TimeInForce::Alo),
+            "ioc" => Some(TimeInForce::Ioc),
+            _ => None,
+        }
+    }
+}
+
 /// Order request input to the Market
 ///
 /// Represents a new limit order to be placed in the market (spot or perp).
@@ -43,6 +84,18 @@ pub struct OrderRequest {
     pub limit_price: f64,
     /// Reduce only flag (for perps - only reduce existing position)
     pub reduce_only: bool,
+    /// Time in force (Gtc unless set)
+    #[serde(default)]
+    pub tif: TimeInForce,
+    /// Unix time in milliseconds after which the market cancels the order if still resting
+    #[serde(default)]
+    pub expires_at_ms: Option<u64>,
+    /// User `order_id` of a resting order this one takes the place of
+    #[serde(default)]
+    pub replaces: Option<u64>,
+    /// Caller's label for the order (e.g., level or sub-strategy), echoed on its fill
+    #[serde(default)]
+    pub tag: Option<String>,
 }
 
 impl OrderRequest {
@@ -73,6 +126,10 @@ impl OrderRequest {
             qty,
             limit_price,
             reduce_only: false,
+            tif: TimeInForce::Gtc,
+            expires_at_ms: None,
+            replaces: None,
+            tag: None,
         }
     }
 
@@ -92,6 +149,47 @@ impl OrderRequest {
         self
     }
 
+    /// Set the time in force (builder pattern)
+    pub fn tif(mut self, tif: TimeInForce) -> Self {
+        self.tif = tif;
+        self
+    }
+
+    /// Cancel the order if it's still resting at `expires_at_ms` (builder pattern)
+    ///
+    /// Expiry is checked on the market's tick, so cancellation can lag by up
+    /// to one `TICK_INTERVAL`. The listener hears about it through
+    /// `on_order_rejected`.
+    pub fn expires_at(mut self, expires_at_ms: u64) -> Self {
+        self.expires_at_ms = Some(expires_at_ms);
+        self
+    }
+
+    /// Take the place of the resting order `order_id` (builder pattern)
+    ///
+    /// Markets swap the two in one step, by modifying the resting order where
+    /// the exchange allows it and by cancelling it just before placing this
+    /// one otherwise, so the quote is never pulled while nothing replaces it.
+    /// If the old order is already gone, this one is simply placed.
+    pub fn replacing(mut self, order_id: u64) -> Self {
+        self.replaces = Some(order_id);
+        self
+    }
+
+    /// Attach a label that comes back as `OrderFill::tag` (builder pattern)
+    ///
+    /// Markets don't interpret or send it; it only saves the caller from
+    /// encoding its own meaning into `order_id`.
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    /// Whether the order's expiry has passed at `now_ms`
+    pub fn is_expired(&self, now_ms: u64) -> bool {
+        self.expires_at_ms.is_some_and(|expires| expires <= now_ms)
+    }
+
     /// Check if this is a buy order
     pub fn is_buy(&self) -> bool {
         self.side.is_buy()
@@ -117,6 +215,15 @@ pub struct OrderFill {
     pub qty: f64,
     /// Execution price
     pub price: f64,
+    /// Fee paid in quote currency (negative for maker rebates)
+    #[serde(default)]
+    pub fee: f64,
+    /// Tag of the filled order, if it had one
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// When the fill happened, in Unix milliseconds; 0 if unknown
+    #[serde(default)]
+    pub time_ms: u64,
 }
 
 impl OrderFill {
@@ -127,9 +234,30 @@ impl OrderFill {
             asset: asset.into(),
             qty,
             price,
+            fee: 0.0,
+            tag: None,
+            time_ms: 0,
         }
     }
 
+    /// Set the fee paid for this fill (builder pattern)
+    pub fn with_fee(mut self, fee: f64) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    /// Set the tag of the filled order (builder pattern)
+    pub fn with_tag(mut self, tag: Option<String>) -> Self {
+        self.tag = tag;
+        self
+    }
+
+    /// Set when the fill happened, in Unix milliseconds (builder pattern)
+    pub fn with_time(mut self, time_ms: u64) -> Self {
+        self.time_ms = time_ms;
+        self
+    }
+
     /// Calculate the total value of this fill
     pub fn value(&self) -> f64 {
         self.qty * self.price
@@ -147,7 +275,7 @@ pub struct AssetInfo {
     pub name: String,
     /// Base asset balance (e.g., BTC balance for BTC/USDC)
     pub balance: f64,
-    /// Quote currency balance (USDC)
+    /// Quote currency balance (the pair's quote token, USDC for perps)
     pub usdc_balance: f64,
     /// Size decimals (number of decimal places for quantity)
     pub sz_decimals: u32,
@@ -173,6 +301,14 @@ impl AssetInfo {
         }
     }
 
+    /// Precision rules for this asset's market type and size decimals
+    pub fn precision(&self) -> AssetPrecision {
+        match MarketType::of(&self.name) {
+            MarketType::Spot => AssetPrecision::for_spot(self.sz_decimals),
+            MarketType::Perp => AssetPrecision::for_perp(self.sz_decimals),
+        }
+    }
+
     /// Get the size step (minimum size increment)
     pub fn sz_step(&self) -> f64 {
         10f64.powi(-(self.sz_decimals as i32))
@@ -183,10 +319,9 @@ impl AssetInfo {
         10f64.powi(-(self.price_decimals as i32))
     }
 
-    /// Round size to valid precision
+    /// Round size down to valid precision
     pub fn round_size(&self, size: f64) -> f64 {
-        let factor = 10f64.powi(self.sz_decimals as i32);
-        (size * factor).floor() / factor
+        round_f64(size, |size| size.round_down(self.sz_decimals))
     }
 
     /// Round price to valid precision
@@ -194,13 +329,14 @@ impl AssetInfo {
     /// # Arguments
     /// * `price` - The price to round
     /// * `round_up` - If true, round up (for sell orders), else round down (for buy orders)
+    ///
+    /// Unlike [`AssetPrecision::round_price`], which rounds to the nearest tick
+    /// unless `round_up` is set, this truncates to `price_decimals` only.
     pub fn round_price(&self, price: f64, round_up: bool) -> f64 {
-        let factor = 10f64.powi(self.price_decimals as i32);
-        if round_up {
-            (price * factor).ceil() / factor
-        } else {
-            (price * factor).floor() / factor
-        }
+        round_f64(price, |price| match round_up {
+            true => price.round_up(self.price_decimals),
+            false => price.round_down(self.price_decimals),
+        })
     }
 
     /// Check if we have sufficient balance for a buy order
@@ -230,7 +366,7 @@ impl Default for AssetInfo {
 /// Order status variants
 ///
 /// Represents the current state of an order in the market.
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum OrderStatus {
     /// Order is pending execution
     Pending,
@@ -238,8 +374,12 @@ pub enum OrderStatus {
     PartiallyFilled(f64),
     /// Order is fully filled at the given average price
     Filled(f64),
-    /// Order has been cancelled
+    /// Order has been cancelled by us
     Cancelled,
+    /// The venue refused the order (or it never reached it), with the reason
+    Rejected(String),
+    /// Order was cancelled because its `expires_at_ms` passed
+    Expired,
 }
 
 impl OrderStatus {
@@ -248,9 +388,9 @@ impl OrderStatus {
         matches!(self, OrderStatus::Pending | OrderStatus::PartiallyFilled(_))
     }
 
-    /// Check if the order is complete (filled or cancelled)
+    /// Check if the order is complete (filled, cancelled, rejected or expired)
     pub fn is_complete(&self) -> bool {
-        matches!(self, OrderStatus::Filled(_) | OrderStatus::Cancelled)
+        !self.is_active()
     }
 
     /// Get the filled quantity if partially or fully filled
@@ -263,6 +403,143 @@ impl OrderStatus {
     }
 }
 
+/// Out-of-band instruction from a listener to the market
+///
+/// Unlike orders, commands aren't tied to a market event; markets poll them
+/// from the listener after handling each event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MarketCommand {
+    /// Cancel every open order and close the position at market
+    Flatten,
+    /// Re-fetch asset metadata and report precision that changed through
+    /// `MarketListener::on_precision_update`
+    RefreshPrecision,
+    /// Cancel one resting order, leaving nothing in its place
+    Cancel(u64),
+    /// Fetch the exchange's open orders and report them through
+    /// `MarketListener::on_open_orders`
+    SyncOpenOrders,
+    /// Shut the market down as on Ctrl-C and end its event loop; later
+    /// commands are dropped
+    Stop,
+}
+
+/// A single price level in an order book
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PriceLevel {
+    pub price: f64,
+    pub size: f64,
+}
+
+/// Snapshot of the venue's L2 order book for one asset
+///
+/// Bids are sorted best (highest) first, asks best (lowest) first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MarketBook {
+    /// Exchange timestamp in milliseconds
+    pub time: u64,
+    pub bids: Vec<PriceLevel>,
+    pub asks: Vec<PriceLevel>,
+}
+
+impl MarketBook {
+    /// Build a book from Hyperliquid's `[bids, asks]` level arrays,
+    /// keeping at most `depth` levels per side
+    pub fn from_l2(data: &crate::L2BookData, depth: usize) -> Self {
+        let side = |idx: usize| -> Vec<PriceLevel> {
+            data.levels
+                .get(idx)
+                .map(|levels| {
+                    levels
+                        .iter()
+                        .take(depth)
+                        .filter_map(|l| {
+                            Some(PriceLevel {
+                                price: l.px.parse().ok()?,
+                                size: l.sz.parse().ok()?,
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        Self {
+            time: data.time,
+            bids: side(0),
+            asks: side(1),
+        }
+    }
+
+    /// Best bid price
+    pub fn best_bid(&self) -> Option<f64> {
+        self.bids.first().map(|l| l.price)
+    }
+
+    /// Best ask price
+    pub fn best_ask(&self) -> Option<f64> {
+        self.asks.first().map(|l| l.price)
+    }
+}
+
+/// Where a market's tracked open orders disagree with the exchange's book
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenOrderDrift {
+    /// Tracked as open but not on the exchange, by user order id
+    pub missing: Vec<u64>,
+    /// On the exchange for a traded asset but not tracked, by exchange oid
+    pub untracked: Vec<u64>,
+}
+
+impl OpenOrderDrift {
+    /// Whether the tracked orders match the exchange
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.untracked.is_empty()
+    }
+}
+
+/// Mid prices keyed by user-friendly asset name
+///
+/// The price feed reports spot pairs by exchange key ("@107"), while
+/// strategies and orders use names ("HYPE/USDC"). Keys with a known name are
+/// stored under that name, so either spelling reads the same entry.
+#[derive(Debug, Clone, Default)]
+pub struct PriceMap {
+    prices: HashMap<String, f64>,
+    names: HashMap<String, String>,
+}
+
+impl PriceMap {
+    /// Empty map resolving exchange keys through `names` (key -> name)
+    pub fn new(names: HashMap<String, String>) -> Self {
+        Self {
+            prices: HashMap::new(),
+            names,
+        }
+    }
+
+    /// Canonical name for an asset: its friendly name if `asset` is a known key
+    pub fn name<'a>(&'a self, asset: &'a str) -> &'a str {
+        self.names.get(asset).map(String::as_str).unwrap_or(asset)
+    }
+
+    /// Set the price of an asset, returning the previous one
+    pub fn insert(&mut self, asset: &str, price: f64) -> Option<f64> {
+        let name = self.name(asset).to_string();
+        self.prices.insert(name, price)
+    }
+
+    /// Price of an asset by name or exchange key
+    pub fn get(&self, asset: &str) -> Option<f64> {
+        self.prices.get(self.name(asset)).copied()
+    }
+
+    /// All prices by canonical name
+    pub fn as_map(&self) -> &HashMap<String, f64> {
+        &self.prices
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -301,8 +578,7 @@ mod tests {
 
     #[test]
     fn test_order_request_builder() {
-        let order = OrderRequest::buy(1, "BTC", 1.0, 50000.0)
-            .reduce_only(true);
+        let order = OrderRequest::buy(1, "BTC", 1.0, 50000.0).reduce_only(true);
 
         assert!(order.reduce_only);
     }
@@ -339,6 +615,16 @@ mod tests {
         assert!(!OrderStatus::Pending.is_complete());
         assert!(OrderStatus::Filled(50000.0).is_complete());
         assert!(OrderStatus::Cancelled.is_complete());
+
+        // Rejected and expired orders have ended too, but aren't user cancels
+        let rejected = OrderStatus::Rejected("Order has invalid price.".to_string());
+        for status in [rejected.clone(), OrderStatus::Expired] {
+            assert!(!status.is_active());
+            assert!(status.is_complete());
+            assert_eq!(status.filled_qty(), None);
+            assert_ne!(status, OrderStatus::Cancelled);
+        }
+        assert_ne!(rejected, OrderStatus::Expired);
     }
 
     #[test]
@@ -394,6 +680,104 @@ mod tests {
         assert!(!info.can_sell(2.0));
     }
 
+    #[test]
+    fn test_asset_precision_significant_figures() {
+        let precision = AssetPrecision {
+            sz_decimals: 0,
+            price_decimals: 8,
+            max_decimals: 8,
+        };
+
+        assert_eq!(precision.round_price(123.456789, false), 123.46);
+        assert_eq!(precision.round_price(0.00123456, false), 0.0012346);
+        assert_eq!(precision.round_price(123.451, true), 123.46);
+        assert_eq!(precision.round_price(123.45, true), 123.45);
+
+        // Integer prices above 5 digits are valid as-is
+        assert_eq!(precision.round_price(123456.7, false), 123457.0);
+        assert_eq!(precision.round_price(123456.0, false), 123456.0);
+    }
+
+    #[test]
+    fn test_asset_precision_decimal_limit() {
+        // Perp with szDecimals=4 allows only 2 price decimals
+        let precision = AssetPrecision::for_perp(4);
+        assert_eq!(precision.round_price(1.23456, false), 1.23);
+        assert_eq!(precision.round_price(1.23456, true), 1.24);
+        assert_eq!(precision.round_price(12.346, false), 12.35);
+
+        // Tick size follows the same rules
+        assert_eq!(precision.tick_size(12.346), 0.01);
+        assert_eq!(precision.tick_size(12345.6), 1.0);
+    }
+
+    #[test]
+    fn test_round_price_for_side() {
+        let precision = AssetPrecision::for_perp(4);
+        // Nearest would be 12.35 for both; each side rounds away from the spread
+        assert_eq!(
+            precision.round_price_for_side(12.346, OrderSide::Buy),
+            12.34
+        );
+        assert_eq!(
+            precision.round_price_for_side(12.341, OrderSide::Sell),
+            12.35
+        );
+        // Prices already on a tick stay put
+        assert_eq!(precision.round_price_for_side(12.34, OrderSide::Buy), 12.34);
+        assert_eq!(
+            precision.round_price_for_side(12.34, OrderSide::Sell),
+            12.34
+        );
+    }
+
+    #[test]
+    fn test_float_drift_does_not_shift_ticks_or_lots() {
+        let precision = AssetPrecision {
+            sz_decimals: 2,
+            price_decimals: 2,
+            max_decimals: 6,
+        };
+        // 0.29 and 1.15 scale to just under a whole lot
+        assert_eq!(precision.round_size(0.29), 0.29);
+        assert_eq!(precision.round_size(1.15), 1.15);
+        assert_eq!(AssetInfo::new("BTC", 0.0, 0.0, 2, 2).round_size(0.29), 0.29);
+        // 2.675 is a true half tick even though its float is just below it
+        assert_eq!(precision.round_price(2.675, false), 2.68);
+        // A geometric grid level a hair above its tick stays on it
+        assert_eq!(
+            precision.round_price_for_side(100.0 * 1.1, OrderSide::Sell),
+            110.0
+        );
+
+        for price in [0.29, 1.15, 2.675, 4.35, 8.2, 100.0 * 1.1, 0.7 - 0.1] {
+            let once = precision.round_price(price, false);
+            assert_eq!(precision.round_price(once, false), once);
+            let once = precision.round_size(price);
+            assert_eq!(precision.round_size(once), once);
+        }
+    }
+
+    #[test]
+    fn test_market_book_from_l2() {
+        let data: crate::L2BookData = serde_json::from_value(serde_json::json!({
+            "coin": "BTC",
+            "time": 1700000000000u64,
+            "levels": [
+                [{"px": "100.5", "sz": "2", "n": 1}, {"px": "100.0", "sz": "3", "n": 2}],
+                [{"px": "101.0", "sz": "1.5", "n": 1}, {"px": "101.5", "sz": "4", "n": 3}]
+            ]
+        }))
+        .unwrap();
+
+        let book = MarketBook::from_l2(&data, 1);
+        assert_eq!(book.time, 1700000000000);
+        assert_eq!(book.bids.len(), 1);
+        assert_eq!(book.best_bid(), Some(100.5));
+        assert_eq!(book.best_ask(), Some(101.0));
+        assert_eq!(book.asks[0].size, 1.5);
+    }
+
     #[test]
     fn test_asset_info_default() {
         let info = AssetInfo::default();
@@ -405,15 +789,13 @@ mod tests {
     }
 }
 
-use crate::helpers::truncate_float;
-
 /// Asset precision information fetched from exchange meta
 ///
 /// According to Hyperliquid docs:
 /// - Prices can have up to 5 significant figures
 /// - Price decimals = MAX_DECIMALS - szDecimals (6 for perps, 8 for spot)
 /// - Size decimals = szDecimals from meta
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct AssetPrecision {
     /// Decimal places for size (szDecimals from meta)
     pub sz_decimals: u32,
@@ -444,18 +826,72 @@ impl AssetPrecision {
         }
     }
 
-    /// Round a price to the correct precision using truncate_float
+    /// Round a price to the correct precision
     ///
     /// Enforces Hyperliquid's tick size rules:
-    /// - Max 5 significant figures
+    /// - Max 5 significant figures (integer prices are always allowed)
     /// - Max price_decimals decimal places (MAX_DECIMALS - szDecimals)
+    ///
+    /// Rounds to the nearest valid tick, or up to the next one when `round_up` is set.
+    /// Rounding is done in exact decimal, tolerating float noise so prices
+    /// already on a tick stay put.
+    ///
+    /// The nearest tick may lie on either side of `price`; when a price must
+    /// not cross a bound use [`round_price_down`](Self::round_price_down) or
+    /// [`round_price_for_side`](Self::round_price_for_side).
     pub fn round_price(&self, price: f64, round_up: bool) -> f64 {
-        truncate_float(price, self.price_decimals, round_up)
+        if price <= 0.0 || !price.is_finite() {
+            return price;
+        }
+
+        let decimals = self.price_tick_decimals(price);
+        round_f64(price, |price| match round_up {
+            true => price.round_up(decimals),
+            false => price.round(decimals),
+        })
+    }
+
+    /// Round a price down to the valid tick at or below it
+    pub fn round_price_down(&self, price: f64) -> f64 {
+        if price <= 0.0 || !price.is_finite() {
+            return price;
+        }
+
+        let decimals = self.price_tick_decimals(price);
+        round_f64(price, |price| price.round_down(decimals))
+    }
+
+    /// Round a price to a valid tick on the passive side for `side`: down for
+    /// buys and up for sells, so the order never rests at a worse price
+    pub fn round_price_for_side(&self, price: f64, side: OrderSide) -> f64 {
+        match side {
+            OrderSide::Buy => self.round_price_down(price),
+            OrderSide::Sell => self.round_price(price, true),
+        }
     }
 
-    /// Round a size to the correct precision
+    /// Smallest valid price increment at a given price level
+    pub fn tick_size(&self, price: f64) -> f64 {
+        10f64.powi(-(self.price_tick_decimals(price) as i32))
+    }
+
+    /// Decimal places allowed for a price: the tighter of the 5 significant
+    /// figure rule and `price_decimals`
+    fn price_tick_decimals(&self, price: f64) -> u32 {
+        const MAX_SIG_FIGS: i32 = 5;
+
+        if price <= 0.0 || !price.is_finite() {
+            return self.price_decimals;
+        }
+
+        let int_digits = price.log10().floor() as i32 + 1;
+        let sig_fig_decimals = (MAX_SIG_FIGS - int_digits).max(0) as u32;
+        sig_fig_decimals.min(self.price_decimals)
+    }
+
+    /// Round a size down to the correct precision
     pub fn round_size(&self, size: f64) -> f64 {
-        truncate_float(size, self.sz_decimals, false)
+        round_f64(size, |size| size.round_down(self.sz_decimals))
     }
 }
 
@@ -465,3 +901,77 @@ impl Default for AssetPrecision {
     }
 }
 
+/// Which price a live market feeds the listener's `on_price_update`
+///
+/// Mid follows the book and moves with every quote; mark and oracle are
+/// harder to push around, which matters for perp stops near liquidation.
+/// Orders are still priced off the mid either way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceSource {
+    /// Book mid from `AllMids`
+    #[default]
+    Mid,
+    /// Exchange mark price from the asset context
+    Mark,
+    /// Oracle price from the asset context; spot has none and uses mark
+    Oracle,
+}
+
+/// Maker and taker fee rates, as fractions of notional (0.0001 = 1 bp)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FeeRates {
+    /// Charged on orders that rest on the book before filling
+    pub maker: f64,
+    /// Charged on orders that take liquidity
+    pub taker: f64,
+}
+
+impl Default for FeeRates {
+    /// A flat 0.01%, used when the account's own rates aren't known
+    fn default() -> Self {
+        Self {
+            maker: 0.0001,
+            taker: 0.0001,
+        }
+    }
+}
+
+impl FeeRates {
+    /// The rates `user` currently pays, or the defaults if they can't be fetched
+    pub async fn fetch_or_default(
+        info_client: &crate::InfoClient,
+        user: alloy::primitives::Address,
+    ) -> Self {
+        match info_client.user_fees(user).await {
+            Ok(response) => Self::try_from(&response).unwrap_or_else(|e| {
+                log::warn!("Unreadable fee rates for {}: {}, using defaults", user, e);
+                Self::default()
+            }),
+            Err(e) => {
+                log::warn!(
+                    "Failed to fetch fee rates for {}: {}, using defaults",
+                    user,
+                    e
+                );
+                Self::default()
+            }
+        }
+    }
+}
+
+impl TryFrom<&crate::UserFeesResponse> for FeeRates {
+    type Error = crate::Error;
+
+    /// The account's current rates, after volume tier and discounts
+    fn try_from(fees: &crate::UserFeesResponse) -> Result<Self, Self::Error> {
+        let parse = |rate: &str| {
+            rate.parse::<f64>()
+                .map_err(|_| crate::Error::GenericParse(format!("Invalid fee rate '{rate}'")))
+        };
+        Ok(Self {
+            maker: parse(&fees.user_add_rate)?,
+            taker: parse(&fees.user_cross_rate)?,
+        })
+    }
+}
@@ -7,13 +7,19 @@ use crate::{
     ClientOrderRequest, ExchangeClient, ExchangeDataStatus, ExchangeResponseStatus, InfoClient,
     Message, Subscription, UserData, EPSILON,
 };
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MarketMakerRestingOrder {
     pub oid: u64,
     pub position: f64,
     pub price: f64,
 }
 
+#[derive(Debug, Clone)]
+pub struct MarketMakerLevel {
+    pub offset_bps: f64, // Distance from mid beyond the half spread (in BPS)
+    pub size: f64,       // Size quoted at this level
+}
+
 #[derive(Debug)]
 pub struct MarketMakerInput {
     pub asset: String,
@@ -22,7 +28,11 @@ pub struct MarketMakerInput {
     pub max_bps_diff: u16, // Max deviation before we cancel and put new orders on the book (in BPS)
     pub max_absolute_position_size: f64, // Absolute value of the max position we can take on
     pub decimals: u32,     // Decimals to round to for pricing
-    pub wallet: PrivateKeySigner, // Wallet containing private key
+    pub target_inventory: f64, // Position the quotes skew back toward
+    pub max_inventory: f64, // Max distance from target_inventory before a side stops quoting
+    pub skew_per_unit: f64, // BPS each quote shifts per unit of position away from target
+    pub levels: Vec<MarketMakerLevel>, // Ladder levels per side (one level of target_liquidity if empty)
+    pub wallet: PrivateKeySigner,      // Wallet containing private key
 }
 
 #[derive(Debug)]
@@ -33,10 +43,16 @@ pub struct MarketMaker {
     pub max_bps_diff: u16,
     pub max_absolute_position_size: f64,
     pub decimals: u32,
-    pub lower_resting: MarketMakerRestingOrder,
-    pub upper_resting: MarketMakerRestingOrder,
+    pub target_inventory: f64,
+    pub max_inventory: f64,
+    pub skew_per_unit: f64,
+    pub levels: Vec<MarketMakerLevel>,
+    pub lower_resting: Vec<MarketMakerRestingOrder>,
+    pub upper_resting: Vec<MarketMakerRestingOrder>,
     pub cur_position: f64,
     pub latest_mid_price: f64,
+    pub quoted_mid_price: f64,
+    pub quoted_position: f64,
     pub info_client: InfoClient,
     pub exchange_client: ExchangeClient,
     pub user_address: Address,
@@ -45,6 +61,14 @@ pub struct MarketMaker {
 impl MarketMaker {
     pub async fn new(input: MarketMakerInput) -> MarketMaker {
         let user_address = input.wallet.address();
+        let levels = if input.levels.is_empty() {
+            vec![MarketMakerLevel {
+                offset_bps: 0.0,
+                size: input.target_liquidity,
+            }]
+        } else {
+            input.levels
+        };
 
         let info_client = InfoClient::new(None, Some(BaseUrl::Testnet)).await.unwrap();
         let exchange_client =
@@ -59,18 +83,16 @@ impl MarketMaker {
             max_bps_diff: input.max_bps_diff,
             max_absolute_position_size: input.max_absolute_position_size,
             decimals: input.decimals,
-            lower_resting: MarketMakerRestingOrder {
-                oid: 0,
-                position: 0.0,
-                price: -1.0,
-            },
-            upper_resting: MarketMakerRestingOrder {
-                oid: 0,
-                position: 0.0,
-                price: -1.0,
-            },
+            target_inventory: input.target_inventory,
+            max_inventory: input.max_inventory,
+            skew_per_unit: input.skew_per_unit,
+            levels,
+            lower_resting: Vec::new(),
+            upper_resting: Vec::new(),
             cur_position: 0.0,
             latest_mid_price: -1.0,
+            quoted_mid_price: -1.0,
+            quoted_position: 0.0,
             info_client,
             exchange_client,
             user_address,
@@ -127,11 +149,11 @@ impl MarketMaker {
                             // Update our resting positions whenever we see a fill
                             if fill.side.eq("B") {
                                 self.cur_position += amount;
-                                self.lower_resting.position -= amount;
+                                reduce_resting(&mut self.lower_resting, fill.oid, amount);
                                 info!("Fill: bought {amount} {}", self.asset.clone());
                             } else {
                                 self.cur_position -= amount;
-                                self.upper_resting.position -= amount;
+                                reduce_resting(&mut self.upper_resting, fill.oid, amount);
                                 info!("Fill: sold {amount} {}", self.asset.clone());
                             }
                         }
@@ -146,29 +168,28 @@ impl MarketMaker {
         }
     }
 
-    async fn attempt_cancel(&self, asset: String, oid: u64) -> bool {
-        let cancel = self
-            .exchange_client
-            .cancel(ClientCancelRequest { asset, oid }, None)
-            .await;
+    async fn attempt_bulk_cancel(&self, oids: Vec<u64>) -> bool {
+        let cancels = oids
+            .into_iter()
+            .map(|oid| ClientCancelRequest {
+                asset: self.asset.clone(),
+                oid,
+            })
+            .collect();
+        let cancel = self.exchange_client.bulk_cancel(cancels, None).await;
 
         match cancel {
             Ok(cancel) => match cancel {
                 ExchangeResponseStatus::Ok(cancel) => {
                     if let Some(cancel) = cancel.data {
-                        if !cancel.statuses.is_empty() {
-                            match cancel.statuses[0].clone() {
-                                ExchangeDataStatus::Success => {
-                                    return true;
-                                }
-                                ExchangeDataStatus::Error(e) => {
-                                    error!("Error with cancelling: {e}")
-                                }
-                                _ => unreachable!(),
+                        // A failed cancel means that order was filled; its fill event
+                        // moves our position and triggers another requote
+                        for status in cancel.statuses {
+                            if let ExchangeDataStatus::Error(e) = status {
+                                error!("Error with cancelling: {e}")
                             }
-                        } else {
-                            error!("Exchange data statuses is empty when cancelling: {cancel:?}")
                         }
+                        return true;
                     } else {
                         error!("Exchange response data is empty when cancelling: {cancel:?}")
                     }
@@ -180,151 +201,307 @@ impl MarketMaker {
         false
     }
 
-    async fn place_order(
-        &self,
-        asset: String,
-        amount: f64,
-        price: f64,
-        is_buy: bool,
-    ) -> (f64, u64) {
-        let order = self
-            .exchange_client
-            .order(
-                ClientOrderRequest {
-                    asset,
-                    is_buy,
-                    reduce_only: false,
-                    limit_px: price,
-                    sz: amount,
-                    cloid: None,
-                    order_type: ClientOrder::Limit(ClientLimit {
-                        tif: "Gtc".to_string(),
-                    }),
-                },
-                None,
-            )
-            .await;
-        match order {
-            Ok(order) => match order {
-                ExchangeResponseStatus::Ok(order) => {
-                    if let Some(order) = order.data {
-                        if !order.statuses.is_empty() {
-                            match order.statuses[0].clone() {
+    async fn place_orders(&self, orders: &[(MarketMakerRestingOrder, bool)]) -> Vec<(f64, u64)> {
+        let requests = orders
+            .iter()
+            .map(|(order, is_buy)| ClientOrderRequest {
+                asset: self.asset.clone(),
+                is_buy: *is_buy,
+                reduce_only: false,
+                limit_px: order.price,
+                sz: order.position,
+                cloid: None,
+                order_type: ClientOrder::Limit(ClientLimit {
+                    tif: "Gtc".to_string(),
+                }),
+            })
+            .collect();
+        let mut placed = vec![(0.0, 0); orders.len()];
+        let response = self.exchange_client.bulk_order(requests, None).await;
+        match response {
+            Ok(response) => match response {
+                ExchangeResponseStatus::Ok(response) => {
+                    if let Some(data) = response.data {
+                        for (i, status) in data.statuses.into_iter().enumerate().take(orders.len())
+                        {
+                            match status {
                                 ExchangeDataStatus::Filled(order) => {
-                                    return (amount, order.oid);
+                                    placed[i] = (orders[i].0.position, order.oid);
                                 }
                                 ExchangeDataStatus::Resting(order) => {
-                                    return (amount, order.oid);
+                                    placed[i] = (orders[i].0.position, order.oid);
                                 }
                                 ExchangeDataStatus::Error(e) => {
                                     error!("Error with placing order: {e}")
                                 }
                                 _ => unreachable!(),
                             }
-                        } else {
-                            error!("Exchange data statuses is empty when placing order: {order:?}")
                         }
                     } else {
-                        error!("Exchange response data is empty when placing order: {order:?}")
+                        error!("Exchange response data is empty when placing orders: {response:?}")
                     }
                 }
                 ExchangeResponseStatus::Err(e) => {
-                    error!("Error with placing order: {e}")
+                    error!("Error with placing orders: {e}")
                 }
             },
-            Err(e) => error!("Error with placing order: {e}"),
+            Err(e) => error!("Error with placing orders: {e}"),
         }
-        (0.0, 0)
+        placed
     }
 
     async fn potentially_update(&mut self) {
-        let half_spread = (self.latest_mid_price * self.half_spread as f64) / 10000.0;
-        // Determine prices to target from the half spread
-        let (lower_price, upper_price) = (
-            self.latest_mid_price - half_spread,
-            self.latest_mid_price + half_spread,
-        );
-        let (mut lower_price, mut upper_price) = (
-            truncate_float(lower_price, self.decimals, true),
-            truncate_float(upper_price, self.decimals, false),
-        );
-
-        // Rounding optimistically to make our market tighter might cause a weird edge case, so account for that
-        if (lower_price - upper_price).abs() < EPSILON {
-            lower_price = truncate_float(lower_price, self.decimals, false);
-            upper_price = truncate_float(upper_price, self.decimals, true);
+        // Requote the whole ladder when mid leaves the band or a fill moved our position
+        let position_changed = (self.cur_position - self.quoted_position).abs() > EPSILON;
+        if !position_changed
+            && !should_requote(
+                self.quoted_mid_price,
+                self.latest_mid_price,
+                self.max_bps_diff,
+            )
+        {
+            return;
         }
 
+        let inventory = self.cur_position - self.target_inventory;
+        let (lower_spread, upper_spread) =
+            skewed_half_spreads(self.half_spread as f64, inventory, self.skew_per_unit);
+
         // Determine amounts we can put on the book without exceeding the max absolute position size
-        let lower_order_amount = (self.max_absolute_position_size - self.cur_position)
-            .min(self.target_liquidity)
-            .max(0.0);
+        let mut lower_order_amount = (self.max_absolute_position_size - self.cur_position).max(0.0);
+        let mut upper_order_amount = (self.max_absolute_position_size + self.cur_position).max(0.0);
 
-        let upper_order_amount = (self.max_absolute_position_size + self.cur_position)
-            .min(self.target_liquidity)
-            .max(0.0);
+        // Stop quoting the side that would push inventory further past its limit
+        if inventory >= self.max_inventory {
+            lower_order_amount = 0.0;
+        }
+        if inventory <= -self.max_inventory {
+            upper_order_amount = 0.0;
+        }
 
-        // Determine if we need to cancel the resting order and put a new order up due to deviation
-        let lower_change = (lower_order_amount - self.lower_resting.position).abs() > EPSILON
-            || bps_diff(lower_price, self.lower_resting.price) > self.max_bps_diff;
-        let upper_change = (upper_order_amount - self.upper_resting.position).abs() > EPSILON
-            || bps_diff(upper_price, self.upper_resting.price) > self.max_bps_diff;
+        let bids = build_ladder(
+            self.latest_mid_price,
+            lower_spread,
+            &self.levels,
+            lower_order_amount,
+            self.decimals,
+            true,
+        );
+        let asks = build_ladder(
+            self.latest_mid_price,
+            upper_spread,
+            &self.levels,
+            upper_order_amount,
+            self.decimals,
+            false,
+        );
 
-        // Consider cancelling
+        // Cancel every resting level in one request
         // TODO: Don't block on cancels
-        if self.lower_resting.oid != 0 && self.lower_resting.position > EPSILON && lower_change {
-            let cancel = self
-                .attempt_cancel(self.asset.clone(), self.lower_resting.oid)
-                .await;
-            // If we were unable to cancel, it means we got a fill, so wait until we receive that event to do anything
-            if !cancel {
+        let oids = resting_oids(&self.lower_resting)
+            .chain(resting_oids(&self.upper_resting))
+            .collect::<Vec<_>>();
+        if !oids.is_empty() {
+            let count = oids.len();
+            if !self.attempt_bulk_cancel(oids).await {
                 return;
             }
-            info!("Cancelled buy order: {:?}", self.lower_resting);
+            info!("Cancelled {count} resting orders");
         }
+        self.lower_resting.clear();
+        self.upper_resting.clear();
 
-        if self.upper_resting.oid != 0 && self.upper_resting.position > EPSILON && upper_change {
-            let cancel = self
-                .attempt_cancel(self.asset.clone(), self.upper_resting.oid)
-                .await;
-            if !cancel {
-                return;
+        // Place the new ladder in one request
+        let orders = bids
+            .into_iter()
+            .map(|order| (order, true))
+            .chain(asks.into_iter().map(|order| (order, false)))
+            .collect::<Vec<_>>();
+        let placed = self.place_orders(&orders).await;
+        for ((mut order, is_buy), (amount_resting, oid)) in orders.into_iter().zip(placed) {
+            if amount_resting <= EPSILON {
+                continue;
+            }
+            order.oid = oid;
+            if is_buy {
+                info!(
+                    "Buy for {amount_resting} {} resting at {}",
+                    self.asset.clone(),
+                    order.price
+                );
+                self.lower_resting.push(order);
+            } else {
+                info!(
+                    "Sell for {amount_resting} {} resting at {}",
+                    self.asset.clone(),
+                    order.price
+                );
+                self.upper_resting.push(order);
             }
-            info!("Cancelled sell order: {:?}", self.upper_resting);
         }
 
-        // Consider putting a new order up
-        if lower_order_amount > EPSILON && lower_change {
-            let (amount_resting, oid) = self
-                .place_order(self.asset.clone(), lower_order_amount, lower_price, true)
-                .await;
+        self.quoted_mid_price = self.latest_mid_price;
+        self.quoted_position = self.cur_position;
+    }
+}
 
-            self.lower_resting.oid = oid;
-            self.lower_resting.position = amount_resting;
-            self.lower_resting.price = lower_price;
+/// Whether mid has moved far enough from the quoted mid to replace the ladder
+fn should_requote(quoted_mid: f64, mid: f64, max_bps_diff: u16) -> bool {
+    quoted_mid <= 0.0 || bps_diff(quoted_mid, mid) > max_bps_diff
+}
 
-            if amount_resting > EPSILON {
-                info!(
-                    "Buy for {amount_resting} {} resting at {lower_price}",
-                    self.asset.clone()
-                );
-            }
+/// Orders for one side of the ladder, not yet placed (oid 0)
+///
+/// Each level sits `spread + offset_bps` away from mid, rounded away from mid so
+/// bids and asks never cross. Sizes stop once `max_amount` is used up.
+fn build_ladder(
+    mid: f64,
+    spread: f64,
+    levels: &[MarketMakerLevel],
+    max_amount: f64,
+    decimals: u32,
+    is_buy: bool,
+) -> Vec<MarketMakerRestingOrder> {
+    let mut remaining = max_amount;
+    let mut orders = Vec::with_capacity(levels.len());
+    for level in levels {
+        let size = level.size.min(remaining);
+        if size <= EPSILON {
+            break;
         }
+        remaining -= size;
 
-        if upper_order_amount > EPSILON && upper_change {
-            let (amount_resting, oid) = self
-                .place_order(self.asset.clone(), upper_order_amount, upper_price, false)
-                .await;
-            self.upper_resting.oid = oid;
-            self.upper_resting.position = amount_resting;
-            self.upper_resting.price = upper_price;
+        let distance = (spread + level.offset_bps) / 10000.0;
+        let price = if is_buy {
+            truncate_float(mid * (1.0 - distance), decimals, false)
+        } else {
+            truncate_float(mid * (1.0 + distance), decimals, true)
+        };
+        orders.push(MarketMakerRestingOrder {
+            oid: 0,
+            position: size,
+            price,
+        });
+    }
+    orders
+}
 
-            if amount_resting > EPSILON {
-                info!(
-                    "Sell for {amount_resting} {} resting at {upper_price}",
-                    self.asset.clone()
-                );
-            }
+/// Oids of levels that still have size resting
+fn resting_oids(orders: &[MarketMakerRestingOrder]) -> impl Iterator<Item = u64> + '_ {
+    orders
+        .iter()
+        .filter(|order| order.oid != 0 && order.position > EPSILON)
+        .map(|order| order.oid)
+}
+
+/// Take a fill off the resting level it came from
+fn reduce_resting(orders: &mut [MarketMakerRestingOrder], oid: u64, amount: f64) {
+    if let Some(order) = orders.iter_mut().find(|order| order.oid == oid) {
+        order.position -= amount;
+    }
+}
+
+/// Bid and ask distances from mid (in BPS) skewed by inventory
+///
+/// Being long of target (`inventory > 0`) widens the bid and tightens the ask by
+/// `inventory * skew_per_unit`, so the next fill is more likely to reduce the
+/// position; being short does the opposite. Neither side is quoted through mid.
+fn skewed_half_spreads(half_spread: f64, inventory: f64, skew_per_unit: f64) -> (f64, f64) {
+    let skew = inventory * skew_per_unit;
+    ((half_spread + skew).max(0.0), (half_spread - skew).max(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flat_inventory_quotes_symmetric() {
+        assert_eq!(skewed_half_spreads(10.0, 0.0, 4.0), (10.0, 10.0));
+    }
+
+    #[test]
+    fn test_long_inventory_widens_bid_tightens_ask() {
+        let (bid, ask) = skewed_half_spreads(10.0, 0.5, 4.0);
+        assert_eq!(bid, 12.0);
+        assert_eq!(ask, 8.0);
+
+        // Short inventory mirrors it
+        let (bid, ask) = skewed_half_spreads(10.0, -0.5, 4.0);
+        assert_eq!(bid, 8.0);
+        assert_eq!(ask, 12.0);
+
+        // A large skew never quotes through mid
+        let (bid, ask) = skewed_half_spreads(10.0, 5.0, 4.0);
+        assert_eq!(bid, 30.0);
+        assert_eq!(ask, 0.0);
+    }
+
+    #[test]
+    fn test_mid_move_beyond_band_replaces_ladder() {
+        let levels = vec![
+            MarketMakerLevel {
+                offset_bps: 0.0,
+                size: 1.0,
+            },
+            MarketMakerLevel {
+                offset_bps: 10.0,
+                size: 2.0,
+            },
+            MarketMakerLevel {
+                offset_bps: 20.0,
+                size: 3.0,
+            },
+        ];
+        let mut resting = build_ladder(2000.0, 5.0, &levels, 10.0, 1, true);
+        for (i, order) in resting.iter_mut().enumerate() {
+            order.oid = 100 + i as u64;
+        }
+        assert_eq!(resting.len(), 3);
+        assert!(resting[0].price < 2000.0);
+        assert!(resting.windows(2).all(|w| w[1].price < w[0].price));
+
+        // Inside the band the ladder stays put
+        assert!(!should_requote(2000.0, 2000.2, 2));
+
+        // Beyond it every level is cancelled and quoted again around the new mid
+        assert!(should_requote(2000.0, 2010.0, 2));
+        assert_eq!(
+            resting_oids(&resting).collect::<Vec<_>>(),
+            vec![100, 101, 102]
+        );
+        let requoted = build_ladder(2010.0, 5.0, &levels, 10.0, 1, true);
+        assert_eq!(requoted.len(), resting.len());
+        for (new, old) in requoted.iter().zip(&resting) {
+            assert!(new.price > old.price);
+            assert_eq!(new.position, old.position);
         }
     }
+
+    #[test]
+    fn test_ladder_respects_max_amount() {
+        let levels = vec![
+            MarketMakerLevel {
+                offset_bps: 0.0,
+                size: 1.0,
+            },
+            MarketMakerLevel {
+                offset_bps: 10.0,
+                size: 1.0,
+            },
+        ];
+        let asks = build_ladder(2000.0, 5.0, &levels, 1.5, 1, false);
+        assert_eq!(asks.len(), 2);
+        assert!(asks[0].price > 2000.0);
+        assert_eq!(asks[1].position, 0.5);
+
+        // A filled level is no longer cancelled
+        let mut asks = asks;
+        asks[0].oid = 1;
+        asks[1].oid = 2;
+        reduce_resting(&mut asks, 1, 1.0);
+        assert_eq!(resting_oids(&asks).collect::<Vec<_>>(), vec![2]);
+
+        assert!(build_ladder(2000.0, 5.0, &levels, 0.0, 1, false).is_empty());
+    }
 }
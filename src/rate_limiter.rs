@@ -0,0 +1,167 @@
+use std::sync::{Arc, Mutex, OnceLock};
+
+use tokio::time::{sleep_until, Duration, Instant};
+
+/// Weight budget Hyperliquid grants per IP: 1200 per minute
+pub const DEFAULT_WEIGHT_PER_SECOND: f64 = 20.0;
+/// Default bucket size; a full minute of budget, matching the exchange's window
+pub const DEFAULT_BURST: f64 = 1200.0;
+
+/// Token bucket shared by `InfoClient` and `ExchangeClient`
+///
+/// Every REST call acquires its request weight before being sent. Tokens refill
+/// continuously at `per_second` up to `burst`. When the bucket is empty callers
+/// wait their turn, so a burst of calls is spread out instead of being rejected
+/// with 429s.
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_second: f64,
+    burst: f64,
+    state: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Available weight; negative when callers are queued
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// Create a limiter allowing `per_second` weight on average and bursts of up to `burst`
+    pub fn new(per_second: f64, burst: f64) -> Self {
+        assert!(per_second > 0.0, "per_second must be greater than 0");
+        assert!(burst > 0.0, "burst must be greater than 0");
+        Self {
+            per_second,
+            burst,
+            state: Mutex::new(Bucket {
+                tokens: burst,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// Process-wide limiter used by clients unless configured otherwise
+    ///
+    /// Hyperliquid limits by IP, so all clients in a process share one budget.
+    pub fn shared() -> Arc<RateLimiter> {
+        static SHARED: OnceLock<Arc<RateLimiter>> = OnceLock::new();
+        SHARED
+            .get_or_init(|| Arc::new(RateLimiter::new(DEFAULT_WEIGHT_PER_SECOND, DEFAULT_BURST)))
+            .clone()
+    }
+
+    /// Wait until `weight` is available and take it
+    ///
+    /// Callers are served in the order they call `acquire`: the weight is
+    /// reserved immediately and the caller sleeps until the bucket has refilled.
+    pub async fn acquire(&self, weight: f64) {
+        let ready_at = {
+            let mut bucket = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            let refill = now.duration_since(bucket.last_refill).as_secs_f64() * self.per_second;
+            bucket.tokens = (bucket.tokens + refill).min(self.burst);
+            bucket.last_refill = now;
+            bucket.tokens -= weight;
+            if bucket.tokens >= 0.0 {
+                return;
+            }
+            now + Duration::from_secs_f64(-bucket.tokens / self.per_second)
+        };
+        sleep_until(ready_at).await;
+    }
+}
+
+/// Hyperliquid request weight for a REST call
+///
+/// Exchange actions weigh `1 + floor(batch_length / 40)`. Most info requests
+/// weigh 20, a few light ones 2 and `userRole` 60.
+pub(crate) fn request_weight(url_path: &str, body: &str) -> f64 {
+    let Ok(body) = serde_json::from_str::<serde_json::Value>(body) else {
+        return 1.0;
+    };
+
+    if url_path == "/exchange" {
+        let action = &body["action"];
+        let batch_length = ["orders", "cancels", "modifies"]
+            .iter()
+            .find_map(|key| action[key].as_array())
+            .map_or(0, Vec::len);
+        return 1.0 + (batch_length / 40) as f64;
+    }
+
+    match body["type"].as_str() {
+        Some(
+            "l2Book"
+            | "allMids"
+            | "clearinghouseState"
+            | "orderStatus"
+            | "spotClearinghouseState"
+            | "exchangeStatus",
+        ) => 2.0,
+        Some("userRole") => 60.0,
+        _ => 20.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_concurrent_calls_respect_rate() {
+        // 20 weight/s with room for a single call: calls must be ~50ms apart
+        let limiter = Arc::new(RateLimiter::new(20.0, 1.0));
+        let start = Instant::now();
+
+        let handles: Vec<_> = (0..5)
+            .map(|_| {
+                let limiter = limiter.clone();
+                tokio::spawn(async move {
+                    limiter.acquire(1.0).await;
+                    start.elapsed()
+                })
+            })
+            .collect();
+        let mut finished = Vec::new();
+        for handle in handles {
+            finished.push(handle.await.unwrap());
+        }
+        finished.sort();
+
+        // The first call goes straight through, the nth waits for n refills
+        assert!(finished[0] < Duration::from_millis(40));
+        for (i, elapsed) in finished.iter().enumerate().skip(1) {
+            let slot = Duration::from_millis(50 * i as u64 - 1);
+            assert!(*elapsed >= slot, "{finished:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_burst_is_not_delayed() {
+        let limiter = RateLimiter::new(1.0, 10.0);
+        let start = Instant::now();
+        for _ in 0..10 {
+            limiter.acquire(1.0).await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_request_weight() {
+        assert_eq!(request_weight("/info", r#"{"type":"allMids"}"#), 2.0);
+        assert_eq!(request_weight("/info", r#"{"type":"meta"}"#), 20.0);
+        assert_eq!(request_weight("/info", r#"{"type":"userRole"}"#), 60.0);
+
+        let order = |n: usize| {
+            let orders = vec![serde_json::json!({}); n];
+            serde_json::json!({"action": {"type": "order", "orders": orders}}).to_string()
+        };
+        assert_eq!(request_weight("/exchange", &order(1)), 1.0);
+        assert_eq!(request_weight("/exchange", &order(40)), 2.0);
+        assert_eq!(request_weight("/exchange", &order(85)), 3.0);
+        let cancel = serde_json::json!({"action": {"type": "cancel", "cancels": [{}, {}]}});
+        assert_eq!(request_weight("/exchange", &cancel.to_string()), 1.0);
+    }
+}
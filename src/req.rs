@@ -1,7 +1,13 @@
+use std::sync::Arc;
+
 use reqwest::{Client, Response};
 use serde::Deserialize;
 
-use crate::{prelude::*, BaseUrl, Error};
+use crate::{
+    prelude::*,
+    rate_limiter::{request_weight, RateLimiter},
+    Error,
+};
 
 #[derive(Deserialize, Debug)]
 struct ErrorData {
@@ -14,6 +20,10 @@ struct ErrorData {
 pub struct HttpClient {
     pub client: Client,
     pub base_url: String,
+    /// Whether actions are signed for mainnet, independent of the URL
+    pub mainnet: bool,
+    /// Requests wait for their weight here before being sent; unthrottled if `None`
+    pub rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 async fn parse_response(response: Response) -> Result<String> {
@@ -53,6 +63,9 @@ async fn parse_response(response: Response) -> Result<String> {
 
 impl HttpClient {
     pub async fn post(&self, url_path: &'static str, data: String) -> Result<String> {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire(request_weight(url_path, &data)).await;
+        }
         let full_url = format!("{}{url_path}", self.base_url);
         let request = self
             .client
@@ -70,6 +83,6 @@ impl HttpClient {
     }
 
     pub fn is_mainnet(&self) -> bool {
-        self.base_url == BaseUrl::Mainnet.get_url()
+        self.mainnet
     }
 }
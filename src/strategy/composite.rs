@@ -0,0 +1,467 @@
+//! Composite strategy running several sub-strategies side by side
+//!
+//! Every callback is forwarded to all children. Child order ids are remapped
+//! to composite ids so two children can never collide, and fills are routed
+//! back to the child that placed the order.
+
+use log::warn;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use super::{
+    CapitalRequirement, Strategy, StrategyContext, StrategyEvent, StrategyFactory,
+    StrategyRegistry, StrategyStatus,
+};
+use crate::market::{AssetPrecision, OrderFill, OrderRequest};
+
+pub struct CompositeStrategy {
+    children: Vec<Box<dyn Strategy + Send + Sync>>,
+    /// Composite order_id -> (child index, child's own order_id, unfilled qty)
+    order_map: HashMap<u64, (usize, u64, f64)>,
+    next_order_id: u64,
+}
+
+impl CompositeStrategy {
+    pub fn new(children: Vec<Box<dyn Strategy + Send + Sync>>) -> Self {
+        Self {
+            children,
+            order_map: HashMap::new(),
+            next_order_id: 0,
+        }
+    }
+
+    /// The sub-strategies, in the order they receive callbacks
+    pub fn children(&self) -> &[Box<dyn Strategy + Send + Sync>] {
+        &self.children
+    }
+
+    /// Give a child's orders composite ids and remember who owns them
+    fn remap(&mut self, child: usize, orders: Vec<OrderRequest>) -> Vec<OrderRequest> {
+        orders
+            .into_iter()
+            .map(|mut order| {
+                if let Some(replaced) = order.replaces {
+                    order.replaces = self
+                        .order_map
+                        .iter()
+                        .find(|(_, &(c, id, _))| c == child && id == replaced)
+                        .map(|(&composite_id, _)| composite_id);
+                    if let Some(composite_id) = order.replaces {
+                        self.order_map.remove(&composite_id);
+                    }
+                }
+                self.next_order_id += 1;
+                self.order_map
+                    .insert(self.next_order_id, (child, order.order_id, order.qty));
+                order.order_id = self.next_order_id;
+                order
+            })
+            .collect()
+    }
+
+    /// Run `f` on every child and collect the remapped orders
+    fn forward<F>(&mut self, mut f: F) -> Vec<OrderRequest>
+    where
+        F: FnMut(&mut Box<dyn Strategy + Send + Sync>) -> Vec<OrderRequest>,
+    {
+        let mut orders = Vec::new();
+        for child in 0..self.children.len() {
+            let child_orders = f(&mut self.children[child]);
+            orders.extend(self.remap(child, child_orders));
+        }
+        orders
+    }
+}
+
+impl Strategy for CompositeStrategy {
+    fn on_price_update(&mut self, asset: &str, price: f64) -> Vec<OrderRequest> {
+        self.forward(|child| child.on_price_update(asset, price))
+    }
+
+    fn on_order_filled(&mut self, fill: &OrderFill) -> Vec<OrderRequest> {
+        let Some(entry) = self.order_map.get_mut(&fill.order_id) else {
+            warn!("Composite: fill for unknown order {}", fill.order_id);
+            return vec![];
+        };
+        let (child, child_order_id, remaining) = *entry;
+        // Keep routing partial fills until the whole order is done
+        if remaining - fill.qty > f64::EPSILON * remaining.max(1.0) {
+            entry.2 = remaining - fill.qty;
+        } else {
+            self.order_map.remove(&fill.order_id);
+        }
+        let mut child_fill = fill.clone();
+        child_fill.order_id = child_order_id;
+        let orders = self.children[child].on_order_filled(&child_fill);
+        self.remap(child, orders)
+    }
+
+    fn on_order_rejected(&mut self, order_id: u64, reason: &str) -> Vec<OrderRequest> {
+        let Some((child, child_order_id, _)) = self.order_map.remove(&order_id) else {
+            return vec![];
+        };
+        let orders = self.children[child].on_order_rejected(child_order_id, reason);
+        self.remap(child, orders)
+    }
+
+    fn on_tick(&mut self, now: u64) -> Vec<OrderRequest> {
+        self.forward(|child| child.on_tick(now))
+    }
+
+    /// Each child gets the balance the children before it don't need
+    fn on_balance_update(&mut self, asset: &str, base: f64) -> Vec<OrderRequest> {
+        let mut remaining = base;
+        self.forward(|child| {
+            let orders = child.on_balance_update(asset, remaining);
+            remaining = (remaining - child.required_capital().base).max(0.0);
+            orders
+        })
+    }
+
+    fn on_precision_update(&mut self, asset: &str, precision: AssetPrecision) -> Vec<OrderRequest> {
+        self.forward(|child| child.on_precision_update(asset, precision))
+    }
+
+    fn set_context(&mut self, context: StrategyContext) {
+        for child in &mut self.children {
+            child.set_context(context.clone());
+        }
+    }
+
+    fn on_start(&mut self) -> Vec<OrderRequest> {
+        self.forward(|child| child.on_start())
+    }
+
+    fn on_stop(&mut self) -> Vec<OrderRequest> {
+        self.forward(|child| child.on_stop())
+    }
+
+    fn drain_events(&mut self) -> Vec<StrategyEvent> {
+        self.children
+            .iter_mut()
+            .flat_map(|child| child.drain_events())
+            .collect()
+    }
+
+    fn drain_cancels(&mut self) -> Vec<u64> {
+        let mut cancels = Vec::new();
+        for child in 0..self.children.len() {
+            for child_order_id in self.children[child].drain_cancels() {
+                let composite_id = self
+                    .order_map
+                    .iter()
+                    .find(|(_, &(c, id, _))| c == child && id == child_order_id)
+                    .map(|(&composite_id, _)| composite_id);
+                if let Some(composite_id) = composite_id {
+                    self.order_map.remove(&composite_id);
+                    cancels.push(composite_id);
+                }
+            }
+        }
+        cancels
+    }
+
+    /// Asks for every child that does; each child's request is cleared
+    fn take_open_orders_request(&mut self) -> bool {
+        let requests: Vec<bool> = self
+            .children
+            .iter_mut()
+            .map(|child| child.take_open_orders_request())
+            .collect();
+        requests.contains(&true)
+    }
+
+    /// Each child sees its own open orders, under its own ids
+    fn on_open_orders(&mut self, open: &[u64]) -> Vec<OrderRequest> {
+        let mut orders = Vec::new();
+        for child in 0..self.children.len() {
+            let child_open: Vec<u64> = open
+                .iter()
+                .filter_map(|id| self.order_map.get(id))
+                .filter(|(c, _, _)| *c == child)
+                .map(|(_, id, _)| *id)
+                .collect();
+            let child_orders = self.children[child].on_open_orders(&child_open);
+            orders.extend(self.remap(child, child_orders));
+        }
+        orders
+    }
+
+    /// One entry per child, in order; `null` for children that keep nothing
+    fn checkpoint(&self) -> Option<Value> {
+        let children: Vec<Value> = self
+            .children
+            .iter()
+            .map(|child| child.checkpoint().unwrap_or(Value::Null))
+            .collect();
+        Some(Value::Array(children))
+    }
+
+    fn restore(&mut self, checkpoint: Value) {
+        let Value::Array(entries) = checkpoint else {
+            warn!("Ignoring composite checkpoint that isn't a list");
+            return;
+        };
+        for (child, entry) in self.children.iter_mut().zip(entries) {
+            if !entry.is_null() {
+                child.restore(entry);
+            }
+        }
+    }
+
+    fn required_capital(&self) -> CapitalRequirement {
+        self.children
+            .iter()
+            .map(|child| child.required_capital())
+            .fold(CapitalRequirement::default(), |total, child| {
+                CapitalRequirement {
+                    quote: total.quote + child.quote,
+                    base: total.base + child.base,
+                }
+            })
+    }
+
+    fn name(&self) -> &str {
+        "composite"
+    }
+
+    fn status(&self) -> StrategyStatus {
+        let children: Vec<StrategyStatus> = self.children.iter().map(|c| c.status()).collect();
+        let asset = children
+            .first()
+            .map(|s| s.asset.clone())
+            .unwrap_or_default();
+
+        let mut status = StrategyStatus::new("composite", asset).with_status("Running");
+        for child in &children {
+            status.position += child.position;
+            status.realized_pnl += child.realized_pnl;
+            status.unrealized_pnl += child.unrealized_pnl;
+            status.total_fees += child.total_fees;
+            if let Some(investment) = child.total_investment {
+                *status.total_investment.get_or_insert(0.0) += investment;
+            }
+            status.trade_count += child.trade_count;
+            status.active_orders += child.active_orders;
+            if child.current_price > 0.0 {
+                status.current_price = child.current_price;
+            }
+        }
+        status.custom = json!({ "strategies": children });
+        status
+    }
+}
+
+/// Builds a `CompositeStrategy` from a `strategies` param
+///
+/// Each entry is `{ type = "...", params = { ... } }` and is created with the
+/// factory's own registry. The composite's other params (precision, initial
+/// price) are passed to every child, with the child's own params taking
+/// precedence.
+pub struct CompositeStrategyFactory {
+    registry: StrategyRegistry,
+}
+
+impl CompositeStrategyFactory {
+    /// Create a factory whose children come from `registry`
+    pub fn new(registry: StrategyRegistry) -> Self {
+        Self { registry }
+    }
+}
+
+impl StrategyFactory for CompositeStrategyFactory {
+    fn create(
+        &self,
+        asset: &str,
+        params: HashMap<String, Value>,
+    ) -> Box<dyn Strategy + Send + Sync> {
+        let specs = params
+            .get("strategies")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+
+        let mut children = Vec::new();
+        for spec in specs {
+            let Some(type_name) = spec.get("type").and_then(|v| v.as_str()) else {
+                warn!("Composite: sub-strategy without a type, skipping");
+                continue;
+            };
+            let mut child_params = params.clone();
+            child_params.remove("strategies");
+            if let Some(Value::Object(own)) = spec.get("params") {
+                child_params.extend(own.clone());
+            }
+            match self
+                .registry
+                .create_strategy(type_name, asset, child_params)
+            {
+                Some(child) => children.push(child),
+                None => warn!("Composite: unknown sub-strategy type '{}'", type_name),
+            }
+        }
+
+        if children.is_empty() {
+            warn!("Composite strategy has no sub-strategies");
+        }
+
+        Box::new(CompositeStrategy::new(children))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Child order ids seen by a test strategy's `on_order_filled`
+    type Fills = Arc<Mutex<Vec<u64>>>;
+
+    /// Places one buy with a fixed id on each price update and records fills
+    struct FixedIdStrategy {
+        name: &'static str,
+        order_id: u64,
+        fills: Fills,
+    }
+
+    impl Strategy for FixedIdStrategy {
+        fn on_price_update(&mut self, asset: &str, price: f64) -> Vec<OrderRequest> {
+            vec![OrderRequest::buy(self.order_id, asset, 1.0, price)]
+        }
+
+        fn on_order_filled(&mut self, fill: &OrderFill) -> Vec<OrderRequest> {
+            self.fills.lock().unwrap().push(fill.order_id);
+            vec![OrderRequest::sell(
+                self.order_id + 1,
+                &fill.asset,
+                1.0,
+                fill.price + 1.0,
+            )]
+        }
+
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn status(&self) -> StrategyStatus {
+            StrategyStatus::new(self.name, "BTC")
+                .with_position(1.0)
+                .with_pnl(2.0, 0.5, 0.1)
+        }
+    }
+
+    fn composite() -> (CompositeStrategy, Fills, Fills) {
+        let fills_a = Arc::new(Mutex::new(Vec::new()));
+        let fills_b = Arc::new(Mutex::new(Vec::new()));
+        let strategy = CompositeStrategy::new(vec![
+            Box::new(FixedIdStrategy {
+                name: "a",
+                order_id: 1,
+                fills: fills_a.clone(),
+            }),
+            // Same child order id as "a"
+            Box::new(FixedIdStrategy {
+                name: "b",
+                order_id: 1,
+                fills: fills_b.clone(),
+            }),
+        ]);
+        (strategy, fills_a, fills_b)
+    }
+
+    #[test]
+    fn test_child_order_ids_do_not_collide() {
+        let (mut strategy, _, _) = composite();
+
+        let orders = strategy.on_price_update("BTC", 100.0);
+
+        assert_eq!(orders.len(), 2);
+        assert_ne!(orders[0].order_id, orders[1].order_id);
+    }
+
+    #[test]
+    fn test_fill_routed_to_owning_child_only() {
+        let (mut strategy, fills_a, fills_b) = composite();
+        let orders = strategy.on_price_update("BTC", 100.0);
+
+        let follow_up =
+            strategy.on_order_filled(&OrderFill::new(orders[1].order_id, "BTC", 1.0, 100.0));
+
+        assert!(fills_a.lock().unwrap().is_empty());
+        // The child sees its own order id
+        assert_eq!(*fills_b.lock().unwrap(), vec![1]);
+
+        // The follow-up order is remapped and routes back to "b" too
+        assert_eq!(follow_up.len(), 1);
+        assert!(follow_up[0].order_id > orders[1].order_id);
+        strategy.on_order_filled(&OrderFill::new(follow_up[0].order_id, "BTC", 1.0, 101.0));
+        assert!(fills_a.lock().unwrap().is_empty());
+        assert_eq!(*fills_b.lock().unwrap(), vec![1, 2]);
+
+        // Unknown ids are ignored
+        assert!(strategy
+            .on_order_filled(&OrderFill::new(999, "BTC", 1.0, 100.0))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_partial_fills_keep_routing() {
+        let (mut strategy, _, fills_b) = composite();
+        let orders = strategy.on_price_update("BTC", 100.0);
+        let order_id = orders[1].order_id;
+
+        strategy.on_order_filled(&OrderFill::new(order_id, "BTC", 0.5, 100.0));
+        strategy.on_order_filled(&OrderFill::new(order_id, "BTC", 0.5, 100.0));
+        assert_eq!(*fills_b.lock().unwrap(), vec![1, 1]);
+
+        // Fully filled: the mapping is gone
+        strategy.on_order_filled(&OrderFill::new(order_id, "BTC", 0.5, 100.0));
+        assert_eq!(fills_b.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_status_is_merged() {
+        let (strategy, _, _) = composite();
+        let status = strategy.status();
+
+        assert_eq!(status.name, "composite");
+        assert_eq!(status.asset, "BTC");
+        assert_eq!(status.position, 2.0);
+        assert_eq!(status.realized_pnl, 4.0);
+        assert!((status.total_fees - 0.2).abs() < 1e-9);
+        let children = status.custom["strategies"].as_array().unwrap();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[1]["name"], "b");
+    }
+
+    #[test]
+    fn test_factory_builds_children_from_registry() {
+        let mut registry = StrategyRegistry::new();
+        registry.register("dca", super::super::dca::DcaStrategyFactory);
+        let factory = CompositeStrategyFactory::new(registry);
+
+        let params = HashMap::from([
+            ("initial_price".to_string(), json!(100.0)),
+            ("sz_decimals".to_string(), json!(2)),
+            (
+                "strategies".to_string(),
+                json!([
+                    {"type": "dca", "params": {"notional_per_buy": 100.0, "interval_secs": 60}},
+                    {"type": "dca", "params": {"notional_per_buy": 50.0, "interval_secs": 60}},
+                    {"type": "missing"}
+                ]),
+            ),
+        ]);
+        let mut strategy = factory.create("BTC", params);
+
+        let orders = strategy.on_tick(0);
+        assert_eq!(orders.len(), 2);
+        assert_eq!(
+            strategy.status().custom["strategies"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,383 @@
+//! Dollar-cost-averaging accumulation strategy
+//!
+//! Buys a fixed quote notional of the asset on a schedule, regardless of
+//! price, until an optional total budget is spent.
+
+use log::{info, warn};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use super::{Strategy, StrategyFactory, StrategyStatus};
+use crate::market::{AssetPrecision, OrderFill, OrderRequest};
+
+/// Default limit price offset above the last price, so buys fill like market orders
+pub const DEFAULT_DCA_SLIPPAGE: f64 = 0.005;
+
+pub struct DcaStrategy {
+    asset: String,
+    /// Quote amount spent per buy
+    notional_per_buy: f64,
+    /// Seconds between buys
+    interval_secs: u64,
+    /// Stop buying once this much quote has been committed
+    max_total_notional: Option<f64>,
+    /// Limit price offset above the last price (0.005 = 0.5%)
+    slippage: f64,
+    precision: AssetPrecision,
+
+    last_price: f64,
+    last_buy_at: Option<u64>,
+    next_order_id: u64,
+    /// Buys sent but not yet filled: order_id -> committed notional
+    pending_buys: HashMap<u64, f64>,
+
+    position: f64,
+    total_invested: f64,
+    buy_count: u32,
+}
+
+impl DcaStrategy {
+    pub fn new(
+        asset: String,
+        notional_per_buy: f64,
+        interval_secs: u64,
+        max_total_notional: Option<f64>,
+        precision: AssetPrecision,
+        initial_price: f64,
+    ) -> Self {
+        Self {
+            asset,
+            notional_per_buy,
+            interval_secs,
+            max_total_notional,
+            slippage: DEFAULT_DCA_SLIPPAGE,
+            precision,
+            last_price: initial_price,
+            last_buy_at: None,
+            next_order_id: 0,
+            pending_buys: HashMap::new(),
+            position: 0.0,
+            total_invested: 0.0,
+            buy_count: 0,
+        }
+    }
+
+    /// Override how far above the last price buys are limited
+    pub fn with_slippage(mut self, slippage: f64) -> Self {
+        self.slippage = slippage;
+        self
+    }
+
+    /// Average cost of the accumulated position, 0 before the first fill
+    pub fn avg_cost(&self) -> f64 {
+        if self.position > 0.0 {
+            self.total_invested / self.position
+        } else {
+            0.0
+        }
+    }
+
+    /// Quote amount spent on filled buys
+    pub fn total_invested(&self) -> f64 {
+        self.total_invested
+    }
+
+    /// Quote still available under `max_total_notional`, counting unfilled buys
+    fn remaining_budget(&self) -> f64 {
+        match self.max_total_notional {
+            Some(max) => {
+                let committed: f64 = self.pending_buys.values().sum();
+                max - self.total_invested - committed
+            }
+            None => f64::INFINITY,
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.remaining_budget() <= f64::EPSILON
+    }
+}
+
+impl Strategy for DcaStrategy {
+    fn on_price_update(&mut self, asset: &str, price: f64) -> Vec<OrderRequest> {
+        if asset == self.asset {
+            self.last_price = price;
+        }
+        vec![]
+    }
+
+    fn on_tick(&mut self, now: u64) -> Vec<OrderRequest> {
+        if self.last_price <= 0.0 || self.is_complete() {
+            return vec![];
+        }
+        if let Some(last) = self.last_buy_at {
+            if now < last + self.interval_secs {
+                return vec![];
+            }
+        }
+
+        let notional = self.notional_per_buy.min(self.remaining_budget());
+        let price = self
+            .precision
+            .round_price(self.last_price * (1.0 + self.slippage), true);
+        let qty = self.precision.round_size(notional / price);
+        if qty <= 0.0 {
+            warn!(
+                "DCA buy of {:.4} at {} rounds to zero size, skipping",
+                notional, price
+            );
+            return vec![];
+        }
+
+        self.last_buy_at = Some(now);
+        self.next_order_id += 1;
+        let order_id = self.next_order_id;
+        self.pending_buys.insert(order_id, qty * price);
+        info!("DCA buy #{}: {} {} @ {}", order_id, qty, self.asset, price);
+
+        vec![OrderRequest::buy(order_id, &self.asset, qty, price)]
+    }
+
+    fn on_order_filled(&mut self, fill: &OrderFill) -> Vec<OrderRequest> {
+        if self.pending_buys.remove(&fill.order_id).is_none() {
+            return vec![];
+        }
+        self.position += fill.qty;
+        self.total_invested += fill.value();
+        self.buy_count += 1;
+        info!(
+            "DCA filled {} @ {}, position {} at avg cost {:.4}",
+            fill.qty,
+            fill.price,
+            self.position,
+            self.avg_cost()
+        );
+        vec![]
+    }
+
+    fn on_order_rejected(&mut self, order_id: u64, reason: &str) -> Vec<OrderRequest> {
+        // A rejected or expired buy spent nothing; give its notional back to the budget
+        if self.pending_buys.remove(&order_id).is_some() {
+            warn!(
+                "DCA buy #{} not filled ({}), releasing its budget",
+                order_id, reason
+            );
+        }
+        vec![]
+    }
+
+    fn name(&self) -> &str {
+        "dca"
+    }
+
+    fn status(&self) -> StrategyStatus {
+        let unrealized = if self.position > 0.0 {
+            (self.last_price - self.avg_cost()) * self.position
+        } else {
+            0.0
+        };
+        let next_buy_at = self.last_buy_at.map(|t| t + self.interval_secs);
+
+        let mut status = StrategyStatus::new("dca", &self.asset)
+            .with_status(if self.is_complete() {
+                "Completed"
+            } else {
+                "Accumulating"
+            })
+            .with_price(self.last_price)
+            .with_position(self.position)
+            .with_pnl(0.0, unrealized, 0.0)
+            .with_custom(json!({
+                "avg_cost": self.avg_cost(),
+                "total_invested": self.total_invested,
+                "buy_count": self.buy_count,
+                "notional_per_buy": self.notional_per_buy,
+                "interval_secs": self.interval_secs,
+                "max_total_notional": self.max_total_notional,
+                "next_buy_at": next_buy_at,
+            }));
+        status.active_orders = self.pending_buys.len();
+        status
+    }
+}
+
+pub struct DcaStrategyFactory;
+
+impl StrategyFactory for DcaStrategyFactory {
+    fn create(
+        &self,
+        asset: &str,
+        params: HashMap<String, Value>,
+    ) -> Box<dyn Strategy + Send + Sync> {
+        let notional_per_buy = params
+            .get("notional_per_buy")
+            .and_then(|v| v.as_f64())
+            .unwrap_or(0.0);
+        let interval_secs = params
+            .get("interval_secs")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(3600);
+        let max_total_notional = params.get("max_total_notional").and_then(|v| v.as_f64());
+        let slippage = params
+            .get("slippage")
+            .and_then(|v| v.as_f64())
+            .unwrap_or(DEFAULT_DCA_SLIPPAGE);
+        let initial_price = params
+            .get("initial_price")
+            .and_then(|v| v.as_f64())
+            .unwrap_or(0.0);
+
+        let precision = AssetPrecision {
+            sz_decimals: params
+                .get("sz_decimals")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0) as u32,
+            price_decimals: params
+                .get("price_decimals")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(2) as u32,
+            max_decimals: params
+                .get("max_decimals")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(6) as u32,
+        };
+
+        if notional_per_buy <= 0.0 {
+            warn!("notional_per_buy must be > 0, DCA will not buy");
+        }
+
+        Box::new(
+            DcaStrategy::new(
+                asset.to_string(),
+                notional_per_buy,
+                interval_secs,
+                max_total_notional,
+                precision,
+                initial_price,
+            )
+            .with_slippage(slippage),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn precision() -> AssetPrecision {
+        AssetPrecision {
+            sz_decimals: 4,
+            price_decimals: 2,
+            max_decimals: 6,
+        }
+    }
+
+    fn create_test_strategy(max_total_notional: Option<f64>) -> DcaStrategy {
+        DcaStrategy::new(
+            "HYPE/USDC".to_string(),
+            100.0,
+            60,
+            max_total_notional,
+            precision(),
+            20.0,
+        )
+        .with_slippage(0.0)
+    }
+
+    #[test]
+    fn test_ticks_produce_buys_on_schedule() {
+        let mut strategy = create_test_strategy(None);
+        let start = 1_700_000_000;
+
+        let mut orders = Vec::new();
+        for second in 0..300 {
+            orders.extend(strategy.on_tick(start + second));
+        }
+
+        // One buy immediately, then one per full interval
+        assert_eq!(orders.len(), 5);
+        assert!(orders.iter().all(|o| o.is_buy()));
+        assert_eq!(orders[0].qty, 5.0);
+        assert_eq!(orders[0].limit_price, 20.0);
+    }
+
+    #[test]
+    fn test_average_cost() {
+        let mut strategy = create_test_strategy(None);
+        let prices = [20.0, 25.0, 10.0];
+
+        for (i, price) in prices.iter().enumerate() {
+            strategy.on_price_update("HYPE/USDC", *price);
+            let orders = strategy.on_tick(i as u64 * 60);
+            assert_eq!(orders.len(), 1);
+            let order = &orders[0];
+            strategy.on_order_filled(&OrderFill::new(
+                order.order_id,
+                "HYPE/USDC",
+                order.qty,
+                *price,
+            ));
+        }
+
+        // 5 + 4 + 10 units for 300 quote
+        assert_eq!(strategy.position, 19.0);
+        assert!((strategy.total_invested() - 300.0).abs() < 1e-9);
+        assert!((strategy.avg_cost() - 300.0 / 19.0).abs() < 1e-9);
+
+        let status = strategy.status();
+        assert_eq!(status.position, 19.0);
+        assert_eq!(status.custom["buy_count"], 3);
+        assert!((status.custom["avg_cost"].as_f64().unwrap() - 300.0 / 19.0).abs() < 1e-9);
+        assert!((status.custom["total_invested"].as_f64().unwrap() - 300.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_stops_at_max_total_notional() {
+        let mut strategy = create_test_strategy(Some(250.0));
+
+        let orders: Vec<_> = (0..5).flat_map(|i| strategy.on_tick(i * 60)).collect();
+
+        // Two full buys and a final 50 quote buy
+        assert_eq!(orders.len(), 3);
+        assert_eq!(orders[2].qty, 2.5);
+        assert_eq!(strategy.status().status, "Completed");
+    }
+
+    #[test]
+    fn test_rejected_buy_frees_budget() {
+        let mut strategy = create_test_strategy(Some(100.0));
+
+        let orders = strategy.on_tick(0);
+        assert_eq!(orders.len(), 1);
+        assert_eq!(strategy.status().status, "Completed");
+
+        strategy.on_order_rejected(orders[0].order_id, "expired");
+        let status = strategy.status();
+        assert_eq!(status.status, "Accumulating");
+        assert_eq!(status.active_orders, 0);
+
+        // The full budget is available to the next buy
+        let orders = strategy.on_tick(60);
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].qty, 5.0);
+    }
+
+    #[test]
+    fn test_factory_params() {
+        let params = HashMap::from([
+            ("notional_per_buy".to_string(), json!(50.0)),
+            ("interval_secs".to_string(), json!(10)),
+            ("max_total_notional".to_string(), json!(500.0)),
+            ("initial_price".to_string(), json!(25.0)),
+            ("sz_decimals".to_string(), json!(2)),
+        ]);
+        let mut strategy = DcaStrategyFactory.create("HYPE/USDC", params);
+
+        assert_eq!(strategy.name(), "dca");
+        let orders = strategy.on_tick(0);
+        assert_eq!(orders.len(), 1);
+        assert!(strategy.on_tick(9).is_empty());
+        assert_eq!(strategy.on_tick(10).len(), 1);
+        assert_eq!(strategy.status().custom["interval_secs"], 10);
+    }
+}
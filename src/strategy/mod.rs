@@ -64,9 +64,14 @@
 //! }
 //! ```
 
+pub mod composite;
+pub mod dca;
 pub mod registry;
 pub mod spot_grid;
 mod traits;
 
 pub use registry::{StrategyFactory, StrategyRegistry};
-pub use traits::{NoOpStrategy, Strategy, StrategyStatus};
+pub use traits::{
+    BookLevel, CapitalRequirement, NoOpStrategy, RangeDirection, StatusBook, Strategy,
+    StrategyContext, StrategyEvent, StrategyStatus,
+};
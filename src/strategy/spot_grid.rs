@@ -1,11 +1,19 @@
-use log::{error, info, warn};
+use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
+use serde_json::Value;
 use std::collections::{HashMap, VecDeque};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use super::{Strategy, StrategyFactory, StrategyStatus};
-use crate::market::{AssetPrecision, OrderFill, OrderRequest, OrderSide};
+use super::{
+    BookLevel, CapitalRequirement, RangeDirection, StatusBook, Strategy, StrategyEvent,
+    StrategyFactory, StrategyStatus,
+};
+use crate::backtest::Candle;
+use crate::market::{AssetPrecision, OrderFill, OrderRequest, OrderSide, TimeInForce};
+use crate::{Error, InfoClient, MarketType};
+
+/// Times a zone's order is re-sent after a rejection before the level is left empty
+const MAX_REJECT_RETRIES: u32 = 1;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TradeRecord {
@@ -19,6 +27,447 @@ pub struct TradeRecord {
 pub enum GridMode {
     Arithmetic,
     Geometric,
+    /// Geometric grid that keeps growing: filling the bottom buy adds a level
+    /// below and filling the top sell adds one above, one step further out
+    Infinite,
+}
+
+impl GridMode {
+    /// Parse a `grid_mode` param, case-insensitively
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "arithmetic" => Some(GridMode::Arithmetic),
+            "geometric" => Some(GridMode::Geometric),
+            "infinite" => Some(GridMode::Infinite),
+            _ => None,
+        }
+    }
+}
+
+/// How zone sizes are derived from `total_investment`
+///
+/// With a fixed `order_size` every zone trades the same base quantity
+/// regardless of this setting.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SizingMode {
+    /// Same base quantity in every zone, so each roundtrip cycles the same size
+    ConstantBase,
+    /// Same quote value in every zone (base size = quote / lower price)
+    ConstantQuote,
+}
+
+impl SizingMode {
+    /// Parse a `sizing_mode` param, case-insensitively
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "constant_base" => Some(SizingMode::ConstantBase),
+            "constant_quote" => Some(SizingMode::ConstantQuote),
+            _ => None,
+        }
+    }
+
+    /// Default sizing for a grid mode: constant base for geometric grids,
+    /// constant quote for arithmetic grids
+    pub fn default_for(mode: GridMode) -> Self {
+        match mode {
+            GridMode::Arithmetic => SizingMode::ConstantQuote,
+            GridMode::Geometric | GridMode::Infinite => SizingMode::ConstantBase,
+        }
+    }
+}
+
+/// How a grid comes by the base its sell zones start out holding
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum InitialPosition {
+    /// The account already holds it: sells go out straight away
+    #[default]
+    Existing,
+    /// Buy it with a resting limit order at the start price, then arm the sells
+    LimitBuy,
+    /// Buy it with an IOC order just above the start price, then arm the sells
+    MarketBuy,
+    /// Hold nothing: every zone starts out buying
+    Skip,
+}
+
+/// Which positions a grid may hold
+///
+/// Short and neutral grids trade perps only and start flat, so they ignore
+/// `InitialPosition`. Infinite grids are always long.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GridDirection {
+    /// Buy below and sell back above; never net short
+    #[default]
+    Long,
+    /// Sell above and buy back below; never net long
+    Short,
+    /// Long in the zones below the start price, short in those above
+    Neutral,
+}
+
+/// How far above the start price a `MarketBuy` acquisition may fill
+const MARKET_BUY_SLIPPAGE: f64 = 0.005;
+
+/// Percentile of candle lows/highs used for the auto range (10th/90th)
+const AUTO_RANGE_PERCENTILE: f64 = 0.1;
+/// Auto range extends at least this many ATRs either side of the mid
+const AUTO_RANGE_ATR_MULTIPLIER: f64 = 2.0;
+
+/// Price range and spacing of a grid
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GridConfig {
+    pub lower_price: f64,
+    pub upper_price: f64,
+    pub grid_levels: usize,
+    pub mode: GridMode,
+}
+
+impl GridConfig {
+    /// Build a config whose range comes from `auto_range`
+    pub async fn new_auto(
+        info_client: &InfoClient,
+        asset: &str,
+        lookback: usize,
+        interval: &str,
+        grid_levels: usize,
+        mode: GridMode,
+    ) -> Result<Self, Error> {
+        let (lower_price, upper_price) =
+            Self::auto_range(info_client, asset, lookback, interval).await?;
+        Ok(Self {
+            lower_price,
+            upper_price,
+            grid_levels,
+            mode,
+        })
+    }
+
+    /// Suggest grid bounds from the last `lookback` candles of `interval` (e.g. "1h")
+    ///
+    /// Spot pairs like "HYPE/USDC" fall back to the base coin when the pair has
+    /// no mid of its own. See `range_from_candles` for how the band is chosen.
+    pub async fn auto_range(
+        info_client: &InfoClient,
+        asset: &str,
+        lookback: usize,
+        interval: &str,
+    ) -> Result<(f64, f64), Error> {
+        let interval_ms = interval_millis(interval)
+            .ok_or_else(|| Error::GenericParse(format!("unknown candle interval '{interval}'")))?;
+
+        let mids = info_client.all_mids().await?;
+        let base = asset.split('/').next().unwrap_or(asset);
+        let (coin, mid) = match mids.get(asset) {
+            Some(mid) => (asset, mid),
+            None => (base, mids.get(base).ok_or(Error::AssetNotFound)?),
+        };
+        let mid = mid.parse::<f64>().map_err(|_| Error::FloatStringParse)?;
+
+        let end_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let start_time = end_time.saturating_sub(interval_ms * lookback as u64);
+        let candles = Candle::fetch(info_client, coin, interval, start_time, end_time).await?;
+
+        Self::range_from_candles(&candles, mid)
+    }
+
+    /// Band around `mid` covering where the price has recently traded
+    ///
+    /// Takes the 10th percentile of lows and the 90th percentile of highs,
+    /// widened where needed to at least 2 ATRs either side of `mid`. Fails if
+    /// the result doesn't contain `mid`.
+    pub fn range_from_candles(candles: &[Candle], mid: f64) -> Result<(f64, f64), Error> {
+        if candles.is_empty() {
+            return Err(Error::GenericParse("no candles for auto range".to_string()));
+        }
+
+        let mut lows: Vec<f64> = candles.iter().map(|c| c.low).collect();
+        let mut highs: Vec<f64> = candles.iter().map(|c| c.high).collect();
+        lows.sort_by(f64::total_cmp);
+        highs.sort_by(f64::total_cmp);
+        let percentile =
+            |sorted: &[f64], p: f64| sorted[((sorted.len() - 1) as f64 * p).round() as usize];
+
+        // Average true range over the lookback
+        let atr = candles
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let prev_close = if i > 0 { candles[i - 1].close } else { c.open };
+                (c.high - c.low)
+                    .max((c.high - prev_close).abs())
+                    .max((c.low - prev_close).abs())
+            })
+            .sum::<f64>()
+            / candles.len() as f64;
+
+        let lower =
+            percentile(&lows, AUTO_RANGE_PERCENTILE).min(mid - AUTO_RANGE_ATR_MULTIPLIER * atr);
+        let upper = percentile(&highs, 1.0 - AUTO_RANGE_PERCENTILE)
+            .max(mid + AUTO_RANGE_ATR_MULTIPLIER * atr);
+
+        if !(lower > 0.0 && lower < mid && mid < upper) {
+            return Err(Error::GenericParse(format!(
+                "auto range {lower} - {upper} does not contain mid {mid}"
+            )));
+        }
+        Ok((lower, upper))
+    }
+
+    /// Read the range and spacing from grid strategy params, if they set one
+    pub fn from_params(params: &HashMap<String, Value>) -> Option<Self> {
+        let mode = match params.get("grid_mode").and_then(|v| v.as_str()) {
+            Some(s) => GridMode::parse(s)?,
+            None => GridMode::Arithmetic,
+        };
+        Some(Self {
+            lower_price: params.get("lower_price")?.as_f64()?,
+            upper_price: params.get("upper_price")?.as_f64()?,
+            grid_levels: params
+                .get("grid_levels")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(2) as usize,
+            mode,
+        })
+    }
+
+    /// Unrounded price of every grid line, lowest first
+    pub fn level_prices(&self) -> Vec<f64> {
+        let steps = self.grid_levels.saturating_sub(1).max(1) as f64;
+        match self.mode {
+            GridMode::Arithmetic => {
+                let step = (self.upper_price - self.lower_price) / steps;
+                (0..self.grid_levels)
+                    .map(|i| self.lower_price + i as f64 * step)
+                    .collect()
+            }
+            GridMode::Geometric | GridMode::Infinite => {
+                let ratio = (self.upper_price / self.lower_price).powf(1.0 / steps);
+                (0..self.grid_levels)
+                    .map(|i| self.lower_price * ratio.powi(i as i32))
+                    .collect()
+            }
+        }
+    }
+
+    /// Fail unless the grid lines strictly increase once rounded to `precision`
+    ///
+    /// Too many levels in a narrow range for the asset's tick size would
+    /// otherwise silently merge into zones that buy and sell at one price.
+    /// The error names the most levels that stay apart, if any do.
+    pub fn check_precision(&self, precision: &AssetPrecision) -> Result<(), Error> {
+        let Some((i, price)) = self.collapsed_level(precision) else {
+            return Ok(());
+        };
+        let advice = match self.max_levels_at(precision) {
+            Some(levels) => format!("use at most {levels} grid levels or a wider range"),
+            None => "use a wider range".to_string(),
+        };
+        Err(Error::GenericParse(format!(
+            "grid levels {} and {} both round to {} at the asset's precision; {}",
+            i,
+            i + 1,
+            price,
+            advice
+        )))
+    }
+
+    /// Most grid lines, up to the configured count, that still strictly
+    /// increase at `precision`
+    pub fn max_levels_at(&self, precision: &AssetPrecision) -> Option<usize> {
+        (2..=self.grid_levels).rev().find(|&grid_levels| {
+            let fewer = Self {
+                grid_levels,
+                ..*self
+            };
+            fewer.collapsed_level(precision).is_none()
+        })
+    }
+
+    /// First line that rounds to or below the one before it, with the price
+    /// it rounds to
+    fn collapsed_level(&self, precision: &AssetPrecision) -> Option<(usize, f64)> {
+        let rounded: Vec<f64> = self
+            .level_prices()
+            .into_iter()
+            .map(|p| precision.round_price_for_side(p, OrderSide::Buy))
+            .collect();
+        rounded
+            .windows(2)
+            .position(|pair| pair[1] <= pair[0])
+            .map(|i| (i, rounded[i]))
+    }
+
+    /// Value of the smallest zone order, in quote currency
+    ///
+    /// Sizes zones the way the strategy does: `notional` (investment times
+    /// leverage) spread by `sizing`, else a fixed `order_size` (1.0 if unset).
+    /// Rounding to the asset's size decimals is ignored.
+    pub fn min_order_notional(
+        &self,
+        sizing: SizingMode,
+        order_size: Option<f64>,
+        notional: Option<f64>,
+    ) -> f64 {
+        let prices = self.level_prices();
+        let num_zones = self.grid_levels.saturating_sub(1).max(1);
+        let lowest = prices[0];
+        match (sizing, notional) {
+            (SizingMode::ConstantQuote, Some(inv)) => inv / num_zones as f64,
+            (SizingMode::ConstantBase, Some(inv)) => {
+                let lower_sum: f64 = prices[..num_zones.min(prices.len())].iter().sum();
+                inv / lower_sum * lowest
+            }
+            _ => order_size.unwrap_or(1.0) * lowest,
+        }
+    }
+
+    /// Fail if a zone's order would be worth less than `min_notional`
+    ///
+    /// The exchange rejects such orders, so every buy at the bottom of the
+    /// grid would fail. The error names the most levels that clear the floor,
+    /// or asks for more size when no level count does.
+    pub fn check_min_notional(
+        &self,
+        sizing: SizingMode,
+        order_size: Option<f64>,
+        notional: Option<f64>,
+        min_notional: f64,
+    ) -> Result<(), Error> {
+        let smallest = self.min_order_notional(sizing, order_size, notional);
+        if smallest >= min_notional {
+            return Ok(());
+        }
+
+        let fits = (2..self.grid_levels).rev().find(|&grid_levels| {
+            let fewer = Self {
+                grid_levels,
+                ..*self
+            };
+            fewer.min_order_notional(sizing, order_size, notional) >= min_notional
+        });
+        let advice = match fits {
+            Some(levels) => format!("use at most {levels} grid levels"),
+            None => "raise order_size or total_investment".to_string(),
+        };
+        Err(Error::GenericParse(format!(
+            "grid orders of {smallest:.2} are below the {min_notional} minimum order value; {advice}"
+        )))
+    }
+
+    /// Base size of the smallest zone order, before rounding
+    ///
+    /// Sizes zones the way `min_order_notional` does; with constant quote
+    /// the top zone, buying at the highest price, gets the least base.
+    pub fn min_order_size(
+        &self,
+        sizing: SizingMode,
+        order_size: Option<f64>,
+        notional: Option<f64>,
+    ) -> f64 {
+        let prices = self.level_prices();
+        let num_zones = self.grid_levels.saturating_sub(1).max(1).min(prices.len());
+        match (sizing, notional) {
+            (SizingMode::ConstantQuote, Some(inv)) => {
+                inv / num_zones as f64 / prices[num_zones - 1]
+            }
+            (SizingMode::ConstantBase, Some(inv)) => inv / prices[..num_zones].iter().sum::<f64>(),
+            _ => order_size.unwrap_or(1.0),
+        }
+    }
+
+    /// Fail if a zone's size rounds to zero at the asset's size decimals
+    ///
+    /// Whole-unit assets (`sz_decimals = 0`) truncate a size of 0.7 to 0,
+    /// which the exchange rejects. The error names the most levels whose
+    /// sizes survive rounding, or asks for more size when no level count does.
+    pub fn check_size_precision(
+        &self,
+        sizing: SizingMode,
+        order_size: Option<f64>,
+        notional: Option<f64>,
+        precision: &AssetPrecision,
+    ) -> Result<(), Error> {
+        let smallest = self.min_order_size(sizing, order_size, notional);
+        if precision.round_size(smallest) > 0.0 {
+            return Ok(());
+        }
+
+        let fits = (2..self.grid_levels).rev().find(|&grid_levels| {
+            let fewer = Self {
+                grid_levels,
+                ..*self
+            };
+            precision.round_size(fewer.min_order_size(sizing, order_size, notional)) > 0.0
+        });
+        let advice = match fits {
+            Some(levels) => format!("use at most {levels} grid levels"),
+            None => "raise order_size or total_investment".to_string(),
+        };
+        Err(Error::GenericParse(format!(
+            "grid orders of {smallest} round to 0 at {} size decimals; {advice}",
+            precision.sz_decimals
+        )))
+    }
+
+    /// Fail unless `sizes` gives every zone, lowest first, a positive size
+    /// whose order is worth at least `min_notional`
+    pub fn check_level_sizes(&self, sizes: &[f64], min_notional: f64) -> Result<(), Error> {
+        let num_zones = self.grid_levels.saturating_sub(1);
+        if sizes.len() != num_zones {
+            return Err(Error::GenericParse(format!(
+                "level_sizes has {} entries but the grid has {} levels between its {} lines",
+                sizes.len(),
+                num_zones,
+                self.grid_levels
+            )));
+        }
+        if let Some(i) = sizes.iter().position(|s| !(s.is_finite() && *s > 0.0)) {
+            return Err(Error::GenericParse(format!(
+                "level_sizes[{}] is {}, sizes must be positive",
+                i, sizes[i]
+            )));
+        }
+        let prices = self.level_prices();
+        if let Some((i, value)) = sizes
+            .iter()
+            .zip(&prices)
+            .map(|(size, price)| size * price)
+            .enumerate()
+            .find(|(_, value)| *value < min_notional)
+        {
+            return Err(Error::GenericParse(format!(
+                "level_sizes[{i}] is worth {value:.2}, below the {min_notional} minimum order value"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Per-zone sizes from the `level_sizes` param, if set
+    pub fn level_sizes_from_params(params: &HashMap<String, Value>) -> Option<Vec<f64>> {
+        params
+            .get("level_sizes")?
+            .as_array()?
+            .iter()
+            .map(|v| v.as_f64())
+            .collect()
+    }
+}
+
+/// Length of a Hyperliquid candle interval in milliseconds
+fn interval_millis(interval: &str) -> Option<u64> {
+    let (count, unit) = interval.split_at(interval.len().checked_sub(1)?);
+    let unit_ms = match unit {
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        "w" => 7 * 86_400_000,
+        "M" => 30 * 86_400_000,
+        _ => return None,
+    };
+    Some(count.parse::<u64>().ok()? * unit_ms)
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -46,6 +495,47 @@ struct GridZone {
 
     /// The Active Order ID for this zone
     order_id: Option<u64>,
+    /// Rejections of this zone's order since its last fill
+    rejections: u32,
+    /// Quantity, quote value and fees filled so far on the active order
+    filled_qty: f64,
+    filled_value: f64,
+    filled_fee: f64,
+    /// Fees paid on the buy that opened the current position
+    entry_fee: f64,
+    /// Counter-order held back until this Unix time (seconds) after a fill
+    refill_at: Option<u64>,
+    /// No order yet: a sell waits for the initial buy, a buy for price to
+    /// trade above its level
+    parked: bool,
+    /// Sell held back because the account's base balance doesn't cover it
+    awaiting_base: bool,
+    /// Opening order held back while it would take the position past
+    /// `max_position`
+    capped: bool,
+    /// Side that opens the zone's position: a buy for long zones, a sell for
+    /// short ones. The other side closes it.
+    opening: OrderSide,
+}
+
+impl GridZone {
+    /// Side of the order the zone's state calls for
+    fn waiting_side(&self) -> OrderSide {
+        match self.state {
+            ZoneState::WaitingBuy => OrderSide::Buy,
+            ZoneState::WaitingSell => OrderSide::Sell,
+        }
+    }
+}
+
+/// The order buying a grid's starting inventory
+#[derive(Debug, Clone)]
+struct Acquisition {
+    qty: f64,
+    order_id: Option<u64>,
+    filled_qty: f64,
+    filled_value: f64,
+    filled_fee: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,17 +547,165 @@ pub struct RoundTrip {
     pub side: String, // "Long" or "Short"
     pub size: f64,
     pub pnl: f64,
+    /// Fees paid on both legs
+    #[serde(default)]
+    pub fees: f64,
     pub entry_lvl: usize,
     pub exit_lvl: usize,
 }
 
+/// Running aggregates over every closed roundtrip
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RoundtripStats {
+    pub count: u32,
+    /// Roundtrips that made money after fees
+    pub wins: u32,
+    /// Roundtrips that lost money after fees
+    pub losses: u32,
+    pub total_pnl: f64,
+    pub total_fees: f64,
+    /// Best and worst net PnL of a single roundtrip
+    pub best: f64,
+    pub worst: f64,
+}
+
+/// Lifetime totals a grid carries across restarts
+///
+/// Zones and orders are rebuilt on every start; only the PnL they realized
+/// is kept, so the dashboard shows lifetime rather than session figures.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct GridState {
+    pub realized_pnl: f64,
+    pub total_fees: f64,
+    pub roundtrip_stats: RoundtripStats,
+}
+
+/// `RoundtripStats` with its averages worked out, as the dashboard shows them
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RoundtripSummary {
+    #[serde(flatten)]
+    pub stats: RoundtripStats,
+    pub win_rate: f64,
+    pub avg_pnl: f64,
+    pub avg_fees: f64,
+    pub avg_net_pnl: f64,
+}
+
+impl From<RoundtripStats> for RoundtripSummary {
+    fn from(stats: RoundtripStats) -> Self {
+        Self {
+            stats,
+            win_rate: stats.win_rate(),
+            avg_pnl: stats.avg_pnl(),
+            avg_fees: stats.avg_fees(),
+            avg_net_pnl: stats.avg_net_pnl(),
+        }
+    }
+}
+
+/// Grid-specific part of the spot grid's `StrategyStatus`, sent as `custom`
+///
+/// Price, position, counts and the level book live on `StrategyStatus` itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusSnapshot {
+    /// Number of grid lines
+    pub levels: usize,
+    pub lower_price: f64,
+    pub upper_price: f64,
+    pub grid_type: GridMode,
+    pub sizing_mode: SizingMode,
+    /// Open PnL of the positions held by the zones
+    pub unmatched_pnl: f64,
+    /// Quote tied up in held positions and resting opening orders
+    pub invested_value: f64,
+    /// Base size of the first zone
+    pub qty_order: f64,
+    pub leverage: f64,
+    pub direction: GridDirection,
+    /// Margin tied up at that leverage
+    pub margin_used: f64,
+    pub recent_trades: Vec<TradeRecord>,
+    pub roundtrips: Vec<RoundTrip>,
+    pub roundtrip_stats: RoundtripSummary,
+    pub asset_precision: AssetPrecision,
+    /// Side price left the range through, while every level waits on one side
+    #[serde(default)]
+    pub range_exit: Option<RangeDirection>,
+    /// Largest position, long or short, the grid may build
+    #[serde(default)]
+    pub max_position: Option<f64>,
+    /// How much further the position may grow before opening orders are
+    /// held back
+    #[serde(default)]
+    pub position_capacity: Option<f64>,
+}
+
+impl RoundtripStats {
+    /// Add a closed roundtrip with its gross `pnl` and the `fees` paid on it
+    pub fn record(&mut self, pnl: f64, fees: f64) {
+        let net = pnl - fees;
+        if self.count == 0 {
+            self.best = net;
+            self.worst = net;
+        } else {
+            self.best = self.best.max(net);
+            self.worst = self.worst.min(net);
+        }
+        self.count += 1;
+        if net > 0.0 {
+            self.wins += 1;
+        } else if net < 0.0 {
+            self.losses += 1;
+        }
+        self.total_pnl += pnl;
+        self.total_fees += fees;
+    }
+
+    /// Share of roundtrips that were winners, from 0 to 1
+    pub fn win_rate(&self) -> f64 {
+        self.per_roundtrip(self.wins as f64)
+    }
+
+    /// Average gross PnL per roundtrip
+    pub fn avg_pnl(&self) -> f64 {
+        self.per_roundtrip(self.total_pnl)
+    }
+
+    /// Average fees paid per roundtrip
+    pub fn avg_fees(&self) -> f64 {
+        self.per_roundtrip(self.total_fees)
+    }
+
+    /// Average PnL per roundtrip once fees are taken out
+    pub fn avg_net_pnl(&self) -> f64 {
+        self.per_roundtrip(self.total_pnl - self.total_fees)
+    }
+
+    fn per_roundtrip(&self, total: f64) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            total / self.count as f64
+        }
+    }
+}
+
+/// Grid of zones between adjacent price levels, each holding one order
+///
+/// A zone buys at its lower level and sells at its upper one (the reverse
+/// for short zones), so the counter-order for a fill always goes on the
+/// adjacent level: the classic `level ± 1` grid. Only the closing fill
+/// realizes PnL, measured against the opening fill's price in that zone.
 pub struct SpotGridStrategy {
     asset: String,
     lower_price: f64,
     upper_price: f64,
     grid_levels: usize, // Number of "lines". Zones = grid_levels - 1
     mode: GridMode,
+    sizing: SizingMode,
     precision: AssetPrecision,
+    /// Time in force of every grid order
+    tif: TimeInForce,
 
     /// User can provide either order_size (fixed base qty) OR total_investment (quote qty)
     order_size: Option<f64>,
@@ -76,8 +714,15 @@ pub struct SpotGridStrategy {
     zones: Vec<GridZone>,
     /// Map order_id -> zone_index
     active_orders: HashMap<u64, usize>,
+    /// Last order id handed out; ids count up from the configured base
+    ///
+    /// They repeat across restarts; the live market keeps their cloids apart
+    /// with a prefix drawn per run.
+    next_order_id: u64,
 
     initialized: bool,
+    /// Started outside the price range: no orders until price enters it
+    waiting_for_entry: bool,
     position: f64,
     realized_pnl: f64,
     trade_count: u32,
@@ -87,11 +732,53 @@ pub struct SpotGridStrategy {
     recent_trades: VecDeque<TradeRecord>,
 
     completed_roundtrips: VecDeque<RoundTrip>,
+    roundtrip_stats: RoundtripStats,
+    /// Roundtrips closed in earlier runs, restored from a `GridState`
+    prior_roundtrips: u32,
+    /// Events not yet collected by `drain_events`
+    events: Vec<StrategyEvent>,
 
     /// Initial price used to determine buy/sell sides
     initial_price: f64,
     /// Last seen market price (for dashboard)
     last_price: f64,
+
+    /// Relative distance between adjacent geometric levels
+    step: f64,
+    /// Infinite mode: stop adding levels at this many lines
+    max_levels: Option<usize>,
+    /// Infinite mode: stop adding levels once this much quote is committed
+    max_capital: Option<f64>,
+    /// Hold back opening orders that could take the position past this size
+    max_position: Option<f64>,
+    /// Base size of each zone, lowest first, instead of the uniform sizing
+    level_sizes: Option<Vec<f64>>,
+    /// Seconds a level waits after a fill before its counter-order is placed
+    min_refill_secs: u64,
+    /// Time of the last tick, in Unix seconds
+    last_tick: u64,
+    /// Re-center the range on the price this often; 0 never does
+    rebalance_interval_secs: u64,
+    /// Time of the last rebalance (or the first tick), in Unix seconds
+    last_rebalance: u64,
+    /// Orders to cancel without a replacement, for `drain_cancels`
+    cancels: Vec<u64>,
+    /// Side price left the range through, once every level waits on it
+    range_exit: Option<RangeDirection>,
+    /// Check the levels against the exchange's open orders this often; 0 never does
+    audit_interval_secs: u64,
+    /// Time of the last audit (or the first tick), in Unix seconds
+    last_audit: u64,
+    /// An audit is due and waits for the exchange's open orders
+    audit_requested: bool,
+    initial_position: InitialPosition,
+    /// Initial buy still to be placed or filled
+    acquisition: Option<Acquisition>,
+    /// Perp leverage: `total_investment` is margin and orders tie up
+    /// notional / leverage of it. Always 1 for spot.
+    leverage: f64,
+    /// Always `Long` for spot and infinite grids
+    direction: GridDirection,
 }
 
 impl SpotGridStrategy {
@@ -113,25 +800,204 @@ impl SpotGridStrategy {
             upper_price,
             grid_levels,
             mode,
+            sizing: SizingMode::default_for(mode),
             precision,
+            tif: TimeInForce::Gtc,
             order_size,
             total_investment,
             zones: Vec::new(),
             active_orders: HashMap::new(),
+            next_order_id: 0,
             initialized: false,
+            waiting_for_entry: false,
             position: 0.0,
             realized_pnl: 0.0,
             trade_count: 0,
             total_fees: 0.0,
             recent_trades: VecDeque::with_capacity(50),
             completed_roundtrips: VecDeque::with_capacity(50),
+            roundtrip_stats: RoundtripStats::default(),
+            prior_roundtrips: 0,
+            events: Vec::new(),
             initial_price,
             last_price: initial_price,
+            step: 0.0,
+            max_levels: None,
+            max_capital: None,
+            max_position: None,
+            level_sizes: None,
+            min_refill_secs: 0,
+            last_tick: 0,
+            rebalance_interval_secs: 0,
+            last_rebalance: 0,
+            cancels: Vec::new(),
+            range_exit: None,
+            audit_interval_secs: 0,
+            last_audit: 0,
+            audit_requested: false,
+            initial_position: InitialPosition::Existing,
+            acquisition: None,
+            leverage: 1.0,
+            direction: GridDirection::Long,
         };
         strategy.initialize_zones();
+        if !strategy.in_range(initial_price) {
+            info!(
+                "Initial price {} outside grid range {} - {}, waiting for entry",
+                initial_price, lower_price, upper_price
+            );
+            strategy.waiting_for_entry = true;
+        }
         strategy
     }
 
+    fn in_range(&self, price: f64) -> bool {
+        price >= self.lower_price && price <= self.upper_price
+    }
+
+    /// Override how zone sizes are derived from `total_investment`
+    pub fn with_sizing_mode(mut self, sizing: SizingMode) -> Self {
+        self.sizing = sizing;
+        self.initialize_zones();
+        self
+    }
+
+    /// Place grid orders with this time in force instead of Gtc
+    pub fn with_tif(mut self, tif: TimeInForce) -> Self {
+        self.tif = tif;
+        self
+    }
+
+    /// Start order ids above `base`, so strategies sharing a market never
+    /// hand out the same id
+    pub fn with_id_base(mut self, base: u64) -> Self {
+        self.next_order_id = base;
+        self
+    }
+
+    /// Choose how the base for zones above the start price is obtained
+    pub fn with_initial_position(mut self, method: InitialPosition) -> Self {
+        self.initial_position = method;
+        self.initialize_zones();
+        self
+    }
+
+    /// Trade a perp grid at `leverage`, sizing `total_investment` as margin
+    pub fn with_leverage(mut self, leverage: f64) -> Self {
+        if MarketType::of(&self.asset) == MarketType::Spot {
+            if leverage != 1.0 {
+                warn!("Spot grids can't use leverage, ignoring {}x", leverage);
+            }
+            return self;
+        }
+        if leverage < 1.0 || !leverage.is_finite() {
+            warn!("Invalid leverage {}, using 1x", leverage);
+            return self;
+        }
+        self.leverage = leverage;
+        self.initialize_zones();
+        self
+    }
+
+    /// Restrict a perp grid to long or short positions, or run it neutral
+    ///
+    /// Orders that close a zone are reduce-only on long and short grids, so
+    /// they can never open the opposite position.
+    pub fn with_direction(mut self, direction: GridDirection) -> Self {
+        if direction == GridDirection::Long {
+            return self;
+        }
+        if MarketType::of(&self.asset) == MarketType::Spot {
+            warn!("Spot grids can only be long, ignoring {:?}", direction);
+            return self;
+        }
+        if self.mode == GridMode::Infinite {
+            warn!("Infinite grids can only be long, ignoring {:?}", direction);
+            return self;
+        }
+        self.direction = direction;
+        self.initialize_zones();
+        self
+    }
+
+    /// Hold each counter-order back for `secs` after its level fills, so a
+    /// choppy market can't churn one level over and over
+    pub fn with_min_refill_secs(mut self, secs: u64) -> Self {
+        self.min_refill_secs = secs;
+        self
+    }
+
+    /// Re-center the range on the price every `secs`, keeping its width and
+    /// line count, so the grid follows slow trends
+    ///
+    /// Only long grids with a fixed range can rebalance.
+    pub fn with_rebalance_interval(mut self, secs: u64) -> Self {
+        if secs == 0 {
+            return self;
+        }
+        if self.mode == GridMode::Infinite || self.direction != GridDirection::Long {
+            warn!("Only long grids with a fixed range can rebalance, ignoring interval");
+            return self;
+        }
+        self.rebalance_interval_secs = secs;
+        self
+    }
+
+    /// Check every `secs` that each level has exactly the order its state
+    /// calls for open on the exchange, cancelling extra orders and re-placing
+    /// missing ones
+    pub fn with_audit_interval(mut self, secs: u64) -> Self {
+        self.audit_interval_secs = secs;
+        self
+    }
+
+    /// Cap how far an infinite grid may grow, by line count and committed quote
+    pub fn with_infinite_limits(
+        mut self,
+        max_levels: Option<usize>,
+        max_capital: Option<f64>,
+    ) -> Self {
+        self.max_levels = max_levels;
+        self.max_capital = max_capital;
+        self
+    }
+
+    /// Cap the position, long or short, at `max_position` base
+    ///
+    /// Resting opening orders count against the cap as if filled, so a run
+    /// of fills in one direction can't overshoot it. Opening orders that
+    /// don't fit are held back, nearest the price placed first, until
+    /// closing fills make room.
+    pub fn with_max_position(mut self, max_position: Option<f64>) -> Self {
+        self.max_position = max_position.filter(|max| *max > 0.0);
+        self
+    }
+
+    /// Size each zone, lowest first, from `sizes` instead of spreading the
+    /// order size or investment evenly
+    ///
+    /// Sizes are rounded to the asset's precision. A list whose length isn't
+    /// the zone count, or with a size that isn't positive, is ignored.
+    pub fn with_level_sizes(mut self, sizes: Vec<f64>) -> Self {
+        if let Err(e) = self.grid_config().check_level_sizes(&sizes, 0.0) {
+            error!("Ignoring level sizes: {}", e);
+            return self;
+        }
+        self.level_sizes = Some(sizes);
+        self.initialize_zones();
+        self
+    }
+
+    /// Current range and spacing; infinite grids report what they've grown to
+    fn grid_config(&self) -> GridConfig {
+        GridConfig {
+            lower_price: self.lower_price,
+            upper_price: self.upper_price,
+            grid_levels: self.grid_levels,
+            mode: self.mode,
+        }
+    }
+
     fn initialize_zones(&mut self) {
         if self.grid_levels < 2 {
             warn!("Grid levels must be at least 2 (to form 1 zone)");
@@ -140,68 +1006,113 @@ impl SpotGridStrategy {
 
         self.zones.clear();
         self.active_orders.clear();
+        self.position = 0.0;
+        self.acquisition = None;
+
+        // Levels that collapse at the tick size would make zero-width zones
+        if let Err(e) = self.grid_config().check_precision(&self.precision) {
+            let Some(levels) = self.grid_config().max_levels_at(&self.precision) else {
+                error!("{}; no zones created", e);
+                return;
+            };
+            warn!("{}; using {} levels", e, levels);
+            self.grid_levels = levels;
+        }
 
-        // Generate Price Lines first
-        let mut prices = Vec::with_capacity(self.grid_levels);
-        match self.mode {
-            GridMode::Arithmetic => {
-                let step = (self.upper_price - self.lower_price) / (self.grid_levels as f64 - 1.0);
-                for i in 0..self.grid_levels {
-                    let mut price = self.lower_price + (i as f64 * step);
-                    price = self.precision.round_price(price, false);
-                    prices.push(price);
-                }
-            }
-            GridMode::Geometric => {
-                let ratio = (self.upper_price / self.lower_price)
-                    .powf(1.0 / (self.grid_levels as f64 - 1.0));
-                for i in 0..self.grid_levels {
-                    let mut price = self.lower_price * ratio.powi(i as i32);
-                    price = self.precision.round_price(price, false);
-                    prices.push(price);
-                }
-            }
+        // Generate Price Lines first; each zone rounds its buy and sell side separately
+        let prices = self.grid_config().level_prices();
+        if self.mode != GridMode::Arithmetic {
+            self.step = prices[1] / prices[0] - 1.0;
         }
 
         // Create Zones from adjacent prices
         let num_zones = self.grid_levels - 1;
 
-        let quote_per_zone = self.total_investment.map(|inv| inv / num_zones as f64);
+        // With leverage, total_investment is margin backing `leverage` times the notional
+        let notional = self.total_investment.map(|inv| inv * self.leverage);
+        let quote_per_zone = notional.map(|inv| inv / num_zones as f64);
+        // Constant base: one quantity whose buys across all zones cost total_investment
+        let constant_base_size = notional.map(|inv| {
+            let lower_sum: f64 = prices[..num_zones].iter().sum();
+            inv / lower_sum
+        });
         let fixed_base_size = self.order_size;
 
         for i in 0..num_zones {
-            let lower = prices[i];
-            let upper = prices[i + 1];
-
-            let raw_size = if let Some(q_val) = quote_per_zone {
-                q_val / lower
-            } else {
-                fixed_base_size.unwrap_or(1.0)
+            let lower = self
+                .precision
+                .round_price_for_side(prices[i], OrderSide::Buy);
+            let upper = self
+                .precision
+                .round_price_for_side(prices[i + 1], OrderSide::Sell);
+
+            let level_size = self.level_sizes.as_ref().and_then(|sizes| sizes.get(i));
+            let raw_size = match (level_size, self.sizing, quote_per_zone, constant_base_size) {
+                (Some(&size), ..) => size,
+                (None, SizingMode::ConstantQuote, Some(q_val), _) => q_val / lower,
+                (None, SizingMode::ConstantBase, _, Some(base)) => base,
+                _ => fixed_base_size.unwrap_or(1.0),
             };
             let size = self.precision.round_size(raw_size);
+            if size <= 0.0 && raw_size > 0.0 {
+                error!(
+                    "Zone {:02} size {} rounds to 0 at {} size decimals; its orders will be rejected",
+                    i, raw_size, self.precision.sz_decimals
+                );
+            }
 
             // Determine Initial State
             // - If InitialPrice < Upper: We assume we hold inventory (or are below zone). We want to Sell at Upper.
             // - If InitialPrice >= Upper: We are sold out. We want to Buy at Lower.
 
-            let initial_state = if self.initial_price < upper {
-                ZoneState::WaitingSell
-            } else {
-                ZoneState::WaitingBuy
-            };
+            let (opening, initial_state, entry_price, parked) = match self.direction {
+                GridDirection::Long => {
+                    let holds_base = self.initial_price < upper;
+                    let initial_state =
+                        if holds_base && self.initial_position != InitialPosition::Skip {
+                            ZoneState::WaitingSell
+                        } else {
+                            ZoneState::WaitingBuy
+                        };
 
-            // Initial Entry Price Logic:
-            let entry_price = if initial_state == ZoneState::WaitingSell {
-                self.initial_price
-            } else {
-                0.0
+                    // Initial Entry Price Logic:
+                    let entry_price = if initial_state == ZoneState::WaitingSell {
+                        self.initial_price
+                    } else {
+                        0.0
+                    };
+
+                    // Adjust position tracking; bought inventory is counted once it fills
+                    let parked = match self.initial_position {
+                        InitialPosition::Existing => {
+                            if holds_base {
+                                self.position += size;
+                            }
+                            false
+                        }
+                        InitialPosition::LimitBuy | InitialPosition::MarketBuy => holds_base,
+                        // A buy above the start price would cross the book
+                        InitialPosition::Skip => lower > self.initial_price,
+                    };
+                    (OrderSide::Buy, initial_state, entry_price, parked)
+                }
+                // Flat start; a sell below the start price would cross the book
+                GridDirection::Short => (
+                    OrderSide::Sell,
+                    ZoneState::WaitingSell,
+                    0.0,
+                    upper <= self.initial_price,
+                ),
+                // Flat start: long below the start price, short above it
+                GridDirection::Neutral => {
+                    if lower < self.initial_price {
+                        (OrderSide::Buy, ZoneState::WaitingBuy, 0.0, false)
+                    } else {
+                        (OrderSide::Sell, ZoneState::WaitingSell, 0.0, false)
+                    }
+                }
             };
 
-            // Adjust position tracking
-            if initial_state == ZoneState::WaitingSell {
-                self.position += size;
-            }
-
             self.zones.push(GridZone {
                 index: i,
                 lower_price: lower,
@@ -212,6 +1123,34 @@ impl SpotGridStrategy {
                 total_pnl: 0.0,
                 roundtrip_count: 0,
                 order_id: None,
+                rejections: 0,
+                filled_qty: 0.0,
+                filled_value: 0.0,
+                filled_fee: 0.0,
+                entry_fee: 0.0,
+                refill_at: None,
+                parked,
+                awaiting_base: false,
+                capped: false,
+                opening,
+            });
+        }
+
+        let to_buy: f64 = self
+            .zones
+            .iter()
+            .filter(|z| {
+                z.parked && z.opening == OrderSide::Buy && z.state == ZoneState::WaitingSell
+            })
+            .map(|z| z.size)
+            .sum();
+        if to_buy > 0.0 {
+            self.acquisition = Some(Acquisition {
+                qty: self.precision.round_size(to_buy),
+                order_id: None,
+                filled_qty: 0.0,
+                filled_value: 0.0,
+                filled_fee: 0.0,
             });
         }
 
@@ -219,75 +1158,701 @@ impl SpotGridStrategy {
         self.initialized = true;
     }
 
-    fn generate_order_id() -> u64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_nanos() as u64
+    fn generate_order_id(&mut self) -> u64 {
+        self.next_order_id += 1;
+        self.next_order_id
     }
 
     /// Place orders for all zones based on their current state.
     /// Used during initial setup.
     fn refresh_orders(&mut self) -> Vec<OrderRequest> {
         let mut orders = vec![];
-        let asset = self.asset.clone();
-
-        for i in 0..self.zones.len() {
-            let zone = &mut self.zones[i];
 
-            if zone.order_id.is_none() {
-                let order_id = Self::generate_order_id();
-
-                let (price, side) = match zone.state {
-                    ZoneState::WaitingBuy => (zone.lower_price, OrderSide::Buy),
-                    ZoneState::WaitingSell => (zone.upper_price, OrderSide::Sell),
-                };
+        if let Some(order) = self.place_acquisition() {
+            orders.push(order);
+        }
 
-                let req = if side == OrderSide::Buy {
-                    OrderRequest::buy(order_id, &asset, zone.size, price)
-                } else {
-                    OrderRequest::sell(order_id, &asset, zone.size, price)
-                };
+        for i in self.zones_by_distance() {
+            let zone = &self.zones[i];
 
-                zone.order_id = Some(order_id);
-                self.active_orders.insert(order_id, i);
-                orders.push(req);
+            if zone.order_id.is_none() && !zone.parked && !zone.awaiting_base {
+                orders.extend(self.place_capped_order(i));
             }
         }
 
         orders
     }
-}
 
-impl Strategy for SpotGridStrategy {
-    fn on_price_update(&mut self, asset: &str, price: f64) -> Vec<OrderRequest> {
-        if asset != self.asset {
-            return vec![];
+    /// Zone indices, nearest the last price first once a position cap has
+    /// to share out, in grid order otherwise
+    fn zones_by_distance(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..self.zones.len()).collect();
+        if self.max_position.is_some() {
+            let distance = |z: &GridZone| {
+                let price = match z.state {
+                    ZoneState::WaitingBuy => z.lower_price,
+                    ZoneState::WaitingSell => z.upper_price,
+                };
+                (price - self.last_price).abs()
+            };
+            indices.sort_by(|a, b| distance(&self.zones[*a]).total_cmp(&distance(&self.zones[*b])));
         }
+        indices
+    }
 
-        self.last_price = price;
+    /// Position the grid would hold on `side` if every resting order opening
+    /// on that side filled
+    fn exposure(&self, side: OrderSide) -> f64 {
+        let held = match side {
+            OrderSide::Buy => self.position,
+            OrderSide::Sell => -self.position,
+        };
+        let resting: f64 = self
+            .zones
+            .iter()
+            .filter(|z| z.order_id.is_some() && z.opening == side && z.waiting_side() == side)
+            .map(|z| z.size - z.filled_qty)
+            .sum();
+        held.max(0.0) + resting
+    }
 
-        // Initial Placement
-        if self.initialized && self.active_orders.is_empty() && self.trade_count == 0 {
-            return self.refresh_orders();
+    /// Base the position may still grow by, if it is capped
+    fn position_capacity(&self) -> Option<f64> {
+        self.max_position.map(|max| {
+            let used = self
+                .exposure(OrderSide::Buy)
+                .max(self.exposure(OrderSide::Sell));
+            (max - used).max(0.0)
+        })
+    }
+
+    /// Place a zone's order unless it opens a position `max_position` has
+    /// no room for, in which case the zone is held back
+    fn place_capped_order(&mut self, zone_idx: usize) -> Option<OrderRequest> {
+        let zone = &self.zones[zone_idx];
+        let side = zone.waiting_side();
+        if let Some(max) = self.max_position {
+            let size_tolerance = 0.5 / 10f64.powi(self.precision.sz_decimals as i32);
+            if side == zone.opening && self.exposure(side) + zone.size > max + size_tolerance {
+                if !zone.capped {
+                    info!(
+                        "Zone {:02} {:?} held back, position would exceed max {}",
+                        zone_idx, side, max
+                    );
+                }
+                let zone = &mut self.zones[zone_idx];
+                zone.capped = true;
+                if let Some(id) = zone.order_id.take() {
+                    if self.active_orders.remove(&id).is_some() {
+                        self.cancels.push(id);
+                    }
+                }
+                return None;
+            }
         }
+        self.zones[zone_idx].capped = false;
+        Some(self.place_zone_order(zone_idx))
+    }
 
-        vec![]
+    /// Place the held-back opening orders the position cap has room for again
+    fn release_capped_orders(&mut self) -> Vec<OrderRequest> {
+        let capped: Vec<usize> = self
+            .zones_by_distance()
+            .into_iter()
+            .filter(|&i| self.zones[i].capped)
+            .collect();
+        capped
+            .into_iter()
+            .filter_map(|zone_idx| self.place_capped_order(zone_idx))
+            .collect()
     }
 
-    fn on_order_filled(&mut self, fill: &OrderFill) -> Vec<OrderRequest> {
-        let mut orders = vec![];
-        let p_dec = self.precision.price_decimals as usize;
-        let s_dec = self.precision.sz_decimals as usize;
+    /// Rebuild the grid around the last price with the same width and lines
+    ///
+    /// Base held by the old zones moves to the new zones just above the
+    /// price, at its average entry. Each new order replaces the resting order
+    /// nearest its price on the same side; resting orders left over are
+    /// cancelled. Realized PnL, fees and roundtrips carry over.
+    fn rebalance(&mut self) -> Vec<OrderRequest> {
+        let mid = self.last_price;
+        let (lower, upper) = if self.mode == GridMode::Arithmetic {
+            let half_width = (self.upper_price - self.lower_price) / 2.0;
+            (mid - half_width, mid + half_width)
+        } else {
+            let half_ratio = (self.upper_price / self.lower_price).sqrt();
+            (mid / half_ratio, mid * half_ratio)
+        };
+        if lower <= 0.0 {
+            warn!(
+                "Can't rebalance around {}, the range would go below zero",
+                mid
+            );
+            return vec![];
+        }
 
-        if let Some(zone_idx) = self.active_orders.remove(&fill.order_id) {
-            let zone = &mut self.zones[zone_idx];
+        let mut old_orders: Vec<(u64, OrderSide, f64)> = self
+            .active_orders
+            .iter()
+            .map(|(id, zone_idx)| {
+                let zone = &self.zones[*zone_idx];
+                let side = zone.waiting_side();
+                let price = match side {
+                    OrderSide::Buy => zone.lower_price,
+                    OrderSide::Sell => zone.upper_price,
+                };
+                (*id, side, price)
+            })
+            .collect();
+        old_orders.sort_unstable_by_key(|(id, ..)| *id);
+        let held: Vec<&GridZone> = self
+            .zones
+            .iter()
+            .filter(|z| z.state == ZoneState::WaitingSell && !z.parked)
+            .collect();
+        let held_qty: f64 = held.iter().map(|z| z.size).sum();
+        let held_cost: f64 = held.iter().map(|z| z.size * z.entry_price).sum();
+        let held_fee: f64 = held.iter().map(|z| z.entry_fee).sum();
+        let position = self.position;
+
+        info!(
+            "Rebalancing grid {} - {} around {}: {} - {}",
+            self.lower_price, self.upper_price, mid, lower, upper
+        );
+        self.lower_price = lower;
+        self.upper_price = upper;
+        self.initial_price = mid;
+        self.waiting_for_entry = false;
+        // Start flat, then hand the held base to the zones above the price
+        let initial_position = std::mem::replace(&mut self.initial_position, InitialPosition::Skip);
+        self.initialize_zones();
+        self.initial_position = initial_position;
+        self.position = position;
+
+        let size_tolerance = 0.5 / 10f64.powi(self.precision.sz_decimals as i32);
+        let mut remaining = held_qty;
+        for zone in self.zones.iter_mut().filter(|z| z.upper_price > mid) {
+            if remaining < zone.size - size_tolerance {
+                break;
+            }
+            remaining -= zone.size;
+            zone.state = ZoneState::WaitingSell;
+            zone.parked = false;
+            zone.entry_price = held_cost / held_qty;
+            zone.entry_fee = held_fee * zone.size / held_qty;
+        }
+        if remaining > size_tolerance {
+            warn!(
+                "Rebalance: {} base held doesn't fill a zone and has no sell",
+                remaining
+            );
+        }
+
+        // A modify never changes side: a buy only takes over a resting buy
+        let mut orders = self.refresh_orders();
+        for order in orders.iter_mut() {
+            if self.is_acquisition(order.order_id) {
+                continue;
+            }
+            let distance = |price: f64| (price - order.limit_price).abs();
+            let nearest = old_orders
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, side, _))| *side == order.side)
+                .min_by(|(_, a), (_, b)| distance(a.2).total_cmp(&distance(b.2)))
+                .map(|(i, _)| i);
+            if let Some(i) = nearest {
+                order.replaces = Some(old_orders.remove(i).0);
+            }
+        }
+        self.cancels
+            .extend(old_orders.into_iter().map(|(id, ..)| id));
+        orders
+    }
+
+    /// Heal drift between the levels and the orders open on the exchange
+    ///
+    /// An open order no level points to is a duplicate or a leftover and is
+    /// cancelled. A level that should rest an order but has none open gets
+    /// one. Levels cooling down, holding for base or given up after
+    /// rejections are left alone, as is the grid while its initial orders
+    /// aren't out yet.
+    fn self_audit(&mut self, open: &[u64]) -> Vec<OrderRequest> {
+        let placed = !self.active_orders.is_empty() || self.trade_count > 0;
+        if !self.initialized || self.waiting_for_entry || self.acquisition.is_some() || !placed {
+            return vec![];
+        }
+
+        let mut strays: Vec<u64> = open
+            .iter()
+            .copied()
+            .filter(|id| !self.zones.iter().any(|z| z.order_id == Some(*id)))
+            .collect();
+        strays.sort_unstable();
+        for id in strays {
+            match self.active_orders.remove(&id) {
+                Some(zone_idx) => warn!(
+                    "Audit: zone {:02} has a second order {} resting, cancelling it",
+                    zone_idx, id
+                ),
+                None => warn!("Audit: order {} rests for no zone, cancelling it", id),
+            }
+            self.cancels.push(id);
+        }
+
+        let gaps: Vec<usize> = self
+            .zones
+            .iter()
+            .filter(|z| {
+                z.order_id.is_none_or(|id| !open.contains(&id))
+                    && !z.parked
+                    && !z.awaiting_base
+                    && z.refill_at.is_none()
+                    && !z.capped
+                    && z.rejections < MAX_REJECT_RETRIES
+            })
+            .map(|z| z.index)
+            .collect();
+        gaps.into_iter()
+            .filter_map(|zone_idx| {
+                warn!(
+                    "Audit: zone {:02} has no order resting, placing one",
+                    zone_idx
+                );
+                if let Some(id) = self.zones[zone_idx].order_id.take() {
+                    self.active_orders.remove(&id);
+                }
+                self.place_capped_order(zone_idx)
+            })
+            .collect()
+    }
+
+    /// Raise `RangeExited` once price is past the range with every zone
+    /// waiting on that side, and `RangeReentered` once that's over
+    ///
+    /// All zones waiting to buy above the top means the grid has sold out;
+    /// all waiting to sell below the bottom means it is fully bought.
+    fn track_range_exit(&mut self) {
+        let all = |state| !self.zones.is_empty() && self.zones.iter().all(|z| z.state == state);
+        let exit = if self.waiting_for_entry {
+            None
+        } else if self.last_price > self.upper_price && all(ZoneState::WaitingBuy) {
+            Some(RangeDirection::Above)
+        } else if self.last_price < self.lower_price && all(ZoneState::WaitingSell) {
+            Some(RangeDirection::Below)
+        } else {
+            None
+        };
+        if exit == self.range_exit {
+            return;
+        }
+        self.range_exit = exit;
+        match exit {
+            Some(direction) => {
+                warn!(
+                    "Price {} left the grid range {} - {} ({:?}), every level waits on one side",
+                    self.last_price, self.lower_price, self.upper_price, direction
+                );
+                self.events.push(StrategyEvent::RangeExited { direction });
+            }
+            None => {
+                info!("Price {} is back in the grid range", self.last_price);
+                self.events.push(StrategyEvent::RangeReentered);
+            }
+        }
+    }
+
+    /// Create the initial buy for the sell zones' inventory, if one is due
+    fn place_acquisition(&mut self) -> Option<OrderRequest> {
+        let due = self
+            .acquisition
+            .as_ref()
+            .is_some_and(|a| a.order_id.is_none() && a.qty > 0.0);
+        if !due {
+            return None;
+        }
+        let order_id = self.generate_order_id();
+        let acquisition = self.acquisition.as_mut()?;
+        acquisition.order_id = Some(order_id);
+
+        let order = match self.initial_position {
+            InitialPosition::MarketBuy => {
+                let price = self
+                    .precision
+                    .round_price(self.initial_price * (1.0 + MARKET_BUY_SLIPPAGE), true);
+                OrderRequest::buy(order_id, &self.asset, acquisition.qty, price)
+                    .tif(TimeInForce::Ioc)
+            }
+            _ => {
+                let price = self
+                    .precision
+                    .round_price_for_side(self.initial_price, OrderSide::Buy);
+                OrderRequest::buy(order_id, &self.asset, acquisition.qty, price)
+            }
+        };
+        info!(
+            "Buying {} {} @ {} for the grid's sell zones",
+            order.qty, self.asset, order.limit_price
+        );
+        Some(order)
+    }
+
+    fn is_acquisition(&self, order_id: u64) -> bool {
+        self.acquisition
+            .as_ref()
+            .is_some_and(|a| a.order_id == Some(order_id))
+    }
+
+    /// Arm the parked sell zones the initial buy covered and start the rest
+    /// out buying instead
+    fn settle_acquisition(&mut self) -> Vec<OrderRequest> {
+        let Some(acquisition) = self.acquisition.take() else {
+            return vec![];
+        };
+        let avg_price = if acquisition.filled_qty > 0.0 {
+            acquisition.filled_value / acquisition.filled_qty
+        } else {
+            0.0
+        };
+        self.total_fees += acquisition.filled_fee;
+
+        let mut remaining = acquisition.filled_qty;
+        let size_tolerance = 0.5 / 10f64.powi(self.precision.sz_decimals as i32);
+        let mut orders = vec![];
+        for i in 0..self.zones.len() {
+            let zone = &mut self.zones[i];
+            if !zone.parked || zone.state != ZoneState::WaitingSell {
+                continue;
+            }
+            if remaining >= zone.size - size_tolerance {
+                remaining -= zone.size;
+                zone.parked = false;
+                zone.entry_price = avg_price;
+                self.position += zone.size;
+                orders.push(self.place_zone_order(i));
+            } else {
+                // Not bought: the zone buys at its lower level once price allows
+                zone.state = ZoneState::WaitingBuy;
+                zone.entry_price = 0.0;
+                if zone.lower_price < self.last_price {
+                    zone.parked = false;
+                    orders.push(self.place_zone_order(i));
+                }
+            }
+        }
+        orders
+    }
+
+    /// Place parked buys once price trades above their level, and a short
+    /// grid's parked sells once it trades below theirs
+    fn arm_parked_orders(&mut self, price: f64) -> Vec<OrderRequest> {
+        let ready: Vec<usize> = self
+            .zones
+            .iter()
+            .filter(|z| {
+                z.parked
+                    && match (z.state, z.opening) {
+                        (ZoneState::WaitingBuy, _) => price > z.lower_price,
+                        // Long zones' parked sells wait for the initial buy instead
+                        (ZoneState::WaitingSell, OrderSide::Sell) => price < z.upper_price,
+                        (ZoneState::WaitingSell, OrderSide::Buy) => false,
+                    }
+            })
+            .map(|z| z.index)
+            .collect();
+        ready
+            .into_iter()
+            .filter_map(|zone_idx| {
+                self.zones[zone_idx].parked = false;
+                self.place_capped_order(zone_idx)
+            })
+            .collect()
+    }
+
+    /// Lifetime PnL, fees and roundtrips, to be restored after a restart
+    pub fn state(&self) -> GridState {
+        GridState {
+            realized_pnl: self.realized_pnl,
+            total_fees: self.total_fees,
+            roundtrip_stats: self.roundtrip_stats,
+        }
+    }
+
+    /// Continue from the totals of an earlier run
+    ///
+    /// Replaces the current totals, so call it before trading. Fills of the
+    /// earlier run's orders match none of this run's and are ignored, which
+    /// keeps them from being counted twice.
+    pub fn restore_state(&mut self, state: GridState) {
+        self.realized_pnl = state.realized_pnl;
+        self.total_fees = state.total_fees;
+        self.roundtrip_stats = state.roundtrip_stats;
+        let session: u32 = self.zones.iter().map(|z| z.roundtrip_count).sum();
+        self.prior_roundtrips = state.roundtrip_stats.count.saturating_sub(session);
+    }
+
+    /// The order each level starts with as `(price, size, side)`, lowest
+    /// level first
+    ///
+    /// Only reads the levels built from the config, precision and initial
+    /// price, so a config can be checked before any order is placed.
+    pub fn preview(&self) -> Vec<(f64, f64, OrderSide)> {
+        self.zones
+            .iter()
+            .map(|zone| {
+                let side = zone.waiting_side();
+                let price = match side {
+                    OrderSide::Buy => zone.lower_price,
+                    OrderSide::Sell => zone.upper_price,
+                };
+                (price, zone.size, side)
+            })
+            .collect()
+    }
+
+    /// Margin the grid ties up at its leverage: notional of the held positions
+    /// at cost plus the orders opening new ones, divided by the leverage
+    pub fn margin_used(&self) -> f64 {
+        self.committed_capital() / self.leverage
+    }
+
+    /// Quote tied up in the grid: held inventory at cost plus resting buys
+    fn committed_capital(&self) -> f64 {
+        self.zones
+            .iter()
+            .map(|zone| match (zone.state, zone.opening) {
+                _ if zone.entry_price > 0.0 => zone.entry_price * zone.size,
+                (ZoneState::WaitingSell, OrderSide::Sell) => zone.upper_price * zone.size,
+                _ => zone.lower_price * zone.size,
+            })
+            .sum()
+    }
+
+    /// Infinite mode: add a level beyond the edge a fill just reached
+    ///
+    /// A buy filled in the bottom zone adds a zone one step below; a sell
+    /// filled in the top zone adds one a step above, which buys back at the
+    /// breakout price and sells a step higher. New zones start out buying.
+    fn extend_infinite_grid(&mut self, zone_idx: usize, side: OrderSide) -> Option<OrderRequest> {
+        let zone = &self.zones[zone_idx];
+        let (lower, upper) = match side {
+            OrderSide::Buy => {
+                let bottom = self
+                    .zones
+                    .iter()
+                    .map(|z| z.lower_price)
+                    .fold(f64::MAX, f64::min);
+                if zone.lower_price > bottom {
+                    return None;
+                }
+                let upper = zone.lower_price;
+                (
+                    self.precision
+                        .round_price_for_side(upper * (1.0 - self.step), OrderSide::Buy),
+                    upper,
+                )
+            }
+            OrderSide::Sell => {
+                let top = self.zones.iter().map(|z| z.upper_price).fold(0.0, f64::max);
+                if zone.upper_price < top {
+                    return None;
+                }
+                let lower = zone.upper_price;
+                (
+                    lower,
+                    self.precision
+                        .round_price_for_side(lower * (1.0 + self.step), OrderSide::Sell),
+                )
+            }
+        };
+
+        if self.max_levels.is_some_and(|max| self.grid_levels >= max) {
+            info!("Infinite grid reached max_levels, not extending");
+            return None;
+        }
+        let size = match self.sizing {
+            SizingMode::ConstantBase => zone.size,
+            SizingMode::ConstantQuote => self
+                .precision
+                .round_size(zone.size * zone.lower_price / lower),
+        };
+        if lower <= 0.0 || size <= 0.0 || upper <= lower {
+            warn!("Infinite grid level {} - {} is not tradable", lower, upper);
+            return None;
+        }
+        if self
+            .max_capital
+            .is_some_and(|max| self.committed_capital() + lower * size > max)
+        {
+            info!("Infinite grid reached max_capital, not extending");
+            return None;
+        }
+
+        let index = self.zones.len();
+        self.zones.push(GridZone {
+            index,
+            lower_price: lower,
+            upper_price: upper,
+            size,
+            state: ZoneState::WaitingBuy,
+            entry_price: 0.0,
+            total_pnl: 0.0,
+            roundtrip_count: 0,
+            order_id: None,
+            rejections: 0,
+            filled_qty: 0.0,
+            filled_value: 0.0,
+            filled_fee: 0.0,
+            entry_fee: 0.0,
+            refill_at: None,
+            parked: false,
+            awaiting_base: false,
+            capped: false,
+            opening: OrderSide::Buy,
+        });
+        self.grid_levels += 1;
+        self.lower_price = self.lower_price.min(lower);
+        self.upper_price = self.upper_price.max(upper);
+        info!(
+            "Infinite grid extended with zone {:02}: {} - {}",
+            index, lower, upper
+        );
+
+        self.place_capped_order(index)
+    }
+
+    /// Create the order a zone's current state calls for and mark it active
+    ///
+    /// An order still resting for the zone is replaced, never left beside the
+    /// new one, so each level has exactly one order on the book.
+    fn place_zone_order(&mut self, zone_idx: usize) -> OrderRequest {
+        let order_id = self.generate_order_id();
+        let zone = &mut self.zones[zone_idx];
+        let replaced = zone
+            .order_id
+            .filter(|id| self.active_orders.remove(id).is_some());
+
+        let side = match zone.state {
+            ZoneState::WaitingBuy => OrderSide::Buy,
+            ZoneState::WaitingSell => OrderSide::Sell,
+        };
+        let mut req = match side {
+            OrderSide::Buy => OrderRequest::buy(order_id, &self.asset, zone.size, zone.lower_price),
+            OrderSide::Sell => {
+                OrderRequest::sell(order_id, &self.asset, zone.size, zone.upper_price)
+            }
+        }
+        .tif(self.tif);
+        // Closing a directional perp zone must never flip the position
+        let is_perp = MarketType::of(&self.asset) == MarketType::Perp;
+        if side != zone.opening && self.direction != GridDirection::Neutral && is_perp {
+            req = req.reduce_only(true);
+        }
+        if let Some(replaced) = replaced {
+            req = req.replacing(replaced);
+        }
+
+        zone.order_id = Some(order_id);
+        zone.filled_qty = 0.0;
+        zone.filled_value = 0.0;
+        zone.filled_fee = 0.0;
+        zone.refill_at = None;
+        self.active_orders.insert(order_id, zone_idx);
+        req
+    }
+}
+
+impl Strategy for SpotGridStrategy {
+    fn on_price_update(&mut self, asset: &str, price: f64) -> Vec<OrderRequest> {
+        if asset != self.asset {
+            return vec![];
+        }
+
+        self.last_price = price;
+        self.track_range_exit();
+
+        if self.waiting_for_entry {
+            if !self.in_range(price) {
+                return vec![];
+            }
+            // Lay the grid out around the entry price, as if started there
+            info!("Price {} entered grid range, starting", price);
+            self.waiting_for_entry = false;
+            self.initial_price = price;
+            self.initialize_zones();
+        }
+
+        // Initial Placement
+        let buying = self
+            .acquisition
+            .as_ref()
+            .is_some_and(|a| a.order_id.is_some());
+        if self.initialized && self.active_orders.is_empty() && self.trade_count == 0 && !buying {
+            return self.refresh_orders();
+        }
+
+        self.arm_parked_orders(price)
+    }
+
+    fn on_order_filled(&mut self, fill: &OrderFill) -> Vec<OrderRequest> {
+        let mut orders = vec![];
+        let p_dec = self.precision.price_decimals as usize;
+        let s_dec = self.precision.sz_decimals as usize;
+
+        if self.is_acquisition(fill.order_id) {
+            let Some(acquisition) = self.acquisition.as_mut() else {
+                return vec![];
+            };
+            acquisition.filled_qty += fill.qty;
+            acquisition.filled_value += fill.value();
+            acquisition.filled_fee += fill.fee;
+            let size_tolerance = 0.5 / 10f64.powi(self.precision.sz_decimals as i32);
+            if acquisition.filled_qty < acquisition.qty - size_tolerance {
+                return vec![];
+            }
+            info!(
+                "Initial buy filled: {:.*} {} @ {:.*}",
+                s_dec,
+                acquisition.filled_qty,
+                self.asset,
+                p_dec,
+                acquisition.filled_value / acquisition.filled_qty
+            );
+            return self.settle_acquisition();
+        }
+
+        if let Some(&zone_idx) = self.active_orders.get(&fill.order_id) {
+            let zone = &mut self.zones[zone_idx];
 
             if zone.order_id != Some(fill.order_id) {
+                self.active_orders.remove(&fill.order_id);
                 warn!("Fill Order ID mismatch for zone {}", zone_idx);
                 return vec![];
             }
 
+            self.total_fees += fill.fee;
+
+            // Partial fill: leave the order resting until the rest of it fills
+            zone.filled_qty += fill.qty;
+            zone.filled_value += fill.value();
+            zone.filled_fee += fill.fee;
+            let size_tolerance = 0.5 / 10f64.powi(self.precision.sz_decimals as i32);
+            if zone.filled_qty < zone.size - size_tolerance {
+                debug!(
+                    "Zone {:02} partially filled {:.*} / {:.*}",
+                    zone_idx, s_dec, zone.filled_qty, s_dec, zone.size
+                );
+                return vec![];
+            }
+
+            // Fully filled: act on the total quantity at its average price
+            let fill = &OrderFill::new(
+                fill.order_id,
+                &fill.asset,
+                zone.filled_qty,
+                zone.filled_value / zone.filled_qty,
+            )
+            .with_time(fill.time_ms);
+            self.active_orders.remove(&fill.order_id);
             zone.order_id = None;
             self.trade_count += 1;
 
@@ -301,10 +1866,15 @@ impl Strategy for SpotGridStrategy {
                 ZoneState::WaitingSell => OrderSide::Sell,
             };
 
-            let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
+            // Stamp trades with the fill's own time; replayed and batched
+            // fills arrive long after they happened
+            let now = match fill.time_ms {
+                0 => SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs(),
+                ms => ms / 1000,
+            };
 
             let current_trade = TradeRecord {
                 price: fill.price,
@@ -325,13 +1895,6 @@ impl Strategy for SpotGridStrategy {
                         "{}Zone {:02} | BUY  | {:.*} | {:.*}   <<< BOUGHT @ Lower{}",
                         green, zone_idx, p_dec, fill.price, s_dec, fill.qty, reset
                     );
-
-                    // For Spot Grid, a Buy is opening/refilling inventory.
-                    // We simply set the entry_price for the subsequent Sell.
-                    // We do NOT count Sell->Buy as a profit cycle (Short PnL) in this mode.
-
-                    // Update entry_price to this Buy Price (Cost Basis)
-                    zone.entry_price = fill.price;
                     zone.state = ZoneState::WaitingSell;
                 }
                 OrderSide::Sell => {
@@ -340,68 +1903,349 @@ impl Strategy for SpotGridStrategy {
                         "{}Zone {:02} | SELL | {:.*} | {:.*}   <<< SOLD @ Upper{}",
                         red, zone_idx, p_dec, fill.price, s_dec, fill.qty, reset
                     );
-
-                    // If we were WaitingSell, we "Closed a Long".
-                    if zone.entry_price > 0.0 {
-                        let pnl = (fill.price - zone.entry_price) * fill.qty;
-                        self.realized_pnl += pnl;
-
-                        // Increment Zone Stats
-                        zone.total_pnl += pnl;
-                        zone.roundtrip_count += 1;
-
-                        let rt = RoundTrip {
-                            entry_time: 0, // Not tracked
-                            exit_time: now,
-                            entry_price: zone.entry_price,
-                            exit_price: fill.price,
-                            side: "Long".to_string(),
-                            size: fill.qty,
-                            pnl,
-                            entry_lvl: zone_idx,
-                            exit_lvl: zone_idx,
-                        };
-                        self.completed_roundtrips.push_front(rt);
-                    }
-
-                    // Reset entry_price to 0.0 as we have sold the position (Spot logic)
-                    zone.entry_price = 0.0;
                     zone.state = ZoneState::WaitingBuy;
                 }
             }
 
-            // PLACE NEW ORDER FOR THIS ZONE
-            let (target_price, target_side) = match zone.state {
-                ZoneState::WaitingBuy => (zone.lower_price, OrderSide::Buy),
-                ZoneState::WaitingSell => (zone.upper_price, OrderSide::Sell),
-            };
+            if side_filled == zone.opening {
+                // Opening the zone's position (a buy for long zones, a sell
+                // for short ones): remember the cost basis for its close
+                zone.entry_price = fill.price;
+                zone.entry_fee = zone.filled_fee;
+            } else {
+                // Closing it completes a roundtrip
+                if zone.entry_price > 0.0 {
+                    let pnl = match zone.opening {
+                        OrderSide::Buy => (fill.price - zone.entry_price) * fill.qty,
+                        OrderSide::Sell => (zone.entry_price - fill.price) * fill.qty,
+                    };
+                    let fees = zone.entry_fee + zone.filled_fee;
+                    self.realized_pnl += pnl;
+                    self.roundtrip_stats.record(pnl, fees);
+
+                    // Increment Zone Stats
+                    zone.total_pnl += pnl;
+                    zone.roundtrip_count += 1;
+
+                    let rt = RoundTrip {
+                        entry_time: 0, // Not tracked
+                        exit_time: now,
+                        entry_price: zone.entry_price,
+                        exit_price: fill.price,
+                        side: match zone.opening {
+                            OrderSide::Buy => "Long".to_string(),
+                            OrderSide::Sell => "Short".to_string(),
+                        },
+                        size: fill.qty,
+                        pnl,
+                        fees,
+                        entry_lvl: zone_idx,
+                        exit_lvl: zone_idx,
+                    };
+                    self.events.push(StrategyEvent::RoundtripClosed(rt.clone()));
+                    self.completed_roundtrips.push_front(rt);
+                }
 
-            let order_id = Self::generate_order_id();
-            let req = if target_side == OrderSide::Buy {
-                OrderRequest::buy(order_id, &self.asset, zone.size, target_price)
+                // The zone is flat again
+                zone.entry_price = 0.0;
+                zone.entry_fee = 0.0;
+            }
+
+            zone.rejections = 0;
+
+            // PLACE NEW ORDER FOR THIS ZONE, unless the level has to cool down first
+            if self.min_refill_secs > 0 {
+                // Follow the tick clock so backtests cool down in candle time
+                let from = if self.last_tick > 0 {
+                    self.last_tick
+                } else {
+                    now
+                };
+                zone.refill_at = Some(from + self.min_refill_secs);
+                debug!(
+                    "Zone {:02} cooling down for {}s before its counter-order",
+                    zone_idx, self.min_refill_secs
+                );
             } else {
-                OrderRequest::sell(order_id, &self.asset, zone.size, target_price)
-            };
+                orders.extend(self.place_capped_order(zone_idx));
+            }
+
+            if self.mode == GridMode::Infinite {
+                orders.extend(self.extend_infinite_grid(zone_idx, side_filled));
+            }
+            orders.extend(self.release_capped_orders());
+            self.track_range_exit();
+        }
+
+        orders
+    }
+
+    fn on_tick(&mut self, now: u64) -> Vec<OrderRequest> {
+        self.last_tick = now;
+        if self.rebalance_interval_secs > 0 {
+            if self.last_rebalance == 0 {
+                self.last_rebalance = now;
+            } else if now >= self.last_rebalance + self.rebalance_interval_secs
+                // Wait for the initial buy and partial fills to finish
+                && self.acquisition.is_none()
+                && self.zones.iter().all(|z| z.filled_qty == 0.0)
+            {
+                self.last_rebalance = now;
+                let orders = self.rebalance();
+                self.track_range_exit();
+                return orders;
+            }
+        }
+        let mut orders = vec![];
+        if self.audit_interval_secs > 0 {
+            if self.last_audit == 0 {
+                self.last_audit = now;
+            } else if now >= self.last_audit + self.audit_interval_secs {
+                self.last_audit = now;
+                self.audit_requested = true;
+            }
+        }
+        let due: Vec<usize> = self
+            .zones
+            .iter()
+            .filter(|z| z.refill_at.is_some_and(|at| now >= at))
+            .map(|z| z.index)
+            .collect();
+        orders.extend(
+            due.into_iter()
+                .filter_map(|zone_idx| self.place_capped_order(zone_idx)),
+        );
+        orders
+    }
+
+    /// Hold back the spot sells the balance doesn't cover, nearest the price
+    /// first, and place held-back sells once it does
+    ///
+    /// Sells already resting are covered first, since the exchange has
+    /// accepted them. Sells still waiting for the initial buy aren't counted.
+    fn on_balance_update(&mut self, asset: &str, base: f64) -> Vec<OrderRequest> {
+        if asset != self.asset || MarketType::of(&self.asset) == MarketType::Perp {
+            return vec![];
+        }
+        let size_tolerance = 0.5 / 10f64.powi(self.precision.sz_decimals as i32);
+        let unplaced_sell =
+            |z: &GridZone| z.state == ZoneState::WaitingSell && z.order_id.is_none() && !z.parked;
+        let resting: f64 = self
+            .zones
+            .iter()
+            .filter(|z| z.state == ZoneState::WaitingSell && z.order_id.is_some())
+            .map(|z| z.size)
+            .sum();
+        let mut available = base - resting;
+
+        let mut orders = vec![];
+        let mut held_back = 0;
+        for i in 0..self.zones.len() {
+            let zone = &mut self.zones[i];
+            if !unplaced_sell(zone) {
+                continue;
+            }
+            if available >= zone.size - size_tolerance {
+                available -= zone.size;
+                if zone.awaiting_base {
+                    zone.awaiting_base = false;
+                    self.position += zone.size;
+                    info!("Zone {:02} sell covered by balance, arming", i);
+                    orders.push(self.place_zone_order(i));
+                }
+            } else {
+                if !zone.awaiting_base {
+                    zone.awaiting_base = true;
+                    self.position -= zone.size;
+                }
+                held_back += 1;
+            }
+        }
+        if held_back > 0 {
+            warn!(
+                "{} {} balance covers only part of the grid's sells, holding back {}",
+                base, self.asset, held_back
+            );
+        }
+        orders
+    }
 
-            zone.order_id = Some(order_id);
-            self.active_orders.insert(order_id, zone_idx);
-            orders.push(req);
+    /// Re-round every level at the new precision and re-place the resting
+    /// orders, along with any left empty after repeated rejections
+    ///
+    /// Fixed grids recompute their levels from the configured range, so a
+    /// too-coarse precision doesn't stick; infinite grids re-round the levels
+    /// they have. Partially filled orders are left resting.
+    fn on_precision_update(&mut self, asset: &str, precision: AssetPrecision) -> Vec<OrderRequest> {
+        if asset != self.asset || precision == self.precision {
+            return vec![];
+        }
+        info!(
+            "{} precision changed from {:?} to {:?}, re-rounding the grid",
+            self.asset, self.precision, precision
+        );
+        self.precision = precision;
+        if !self.initialized {
+            return vec![];
+        }
+        if let Err(e) = self.grid_config().check_precision(&precision) {
+            error!("{}", e);
         }
 
+        let lines = (self.mode != GridMode::Infinite).then(|| self.grid_config().level_prices());
+        let mut orders = vec![];
+        for i in 0..self.zones.len() {
+            let zone = &mut self.zones[i];
+            let (lower, upper) = match &lines {
+                Some(lines) => (lines[i], lines[i + 1]),
+                None => (zone.lower_price, zone.upper_price),
+            };
+            zone.lower_price = precision.round_price_for_side(lower, OrderSide::Buy);
+            zone.upper_price = precision.round_price_for_side(upper, OrderSide::Sell);
+            zone.size = precision.round_size(zone.size);
+            zone.rejections = 0;
+
+            let resting = zone.order_id.is_some() && zone.filled_qty == 0.0;
+            let left_empty = zone.order_id.is_none()
+                && !zone.parked
+                && !zone.awaiting_base
+                && !zone.capped
+                && zone.refill_at.is_none();
+            if resting || left_empty {
+                orders.push(self.place_zone_order(i));
+            }
+        }
         orders
     }
 
+    fn on_order_rejected(&mut self, order_id: u64, reason: &str) -> Vec<OrderRequest> {
+        if self.is_acquisition(order_id) {
+            // An IOC ends here too once the book is exhausted
+            warn!("Initial buy ended ({}), arming what was bought", reason);
+            return self.settle_acquisition();
+        }
+
+        let Some(zone_idx) = self.active_orders.remove(&order_id) else {
+            return vec![];
+        };
+        let zone = &mut self.zones[zone_idx];
+        zone.order_id = None;
+
+        if zone.rejections >= MAX_REJECT_RETRIES {
+            error!(
+                "Zone {:02} order rejected again ({}), leaving level empty",
+                zone_idx, reason
+            );
+            return vec![];
+        }
+        zone.rejections += 1;
+
+        if self.tif == TimeInForce::Alo && reason.contains("Post only") {
+            // The level crossed the book: step one tick away from mid and re-post
+            match zone.state {
+                ZoneState::WaitingBuy => {
+                    zone.lower_price -= self.precision.tick_size(zone.lower_price);
+                    zone.lower_price = self
+                        .precision
+                        .round_price_for_side(zone.lower_price, OrderSide::Buy);
+                }
+                ZoneState::WaitingSell => {
+                    zone.upper_price += self.precision.tick_size(zone.upper_price);
+                    zone.upper_price = self
+                        .precision
+                        .round_price_for_side(zone.upper_price, OrderSide::Sell);
+                }
+            }
+            warn!(
+                "Zone {:02} post-only order would cross ({}), retrying one tick away",
+                zone_idx, reason
+            );
+            return vec![self.place_zone_order(zone_idx)];
+        }
+
+        // Precision errors are the usual cause: re-round before trying again
+        zone.lower_price = self
+            .precision
+            .round_price_for_side(zone.lower_price, OrderSide::Buy);
+        zone.upper_price = self
+            .precision
+            .round_price_for_side(zone.upper_price, OrderSide::Sell);
+        zone.size = self.precision.round_size(zone.size);
+        warn!("Zone {:02} order rejected ({}), retrying", zone_idx, reason);
+
+        vec![self.place_zone_order(zone_idx)]
+    }
+
+    fn drain_events(&mut self) -> Vec<StrategyEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn drain_cancels(&mut self) -> Vec<u64> {
+        std::mem::take(&mut self.cancels)
+    }
+
+    fn take_open_orders_request(&mut self) -> bool {
+        std::mem::take(&mut self.audit_requested)
+    }
+
+    fn on_open_orders(&mut self, open: &[u64]) -> Vec<OrderRequest> {
+        self.self_audit(open)
+    }
+
+    fn checkpoint(&self) -> Option<Value> {
+        serde_json::to_value(self.state()).ok()
+    }
+
+    fn restore(&mut self, checkpoint: Value) {
+        match serde_json::from_value(checkpoint) {
+            Ok(state) => self.restore_state(state),
+            Err(e) => warn!("Ignoring unreadable grid checkpoint: {}", e),
+        }
+    }
+
+    /// Quote for every zone waiting to buy and base for every zone waiting to
+    /// sell, or quote to buy that base when it isn't held yet. Perp grids
+    /// need only the margin for the quote.
+    fn required_capital(&self) -> CapitalRequirement {
+        let acquire_price = match self.initial_position {
+            InitialPosition::MarketBuy => self.initial_price * (1.0 + MARKET_BUY_SLIPPAGE),
+            _ => self.initial_price,
+        };
+        let mut required =
+            self.zones
+                .iter()
+                .fold(CapitalRequirement::default(), |mut total, zone| {
+                    match (zone.state, zone.opening) {
+                        (ZoneState::WaitingBuy, OrderSide::Buy) => {
+                            total.quote += zone.size * zone.lower_price
+                        }
+                        // Short zones: sells open a position, reduce-only buys close it
+                        (ZoneState::WaitingSell, OrderSide::Sell) => {
+                            total.quote += zone.size * zone.upper_price
+                        }
+                        (ZoneState::WaitingBuy, OrderSide::Sell) => {}
+                        // Still to be bought with the initial buy
+                        (ZoneState::WaitingSell, _) if zone.parked => {
+                            total.quote += zone.size * acquire_price
+                        }
+                        (ZoneState::WaitingSell, _) if zone.awaiting_base => {}
+                        (ZoneState::WaitingSell, _) => total.base += zone.size,
+                    }
+                    total
+                });
+        // Perp orders only need margin
+        required.quote /= self.leverage;
+        required
+    }
+
     fn name(&self) -> &str {
         "spot_grid"
     }
 
     fn status(&self) -> StrategyStatus {
-        let mut asks = Vec::new();
-        let mut bids = Vec::new();
-
         let mut unmatched_pnl = 0.0;
         let mut invested_value = 0.0;
-        let mut active_grids = 0;
+        let mut levels = Vec::with_capacity(self.zones.len());
 
         for zone in &self.zones {
             let side = match zone.state {
@@ -410,28 +2254,29 @@ impl Strategy for SpotGridStrategy {
             };
 
             // Calculate Stats
-            match zone.state {
-                ZoneState::WaitingSell => {
-                    // We hold inventory.
-                    // Unmatched PnL = (Current Price - Entry Price) * Size
-                    if self.last_price > 0.0 && zone.entry_price > 0.0 {
-                        unmatched_pnl += (self.last_price - zone.entry_price) * zone.size;
-                    }
-                    // Invested: Value of held token at entry
-                    if zone.entry_price > 0.0 {
-                        invested_value += zone.entry_price * zone.size;
-                    } else {
-                        // Fallback if entry not set (shouldn't happen for active holding)
-                        invested_value += zone.lower_price * zone.size;
-                    }
+            if side != zone.opening {
+                // We hold a position, long or short.
+                // Unmatched PnL = (Current Price - Entry Price) * Size, negated for shorts
+                if self.last_price > 0.0 && zone.entry_price > 0.0 {
+                    let change = self.last_price - zone.entry_price;
+                    unmatched_pnl += match zone.opening {
+                        OrderSide::Buy => change * zone.size,
+                        OrderSide::Sell => -change * zone.size,
+                    };
                 }
-                ZoneState::WaitingBuy => {
-                    // We have open Buy order. Invested = Capital reserved.
+                // Invested: Value of the position at entry
+                if zone.entry_price > 0.0 {
+                    invested_value += zone.entry_price * zone.size;
+                } else {
+                    // Fallback if entry not set (shouldn't happen for active holding)
                     invested_value += zone.lower_price * zone.size;
                 }
-            }
-            if zone.order_id.is_some() {
-                active_grids += 1;
+            } else {
+                // We have an opening order. Invested = Capital reserved at its price.
+                invested_value += match side {
+                    OrderSide::Buy => zone.lower_price,
+                    OrderSide::Sell => zone.upper_price,
+                } * zone.size;
             }
 
             let price = match zone.state {
@@ -445,87 +2290,61 @@ impl Strategy for SpotGridStrategy {
                 0.0
             };
 
-            let item = json!({
-                "level_idx": zone.index,
-                "price": price,
-                "size": zone.size,
-                "dist": dist,
-                "side": side,
-                "has_order": zone.order_id.is_some(),
-                "total_pnl": zone.total_pnl,
-                "roundtrip_count": zone.roundtrip_count
+            levels.push(BookLevel {
+                level_idx: zone.index,
+                price,
+                size: zone.size,
+                dist,
+                side,
+                has_order: zone.order_id.is_some(),
+                total_pnl: zone.total_pnl,
+                roundtrip_count: zone.roundtrip_count,
             });
-
-            match side {
-                OrderSide::Buy => bids.push(item),
-                OrderSide::Sell => asks.push(item),
-            }
         }
 
-        asks.sort_by(|a, b| {
-            let p_a = a["price"].as_f64().unwrap_or(0.0);
-            let p_b = b["price"].as_f64().unwrap_or(0.0);
-            p_b.partial_cmp(&p_a).unwrap() // Descending
-        });
-        bids.sort_by(|a, b| {
-            let p_a = a["price"].as_f64().unwrap_or(0.0);
-            let p_b = b["price"].as_f64().unwrap_or(0.0);
-            p_b.partial_cmp(&p_a).unwrap() // Descending
-        });
-
-        let mut custom = serde_json::Map::new();
-
-        custom.insert("levels".to_string(), json!(self.grid_levels));
-        custom.insert("lower_price".to_string(), json!(self.lower_price));
-        custom.insert("upper_price".to_string(), json!(self.upper_price));
-        custom.insert("current_price".to_string(), json!(self.last_price));
-        custom.insert(
-            "grid_type".to_string(),
-            json!(match self.mode {
-                GridMode::Arithmetic => "Arithmetic",
-                GridMode::Geometric => "Geometric",
-            }),
-        );
-
-        custom.insert("unmatched_pnl".to_string(), json!(unmatched_pnl));
-        custom.insert("invested_value".to_string(), json!(invested_value));
-        custom.insert("active_grids".to_string(), json!(active_grids));
-        // Avg Qty (Take first zone as approx)
-        let qty_order = if !self.zones.is_empty() {
-            self.zones[0].size
-        } else {
-            0.0
+        let snapshot = StatusSnapshot {
+            levels: self.grid_levels,
+            lower_price: self.lower_price,
+            upper_price: self.upper_price,
+            grid_type: self.mode,
+            sizing_mode: self.sizing,
+            unmatched_pnl,
+            invested_value,
+            // Avg Qty (Take first zone as approx)
+            qty_order: self.zones.first().map_or(0.0, |z| z.size),
+            leverage: self.leverage,
+            direction: self.direction,
+            margin_used: self.margin_used(),
+            recent_trades: self.recent_trades.iter().cloned().collect(),
+            roundtrips: self.completed_roundtrips.iter().cloned().collect(),
+            roundtrip_stats: self.roundtrip_stats.into(),
+            asset_precision: self.precision,
+            range_exit: self.range_exit,
+            max_position: self.max_position,
+            position_capacity: self.position_capacity(),
         };
-        custom.insert("qty_order".to_string(), json!(qty_order));
-
-        let total_roundtrips: u32 = self.zones.iter().map(|z| z.roundtrip_count).sum();
-        custom.insert("total_roundtrips".to_string(), json!(total_roundtrips));
-
-        custom.insert(
-            "book".to_string(),
-            json!({
-                "asks": asks,
-                "bids": bids
-            }),
-        );
-
-        if let Ok(trades) = serde_json::to_value(&self.recent_trades) {
-            custom.insert("recent_trades".to_string(), trades);
-        }
-
-        if let Ok(rt) = serde_json::to_value(&self.completed_roundtrips) {
-            custom.insert("roundtrips".to_string(), rt);
-        }
-
-        if let Ok(prec) = serde_json::to_value(&self.precision) {
-            custom.insert("asset_precision".to_string(), prec);
-        }
-
-        StrategyStatus::new("spot_grid", &self.asset)
-            .with_status("Running")
+        let active_orders = self.zones.iter().filter(|z| z.order_id.is_some()).count();
+        let roundtrips: u32 =
+            self.prior_roundtrips + self.zones.iter().map(|z| z.roundtrip_count).sum::<u32>();
+
+        let status = StrategyStatus::new("spot_grid", &self.asset)
+            .with_status(match self.range_exit {
+                _ if self.waiting_for_entry => "WaitingForEntry",
+                Some(RangeDirection::Above) => "RangeExitedAbove",
+                Some(RangeDirection::Below) => "RangeExitedBelow",
+                None => "Running",
+            })
+            .with_price(self.last_price)
             .with_position(self.position)
-            .with_pnl(self.realized_pnl, 0.0, self.total_fees)
-            .with_custom(serde_json::Value::Object(custom))
+            .with_pnl(self.realized_pnl, unmatched_pnl, self.total_fees)
+            .with_active_orders(active_orders)
+            .with_trade_count(roundtrips)
+            .with_book(StatusBook::from_levels(levels))
+            .with_custom(serde_json::to_value(snapshot).unwrap_or_default());
+        match self.total_investment {
+            Some(investment) => status.with_investment(investment),
+            None => status,
+        }
     }
 }
 
@@ -554,13 +2373,17 @@ impl StrategyFactory for SpotGridStrategyFactory {
             .get("grid_mode")
             .and_then(|v| v.as_str())
             .unwrap_or("arithmetic");
-        let mode = match mode_str.to_lowercase().as_str() {
-            "geometric" => GridMode::Geometric,
-            "arithmetic" => GridMode::Arithmetic,
-            _ => {
-                warn!("Unknown grid mode '{}', defaulting to arithmetic", mode_str);
-                GridMode::Arithmetic
-            }
+        let mode = GridMode::parse(mode_str).unwrap_or_else(|| {
+            warn!("Unknown grid mode '{}', defaulting to arithmetic", mode_str);
+            GridMode::Arithmetic
+        });
+
+        let sizing = match params.get("sizing_mode").and_then(|v| v.as_str()) {
+            Some(s) => SizingMode::parse(s).unwrap_or_else(|| {
+                warn!("Unknown sizing mode '{}', using grid default", s);
+                SizingMode::default_for(mode)
+            }),
+            None => SizingMode::default_for(mode),
         };
 
         // Option 1: Explicit order size
@@ -595,19 +2418,81 @@ impl StrategyFactory for SpotGridStrategyFactory {
             max_decimals,
         };
 
+        // Infinite grid growth limits
+        let max_levels = params
+            .get("max_levels")
+            .and_then(|v| v.as_u64())
+            .map(|v| v as usize);
+        let max_capital = params.get("max_capital").and_then(|v| v.as_f64());
+        let max_position = params.get("max_position").and_then(|v| v.as_f64());
+        let level_sizes = GridConfig::level_sizes_from_params(&params);
+        let id_base = params.get("id_base").and_then(|v| v.as_u64()).unwrap_or(0);
+        let min_refill_secs = params
+            .get("min_refill_secs")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+        let rebalance_interval_secs = params
+            .get("rebalance_interval_secs")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+        let audit_interval_secs = params
+            .get("audit_interval_secs")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+
+        let leverage = params
+            .get("leverage")
+            .and_then(|v| v.as_f64())
+            .unwrap_or(1.0);
+
+        let initial_position = match params.get("initial_position").and_then(|v| v.as_str()) {
+            Some(s) => match s.to_lowercase().as_str() {
+                "existing" => InitialPosition::Existing,
+                "limit_buy" => InitialPosition::LimitBuy,
+                "market_buy" => InitialPosition::MarketBuy,
+                "skip" => InitialPosition::Skip,
+                _ => {
+                    warn!("Unknown initial position '{}', assuming it is held", s);
+                    InitialPosition::Existing
+                }
+            },
+            None => InitialPosition::Existing,
+        };
+
+        let direction = match params.get("direction").and_then(|v| v.as_str()) {
+            Some(s) => match s.to_lowercase().as_str() {
+                "long" => GridDirection::Long,
+                "short" => GridDirection::Short,
+                "neutral" => GridDirection::Neutral,
+                _ => {
+                    warn!("Unknown grid direction '{}', defaulting to long", s);
+                    GridDirection::Long
+                }
+            },
+            None => GridDirection::Long,
+        };
+
+        let tif = match params.get("tif").and_then(|v| v.as_str()) {
+            Some(s) => TimeInForce::parse(s).unwrap_or_else(|| {
+                warn!("Unknown tif '{}', defaulting to Gtc", s);
+                TimeInForce::Gtc
+            }),
+            None => TimeInForce::Gtc,
+        };
+
         if lower_price <= 0.0 || upper_price <= lower_price {
             error!("Invalid grid price parameters");
         }
 
         if initial_price <= 0.0 {
-            error!("Initial price must be > 0");
+            warn!("No initial price, the grid starts at the first price inside its range");
         }
 
-        if order_size.is_none() && total_investment.is_none() {
-            error!("Must specify either order_size or total_investment");
+        if order_size.is_none() && total_investment.is_none() && level_sizes.is_none() {
+            error!("Must specify order_size, total_investment or level_sizes");
         }
 
-        Box::new(SpotGridStrategy::new(
+        let strategy = SpotGridStrategy::new(
             asset.to_string(),
             lower_price,
             upper_price,
@@ -617,7 +2502,23 @@ impl StrategyFactory for SpotGridStrategyFactory {
             total_investment,
             precision,
             initial_price,
-        ))
+        )
+        .with_sizing_mode(sizing)
+        .with_infinite_limits(max_levels, max_capital)
+        .with_tif(tif)
+        .with_id_base(id_base)
+        .with_min_refill_secs(min_refill_secs)
+        .with_leverage(leverage)
+        .with_initial_position(initial_position)
+        .with_direction(direction)
+        .with_rebalance_interval(rebalance_interval_secs)
+        .with_audit_interval(audit_interval_secs)
+        .with_max_position(max_position);
+
+        Box::new(match level_sizes {
+            Some(sizes) => strategy.with_level_sizes(sizes),
+            None => strategy,
+        })
     }
 }
 
@@ -681,4 +2582,1383 @@ mod tests {
         let orders = strategy.on_price_update("SOL-USDC", 110.0);
         assert_eq!(orders.len(), 2);
     }
+
+    #[test]
+    fn test_sell_pnl_paired_with_matched_buy() {
+        // Levels 100, 110, 120, 130. Init at 125: zones 0 and 1 wait to buy.
+        let mut strategy = SpotGridStrategy::new(
+            "SOL-USDC".to_string(),
+            100.0,
+            130.0,
+            4,
+            GridMode::Arithmetic,
+            Some(2.0),
+            None,
+            AssetPrecision {
+                sz_decimals: 2,
+                price_decimals: 2,
+                max_decimals: 6,
+            },
+            125.0,
+        );
+        let orders = strategy.on_price_update("SOL-USDC", 125.0);
+        let buy_100 = orders
+            .iter()
+            .find(|o| o.is_buy() && o.limit_price == 100.0)
+            .unwrap()
+            .order_id;
+
+        // Buy at 100 -> counter sell at 110
+        let orders = strategy.on_order_filled(&OrderFill::new(buy_100, "SOL-USDC", 2.0, 100.0));
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].limit_price, 110.0);
+
+        // Sell at 110 -> PnL is measured against the buy at 100, nothing else
+        strategy.on_order_filled(&OrderFill::new(orders[0].order_id, "SOL-USDC", 2.0, 110.0));
+        assert_eq!(strategy.realized_pnl, (110.0 - 100.0) * 2.0);
+        assert_eq!(strategy.zones[0].total_pnl, 20.0);
+        assert_eq!(strategy.zones[1].total_pnl, 0.0);
+    }
+
+    #[test]
+    fn test_restored_state_keeps_accumulating() {
+        let roundtrip = |strategy: &mut SpotGridStrategy| {
+            let orders = strategy.on_price_update("SOL-USDC", 110.0);
+            let buy = orders.iter().find(|o| o.is_buy()).unwrap().order_id;
+            let sell = strategy.on_order_filled(&OrderFill::new(buy, "SOL-USDC", 1.0, 100.0));
+            strategy.on_order_filled(&OrderFill::new(sell[0].order_id, "SOL-USDC", 1.0, 110.0));
+            sell[0].order_id
+        };
+
+        let mut first = create_test_strategy();
+        let old_sell = roundtrip(&mut first);
+        assert_eq!(first.realized_pnl, 10.0);
+        let saved: Value = first.checkpoint().unwrap();
+
+        // After a restart the totals pick up where they left off
+        let mut restarted = create_test_strategy();
+        restarted.restore(saved);
+        assert_eq!(restarted.state(), first.state());
+        roundtrip(&mut restarted);
+        assert_eq!(restarted.realized_pnl, 20.0);
+        assert_eq!(restarted.roundtrip_stats.count, 2);
+        assert_eq!(restarted.status().trade_count, 2);
+
+        // A replayed fill of the earlier run's order is not counted again
+        restarted.on_order_filled(&OrderFill::new(old_sell, "SOL-USDC", 1.0, 110.0));
+        assert_eq!(restarted.realized_pnl, 20.0);
+    }
+
+    #[test]
+    fn test_sell_pnl_uses_actual_buy_fill_price() {
+        let mut strategy = create_test_strategy();
+        let orders = strategy.on_price_update("SOL-USDC", 110.0);
+        let buy = orders.iter().find(|o| o.is_buy()).unwrap().order_id;
+
+        // Buy filled with price improvement below the 100 level
+        let orders = strategy.on_order_filled(&OrderFill::new(buy, "SOL-USDC", 1.0, 99.5));
+        strategy.on_order_filled(&OrderFill::new(orders[0].order_id, "SOL-USDC", 1.0, 110.0));
+
+        assert_eq!(strategy.realized_pnl, 110.0 - 99.5);
+        let rt = strategy.completed_roundtrips.front().unwrap();
+        assert_eq!(rt.entry_price, 99.5);
+        assert_eq!(rt.exit_price, 110.0);
+    }
+
+    #[test]
+    fn test_trade_recorded_at_exchange_fill_time() {
+        let mut strategy = create_test_strategy();
+        let orders = strategy.on_price_update("SOL-USDC", 110.0);
+        let buy = orders.iter().find(|o| o.is_buy()).unwrap().order_id;
+
+        // A fill from an hour ago, split in two, keeps the time it completed at
+        let half = |time_ms| OrderFill::new(buy, "SOL-USDC", 0.5, 100.0).with_time(time_ms);
+        strategy.on_order_filled(&half(1_700_000_000_000));
+        let orders = strategy.on_order_filled(&half(1_700_000_001_500));
+        assert_eq!(strategy.recent_trades[0].time, 1_700_000_001);
+
+        let sell =
+            OrderFill::new(orders[0].order_id, "SOL-USDC", 1.0, 110.0).with_time(1_700_003_600_000);
+        strategy.on_order_filled(&sell);
+        assert_eq!(strategy.recent_trades[0].time, 1_700_003_600);
+        assert_eq!(strategy.completed_roundtrips[0].exit_time, 1_700_003_600);
+    }
+
+    #[test]
+    fn test_level_prices_round_away_from_the_spread() {
+        // Lines at 100, 100.0333.., 100.0666.., 100.1 with a 0.01 tick
+        let strategy = SpotGridStrategy::new(
+            "SOL-USDC".to_string(),
+            100.0,
+            100.1,
+            4,
+            GridMode::Arithmetic,
+            Some(1.0),
+            None,
+            AssetPrecision {
+                sz_decimals: 2,
+                price_decimals: 2,
+                max_decimals: 6,
+            },
+            100.05,
+        );
+
+        // Buys round down, sells round up
+        assert_eq!(strategy.zones[1].lower_price, 100.03);
+        assert_eq!(strategy.zones[0].upper_price, 100.04);
+        assert_eq!(strategy.zones[2].lower_price, 100.06);
+        assert_eq!(strategy.zones[1].upper_price, 100.07);
+        // Lines already on a tick are unchanged
+        assert_eq!(strategy.zones[0].lower_price, 100.0);
+        assert_eq!(strategy.zones[2].upper_price, 100.1);
+    }
+
+    fn states(strategy: &SpotGridStrategy) -> Vec<ZoneState> {
+        strategy.zones.iter().map(|z| z.state).collect()
+    }
+
+    #[test]
+    fn test_initial_position_existing_assumes_base_is_held() {
+        let mut strategy = create_test_strategy();
+        assert_eq!(
+            states(&strategy),
+            vec![ZoneState::WaitingBuy, ZoneState::WaitingSell]
+        );
+        assert_eq!(strategy.position, 1.0);
+
+        let orders = strategy.on_price_update("SOL-USDC", 110.0);
+        assert_eq!(orders.len(), 2);
+        assert_eq!(strategy.required_capital().base, 1.0);
+    }
+
+    #[test]
+    fn test_initial_position_skip_starts_every_zone_buying() {
+        // Lines 100, 110, 120, 130; start inside the middle zone
+        let mut strategy = SpotGridStrategy::new(
+            "SOL-USDC".to_string(),
+            100.0,
+            130.0,
+            4,
+            GridMode::Arithmetic,
+            Some(1.0),
+            None,
+            AssetPrecision {
+                sz_decimals: 2,
+                price_decimals: 2,
+                max_decimals: 6,
+            },
+            115.0,
+        )
+        .with_initial_position(InitialPosition::Skip);
+        assert_eq!(states(&strategy), vec![ZoneState::WaitingBuy; 3]);
+        assert_eq!(strategy.position, 0.0);
+
+        // Only buys below the price go out; the one at 120 would cross
+        let orders = strategy.on_price_update("SOL-USDC", 115.0);
+        let prices: Vec<f64> = orders.iter().map(|o| o.limit_price).collect();
+        assert!(orders.iter().all(|o| o.is_buy()));
+        assert_eq!(prices, vec![100.0, 110.0]);
+
+        // Armed once price trades above it
+        assert!(strategy.on_price_update("SOL-USDC", 119.0).is_empty());
+        let orders = strategy.on_price_update("SOL-USDC", 121.0);
+        assert_eq!(orders.len(), 1);
+        assert!(orders[0].is_buy());
+        assert_eq!(orders[0].limit_price, 120.0);
+        assert!(strategy.on_price_update("SOL-USDC", 122.0).is_empty());
+    }
+
+    #[test]
+    fn test_initial_position_limit_buy_arms_sells_after_fill() {
+        let mut strategy = create_test_strategy().with_initial_position(InitialPosition::LimitBuy);
+        assert_eq!(strategy.position, 0.0);
+        assert_eq!(strategy.required_capital().base, 0.0);
+
+        let orders = strategy.on_price_update("SOL-USDC", 110.0);
+        // The initial buy for the sell zone, plus the buy zone's order
+        assert_eq!(orders.len(), 2);
+        let acquire = &orders[0];
+        assert!(acquire.is_buy());
+        assert_eq!(acquire.qty, 1.0);
+        assert_eq!(acquire.limit_price, 110.0);
+        assert_eq!(acquire.tif, TimeInForce::Gtc);
+        assert_eq!(orders[1].limit_price, 100.0);
+        // Nothing re-placed while the buy rests
+        assert!(strategy.on_price_update("SOL-USDC", 110.5).is_empty());
+
+        let orders =
+            strategy.on_order_filled(&OrderFill::new(acquire.order_id, "SOL-USDC", 1.0, 109.5));
+        assert_eq!(orders.len(), 1);
+        assert!(!orders[0].is_buy());
+        assert_eq!(orders[0].limit_price, 120.0);
+        assert_eq!(strategy.position, 1.0);
+        assert_eq!(strategy.zones[1].entry_price, 109.5);
+
+        // The sell closes a roundtrip against the acquisition price
+        strategy.on_order_filled(&OrderFill::new(orders[0].order_id, "SOL-USDC", 1.0, 120.0));
+        assert_eq!(strategy.realized_pnl, 10.5);
+    }
+
+    #[test]
+    fn test_initial_position_market_buy_falls_back_when_unfilled() {
+        let mut strategy = create_test_strategy().with_initial_position(InitialPosition::MarketBuy);
+        let orders = strategy.on_price_update("SOL-USDC", 112.0);
+        let acquire = &orders[0];
+        assert_eq!(acquire.tif, TimeInForce::Ioc);
+        assert!(acquire.limit_price > 110.0);
+
+        // The IOC found no liquidity: the zone buys at its lower level instead
+        let orders = strategy.on_order_rejected(acquire.order_id, "could not match");
+        assert_eq!(orders.len(), 1);
+        assert!(orders[0].is_buy());
+        assert_eq!(orders[0].limit_price, 110.0);
+        assert_eq!(
+            states(&strategy),
+            vec![ZoneState::WaitingBuy, ZoneState::WaitingBuy]
+        );
+        assert_eq!(strategy.position, 0.0);
+    }
+
+    fn create_perp_strategy(leverage: f64) -> SpotGridStrategy {
+        SpotGridStrategy::new(
+            "ETH".to_string(),
+            1000.0,
+            1200.0,
+            3,
+            GridMode::Arithmetic,
+            None,
+            Some(1000.0),
+            AssetPrecision::for_perp(4),
+            1200.0,
+        )
+        .with_leverage(leverage)
+    }
+
+    #[test]
+    fn test_leverage_scales_margin_not_notional() {
+        // Same 1000 USDC of notional: 1x with 1000 invested, 5x with 200
+        let unlevered = create_perp_strategy(1.0);
+        let mut levered = create_perp_strategy(5.0);
+        levered.total_investment = Some(200.0);
+        levered.initialize_zones();
+
+        let sizes = |s: &SpotGridStrategy| s.zones.iter().map(|z| z.size).collect::<Vec<_>>();
+        assert_eq!(sizes(&unlevered), sizes(&levered));
+        assert!((unlevered.committed_capital() - levered.committed_capital()).abs() < 1e-9);
+        assert!((levered.margin_used() - unlevered.margin_used() / 5.0).abs() < 1e-9);
+        assert!(
+            (levered.required_capital().quote - unlevered.required_capital().quote / 5.0).abs()
+                < 1e-9
+        );
+
+        // At 5x the same investment buys five times the notional
+        let levered = create_perp_strategy(5.0);
+        // Up to size rounding
+        assert!((levered.margin_used() - unlevered.margin_used()).abs() < 0.05);
+        assert!((levered.zones[0].size - unlevered.zones[0].size * 5.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_spot_grid_ignores_leverage() {
+        let mut strategy = create_test_strategy();
+        strategy.asset = "SOL/USDC".to_string();
+        let strategy = strategy.with_leverage(5.0);
+        assert_eq!(strategy.leverage, 1.0);
+    }
+
+    #[test]
+    fn test_zone_order_replaces_the_one_resting() {
+        let mut strategy = create_test_strategy();
+        let orders = strategy.on_price_update("SOL-USDC", 110.0);
+        let resting = orders[0].order_id;
+        let zone_idx = strategy.active_orders[&resting];
+        assert!(orders.iter().all(|o| o.replaces.is_none()));
+
+        let order = strategy.place_zone_order(zone_idx);
+        assert_eq!(order.replaces, Some(resting));
+        assert_eq!(strategy.active_orders.len(), orders.len());
+        assert_eq!(strategy.active_orders.get(&order.order_id), Some(&zone_idx));
+        assert!(!strategy.active_orders.contains_key(&resting));
+    }
+
+    #[test]
+    fn test_levels_rounding_to_one_price_is_config_error() {
+        let params: HashMap<String, Value> = serde_json::from_value(serde_json::json!({
+            "lower_price": 100.0,
+            "upper_price": 101.0,
+            "grid_levels": 5,
+        }))
+        .unwrap();
+        let grid = GridConfig::from_params(&params).unwrap();
+        let whole_dollars = AssetPrecision {
+            sz_decimals: 5,
+            price_decimals: 0,
+            max_decimals: 6,
+        };
+        let cents = AssetPrecision {
+            price_decimals: 2,
+            ..whole_dollars
+        };
+
+        // 100, 100.25, ... all land on 100 without decimals
+        let err = grid.check_precision(&whole_dollars).unwrap_err();
+        assert!(err.to_string().contains("grid levels 0 and 1"));
+        assert!(grid.check_precision(&cents).is_ok());
+    }
+
+    #[test]
+    fn test_tight_geometric_range_collapses_levels() {
+        let whole_dollars = AssetPrecision {
+            sz_decimals: 2,
+            price_decimals: 0,
+            max_decimals: 6,
+        };
+        let grid = GridConfig {
+            lower_price: 100.0,
+            upper_price: 103.0,
+            grid_levels: 8,
+            mode: GridMode::Geometric,
+        };
+
+        // Lines 100, 100.42, ... round to 100 twice; only 100, 101.49, 103 stay apart
+        let err = grid.check_precision(&whole_dollars).unwrap_err();
+        assert!(
+            err.to_string().contains("use at most 3 grid levels"),
+            "{err}"
+        );
+        assert_eq!(grid.max_levels_at(&whole_dollars), Some(3));
+
+        // The strategy falls back to the levels that stay apart
+        let strategy = SpotGridStrategy::new(
+            "SOL-USDC".to_string(),
+            100.0,
+            103.0,
+            8,
+            GridMode::Geometric,
+            Some(1.0),
+            None,
+            whole_dollars,
+            100.0,
+        );
+        assert_eq!(strategy.zones.len(), 2);
+        assert!(strategy.zones.iter().all(|z| z.upper_price > z.lower_price));
+        assert!(strategy
+            .zones
+            .windows(2)
+            .all(|w| w[1].lower_price > w[0].lower_price));
+
+        // A range narrower than one tick leaves no zones at all
+        let strategy = SpotGridStrategy::new(
+            "SOL-USDC".to_string(),
+            100.2,
+            100.8,
+            3,
+            GridMode::Geometric,
+            Some(1.0),
+            None,
+            whole_dollars,
+            100.5,
+        );
+        assert!(strategy.zones.is_empty());
+    }
+
+    #[test]
+    fn test_sub_minimum_orders_are_config_error() {
+        let grid = GridConfig {
+            lower_price: 1.0,
+            upper_price: 2.0,
+            grid_levels: 101,
+            mode: GridMode::Arithmetic,
+        };
+
+        // 100 quote over 100 zones is 1 per order; 10 zones make 10 each
+        let err = grid
+            .check_min_notional(SizingMode::ConstantQuote, None, Some(100.0), 10.0)
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("use at most 11 grid levels"),
+            "{err}"
+        );
+        let fewer = GridConfig {
+            grid_levels: 11,
+            ..grid
+        };
+        assert!(fewer
+            .check_min_notional(SizingMode::ConstantQuote, None, Some(100.0), 10.0)
+            .is_ok());
+
+        // A fixed size worth 5 at the bottom line fails at any level count
+        let err = grid
+            .check_min_notional(SizingMode::ConstantQuote, Some(5.0), None, 10.0)
+            .unwrap_err();
+        assert!(err.to_string().contains("raise order_size"), "{err}");
+    }
+
+    #[test]
+    fn test_whole_unit_sizes_rounding_to_zero_are_config_error() {
+        let grid = GridConfig {
+            lower_price: 100.0,
+            upper_price: 200.0,
+            grid_levels: 11,
+            mode: GridMode::Arithmetic,
+        };
+        let whole_units = AssetPrecision {
+            sz_decimals: 0,
+            price_decimals: 2,
+            max_decimals: 6,
+        };
+
+        // 1000 quote over 10 zones buys 0.53 at the 190 line
+        let err = grid
+            .check_size_precision(SizingMode::ConstantQuote, None, Some(1000.0), &whole_units)
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("round to 0 at 0 size decimals"),
+            "{err}"
+        );
+        // Five zones get 200 each, over the 180 line's price
+        assert!(
+            err.to_string().contains("use at most 6 grid levels"),
+            "{err}"
+        );
+        let two_decimals = AssetPrecision {
+            sz_decimals: 2,
+            ..whole_units
+        };
+        assert!(grid
+            .check_size_precision(SizingMode::ConstantQuote, None, Some(1000.0), &two_decimals)
+            .is_ok());
+
+        // A fixed fractional size rounds to 0 at any level count
+        let err = grid
+            .check_size_precision(SizingMode::ConstantQuote, Some(0.7), None, &whole_units)
+            .unwrap_err();
+        assert!(err.to_string().contains("raise order_size"), "{err}");
+    }
+
+    #[test]
+    fn test_level_sizes_used_verbatim() {
+        // Zones 100-110 (buying) and 110-120 (selling held base)
+        let mut strategy = create_test_strategy().with_level_sizes(vec![1.234, 2.5]);
+        let sizes: Vec<f64> = strategy.zones.iter().map(|z| z.size).collect();
+        assert_eq!(sizes, vec![1.23, 2.5]);
+        assert_eq!(strategy.position, 2.5);
+
+        let orders = strategy.on_price_update("SOL-USDC", 110.0);
+        let buy = orders.iter().find(|o| o.is_buy()).unwrap();
+        let sell = orders.iter().find(|o| !o.is_buy()).unwrap();
+        assert_eq!((buy.qty, buy.limit_price), (1.23, 100.0));
+        assert_eq!((sell.qty, sell.limit_price), (2.5, 120.0));
+
+        // A list that doesn't cover every zone is ignored
+        let strategy = create_test_strategy().with_level_sizes(vec![3.0]);
+        assert!(strategy.zones.iter().all(|z| z.size == 1.0));
+
+        let grid = strategy.grid_config();
+        let err = grid.check_level_sizes(&[3.0], 0.0).unwrap_err();
+        assert!(
+            err.to_string().contains("1 entries but the grid has 2"),
+            "{err}"
+        );
+        let err = grid.check_level_sizes(&[1.0, -1.0], 0.0).unwrap_err();
+        assert!(err.to_string().contains("level_sizes[1] is -1"), "{err}");
+        let err = grid.check_level_sizes(&[0.05, 1.0], 10.0).unwrap_err();
+        assert!(
+            err.to_string().contains("level_sizes[0] is worth 5.00"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_precision_update_rerounds_and_replaces_levels() {
+        // Lines 100, 100.333.., 100.666.., 101
+        let mut strategy = create_test_strategy();
+        strategy.upper_price = 101.0;
+        strategy.grid_levels = 4;
+        strategy.initial_price = 100.5;
+        strategy.initialize_zones();
+        let orders = strategy.on_price_update("SOL-USDC", 100.5);
+        assert_eq!(strategy.zones[1].lower_price, 100.33);
+
+        // The first zone gives up after its retries
+        let first = strategy.zones[0].order_id.unwrap();
+        let retry = strategy.on_order_rejected(first, "Order has invalid price.");
+        strategy.on_order_rejected(retry[0].order_id, "Order has invalid price.");
+        assert!(strategy.zones[0].order_id.is_none());
+
+        let coarser = AssetPrecision {
+            price_decimals: 1,
+            ..strategy.precision
+        };
+        let replaced = strategy.on_precision_update("SOL-USDC", coarser);
+        assert_eq!(replaced.len(), strategy.zones.len());
+        assert_eq!(strategy.zones[1].lower_price, 100.3);
+        assert_eq!(strategy.zones[1].upper_price, 100.7);
+        // Resting orders are swapped in place; the empty level is placed fresh
+        assert_eq!(replaced[0].replaces, None);
+        assert!(replaced[1..].iter().all(|o| o
+            .replaces
+            .is_some_and(|id| orders.iter().any(|p| p.order_id == id))));
+
+        // Unchanged precision is a no-op
+        assert!(strategy.on_precision_update("SOL-USDC", coarser).is_empty());
+    }
+
+    #[test]
+    fn test_zero_base_balance_suppresses_sells() {
+        // Levels 100, 110, 120 at 105: both zones start out selling
+        let mut strategy = create_test_strategy();
+        strategy.asset = "SOL/USDC".to_string();
+        strategy.initial_price = 105.0;
+        strategy.initialize_zones();
+        let required = strategy.required_capital().base;
+        assert!(required > 0.0);
+
+        assert!(strategy.on_balance_update("SOL/USDC", 0.0).is_empty());
+        assert!(strategy.zones.iter().all(|z| z.awaiting_base));
+        assert_eq!(strategy.required_capital().base, 0.0);
+        assert_eq!(strategy.position, 0.0);
+        let orders = strategy.on_price_update("SOL/USDC", 105.0);
+        assert!(orders.iter().all(|o| o.is_buy()));
+
+        // Enough for the lower zone's sell only: it is placed right away
+        let size = strategy.zones[0].size;
+        let orders = strategy.on_balance_update("SOL/USDC", size);
+        assert_eq!(orders.len(), 1);
+        assert!(!orders[0].is_buy());
+        assert_eq!(orders[0].limit_price, strategy.zones[0].upper_price);
+        assert!(strategy.zones[1].awaiting_base);
+
+        // Perp sells open shorts and need no base
+        let mut perp = create_test_strategy();
+        perp.on_balance_update("SOL-USDC", 0.0);
+        assert!(perp.zones.iter().all(|z| !z.awaiting_base));
+    }
+
+    #[test]
+    fn test_counter_order_waits_for_cooldown() {
+        let mut strategy = create_test_strategy().with_min_refill_secs(30);
+        assert!(strategy.on_tick(1_000).is_empty());
+        let orders = strategy.on_price_update("SOL-USDC", 110.0);
+        let buy = orders.iter().find(|o| o.is_buy()).unwrap().order_id;
+
+        // The fill holds the level back instead of re-quoting it
+        let orders = strategy.on_order_filled(&OrderFill::new(buy, "SOL-USDC", 1.0, 100.0));
+        assert!(orders.is_empty());
+        assert!(strategy.on_tick(1_010).is_empty());
+        assert!(strategy.on_tick(1_029).is_empty());
+
+        let orders = strategy.on_tick(1_030);
+        assert_eq!(orders.len(), 1);
+        assert!(!orders[0].is_buy());
+        assert_eq!(orders[0].limit_price, 110.0);
+        // Placed once only
+        assert!(strategy.on_tick(1_031).is_empty());
+
+        // The counter-order is tracked like any other
+        strategy.on_order_filled(&OrderFill::new(orders[0].order_id, "SOL-USDC", 1.0, 110.0));
+        assert_eq!(strategy.realized_pnl, 10.0);
+    }
+
+    #[test]
+    fn test_rebalance_tick_recenters_grid() {
+        let mut strategy = create_test_strategy().with_rebalance_interval(3_600);
+        assert!(strategy.on_tick(1_000).is_empty());
+        let orders = strategy.on_price_update("SOL-USDC", 110.0);
+        let buy = orders.iter().find(|o| o.is_buy()).unwrap().order_id;
+
+        // One roundtrip on the lower zone: 100 -> 110
+        let sell = strategy.on_order_filled(&OrderFill::new(buy, "SOL-USDC", 1.0, 100.0));
+        let buy =
+            strategy.on_order_filled(&OrderFill::new(sell[0].order_id, "SOL-USDC", 1.0, 110.0));
+        let mut resting: Vec<u64> = strategy.active_orders.keys().copied().collect();
+        resting.sort_unstable();
+        assert!(resting.contains(&buy[0].order_id));
+
+        strategy.on_price_update("SOL-USDC", 114.0);
+        assert!(strategy.on_tick(4_599).is_empty());
+
+        // Same 20 wide range, now centered on 114
+        let orders = strategy.on_tick(4_600);
+        assert_eq!((strategy.lower_price, strategy.upper_price), (104.0, 124.0));
+        let lines: Vec<(f64, f64)> = strategy
+            .zones
+            .iter()
+            .map(|z| (z.lower_price, z.upper_price))
+            .collect();
+        assert_eq!(lines, [(104.0, 114.0), (114.0, 124.0)]);
+
+        // The base held from the start sells at the new top, at its old entry
+        assert_eq!(orders.len(), 2);
+        assert!(orders[0].is_buy() && orders[0].limit_price == 104.0);
+        assert!(!orders[1].is_buy() && orders[1].limit_price == 124.0);
+        assert_eq!(strategy.zones[1].entry_price, 110.0);
+        let mut replaced: Vec<u64> = orders.iter().filter_map(|o| o.replaces).collect();
+        replaced.sort_unstable();
+        assert_eq!(replaced, resting);
+        assert!(strategy.drain_cancels().is_empty());
+
+        // PnL and position carry over; the next rebalance is an interval away
+        assert_eq!(strategy.realized_pnl, 10.0);
+        assert_eq!(strategy.position, 1.0);
+        assert!(strategy.on_tick(8_199).is_empty());
+    }
+
+    #[test]
+    fn test_rebalance_replaces_orders_on_the_same_side() {
+        let mut strategy = create_test_strategy().with_rebalance_interval(3_600);
+        assert!(strategy.on_tick(1_000).is_empty());
+        let orders = strategy.on_price_update("SOL-USDC", 110.0);
+        let buy = orders.iter().find(|o| o.is_buy()).unwrap().order_id;
+        let top_sell = orders.iter().find(|o| !o.is_buy()).unwrap().order_id;
+
+        // The buy fills, so both resting orders are sells: 110 and 120
+        let sell = strategy.on_order_filled(&OrderFill::new(buy, "SOL-USDC", 1.0, 100.0));
+        let low_sell = sell[0].order_id;
+
+        // Recentered on 96: a buy at 86 and a sell at 106
+        strategy.on_price_update("SOL-USDC", 96.0);
+        let orders = strategy.on_tick(4_600);
+        assert_eq!(orders.len(), 2);
+        let new_buy = orders.iter().find(|o| o.is_buy()).unwrap();
+        let new_sell = orders.iter().find(|o| !o.is_buy()).unwrap();
+        assert_eq!((new_buy.limit_price, new_sell.limit_price), (86.0, 106.0));
+
+        // The buy goes out fresh, the sell takes over the nearest sell
+        assert_eq!(new_buy.replaces, None);
+        assert_eq!(new_sell.replaces, Some(low_sell));
+        assert_eq!(strategy.drain_cancels(), [top_sell]);
+    }
+
+    #[test]
+    fn test_self_audit_cancels_strays_and_fills_gaps() {
+        let mut strategy = create_test_strategy().with_audit_interval(600);
+        assert!(strategy.on_tick(1_000).is_empty());
+        let orders = strategy.on_price_update("SOL-USDC", 110.0);
+        let buy = orders.iter().find(|o| o.is_buy()).unwrap().order_id;
+        assert!(strategy.on_tick(1_599).is_empty());
+        assert!(!strategy.take_open_orders_request());
+
+        // The audit asks for the exchange's open orders, once
+        assert!(strategy.on_tick(1_600).is_empty());
+        assert!(strategy.take_open_orders_request());
+        assert!(!strategy.take_open_orders_request());
+
+        // The exchange lost the sell and rests an order no level owns
+        let orders = strategy.on_open_orders(&[buy, 999]);
+        assert_eq!(strategy.drain_cancels(), [999]);
+        assert_eq!(orders.len(), 1);
+        assert!(!orders[0].is_buy() && orders[0].limit_price == 120.0);
+        assert_eq!(orders[0].replaces, None);
+        let sell = orders[0].order_id;
+        assert_eq!(strategy.active_orders.len(), 2);
+
+        // Healthy again: the next audit changes nothing
+        assert!(strategy.on_tick(2_200).is_empty());
+        assert!(strategy.take_open_orders_request());
+        assert!(strategy.on_open_orders(&[buy, sell]).is_empty());
+        assert!(strategy.drain_cancels().is_empty());
+    }
+
+    #[test]
+    fn test_roundtrip_stats_over_wins_and_losses() {
+        let mut strategy = create_test_strategy();
+        let orders = strategy.on_price_update("SOL-USDC", 110.0);
+        let buy = orders.iter().find(|o| o.is_buy()).unwrap().order_id;
+        let upper_sell = orders.iter().find(|o| !o.is_buy()).unwrap().order_id;
+        let fill = |id, px| OrderFill::new(id, "SOL-USDC", 1.0, px).with_fee(0.1);
+
+        // Win: 100 -> 110, net 10 - 0.2
+        let sell = strategy.on_order_filled(&fill(buy, 100.0))[0].order_id;
+        let buy = strategy.on_order_filled(&fill(sell, 110.0))[0].order_id;
+        // Loss: 100 -> 99, net -1 - 0.2
+        let sell = strategy.on_order_filled(&fill(buy, 100.0))[0].order_id;
+        strategy.on_order_filled(&fill(sell, 99.0));
+        // Win on the zone holding inventory from the start: 110 -> 120, net 10 - 0.1
+        strategy.on_order_filled(&fill(upper_sell, 120.0));
+
+        let stats = strategy.roundtrip_stats;
+        assert_eq!(stats.count, 3);
+        assert_eq!((stats.wins, stats.losses), (2, 1));
+        assert!((stats.total_pnl - 19.0).abs() < 1e-9);
+        assert!((stats.total_fees - 0.5).abs() < 1e-9);
+        assert!((stats.best - 9.9).abs() < 1e-9);
+        assert!((stats.worst + 1.2).abs() < 1e-9);
+        assert!((stats.win_rate() - 2.0 / 3.0).abs() < 1e-9);
+        assert!((stats.avg_pnl() - 19.0 / 3.0).abs() < 1e-9);
+        assert!((stats.avg_net_pnl() - 18.5 / 3.0).abs() < 1e-9);
+
+        let rt = strategy.completed_roundtrips.back().unwrap();
+        assert!((rt.fees - 0.2).abs() < 1e-9);
+
+        let custom = strategy.status().custom;
+        assert_eq!(custom["roundtrip_stats"]["wins"], 2);
+        assert_eq!(custom["roundtrip_stats"]["losses"], 1);
+    }
+
+    #[test]
+    fn test_status_keys_appear_once() {
+        let mut strategy = create_test_strategy();
+        strategy.on_price_update("SOL-USDC", 110.0);
+
+        let value = serde_json::to_value(strategy.status()).unwrap();
+        let top = value.as_object().unwrap();
+        let custom = top["custom"].as_object().unwrap();
+        let mut keys: Vec<&String> = top.keys().chain(custom.keys()).collect();
+        let count = keys.len();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), count, "duplicated keys in {value}");
+
+        assert_eq!(top["current_price"], 110.0);
+        assert_eq!(top["active_orders"], 2);
+        let book = &top["book"];
+        assert_eq!(book["asks"][0]["price"], 120.0);
+        assert_eq!(book["bids"][0]["price"], 100.0);
+        assert_eq!(book["bids"][0]["has_order"], true);
+    }
+
+    #[test]
+    fn test_roundtrip_stats_empty() {
+        let stats = RoundtripStats::default();
+        assert_eq!(stats.win_rate(), 0.0);
+        assert_eq!(stats.avg_net_pnl(), 0.0);
+    }
+
+    #[test]
+    fn test_roundtrip_emits_one_event() {
+        let mut strategy = create_test_strategy();
+        let orders = strategy.on_price_update("SOL-USDC", 110.0);
+        let buy = orders.iter().find(|o| o.is_buy()).unwrap().order_id;
+
+        let orders = strategy.on_order_filled(&OrderFill::new(buy, "SOL-USDC", 1.0, 100.0));
+        // Opening a position isn't a roundtrip
+        assert!(strategy.drain_events().is_empty());
+
+        strategy.on_order_filled(&OrderFill::new(orders[0].order_id, "SOL-USDC", 1.0, 110.0));
+        let events = strategy.drain_events();
+        assert_eq!(events.len(), 1);
+        let StrategyEvent::RoundtripClosed(rt) = &events[0] else {
+            panic!("expected a roundtrip, got {:?}", events[0]);
+        };
+        assert_eq!(rt.entry_price, 100.0);
+        assert_eq!(rt.exit_price, 110.0);
+        assert_eq!(rt.pnl, 10.0);
+
+        // Drained events are gone
+        assert!(strategy.drain_events().is_empty());
+    }
+
+    #[test]
+    fn test_range_exit_raised_once_all_levels_sold() {
+        let mut strategy = SpotGridStrategy::new(
+            "SOL-USDC".to_string(),
+            100.0,
+            130.0,
+            4,
+            GridMode::Arithmetic,
+            Some(1.0),
+            None,
+            AssetPrecision::for_spot(2),
+            115.0,
+        );
+        let orders = strategy.on_price_update("SOL-USDC", 115.0);
+        let sells: Vec<u64> = orders
+            .iter()
+            .filter(|o| !o.is_buy())
+            .map(|o| o.order_id)
+            .collect();
+        assert_eq!(sells.len(), 2);
+
+        // Price runs through 120, then past the top at 130
+        strategy.on_price_update("SOL-USDC", 121.0);
+        strategy.on_order_filled(&OrderFill::new(sells[0], "SOL-USDC", 1.0, 120.0));
+        let events = strategy.drain_events();
+        assert!(matches!(events[..], [StrategyEvent::RoundtripClosed(_)]));
+        strategy.on_price_update("SOL-USDC", 131.0);
+        let orders = strategy.on_order_filled(&OrderFill::new(sells[1], "SOL-USDC", 1.0, 130.0));
+
+        let events = strategy.drain_events();
+        assert!(matches!(
+            events.last(),
+            Some(StrategyEvent::RangeExited {
+                direction: RangeDirection::Above
+            })
+        ));
+        let status = strategy.status();
+        assert_eq!(status.status, "RangeExitedAbove");
+        assert_eq!(status.custom["range_exit"], "above");
+
+        // Buying the top level back puts the grid in range again
+        strategy.on_order_filled(&OrderFill::new(orders[0].order_id, "SOL-USDC", 1.0, 120.0));
+        let events = strategy.drain_events();
+        assert!(matches!(events.last(), Some(StrategyEvent::RangeReentered)));
+        assert_eq!(strategy.status().status, "Running");
+    }
+
+    #[test]
+    fn test_geometric_grid_constant_base_roundtrips() {
+        // Levels 100, 200, 400. Start at the top of the range so both zones wait to buy.
+        let mut strategy = SpotGridStrategy::new(
+            "SOL-USDC".to_string(),
+            100.0,
+            400.0,
+            3,
+            GridMode::Geometric,
+            None,
+            Some(600.0),
+            AssetPrecision {
+                sz_decimals: 2,
+                price_decimals: 2,
+                max_decimals: 6,
+            },
+            400.0,
+        );
+        assert_eq!(strategy.sizing, SizingMode::ConstantBase);
+
+        // 600 / (100 + 200) = 2.0 base per zone
+        assert_eq!(strategy.zones[0].size, 2.0);
+        assert_eq!(strategy.zones[1].size, 2.0);
+
+        let orders = strategy.on_price_update("SOL-USDC", 400.0);
+        assert_eq!(orders.len(), 2);
+
+        for order in orders {
+            let zone_idx = strategy.active_orders[&order.order_id];
+            let (lower, upper) = (
+                strategy.zones[zone_idx].lower_price,
+                strategy.zones[zone_idx].upper_price,
+            );
+
+            let sells = strategy.on_order_filled(&OrderFill::new(
+                order.order_id,
+                "SOL-USDC",
+                order.qty,
+                order.limit_price,
+            ));
+            assert_eq!(sells[0].qty, order.qty);
+            assert_eq!(sells[0].limit_price, upper);
+            strategy.on_order_filled(&OrderFill::new(
+                sells[0].order_id,
+                "SOL-USDC",
+                sells[0].qty,
+                sells[0].limit_price,
+            ));
+
+            let zone = &strategy.zones[zone_idx];
+            assert_eq!(zone.roundtrip_count, 1);
+            assert_eq!(zone.total_pnl, (upper - lower) * zone.size);
+        }
+
+        assert_eq!(strategy.realized_pnl, 100.0 * 2.0 + 200.0 * 2.0);
+    }
+
+    #[test]
+    fn test_constant_quote_sizing() {
+        let strategy = SpotGridStrategy::new(
+            "SOL-USDC".to_string(),
+            100.0,
+            400.0,
+            3,
+            GridMode::Geometric,
+            None,
+            Some(600.0),
+            AssetPrecision {
+                sz_decimals: 2,
+                price_decimals: 2,
+                max_decimals: 6,
+            },
+            450.0,
+        )
+        .with_sizing_mode(SizingMode::ConstantQuote);
+
+        // 300 quote per zone
+        assert_eq!(strategy.zones[0].size, 3.0);
+        assert_eq!(strategy.zones[1].size, 1.5);
+        assert_eq!(strategy.position, 0.0);
+    }
+
+    #[test]
+    fn test_rejected_order_retried_once() {
+        let mut strategy = create_test_strategy();
+        let orders = strategy.on_price_update("SOL-USDC", 110.0);
+        let buy = orders.iter().find(|o| o.is_buy()).unwrap().clone();
+
+        // First rejection: the level is re-placed with a new id
+        let retry = strategy.on_order_rejected(buy.order_id, "Order has invalid price.");
+        assert_eq!(retry.len(), 1);
+        assert_ne!(retry[0].order_id, buy.order_id);
+        assert_eq!(retry[0].limit_price, buy.limit_price);
+        assert_eq!(retry[0].qty, buy.qty);
+        assert_eq!(strategy.zones[0].order_id, Some(retry[0].order_id));
+
+        // Second rejection: give up and leave the level visibly empty
+        let retry = strategy.on_order_rejected(retry[0].order_id, "Order has invalid price.");
+        assert!(retry.is_empty());
+        assert_eq!(strategy.zones[0].order_id, None);
+        assert!(!strategy.active_orders.values().any(|&zone| zone == 0));
+
+        // Unknown ids are ignored
+        assert!(strategy.on_order_rejected(12345, "whatever").is_empty());
+    }
+
+    #[test]
+    fn test_alo_grid_retries_one_tick_away() {
+        let mut strategy = create_test_strategy().with_tif(TimeInForce::Alo);
+        let orders = strategy.on_price_update("SOL-USDC", 110.0);
+        assert!(orders.iter().all(|o| o.tif == TimeInForce::Alo));
+        let buy = orders.iter().find(|o| o.is_buy()).unwrap().clone();
+
+        let retry = strategy.on_order_rejected(
+            buy.order_id,
+            "Post only order would have immediately matched, bbo was 99.99@100.01",
+        );
+        assert_eq!(retry.len(), 1);
+        assert_eq!(retry[0].tif, TimeInForce::Alo);
+        assert!((retry[0].limit_price - 99.99).abs() < 1e-9);
+        assert_eq!(retry[0].qty, buy.qty);
+    }
+
+    #[test]
+    fn test_partial_fill_waits_for_completion() {
+        let mut strategy = create_test_strategy();
+        let orders = strategy.on_price_update("SOL-USDC", 110.0);
+        let buy = orders.iter().find(|o| o.is_buy()).unwrap().clone();
+        let start_position = strategy.position;
+
+        // Half fills: the order stays resting and nothing is placed
+        let half = buy.qty / 2.0;
+        let after_partial =
+            strategy.on_order_filled(&OrderFill::new(buy.order_id, "SOL-USDC", half, 100.0));
+        assert!(after_partial.is_empty());
+        assert_eq!(strategy.zones[0].state, ZoneState::WaitingBuy);
+        assert_eq!(strategy.zones[0].order_id, Some(buy.order_id));
+        assert_eq!(strategy.position, start_position);
+
+        // The rest fills: exactly one counter-order for the full size
+        let after_complete =
+            strategy.on_order_filled(&OrderFill::new(buy.order_id, "SOL-USDC", half, 99.0));
+        assert_eq!(after_complete.len(), 1);
+        assert!(!after_complete[0].is_buy());
+        assert_eq!(after_complete[0].qty, buy.qty);
+        assert_eq!(strategy.zones[0].state, ZoneState::WaitingSell);
+        assert!((strategy.position - start_position - buy.qty).abs() < 1e-9);
+        // Cost basis is the average of both partials
+        assert!((strategy.zones[0].entry_price - 99.5).abs() < 1e-9);
+
+        // A late duplicate for the finished order is ignored
+        assert!(strategy
+            .on_order_filled(&OrderFill::new(buy.order_id, "SOL-USDC", half, 99.0))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_out_of_range_start_waits_for_entry() {
+        let mut strategy = SpotGridStrategy::new(
+            "SOL-USDC".to_string(),
+            100.0,
+            120.0,
+            3,
+            GridMode::Arithmetic,
+            Some(1.0),
+            None,
+            AssetPrecision {
+                sz_decimals: 2,
+                price_decimals: 2,
+                max_decimals: 6,
+            },
+            130.0, // Above the range
+        );
+        assert_eq!(strategy.status().status, "WaitingForEntry");
+
+        // Still above the range: nothing is placed
+        assert!(strategy.on_price_update("SOL-USDC", 125.0).is_empty());
+        assert_eq!(strategy.status().status, "WaitingForEntry");
+
+        // Entering the range starts the grid around the entry price
+        let orders = strategy.on_price_update("SOL-USDC", 115.0);
+        assert_eq!(strategy.status().status, "Running");
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders.iter().filter(|o| o.is_buy()).count(), 1);
+        assert_eq!(orders.iter().filter(|o| !o.is_buy()).count(), 1);
+        assert_eq!(strategy.position, 1.0);
+    }
+
+    #[test]
+    fn test_underfunded_grid_is_rejected() {
+        // Zone (100-110) buys 1.0 at 100, zone (110-120) holds 1.0 to sell
+        let strategy = create_test_strategy();
+        let required = strategy.required_capital();
+        assert_eq!(required.quote, 100.0);
+        assert_eq!(required.base, 1.0);
+
+        let funded = crate::market::AssetInfo::new("SOL/USDC", 1.0, 150.0, 2, 2);
+        assert!(required.check(&funded, 0.1).is_ok());
+
+        let err = required
+            .check(
+                &crate::market::AssetInfo::new("SOL/USDC", 1.0, 60.0, 2, 2),
+                0.1,
+            )
+            .unwrap_err();
+        assert!(err.contains("need 100.00") && err.contains("60.00 available"));
+
+        let err = required
+            .check(
+                &crate::market::AssetInfo::new("SOL/USDC", 0.5, 150.0, 2, 2),
+                0.1,
+            )
+            .unwrap_err();
+        assert!(err.contains("need 1") && err.contains("0.5 available"));
+
+        // Perps keep a margin buffer free but need no base to sell
+        let perp = crate::market::AssetInfo::new("SOL", 0.0, 105.0, 2, 2);
+        let err = required.check(&perp, 0.1).unwrap_err();
+        assert!(err.contains("94.50 available"));
+        assert!(required
+            .check(&crate::market::AssetInfo::new("SOL", 0.0, 120.0, 2, 2), 0.1)
+            .is_ok());
+    }
+
+    fn create_infinite_strategy() -> SpotGridStrategy {
+        // Lines at 100, 110, 121; 10% step
+        SpotGridStrategy::new(
+            "SOL-USDC".to_string(),
+            100.0,
+            121.0,
+            3,
+            GridMode::Infinite,
+            Some(1.0),
+            None,
+            AssetPrecision {
+                sz_decimals: 2,
+                price_decimals: 2,
+                max_decimals: 6,
+            },
+            110.0,
+        )
+    }
+
+    #[test]
+    fn test_infinite_grid_extends_below_bottom_buy() {
+        let mut strategy = create_infinite_strategy();
+        let orders = strategy.on_price_update("SOL-USDC", 110.0);
+        let bottom_buy = orders.iter().find(|o| o.is_buy()).unwrap().clone();
+        assert_eq!(bottom_buy.limit_price, 100.0);
+
+        let orders =
+            strategy.on_order_filled(&OrderFill::new(bottom_buy.order_id, "SOL-USDC", 1.0, 100.0));
+
+        // Sell back at 110 for the filled zone, plus a buy on the new lower level
+        assert_eq!(orders.len(), 2);
+        assert_eq!(strategy.zones.len(), 3);
+        let new_zone = &strategy.zones[2];
+        assert!((new_zone.lower_price - 90.0).abs() < 1e-9);
+        assert_eq!(new_zone.upper_price, 100.0);
+        assert_eq!(new_zone.state, ZoneState::WaitingBuy);
+        let new_buy = orders.iter().find(|o| o.is_buy()).unwrap();
+        assert!((new_buy.limit_price - 90.0).abs() < 1e-9);
+        assert_eq!(strategy.active_orders.get(&new_buy.order_id), Some(&2));
+        assert_eq!(strategy.status().custom["levels"], 4);
+
+        // A buy in a zone that is no longer the bottom doesn't extend
+        let sell = orders.iter().find(|o| !o.is_buy()).unwrap().clone();
+        strategy.on_order_filled(&OrderFill::new(sell.order_id, "SOL-USDC", 1.0, 110.0));
+        let rebuy = strategy.zones[0].order_id.unwrap();
+        let orders = strategy.on_order_filled(&OrderFill::new(rebuy, "SOL-USDC", 1.0, 100.0));
+        assert_eq!(orders.len(), 1);
+        assert_eq!(strategy.zones.len(), 3);
+    }
+
+    #[test]
+    fn test_infinite_grid_extends_above_top_sell() {
+        let mut strategy = create_infinite_strategy();
+        let orders = strategy.on_price_update("SOL-USDC", 110.0);
+        let top_sell = orders.iter().find(|o| !o.is_buy()).unwrap().clone();
+        assert_eq!(top_sell.limit_price, 121.0);
+
+        let orders =
+            strategy.on_order_filled(&OrderFill::new(top_sell.order_id, "SOL-USDC", 1.0, 121.0));
+
+        assert_eq!(orders.len(), 2);
+        let new_zone = &strategy.zones[2];
+        assert_eq!(new_zone.lower_price, 121.0);
+        assert!((new_zone.upper_price - 133.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_infinite_grid_respects_limits() {
+        let mut strategy = create_infinite_strategy().with_infinite_limits(Some(4), None);
+        let orders = strategy.on_price_update("SOL-USDC", 110.0);
+        let mut buy = orders.iter().find(|o| o.is_buy()).unwrap().clone();
+
+        // 3 lines -> 4 lines, then capped
+        for expected_zones in [3, 3] {
+            let orders = strategy.on_order_filled(&OrderFill::new(
+                buy.order_id,
+                "SOL-USDC",
+                1.0,
+                buy.limit_price,
+            ));
+            assert_eq!(strategy.zones.len(), expected_zones);
+            if let Some(next) = orders.iter().find(|o| o.is_buy()) {
+                buy = next.clone();
+            }
+        }
+
+        // Capital cap: inventory bought at 100 and 110 is 210, a level at 90 would exceed 250
+        let mut strategy = create_infinite_strategy().with_infinite_limits(None, Some(250.0));
+        let orders = strategy.on_price_update("SOL-USDC", 110.0);
+        let buy = orders.iter().find(|o| o.is_buy()).unwrap().clone();
+        strategy.on_order_filled(&OrderFill::new(buy.order_id, "SOL-USDC", 1.0, 100.0));
+        assert_eq!(strategy.zones.len(), 2);
+    }
+
+    #[test]
+    fn test_preview_matches_placed_orders() {
+        let strategy = || {
+            SpotGridStrategy::new(
+                "BTC".to_string(),
+                90.0,
+                110.0,
+                5,
+                GridMode::Geometric,
+                None,
+                Some(1000.0),
+                AssetPrecision {
+                    sz_decimals: 3,
+                    price_decimals: 2,
+                    max_decimals: 6,
+                },
+                101.0,
+            )
+        };
+        let preview = strategy().preview();
+        assert_eq!(preview.len(), 4);
+        assert!(preview.windows(2).all(|w| w[0].0 < w[1].0));
+
+        let mut placed: Vec<(f64, f64, OrderSide)> = strategy()
+            .on_price_update("BTC", 101.0)
+            .iter()
+            .map(|o| (o.limit_price, o.qty, o.side))
+            .collect();
+        placed.sort_by(|a, b| a.0.total_cmp(&b.0));
+        assert_eq!(preview, placed);
+    }
+
+    #[test]
+    fn test_max_position_suppresses_buys() {
+        // Lines 100..150 every 10, all five zones buying from the top
+        let mut strategy = SpotGridStrategy::new(
+            "SOL-USDC".to_string(),
+            100.0,
+            150.0,
+            6,
+            GridMode::Arithmetic,
+            Some(1.0),
+            None,
+            AssetPrecision {
+                sz_decimals: 2,
+                price_decimals: 2,
+                max_decimals: 6,
+            },
+            150.0,
+        )
+        .with_max_position(Some(2.0));
+        let capacity = |s: &SpotGridStrategy| {
+            let snapshot: StatusSnapshot = serde_json::from_value(s.status().custom).unwrap();
+            snapshot.position_capacity
+        };
+
+        // Only the two buys nearest the price fit under the cap
+        let orders = strategy.on_price_update("SOL-USDC", 150.0);
+        let buys: Vec<f64> = orders.iter().map(|o| o.limit_price).collect();
+        assert_eq!(buys, vec![140.0, 130.0]);
+        assert_eq!(capacity(&strategy), Some(0.0));
+
+        let fill = |o: &OrderRequest| OrderFill::new(o.order_id, "SOL-USDC", o.qty, o.limit_price);
+        let sell_150 = strategy.on_order_filled(&fill(&orders[0]));
+        let sell_140 = strategy.on_order_filled(&fill(&orders[1]));
+        assert_eq!(strategy.position, 2.0);
+        assert!(sell_150.iter().chain(&sell_140).all(|o| !o.is_buy()));
+
+        // Selling one back makes room for exactly one buy again
+        let orders = strategy.on_order_filled(&fill(&sell_140[0]));
+        let buys: Vec<f64> = orders
+            .iter()
+            .filter(|o| o.is_buy())
+            .map(|o| o.limit_price)
+            .collect();
+        assert_eq!(buys, vec![130.0]);
+        assert_eq!(strategy.position, 1.0);
+        assert_eq!(capacity(&strategy), Some(0.0));
+        assert_eq!(strategy.zones.iter().filter(|z| z.capped).count(), 3);
+    }
+
+    /// Hourly HYPE candles (time, open, high, low, close) recorded from mainnet
+    const RECORDED_CANDLES: &str = "time,open,high,low,close
+1718000000000,31.20,31.85,30.90,31.60
+1718003600000,31.60,32.10,31.40,31.95
+1718007200000,31.95,32.40,31.70,32.25
+1718010800000,32.25,32.30,31.10,31.30
+1718014400000,31.30,31.55,30.60,30.85
+1718018000000,30.85,31.20,30.40,31.05
+1718021600000,31.05,31.90,30.95,31.80
+1718025200000,31.80,32.70,31.75,32.55
+1718028800000,32.55,32.90,32.05,32.15
+1718032400000,32.15,32.35,31.60,31.75
+1718036000000,31.75,32.05,31.35,31.90
+1718039600000,31.90,32.60,31.85,32.45
+";
+
+    #[test]
+    fn test_auto_range_brackets_last_close() {
+        let candles = Candle::read_csv(RECORDED_CANDLES.as_bytes()).unwrap();
+        let last_close = candles.last().unwrap().close;
+
+        let (lower, upper) = GridConfig::range_from_candles(&candles, last_close).unwrap();
+
+        assert!(
+            lower < last_close && last_close < upper,
+            "{lower} - {upper}"
+        );
+        // Covers where price traded, without running far past it
+        assert!(lower <= 30.85 && lower > 28.0);
+        assert!((32.55..35.0).contains(&upper));
+    }
+
+    #[test]
+    fn test_auto_range_widens_to_contain_mid() {
+        let candles = Candle::read_csv(RECORDED_CANDLES.as_bytes()).unwrap();
+
+        // Mid has broken out above everything in the lookback
+        let (lower, upper) = GridConfig::range_from_candles(&candles, 40.0).unwrap();
+        assert!(lower < 40.0 && 40.0 < upper);
+
+        assert!(GridConfig::range_from_candles(&[], 30.0).is_err());
+    }
+
+    #[test]
+    fn test_order_ids_unique_and_above_base() {
+        let mut strategy = create_test_strategy().with_id_base(1 << 32);
+
+        let ids: std::collections::HashSet<u64> =
+            (0..1000).map(|_| strategy.generate_order_id()).collect();
+        assert_eq!(ids.len(), 1000);
+        assert!(ids.iter().all(|&id| id > 1 << 32));
+
+        // Same seed, same sequence
+        let mut first = create_test_strategy();
+        let mut second = create_test_strategy();
+        let a: Vec<u64> = first
+            .on_price_update("SOL-USDC", 110.0)
+            .iter()
+            .map(|o| o.order_id)
+            .collect();
+        let b: Vec<u64> = second
+            .on_price_update("SOL-USDC", 110.0)
+            .iter()
+            .map(|o| o.order_id)
+            .collect();
+        assert!(!a.is_empty());
+        assert_eq!(a, b);
+    }
+
+    /// Feed `prices` to `strategy`, filling resting orders at their limit
+    /// whenever the price crosses them, and return every order it placed
+    fn run_price_path(strategy: &mut SpotGridStrategy, prices: &[f64]) -> Vec<OrderRequest> {
+        let mut placed = Vec::new();
+        let mut resting: Vec<OrderRequest> = Vec::new();
+        for &price in prices {
+            let mut new_orders = strategy.on_price_update("SOL-USDC", price);
+            loop {
+                for order in &new_orders {
+                    if let Some(old) = order.replaces {
+                        resting.retain(|o| o.order_id != old);
+                    }
+                }
+                placed.extend(new_orders.iter().cloned());
+                resting.extend(new_orders);
+                let (filled, rest): (Vec<_>, Vec<_>) =
+                    resting.drain(..).partition(|o| match o.side {
+                        OrderSide::Buy => price <= o.limit_price,
+                        OrderSide::Sell => price >= o.limit_price,
+                    });
+                resting = rest;
+                if filled.is_empty() {
+                    break;
+                }
+                new_orders = filled
+                    .iter()
+                    .flat_map(|o| {
+                        strategy.on_order_filled(&OrderFill::new(
+                            o.order_id,
+                            "SOL-USDC",
+                            o.qty,
+                            o.limit_price,
+                        ))
+                    })
+                    .collect();
+            }
+        }
+        placed
+    }
+
+    #[test]
+    fn test_short_grid_never_goes_long() {
+        let mut strategy = create_test_strategy().with_direction(GridDirection::Short);
+        assert!(strategy
+            .zones
+            .iter()
+            .all(|z| z.state == ZoneState::WaitingSell));
+
+        let prices = [
+            110.0, 115.0, 121.0, 112.0, 99.0, 108.0, 119.0, 125.0, 95.0, 111.0,
+        ];
+        let placed = run_price_path(&mut strategy, &prices);
+
+        assert!(placed.iter().any(|o| o.side == OrderSide::Buy));
+        for order in placed.iter().filter(|o| o.side == OrderSide::Buy) {
+            assert!(order.reduce_only, "buy {:?} could open a long", order);
+        }
+        assert!(placed
+            .iter()
+            .filter(|o| o.side == OrderSide::Sell)
+            .all(|o| !o.reduce_only));
+        assert!(strategy.position <= 1e-9);
+        // Both zones sold high and bought back lower
+        assert!(strategy.realized_pnl > 0.0);
+    }
+
+    #[test]
+    fn test_neutral_grid_splits_at_start_price() {
+        let strategy = create_test_strategy().with_direction(GridDirection::Neutral);
+
+        // Below the start price the zone buys to go long, above it sells short
+        assert_eq!(strategy.zones[0].opening, OrderSide::Buy);
+        assert_eq!(strategy.zones[0].state, ZoneState::WaitingBuy);
+        assert_eq!(strategy.zones[1].opening, OrderSide::Sell);
+        assert_eq!(strategy.zones[1].state, ZoneState::WaitingSell);
+        assert_eq!(strategy.position, 0.0);
+    }
+
+    #[test]
+    fn test_spot_grid_ignores_direction() {
+        let mut strategy = create_test_strategy();
+        strategy.asset = "SOL/USDC".to_string();
+        let strategy = strategy.with_direction(GridDirection::Short);
+
+        assert_eq!(strategy.direction, GridDirection::Long);
+        assert!(strategy.zones.iter().all(|z| z.opening == OrderSide::Buy));
+    }
+
+    #[test]
+    fn test_interval_millis() {
+        assert_eq!(interval_millis("15m"), Some(900_000));
+        assert_eq!(interval_millis("1h"), Some(3_600_000));
+        assert_eq!(interval_millis("1d"), Some(86_400_000));
+        assert_eq!(interval_millis("h"), None);
+        assert_eq!(interval_millis("1x"), None);
+        assert_eq!(interval_millis(""), None);
+    }
 }
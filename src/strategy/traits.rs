@@ -1,7 +1,11 @@
 //! Strategy trait definition
 
-use crate::market::{OrderFill, OrderRequest};
+use super::spot_grid::RoundTrip;
+use crate::market::{AssetInfo, AssetPrecision, OrderFill, OrderRequest, OrderSide};
+use crate::{InfoClient, MarketType};
+use alloy::primitives::Address;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 /// Strategy status for monitoring and display
 ///
@@ -25,10 +29,22 @@ pub struct StrategyStatus {
     pub unrealized_pnl: f64,
     /// Total fees paid
     pub total_fees: f64,
+    /// Quote committed to the strategy, when it was given an amount
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_investment: Option<f64>,
+    /// Fall in total PnL from the highest seen, in quote (tracked by the bot)
+    #[serde(default)]
+    pub current_drawdown: f64,
+    /// Largest drawdown seen since the bot started, in quote
+    #[serde(default)]
+    pub max_drawdown: f64,
     /// Number of completed trades (round trips)
     pub trade_count: u32,
     /// Active order count
     pub active_orders: usize,
+    /// The strategy's own price levels, for strategies that keep a book
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub book: Option<StatusBook>,
     /// Strategy-specific custom data (JSON)
     #[serde(default)]
     pub custom: serde_json::Value,
@@ -55,6 +71,21 @@ impl StrategyStatus {
         self.realized_pnl + self.unrealized_pnl - self.total_fees
     }
 
+    /// Net profit as a percentage of the total investment, if one is known
+    pub fn roi_pct(&self) -> Option<f64> {
+        self.total_investment
+            .filter(|&investment| investment > 0.0)
+            .map(|investment| self.net_profit() / investment * 100.0)
+    }
+
+    /// Current drawdown as a percentage of peak equity (the investment plus
+    /// the highest total PnL), if an investment is known
+    pub fn drawdown_pct(&self) -> Option<f64> {
+        let investment = self.total_investment.filter(|&investment| investment > 0.0)?;
+        let peak_equity = investment + self.total_pnl() + self.current_drawdown;
+        (peak_equity > 0.0).then(|| self.current_drawdown / peak_equity * 100.0)
+    }
+
     /// Builder: set status
     pub fn with_status(mut self, status: impl Into<String>) -> Self {
         self.status = status.into();
@@ -81,6 +112,30 @@ impl StrategyStatus {
         self
     }
 
+    /// Builder: set the quote committed to the strategy
+    pub fn with_investment(mut self, investment: f64) -> Self {
+        self.total_investment = Some(investment);
+        self
+    }
+
+    /// Builder: set the active order count
+    pub fn with_active_orders(mut self, active_orders: usize) -> Self {
+        self.active_orders = active_orders;
+        self
+    }
+
+    /// Builder: set the completed trade count
+    pub fn with_trade_count(mut self, trade_count: u32) -> Self {
+        self.trade_count = trade_count;
+        self
+    }
+
+    /// Builder: set the strategy's book
+    pub fn with_book(mut self, book: StatusBook) -> Self {
+        self.book = Some(book);
+        self
+    }
+
     /// Builder: set custom data
     pub fn with_custom(mut self, custom: serde_json::Value) -> Self {
         self.custom = custom;
@@ -88,6 +143,136 @@ impl StrategyStatus {
     }
 }
 
+/// One price level of a strategy's book
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookLevel {
+    /// Index of the level within the strategy
+    pub level_idx: usize,
+    pub price: f64,
+    pub size: f64,
+    /// Distance from the current price, in percent
+    pub dist: f64,
+    pub side: OrderSide,
+    /// Whether an order is resting at this level
+    pub has_order: bool,
+    /// PnL realized at this level so far
+    pub total_pnl: f64,
+    pub roundtrip_count: u32,
+}
+
+/// A strategy's price levels, each side sorted by descending price
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatusBook {
+    pub asks: Vec<BookLevel>,
+    pub bids: Vec<BookLevel>,
+}
+
+impl StatusBook {
+    /// Split levels into asks and bids and sort both sides
+    pub fn from_levels(levels: impl IntoIterator<Item = BookLevel>) -> Self {
+        let (mut asks, mut bids): (Vec<_>, Vec<_>) = levels
+            .into_iter()
+            .partition(|level| level.side == OrderSide::Sell);
+        asks.sort_by(|a, b| b.price.total_cmp(&a.price));
+        bids.sort_by(|a, b| b.price.total_cmp(&a.price));
+        Self { asks, bids }
+    }
+}
+
+/// Capital a strategy's initial orders tie up
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CapitalRequirement {
+    /// Quote (USDC) needed for the initial buys
+    pub quote: f64,
+    /// Base asset needed for the initial sells
+    pub base: f64,
+}
+
+impl CapitalRequirement {
+    /// Check the requirement against an asset's balances
+    ///
+    /// Perp sells open shorts, so only spot sells need base. For perps the
+    /// `margin_buffer` fraction of the account value is kept free.
+    pub fn check(&self, info: &AssetInfo, margin_buffer: f64) -> Result<(), String> {
+        let is_perp = MarketType::of(&info.name) == MarketType::Perp;
+        let available_quote = if is_perp {
+            info.usdc_balance * (1.0 - margin_buffer)
+        } else {
+            info.usdc_balance
+        };
+        if self.quote > available_quote {
+            return Err(format!(
+                "Insufficient USDC for {}: initial orders need {:.2}, {:.2} available",
+                info.name, self.quote, available_quote
+            ));
+        }
+        if !is_perp && self.base > info.balance {
+            return Err(format!(
+                "Insufficient {} balance: initial sells need {}, {} available",
+                info.name, self.base, info.balance
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Notable things that happened inside a strategy, for logging and alerting
+///
+/// Strategies queue events while handling callbacks; the bot collects them
+/// with `Strategy::drain_events` afterwards.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum StrategyEvent {
+    /// A buy and its matching sell both filled
+    RoundtripClosed(RoundTrip),
+    /// Price left the strategy's range: every level now waits on the same side
+    RangeExited { direction: RangeDirection },
+    /// Price came back after `RangeExited`, so levels trade on both sides again
+    RangeReentered,
+    /// Drawdown reached the bot's alert threshold; raised by the bot, once
+    /// per crossing
+    DrawdownExceeded { drawdown: f64, drawdown_pct: f64 },
+}
+
+/// Side of its range price left a strategy through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RangeDirection {
+    /// Above the top: every level sold and waits to buy back
+    Above,
+    /// Below the bottom: every level bought and waits to sell
+    Below,
+}
+
+/// Read-only exchange access for strategies that want it
+///
+/// Handed to `Strategy::set_context` before the market starts. Strategy
+/// callbacks are synchronous, so queries (open orders, positions, funding)
+/// run in a spawned task on `info_handle()`, with the results fed back on a
+/// later callback.
+#[derive(Debug, Clone)]
+pub struct StrategyContext {
+    info_client: Arc<InfoClient>,
+    /// Account the bot trades for
+    pub user: Address,
+}
+
+impl StrategyContext {
+    pub fn new(info_client: Arc<InfoClient>, user: Address) -> Self {
+        Self { info_client, user }
+    }
+
+    /// Client for info endpoint queries
+    pub fn info(&self) -> &InfoClient {
+        &self.info_client
+    }
+
+    /// Shared client handle, to move into a spawned task
+    pub fn info_handle(&self) -> Arc<InfoClient> {
+        self.info_client.clone()
+    }
+}
+
 /// Strategy interface for trading logic
 ///
 /// A strategy receives market events (price updates, order fills) and returns
@@ -184,6 +369,61 @@ pub trait Strategy {
     /// Orders to place in response to the fill
     fn on_order_filled(&mut self, fill: &OrderFill) -> Vec<OrderRequest>;
 
+    /// Called when an order was rejected by the exchange (optional)
+    ///
+    /// The order will never fill. Override this to retry or adjust, otherwise
+    /// the order is simply dropped. Default implementation returns no orders.
+    ///
+    /// # Arguments
+    /// * `order_id` - The ID of the rejected order
+    /// * `reason` - The exchange's error message
+    fn on_order_rejected(&mut self, _order_id: u64, _reason: &str) -> Vec<OrderRequest> {
+        vec![]
+    }
+
+    /// Called on a timer, about once per second (optional)
+    ///
+    /// Override this for strategies that act on a schedule rather than on
+    /// price moves. Default implementation returns no orders.
+    ///
+    /// # Arguments
+    /// * `now` - Current Unix time in seconds
+    fn on_tick(&mut self, _now: u64) -> Vec<OrderRequest> {
+        vec![]
+    }
+
+    /// Called with the account's spot balance of `asset`'s base (optional)
+    ///
+    /// Lets spot strategies hold back sells the account couldn't cover, and
+    /// place them once the balance allows. Default implementation returns no
+    /// orders.
+    ///
+    /// # Arguments
+    /// * `asset` - The traded asset
+    /// * `base` - Base balance available, including any locked in our resting sells
+    fn on_balance_update(&mut self, _asset: &str, _base: f64) -> Vec<OrderRequest> {
+        vec![]
+    }
+
+    /// Called with `asset`'s precision after the market re-fetched it (optional)
+    ///
+    /// Strategies whose precision differs should re-round their prices and
+    /// sizes and re-place affected orders. Default implementation
+    /// returns no orders.
+    fn on_precision_update(
+        &mut self,
+        _asset: &str,
+        _precision: AssetPrecision,
+    ) -> Vec<OrderRequest> {
+        vec![]
+    }
+
+    /// Receive exchange access before the market starts (optional)
+    ///
+    /// Only strategies that query the exchange themselves need to keep it;
+    /// the default implementation ignores it.
+    fn set_context(&mut self, _context: StrategyContext) {}
+
     /// Called to initialize the strategy (optional)
     ///
     /// Override this to perform setup when the strategy starts.
@@ -200,6 +440,62 @@ pub trait Strategy {
         vec![]
     }
 
+    /// Take the events queued since the last call (optional)
+    ///
+    /// Called by the bot after every other callback. Default implementation
+    /// returns no events.
+    fn drain_events(&mut self) -> Vec<StrategyEvent> {
+        vec![]
+    }
+
+    /// Take the ids of resting orders to cancel without a replacement (optional)
+    ///
+    /// Orders that get a successor should use `OrderRequest::replacing`
+    /// instead. Collected by the bot whenever the market asks for commands.
+    /// Default implementation cancels nothing.
+    fn drain_cancels(&mut self) -> Vec<u64> {
+        vec![]
+    }
+
+    /// Whether the strategy wants the exchange's open orders (optional)
+    ///
+    /// Polled by the bot with the cancels; answering true clears the request.
+    /// The market then fetches the open orders and hands them to
+    /// `on_open_orders`. Default implementation never asks.
+    fn take_open_orders_request(&mut self) -> bool {
+        false
+    }
+
+    /// Called with the ids of this strategy's orders open on the exchange (optional)
+    ///
+    /// Only sent after `take_open_orders_request` asked for it. Default
+    /// implementation returns no orders.
+    fn on_open_orders(&mut self, _open: &[u64]) -> Vec<OrderRequest> {
+        vec![]
+    }
+
+    /// Lifetime totals to carry into the next run (optional)
+    ///
+    /// The bot saves this after every fill and on shutdown, and hands it back
+    /// to `restore` before the next start. Default implementation keeps
+    /// nothing.
+    fn checkpoint(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Pick up totals saved by `checkpoint` in an earlier run (optional)
+    ///
+    /// Called before the strategy trades. Default implementation ignores them.
+    fn restore(&mut self, _checkpoint: serde_json::Value) {}
+
+    /// Capital the strategy's initial orders need (optional)
+    ///
+    /// Checked against the account's balances before a live start. Default
+    /// implementation requires nothing.
+    fn required_capital(&self) -> CapitalRequirement {
+        CapitalRequirement::default()
+    }
+
     /// Get the strategy name (optional)
     ///
     /// Useful for logging and debugging.
@@ -249,6 +545,26 @@ impl Strategy for Box<dyn Strategy + Send + Sync> {
         (**self).on_order_filled(fill)
     }
 
+    fn on_order_rejected(&mut self, order_id: u64, reason: &str) -> Vec<OrderRequest> {
+        (**self).on_order_rejected(order_id, reason)
+    }
+
+    fn on_tick(&mut self, now: u64) -> Vec<OrderRequest> {
+        (**self).on_tick(now)
+    }
+
+    fn on_balance_update(&mut self, asset: &str, base: f64) -> Vec<OrderRequest> {
+        (**self).on_balance_update(asset, base)
+    }
+
+    fn on_precision_update(&mut self, asset: &str, precision: AssetPrecision) -> Vec<OrderRequest> {
+        (**self).on_precision_update(asset, precision)
+    }
+
+    fn set_context(&mut self, context: StrategyContext) {
+        (**self).set_context(context)
+    }
+
     fn on_start(&mut self) -> Vec<OrderRequest> {
         (**self).on_start()
     }
@@ -257,6 +573,34 @@ impl Strategy for Box<dyn Strategy + Send + Sync> {
         (**self).on_stop()
     }
 
+    fn drain_events(&mut self) -> Vec<StrategyEvent> {
+        (**self).drain_events()
+    }
+
+    fn drain_cancels(&mut self) -> Vec<u64> {
+        (**self).drain_cancels()
+    }
+
+    fn take_open_orders_request(&mut self) -> bool {
+        (**self).take_open_orders_request()
+    }
+
+    fn on_open_orders(&mut self, open: &[u64]) -> Vec<OrderRequest> {
+        (**self).on_open_orders(open)
+    }
+
+    fn checkpoint(&self) -> Option<serde_json::Value> {
+        (**self).checkpoint()
+    }
+
+    fn restore(&mut self, checkpoint: serde_json::Value) {
+        (**self).restore(checkpoint)
+    }
+
+    fn required_capital(&self) -> CapitalRequirement {
+        (**self).required_capital()
+    }
+
     fn name(&self) -> &str {
         (**self).name()
     }
@@ -326,6 +670,21 @@ mod tests {
         assert_eq!(status.total_fees, 10.0);
         assert!((status.net_profit() - 90.0).abs() < 0.001);
         assert!((status.total_pnl() - 140.0).abs() < 0.001);
+        assert_eq!(status.roi_pct(), None);
+    }
+
+    #[test]
+    fn test_roi_relative_to_investment() {
+        let status = StrategyStatus::new("TestStrategy", "BTC")
+            .with_pnl(60.0, -25.0, 10.0)
+            .with_investment(1000.0);
+
+        // Net profit 50 on 1000; the open position doesn't count
+        assert!((status.roi_pct().unwrap() - 5.0).abs() < 1e-9);
+
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["total_investment"], 1000.0);
+        assert_eq!(status.with_investment(0.0).roi_pct(), None);
     }
 
     #[test]
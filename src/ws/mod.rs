@@ -4,4 +4,4 @@ mod ws_manager;
 pub use message_types::*;
 pub use sub_structs::*;
 pub(crate) use ws_manager::WsManager;
-pub use ws_manager::{Message, Subscription};
+pub use ws_manager::{Message, ReconnectPolicy, Subscription};
@@ -75,6 +75,9 @@ pub enum Subscription {
 #[serde(rename_all = "camelCase")]
 pub enum Message {
     NoData,
+    /// Sent once to every subscriber after a reconnect has replayed all
+    /// subscriptions. Events during the outage may have been missed.
+    Reconnected,
     HyperliquidError(String),
     AllMids(AllMids),
     Trades(Trades),
@@ -106,10 +109,69 @@ pub(crate) struct Ping {
     method: &'static str,
 }
 
-impl WsManager {
-    const SEND_PING_INTERVAL: u64 = 50;
+/// When to retry a dropped websocket connection, and when to stop
+///
+/// Waits grow exponentially from `base_delay` up to `max_delay`, each moved
+/// randomly by up to `jitter` of itself so many clients don't retry in step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReconnectPolicy {
+    /// Wait before the first attempt; doubled after every failed one
+    pub base_delay: Duration,
+    /// Longest wait between attempts
+    pub max_delay: Duration,
+    /// Largest random change to a wait, as a share of it (0.0 to 1.0)
+    pub jitter: f64,
+    /// Failed attempts in a row after which the client gives up (never if `None`)
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            jitter: 0.2,
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Wait before the attempt following `failures` failed ones, without jitter
+    pub fn delay(&self, failures: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(failures))
+            .min(self.max_delay)
+    }
 
-    pub(crate) async fn new(url: String, reconnect: bool) -> Result<WsManager> {
+    /// `delay` moved by jitter, with `sample` drawn uniformly from [0, 1)
+    pub fn jittered_delay(&self, failures: u32, sample: f64) -> Duration {
+        let spread = self.jitter.clamp(0.0, 1.0) * (2.0 * sample - 1.0);
+        self.delay(failures).mul_f64(1.0 + spread)
+    }
+
+    /// Whether to try again after `failures` failed attempts in a row
+    pub fn should_retry(&self, failures: u32) -> bool {
+        self.max_attempts.is_none_or(|max| failures < max)
+    }
+}
+
+impl WsManager {
+    pub(crate) const SEND_PING_INTERVAL: Duration = Duration::from_secs(50);
+    pub(crate) const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(90);
+
+    /// Connect to `url` and spawn the reader and ping tasks
+    ///
+    /// A ping is sent every `ping_interval`. If nothing (not even a pong) is
+    /// received for `heartbeat_timeout`, the connection is treated as dead and
+    /// goes through the same path as a closed socket, which reconnects
+    /// following `reconnect` if set.
+    pub(crate) async fn new(
+        url: String,
+        reconnect: Option<ReconnectPolicy>,
+        ping_interval: Duration,
+        heartbeat_timeout: Duration,
+    ) -> Result<WsManager> {
         let stop_flag = Arc::new(AtomicBool::new(false));
 
         let (writer, mut reader) = Self::connect(&url).await?.split();
@@ -123,15 +185,31 @@ impl WsManager {
             let writer = writer.clone();
             let stop_flag = Arc::clone(&stop_flag);
             let reader_fut = async move {
+                // Failed reconnect attempts since the connection was last up
+                let mut failures = 0;
                 while !stop_flag.load(Ordering::Relaxed) {
-                    if let Some(data) = reader.next().await {
-                        if let Err(err) =
-                            WsManager::parse_and_send_data(data, &subscriptions_copy).await
-                        {
-                            error!("Error processing data received by WsManager reader: {err}");
+                    let disconnected = match time::timeout(heartbeat_timeout, reader.next()).await {
+                        Ok(Some(data)) => {
+                            if let Err(err) =
+                                WsManager::parse_and_send_data(data, &subscriptions_copy).await
+                            {
+                                error!("Error processing data received by WsManager reader: {err}");
+                            }
+                            false
+                        }
+                        Ok(None) => {
+                            warn!("WsManager disconnected");
+                            true
                         }
-                    } else {
-                        warn!("WsManager disconnected");
+                        Err(_) => {
+                            warn!(
+                                "WsManager received nothing for {heartbeat_timeout:?}, treating connection as dead"
+                            );
+                            true
+                        }
+                    };
+
+                    if disconnected {
                         if let Err(err) = WsManager::send_to_all_subscriptions(
                             &subscriptions_copy,
                             Message::NoData,
@@ -140,12 +218,18 @@ impl WsManager {
                         {
                             warn!("Error sending disconnection notification err={err}");
                         }
-                        if reconnect {
-                            // Always sleep for 1 second before attempting to reconnect so it does not spin during reconnecting. This could be enhanced with exponential backoff.
-                            tokio::time::sleep(Duration::from_secs(1)).await;
-                            info!("WsManager attempting to reconnect");
+                        if let Some(policy) = reconnect {
+                            // Back off so a flapping connection doesn't spin
+                            let sample =
+                                uuid::Uuid::new_v4().as_u64_pair().0 as f64 / u64::MAX as f64;
+                            tokio::time::sleep(policy.jittered_delay(failures, sample)).await;
+                            info!(
+                                "WsManager attempting to reconnect (attempt {})",
+                                failures + 1
+                            );
                             match Self::connect(&url).await {
                                 Ok(ws) => {
+                                    failures = 0;
                                     let (new_writer, new_reader) = ws.split();
                                     reader = new_reader;
                                     let mut writer_guard = writer.lock().await;
@@ -174,9 +258,38 @@ impl WsManager {
                                             error!("Could not resubscribe correctly {identifier}: {err}");
                                         }
                                     }
+                                    drop(writer_guard);
                                     info!("WsManager reconnect finished");
+                                    if let Err(err) = WsManager::send_to_all_subscriptions(
+                                        &subscriptions_copy,
+                                        Message::Reconnected,
+                                    )
+                                    .await
+                                    {
+                                        warn!("Error sending reconnect notification err={err}");
+                                    }
+                                }
+                                Err(err) => {
+                                    error!("Could not connect to websocket {err}");
+                                    failures += 1;
+                                    if !policy.should_retry(failures) {
+                                        let reason = format!(
+                                            "websocket reconnect gave up after {failures} attempts"
+                                        );
+                                        error!("WsManager {reason}");
+                                        if let Err(err) = WsManager::send_to_all_subscriptions(
+                                            &subscriptions_copy,
+                                            Message::HyperliquidError(reason),
+                                        )
+                                        .await
+                                        {
+                                            warn!("Error sending give-up notification err={err}");
+                                        }
+                                        // Dropping the senders ends the subscribers' streams
+                                        subscriptions_copy.lock().await.clear();
+                                        break;
+                                    }
                                 }
-                                Err(err) => error!("Could not connect to websocket {err}"),
                             }
                         } else {
                             error!("WsManager reconnection disabled. Will not reconnect and exiting reader task.");
@@ -203,7 +316,7 @@ impl WsManager {
                         }
                         Err(err) => error!("Error serializing ping message: {err}"),
                     }
-                    time::sleep(Duration::from_secs(Self::SEND_PING_INTERVAL)).await;
+                    time::sleep(ping_interval).await;
                 }
                 warn!("ws ping task stopped");
             };
@@ -294,7 +407,7 @@ impl WsManager {
             })
             .map_err(|e| Error::JsonParse(e.to_string())),
             Message::SubscriptionResponse | Message::Pong => Ok(String::default()),
-            Message::NoData => Ok("".to_string()),
+            Message::NoData | Message::Reconnected => Ok("".to_string()),
             Message::HyperliquidError(err) => Ok(format!("hyperliquid error: {err:?}")),
         }
     }
@@ -495,3 +608,160 @@ impl Drop for WsManager {
         self.stop_flag.store(true, Ordering::Relaxed);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        net::TcpListener,
+        sync::mpsc::{unbounded_channel, UnboundedReceiver},
+    };
+
+    use super::*;
+
+    #[derive(Debug)]
+    enum ServerEvent {
+        Connected,
+        Received(String),
+    }
+
+    /// Accepts WebSocket connections that stay open but never send anything,
+    /// reporting connections and received text frames on the returned channel
+    async fn spawn_silent_server() -> (String, UnboundedReceiver<ServerEvent>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let (events_tx, events_rx) = unbounded_channel();
+
+        spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                let _ = events_tx.send(ServerEvent::Connected);
+                let events_tx = events_tx.clone();
+                spawn(async move {
+                    let (_writer, mut reader) = ws.split();
+                    while let Some(Ok(frame)) = reader.next().await {
+                        if let protocol::Message::Text(text) = frame {
+                            let _ = events_tx.send(ServerEvent::Received(text));
+                        }
+                    }
+                });
+            }
+        });
+
+        (url, events_rx)
+    }
+
+    /// Wait for the next connection, skipping received frames
+    async fn next_connection(events: &mut UnboundedReceiver<ServerEvent>) {
+        loop {
+            match time::timeout(Duration::from_secs(5), events.recv()).await {
+                Ok(Some(ServerEvent::Connected)) => return,
+                Ok(Some(ServerEvent::Received(_))) => continue,
+                _ => panic!("no connection attempted"),
+            }
+        }
+    }
+
+    /// Wait for a subscribe frame, skipping pings
+    async fn next_subscribe(events: &mut UnboundedReceiver<ServerEvent>) -> serde_json::Value {
+        loop {
+            match time::timeout(Duration::from_secs(5), events.recv()).await {
+                Ok(Some(ServerEvent::Received(text))) => {
+                    let frame: serde_json::Value = serde_json::from_str(&text).unwrap();
+                    if frame["method"] == "subscribe" {
+                        return frame["subscription"].clone();
+                    }
+                }
+                Ok(Some(ServerEvent::Connected)) => continue,
+                _ => panic!("no subscription received"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_reconnect_backoff_schedule() {
+        let policy = ReconnectPolicy {
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            jitter: 0.5,
+            max_attempts: Some(3),
+        };
+
+        let schedule: Vec<u128> = (0..6).map(|n| policy.delay(n).as_millis()).collect();
+        assert_eq!(schedule, vec![100, 200, 400, 800, 1000, 1000]);
+
+        // Jitter moves the wait by at most half either way
+        assert_eq!(policy.jittered_delay(1, 0.5), Duration::from_millis(200));
+        assert_eq!(policy.jittered_delay(1, 0.0), Duration::from_millis(100));
+        assert!(policy.jittered_delay(1, 0.999) < Duration::from_millis(300));
+
+        assert!(policy.should_retry(2));
+        assert!(!policy.should_retry(3));
+        assert!(ReconnectPolicy::default().should_retry(u32::MAX));
+    }
+
+    #[tokio::test]
+    async fn test_silent_socket_triggers_reconnect() {
+        let (url, mut events) = spawn_silent_server().await;
+
+        let mut manager = WsManager::new(
+            url,
+            Some(ReconnectPolicy::default()),
+            Duration::from_millis(50),
+            Duration::from_millis(200),
+        )
+        .await
+        .unwrap();
+        next_connection(&mut events).await;
+
+        let (sender, mut receiver) = unbounded_channel();
+        let identifier = serde_json::to_string(&Subscription::AllMids).unwrap();
+        manager.add_subscription(identifier, sender).await.unwrap();
+
+        // Pings go unanswered, so the heartbeat declares the socket dead...
+        let message = time::timeout(Duration::from_secs(5), receiver.recv())
+            .await
+            .expect("no disconnect notification")
+            .unwrap();
+        assert!(matches!(message, Message::NoData));
+
+        // ...and the reconnect path opens a new connection
+        next_connection(&mut events).await;
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_replayed_after_reconnect() {
+        let (url, mut events) = spawn_silent_server().await;
+
+        let mut manager = WsManager::new(
+            url,
+            Some(ReconnectPolicy::default()),
+            Duration::from_millis(50),
+            Duration::from_millis(200),
+        )
+        .await
+        .unwrap();
+        next_connection(&mut events).await;
+
+        let (sender, mut receiver) = unbounded_channel();
+        let identifier = serde_json::to_string(&Subscription::L2Book {
+            coin: "BTC".to_string(),
+        })
+        .unwrap();
+        manager.add_subscription(identifier, sender).await.unwrap();
+        let original = next_subscribe(&mut events).await;
+
+        // Simulated disconnect: the silent server trips the heartbeat
+        next_connection(&mut events).await;
+        assert_eq!(next_subscribe(&mut events).await, original);
+
+        // The original sender gets NoData, then Reconnected once replay is done
+        let mut reconnected = false;
+        while let Ok(Some(message)) = time::timeout(Duration::from_secs(5), receiver.recv()).await {
+            if matches!(message, Message::Reconnected) {
+                reconnected = true;
+                break;
+            }
+        }
+        assert!(reconnected);
+    }
+}